@@ -4,7 +4,16 @@ use std::{
     sync::Arc,
 };
 
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+/// Characters that are left as they are in each segment of a URL path.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
 
 /// Represents a disambiguated and cleaned up path to an asset from a Tarmac
 /// project.
@@ -32,9 +41,28 @@ impl AssetName {
             displayed.replace(path::MAIN_SEPARATOR, "/")
         };
 
+        // Normalize the name so that file names stored decomposed, like on
+        // HFS+, produce the same manifest as everywhere else.
+        let displayed: String = displayed.nfc().collect();
+
         AssetName(displayed.into())
     }
 
+    /// This name with every letter made lowercase.
+    pub fn to_lowercase(&self) -> Self {
+        AssetName(self.0.to_lowercase().into())
+    }
+
+    /// This name as the path of a URL, with each of its segments
+    /// percent-encoded so that characters like spaces and `#` stay part of it.
+    pub fn to_url_path(&self) -> String {
+        self.0
+            .split('/')
+            .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT).to_string())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
     #[cfg(test)]
     pub(crate) fn new<S: AsRef<str>>(inner: S) -> Self {
         Self(inner.as_ref().into())
@@ -52,3 +80,24 @@ impl fmt::Display for AssetName {
         write!(formatter, "{}", self.0)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decomposed_names_are_normalized() {
+        let root = Path::new("project");
+        let decomposed = AssetName::from_paths(root, Path::new("project/cafe\u{301}.png"));
+        let composed = AssetName::from_paths(root, Path::new("project/caf\u{e9}.png"));
+
+        assert_eq!(decomposed, composed);
+    }
+
+    #[test]
+    fn url_paths_are_encoded_by_segment() {
+        let name = AssetName::new("ui/play button#2/caf\u{e9}.png");
+
+        assert_eq!(name.to_url_path(), "ui/play%20button%232/caf%C3%A9.png");
+    }
+}
@@ -0,0 +1,188 @@
+//! Abstraction over the service that uploaded assets are stored in, so that
+//! sync backends can be exercised without talking to Roblox.
+
+use crate::roblox_web_api::{
+    AssetCreator, AssetUploadData, ImageUploadData, RobloxApiClient, RobloxApiError, UploadResponse,
+};
+
+/// The uploads that Tarmac's sync backends need to perform.
+pub trait AssetUploader {
+    /// Upload an image as a decal, retrying with a generic name if the
+    /// original name is moderated.
+    fn upload_image_with_moderation_retry(
+        &mut self,
+        data: ImageUploadData,
+    ) -> Result<UploadResponse, RobloxApiError>;
+
+    /// Upload an audio file, returning the ID of the new asset.
+    fn upload_audio(
+        &mut self,
+        data: &AssetUploadData,
+        group_id: Option<u64>,
+    ) -> Result<u64, RobloxApiError>;
+
+    /// Upload a model file, returning the ID of the new asset.
+    fn upload_model(
+        &mut self,
+        data: &AssetUploadData,
+        group_id: Option<u64>,
+    ) -> Result<u64, RobloxApiError>;
+
+    /// Upload any supported asset through Open Cloud, returning the ID of the
+    /// new asset.
+    fn upload_asset_open_cloud(
+        &mut self,
+        data: &AssetUploadData,
+        api_key: &str,
+        creator: AssetCreator,
+    ) -> Result<u64, RobloxApiError>;
+}
+
+impl AssetUploader for RobloxApiClient {
+    fn upload_image_with_moderation_retry(
+        &mut self,
+        data: ImageUploadData,
+    ) -> Result<UploadResponse, RobloxApiError> {
+        RobloxApiClient::upload_image_with_moderation_retry(self, data)
+    }
+
+    fn upload_audio(
+        &mut self,
+        data: &AssetUploadData,
+        group_id: Option<u64>,
+    ) -> Result<u64, RobloxApiError> {
+        RobloxApiClient::upload_audio(self, data, group_id)
+    }
+
+    fn upload_model(
+        &mut self,
+        data: &AssetUploadData,
+        group_id: Option<u64>,
+    ) -> Result<u64, RobloxApiError> {
+        RobloxApiClient::upload_model(self, data, group_id)
+    }
+
+    fn upload_asset_open_cloud(
+        &mut self,
+        data: &AssetUploadData,
+        api_key: &str,
+        creator: AssetCreator,
+    ) -> Result<u64, RobloxApiError> {
+        RobloxApiClient::upload_asset_open_cloud(self, data, api_key, creator)
+    }
+}
+
+/// An `AssetUploader` that keeps every upload in memory and hands out
+/// sequential IDs, for testing code that uploads assets.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MemoryUploader {
+    pub uploads: Vec<MemoryUpload>,
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryUpload {
+    pub id: u64,
+    pub name: String,
+    pub description: String,
+    pub contents: Vec<u8>,
+
+    /// The group the asset was uploaded to through a legacy endpoint.
+    pub group_id: Option<u64>,
+
+    /// The creator the asset was uploaded for through Open Cloud.
+    pub creator: Option<AssetCreator>,
+}
+
+#[cfg(test)]
+impl MemoryUploader {
+    fn record(
+        &mut self,
+        name: &str,
+        description: &str,
+        contents: &[u8],
+        group_id: Option<u64>,
+        creator: Option<AssetCreator>,
+    ) -> u64 {
+        let id = self.uploads.len() as u64 + 1;
+
+        self.uploads.push(MemoryUpload {
+            id,
+            name: name.to_owned(),
+            description: description.to_owned(),
+            contents: contents.to_vec(),
+            group_id,
+            creator,
+        });
+
+        id
+    }
+}
+
+#[cfg(test)]
+impl AssetUploader for MemoryUploader {
+    fn upload_image_with_moderation_retry(
+        &mut self,
+        data: ImageUploadData,
+    ) -> Result<UploadResponse, RobloxApiError> {
+        // Decals are backed by a separate image asset, so each image upload
+        // takes up two IDs.
+        let asset_id = self.record(data.name, data.description, &[], data.group_id, None);
+        let backing_asset_id = self.record(
+            data.name,
+            data.description,
+            &data.image_data.read().unwrap(),
+            data.group_id,
+            None,
+        );
+
+        Ok(UploadResponse {
+            asset_id,
+            backing_asset_id,
+        })
+    }
+
+    fn upload_audio(
+        &mut self,
+        data: &AssetUploadData,
+        group_id: Option<u64>,
+    ) -> Result<u64, RobloxApiError> {
+        Ok(self.record(
+            data.name,
+            data.description,
+            &data.contents.read().unwrap(),
+            group_id,
+            None,
+        ))
+    }
+
+    fn upload_model(
+        &mut self,
+        data: &AssetUploadData,
+        group_id: Option<u64>,
+    ) -> Result<u64, RobloxApiError> {
+        Ok(self.record(
+            data.name,
+            data.description,
+            &data.contents.read().unwrap(),
+            group_id,
+            None,
+        ))
+    }
+
+    fn upload_asset_open_cloud(
+        &mut self,
+        data: &AssetUploadData,
+        _api_key: &str,
+        creator: AssetCreator,
+    ) -> Result<u64, RobloxApiError> {
+        Ok(self.record(
+            data.name,
+            data.description,
+            &data.contents.read().unwrap(),
+            None,
+            Some(creator),
+        ))
+    }
+}
@@ -0,0 +1,150 @@
+//! Generates a JSON description of grouped inputs, for tooling that consumes
+//! asset IDs but can't read Lua.
+
+use std::{
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use fs_err::File;
+use serde_json::{json, Map, Value};
+
+use crate::data::{SliceFormat, SyncInput, UrlFormat};
+
+use super::{native_size, sort_children, CodegenOptions, GroupedItem};
+
+pub(super) fn codegen_json(
+    output_path: &Path,
+    options: &CodegenOptions<'_>,
+    root: &GroupedItem<'_>,
+) -> io::Result<()> {
+    let value = build_value(root, options).unwrap_or_else(|| Value::Object(Map::new()));
+
+    let mut file = BufWriter::new(File::create(output_path)?);
+    serde_json::to_writer_pretty(&mut file, &value)?;
+    writeln!(file)?;
+    file.flush()?;
+
+    Ok(())
+}
+
+fn build_value(item: &GroupedItem<'_>, options: &CodegenOptions<'_>) -> Option<Value> {
+    match item {
+        GroupedItem::Folder { children_by_name } => {
+            let mut children: Vec<_> = children_by_name.iter().collect();
+            sort_children(&mut children, options);
+
+            let entries = children
+                .into_iter()
+                .filter_map(|(name, child)| {
+                    build_value(child, options).map(|value| (name.clone(), value))
+                })
+                .collect();
+
+            Some(Value::Object(entries))
+        }
+        GroupedItem::InputGroup {
+            inputs_by_dpi_scale,
+        } => {
+            if inputs_by_dpi_scale.len() == 1 {
+                let input = inputs_by_dpi_scale.values().next().unwrap();
+
+                input_value(input)
+            } else {
+                // JSON has no way to pick a variant at runtime, so we describe
+                // every DPI variant, keyed by its scale.
+                let variants = inputs_by_dpi_scale
+                    .iter()
+                    .filter_map(|(scale, input)| {
+                        input_value(input).map(|value| (format!("{}x", scale), value))
+                    })
+                    .collect();
+
+                Some(Value::Object(variants))
+            }
+        }
+    }
+}
+
+fn input_value(input: &SyncInput) -> Option<Value> {
+    // Fonts are loaded with Font.fromId, so only their ID is useful.
+    if input.is_font() {
+        return match input.id {
+            Some(id) => Some(json!(id)),
+            None if input.rejected => Some(json!(0)),
+            None => None,
+        };
+    }
+
+    // Inputs without an ID use the configured placeholder, and inputs
+    // rejected by moderation are otherwise generated with an empty image.
+    let id = input.codegen_id().or(input.config.codegen_placeholder_id);
+    let url = match (id, input.config.codegen_url_format) {
+        (Some(id), UrlFormat::Id) => json!(id),
+        (Some(id), format) => Value::String(format.asset_url(id)),
+        (None, UrlFormat::Id) if input.rejected => json!(0),
+        (None, _) if input.rejected => Value::String(String::new()),
+        (None, _) => return None,
+    };
+
+    if !input.is_image() || (input.slice.is_none() && !input.config.codegen_include_size) {
+        return Some(url);
+    }
+
+    let mut properties = Map::new();
+    properties.insert("Image".to_owned(), url);
+
+    if let Some(slice) = input.slice {
+        let offset = slice.min();
+        let size = slice.size();
+
+        if input.config.codegen_slice_format == SliceFormat::Rect {
+            let max = slice.max();
+
+            properties.insert(
+                "ImageRect".to_owned(),
+                json!([offset.0, offset.1, max.0, max.1]),
+            );
+        } else {
+            properties.insert("ImageRectOffset".to_owned(), json!([offset.0, offset.1]));
+            properties.insert("ImageRectSize".to_owned(), json!([size.0, size.1]));
+        }
+    }
+
+    if input.config.codegen_include_size {
+        if let Some((width, height)) = native_size(input) {
+            properties.insert("Size".to_owned(), json!([width, height]));
+        }
+    }
+
+    Some(Value::Object(properties))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn placeholders_stand_in_for_missing_ids() {
+        let mut input = SyncInput::new("a.png", "codegen-placeholder-id = 99");
+        assert_eq!(input_value(&input), Some(json!("rbxassetid://99")));
+
+        input.rejected = true;
+        assert_eq!(input_value(&input), Some(json!("rbxassetid://99")));
+
+        input.config.codegen_url_format = UrlFormat::Id;
+        assert_eq!(input_value(&input), Some(json!(99)));
+    }
+
+    #[test]
+    fn rejected_inputs_without_placeholders_are_empty() {
+        let mut input = SyncInput::new("a.png", "");
+        assert_eq!(input_value(&input), None);
+
+        input.rejected = true;
+        assert_eq!(input_value(&input), Some(json!("")));
+
+        input.config.codegen_url_format = UrlFormat::Id;
+        assert_eq!(input_value(&input), Some(json!(0)));
+    }
+}
@@ -0,0 +1,1058 @@
+//! Defines how Tarmac generates Lua code for linking to assets.
+//!
+//! Tarmac uses a small Lua AST to build up generated code. Grouped inputs can
+//! also be described as JSON or as TypeScript declarations for the generated
+//! Lua modules.
+
+mod json;
+mod typescript;
+
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    io::{self, BufWriter, Write},
+    path::{self, Path, PathBuf},
+};
+
+use fs_err::File;
+use thiserror::Error;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+    data::ImageSlice,
+    data::SyncInput,
+    data::{CodegenFormat, CodegenKind, CodegenOrder, InputConfig, SliceFormat, UrlFormat},
+    image::read_png_size,
+    lua_ast::{Block, Expression, Function, IfBlock, IfExpression, MethodCall, Statement, Table},
+    rbxmx,
+};
+
+/// Looks up assets by slash-separated paths in modules generated with the
+/// `asset-map` and `lookup` codegen kinds. It expects a local named `assets` to
+/// be defined, and is meant to be called by another helper.
+const PATH_LOOKUP_HELPER: &str = r#"local function lookup(path)
+	local current = assets
+	local visited = {}
+
+	for segment in string.gmatch(path, "[^/]+") do
+		if type(current) ~= "table" or current.Image ~= nil then
+			error(string.format("Cannot find %q in asset path %q because %q is an asset, not a folder", segment, path, "/" .. table.concat(visited, "/")), 3)
+		end
+
+		local child = current[segment]
+
+		if child == nil then
+			local available = {}
+			for key in pairs(current) do
+				table.insert(available, tostring(key))
+			end
+			table.sort(available)
+
+			error(string.format("No asset named %q in %q while looking up asset path %q. Available names are: %s", segment, "/" .. table.concat(visited, "/"), path, table.concat(available, ", ")), 3)
+		end
+
+		table.insert(visited, segment)
+		current = child
+	end
+
+	return current
+end"#;
+
+/// Runtime helpers appended to modules generated with the `lookup` codegen
+/// kind, after the path lookup helper. Assets are reached through the returned
+/// table's metatable, so that an asset named `byPath` can't hide the helper.
+const LOOKUP_HELPERS: &str = r#"local Lookup = {}
+
+-- Returns the asset at the given slash-separated path, like "ui/buttons/play".
+function Lookup.byPath(path)
+	return lookup(path)
+end
+
+return setmetatable(Lookup, {
+	__index = assets,
+})"#;
+
+/// The global that generated code checks to decide whether to use local copies
+/// of assets from `dev-content` instead of uploaded ones.
+const DEV_FLAG: &str = "_G.TARMAC_DEV";
+
+const CODEGEN_HEADER: &str =
+    "This file was @generated by Tarmac. It is not intended for manual editing.";
+
+/// Runtime helpers appended to modules generated with the `asset-map` codegen
+/// kind, after the path lookup helper. They expect a local named
+/// `ContentProvider` to be defined.
+const ASSET_MAP_HELPERS: &str = r#"local AssetMap = {}
+AssetMap.assets = assets
+
+local function resolve(value, dpiScale)
+	if type(value) == "function" then
+		return value(dpiScale or 1)
+	end
+
+	return value
+end
+
+-- Returns the asset at the given slash-separated path. Assets packed into
+-- spritesheets are returned as a table of ImageLabel properties.
+function AssetMap.get(path, dpiScale)
+	return resolve(lookup(path), dpiScale)
+end
+
+-- Returns the ImageRectOffset and ImageRectSize of the asset at the given
+-- path, or nil if the asset was not packed into a spritesheet. Assets that use
+-- the "rect" slice format return a single Rect instead.
+function AssetMap.slice(path, dpiScale)
+	local value = AssetMap.get(path, dpiScale)
+
+	if type(value) == "table" and value.ImageRect ~= nil then
+		return value.ImageRect
+	end
+
+	if type(value) == "table" and value.Image ~= nil then
+		return value.ImageRectOffset, value.ImageRectSize
+	end
+
+	return nil
+end
+
+-- Preloads every asset in this module with ContentProvider.
+function AssetMap.preloadAll()
+	local urls = {}
+	local seen = {}
+
+	local function visit(value)
+		value = resolve(value)
+
+		if type(value) == "number" then
+			value = "rbxassetid://" .. value
+		end
+
+		if type(value) == "string" then
+			if not seen[value] then
+				seen[value] = true
+				table.insert(urls, value)
+			end
+		elseif type(value) == "table" and value.Image ~= nil then
+			visit(value.Image)
+		elseif type(value) == "table" then
+			for _, child in pairs(value) do
+				visit(child)
+			end
+		end
+	end
+
+	visit(assets)
+
+	ContentProvider:PreloadAsync(urls)
+end
+
+return AssetMap"#;
+
+/// Options that apply to an entire generated file when grouping inputs via
+/// `codegen_path`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CodegenOptions<'a> {
+    pub kind: CodegenKind,
+
+    /// A dot-separated path of tables that the generated tree is nested under.
+    pub namespace: Option<&'a str>,
+
+    /// A prefix prepended to each top-level key of the generated tree.
+    pub key_prefix: Option<&'a str>,
+
+    /// The order that keys are generated in within each table.
+    pub order: CodegenOrder,
+
+    /// Keys that should be generated before any others, in this order.
+    pub key_order: &'a [String],
+}
+
+impl<'a> CodegenOptions<'a> {
+    pub fn from_input_config(config: &'a InputConfig) -> Self {
+        Self {
+            kind: config.codegen_kind,
+            namespace: config.codegen_namespace.as_deref(),
+            key_prefix: config.codegen_key_prefix.as_deref(),
+            order: config.codegen_order,
+            key_order: &config.codegen_key_order,
+        }
+    }
+}
+
+pub fn perform_codegen(
+    output_path: Option<&Path>,
+    format: CodegenFormat,
+    options: &CodegenOptions<'_>,
+    inputs: &[&SyncInput],
+) -> Result<(), CodegenError> {
+    if let Some(path) = output_path {
+        let root = build_tree(options, inputs)?;
+
+        // Each input group can generate into its own folder, which might not
+        // exist yet.
+        if let Some(parent) = path.parent() {
+            fs_err::create_dir_all(parent)?;
+        }
+
+        match format {
+            CodegenFormat::Lua | CodegenFormat::Rbxmx => {
+                codegen_grouped(path, format, options, &root)?
+            }
+            CodegenFormat::Json => json::codegen_json(path, options, &root)?,
+            CodegenFormat::Typescript => typescript::codegen_typescript(path, options, &root)?,
+        }
+    } else {
+        codegen_individual(inputs)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum CodegenError {
+    #[error(
+        "More than one input would be generated under the key '{}':{}",
+        .key,
+        display_paths(.paths)
+    )]
+    DuplicateKey { key: String, paths: Vec<PathBuf> },
+
+    #[error(
+        "The key '{}' would refer to both an input and a folder of inputs:{}",
+        .key,
+        display_paths(.paths)
+    )]
+    FileFolderConflict { key: String, paths: Vec<PathBuf> },
+
+    #[error(
+        "Input {} is not inside its codegen-base-path, {}",
+        .path.display(),
+        .base_path.display()
+    )]
+    OutsideBasePath { path: PathBuf, base_path: PathBuf },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| format!("\n  - {}", path.display()))
+        .collect()
+}
+
+fn codegen_header() -> Statement {
+    Statement::Comment(CODEGEN_HEADER.to_owned())
+}
+
+/// Generate a Lua module that returns a flat list of every asset URL
+/// referenced by the given inputs, intended to be passed directly to
+/// `ContentProvider:PreloadAsync`.
+pub fn perform_preload_codegen(output_path: &Path, known_ids: &BTreeSet<u64>) -> io::Result<()> {
+    let urls = known_ids
+        .iter()
+        .map(|id| format!("rbxassetid://{}", id).into())
+        .collect();
+
+    let ast = Statement::Return(Expression::array(urls));
+
+    if let Some(parent) = output_path.parent() {
+        fs_err::create_dir_all(parent)?;
+    }
+
+    let mut file = File::create(output_path)?;
+    writeln!(file, "{}", codegen_header())?;
+    write!(file, "{}", ast)?;
+
+    Ok(())
+}
+
+/// Tree used to track and group inputs hierarchically, before turning them into
+/// Lua tables.
+enum GroupedItem<'a> {
+    Folder {
+        children_by_name: BTreeMap<String, GroupedItem<'a>>,
+    },
+    InputGroup {
+        inputs_by_dpi_scale: BTreeMap<u32, &'a SyncInput>,
+    },
+}
+
+impl GroupedItem<'_> {
+    /// The earliest position that any input contained in this item was
+    /// discovered at.
+    fn discovery_index(&self) -> usize {
+        match self {
+            GroupedItem::Folder { children_by_name } => children_by_name
+                .values()
+                .map(GroupedItem::discovery_index)
+                .min()
+                .unwrap_or(usize::MAX),
+            GroupedItem::InputGroup {
+                inputs_by_dpi_scale,
+            } => inputs_by_dpi_scale
+                .values()
+                .map(|input| input.discovery_index)
+                .min()
+                .unwrap_or(usize::MAX),
+        }
+    }
+
+    /// The paths of every input contained in this item.
+    fn input_paths(&self) -> Vec<PathBuf> {
+        match self {
+            GroupedItem::Folder { children_by_name } => children_by_name
+                .values()
+                .flat_map(GroupedItem::input_paths)
+                .collect(),
+            GroupedItem::InputGroup {
+                inputs_by_dpi_scale,
+            } => inputs_by_dpi_scale
+                .values()
+                .map(|input| input.path.clone())
+                .collect(),
+        }
+    }
+}
+
+/// Sorts the children of a folder according to the ordering requested by the
+/// codegen options. Children start out in alphabetical order.
+fn sort_children(children: &mut Vec<(&String, &GroupedItem<'_>)>, options: &CodegenOptions<'_>) {
+    match options.order {
+        CodegenOrder::Alphabetical => {}
+        CodegenOrder::Natural => children.sort_by(|(a, _), (b, _)| natural_cmp(a, b)),
+        CodegenOrder::Discovery => children.sort_by_key(|(_, item)| item.discovery_index()),
+    }
+
+    // Keys that are listed explicitly come first, in the listed order. Since
+    // this sort is stable, all other keys keep their relative order.
+    if !options.key_order.is_empty() {
+        children.sort_by_key(|(name, _)| {
+            options
+                .key_order
+                .iter()
+                .position(|key| key == *name)
+                .unwrap_or(usize::MAX)
+        });
+    }
+}
+
+/// Compares two strings, treating runs of ASCII digits as numbers so that
+/// `frame_2` sorts before `frame_10`.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    fn split_digits(value: &str) -> (&str, &str) {
+        let end = value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len());
+
+        value.split_at(end)
+    }
+
+    let mut a_rest = a;
+    let mut b_rest = b;
+
+    loop {
+        match (a_rest.chars().next(), b_rest.chars().next()) {
+            // Strings like `01` and `1` compare equal numerically, so fall
+            // back to a plain comparison to keep the ordering total.
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+
+            (Some(a_char), Some(b_char)) if a_char.is_ascii_digit() && b_char.is_ascii_digit() => {
+                let (a_number, a_next) = split_digits(a_rest);
+                let (b_number, b_next) = split_digits(b_rest);
+
+                let a_number = a_number.trim_start_matches('0');
+                let b_number = b_number.trim_start_matches('0');
+
+                let ordering = a_number
+                    .len()
+                    .cmp(&b_number.len())
+                    .then_with(|| a_number.cmp(b_number));
+
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+
+                a_rest = a_next;
+                b_rest = b_next;
+            }
+
+            (Some(a_char), Some(b_char)) => {
+                if a_char != b_char {
+                    return a_char.cmp(&b_char);
+                }
+
+                a_rest = &a_rest[a_char.len_utf8()..];
+                b_rest = &b_rest[b_char.len_utf8()..];
+            }
+        }
+    }
+}
+
+/// Builds a tree from all of the inputs that are marked for codegen, shaped like
+/// the inputs' paths with their base paths stripped away.
+///
+/// The returned item is always a folder. Any key prefix and namespace from the
+/// codegen options are applied to it.
+fn build_tree<'a>(
+    options: &CodegenOptions<'_>,
+    inputs: &[&'a SyncInput],
+) -> Result<GroupedItem<'a>, CodegenError> {
+    let mut root_folder: BTreeMap<String, GroupedItem<'_>> = BTreeMap::new();
+
+    // First, collect all of the inputs and group them together into a tree
+    // according to their relative paths.
+    for &input in inputs {
+        // Not all inputs will be marked for codegen. We can eliminate those
+        // right away.
+        if !input.config.codegen {
+            continue;
+        }
+
+        // The extension portion of the path is not useful for code generation.
+        // By stripping it off, we generate the names that users expect.
+        let mut path_without_extension = input.path_without_dpi_scale.clone();
+        path_without_extension.set_extension("");
+
+        // If we can't construct a relative path, there isn't a sensible name
+        // that we can use to refer to this input.
+        let relative_path = path_without_extension
+            .strip_prefix(&input.config.codegen_base_path)
+            .map_err(|_| CodegenError::OutsideBasePath {
+                path: input.path.clone(),
+                base_path: input.config.codegen_base_path.clone(),
+            })?;
+
+        // Collapse `..` path segments so that we can map this path onto our
+        // tree of inputs.
+        //
+        // Some filesystems, like HFS+ on macOS, store file names decomposed,
+        // so we normalize each segment to make sure that the same file
+        // generates the same key on every platform.
+        let mut segments: Vec<String> = Vec::new();
+        for component in relative_path.components() {
+            match component {
+                path::Component::Prefix(_)
+                | path::Component::RootDir
+                | path::Component::Normal(_) => {
+                    segments.push(component.as_os_str().to_str().unwrap().nfc().collect())
+                }
+                path::Component::CurDir => {}
+                path::Component::ParentDir => assert!(segments.pop().is_some()),
+            }
+        }
+
+        // Inputs can override the key they're generated under.
+        if let Some(codegen_name) = &input.codegen_name {
+            if let Some(last) = segments.last_mut() {
+                *last = codegen_name.clone();
+            }
+        }
+
+        // Navigate down the tree, creating any folder entries that don't exist
+        // yet.
+        let mut current_dir = &mut root_folder;
+        for (i, segment) in segments.iter().enumerate() {
+            if i == segments.len() - 1 {
+                // We assume that the last segment of a path must be a file.
+
+                let input_group = current_dir.entry(segment.to_owned()).or_insert_with(|| {
+                    GroupedItem::InputGroup {
+                        inputs_by_dpi_scale: BTreeMap::new(),
+                    }
+                });
+
+                match input_group {
+                    GroupedItem::InputGroup {
+                        inputs_by_dpi_scale,
+                    } => {
+                        // Inputs whose names only differ by extension, like
+                        // `icon.png` and `icon.jpg`, would share a key.
+                        if let Some(existing) = inputs_by_dpi_scale.get(&input.dpi_scale) {
+                            return Err(CodegenError::DuplicateKey {
+                                key: segments.join("/"),
+                                paths: vec![existing.path.clone(), input.path.clone()],
+                            });
+                        }
+
+                        inputs_by_dpi_scale.insert(input.dpi_scale, input);
+                    }
+                    GroupedItem::Folder { .. } => {
+                        let mut paths = input_group.input_paths();
+                        paths.push(input.path.clone());
+
+                        return Err(CodegenError::FileFolderConflict {
+                            key: segments.join("/"),
+                            paths,
+                        });
+                    }
+                }
+            } else {
+                let next_entry =
+                    current_dir
+                        .entry(segment.to_owned())
+                        .or_insert_with(|| GroupedItem::Folder {
+                            children_by_name: BTreeMap::new(),
+                        });
+
+                match next_entry {
+                    GroupedItem::Folder { children_by_name } => {
+                        current_dir = children_by_name;
+                    }
+                    GroupedItem::InputGroup { .. } => {
+                        let mut paths = next_entry.input_paths();
+                        paths.push(input.path.clone());
+
+                        return Err(CodegenError::FileFolderConflict {
+                            key: segments[..=i].join("/"),
+                            paths,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(prefix) = options.key_prefix {
+        root_folder = root_folder
+            .into_iter()
+            .map(|(name, item)| (format!("{}{}", prefix, name), item))
+            .collect();
+    }
+
+    let mut root = GroupedItem::Folder {
+        children_by_name: root_folder,
+    };
+
+    // Wrap the tree in folders from the innermost namespace outwards, so that
+    // `UI.Icons` yields `{ UI = { Icons = { ... } } }`.
+    if let Some(namespace) = options.namespace {
+        for segment in namespace.rsplit('.') {
+            let mut children_by_name = BTreeMap::new();
+            children_by_name.insert(segment.to_owned(), root);
+
+            root = GroupedItem::Folder { children_by_name };
+        }
+    }
+
+    Ok(root)
+}
+
+/// Perform codegen for a group of inputs who have `codegen_path` defined.
+///
+/// We'll build up a Lua file containing nested tables that match the structure
+/// of the input's path with its base path stripped away.
+fn codegen_grouped(
+    output_path: &Path,
+    format: CodegenFormat,
+    options: &CodegenOptions<'_>,
+    root: &GroupedItem<'_>,
+) -> io::Result<()> {
+    let source = grouped_source(options, root);
+
+    match format {
+        CodegenFormat::Rbxmx => {
+            let name = output_path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("Assets");
+
+            let module = rbxmx::Instance::new("ModuleScript", name)
+                .with_property("Source", rbxmx::Property::ProtectedString(source));
+
+            let mut file = BufWriter::new(File::create(output_path)?);
+            rbxmx::write_model(&mut file, &[module])?;
+            file.flush()
+        }
+        _ => fs_err::write(output_path, source),
+    }
+}
+
+/// Generates the source of the Lua module for a tree of grouped inputs.
+fn grouped_source(options: &CodegenOptions<'_>, root: &GroupedItem<'_>) -> String {
+    fn build_item(item: &GroupedItem<'_>, options: &CodegenOptions<'_>) -> Option<Expression> {
+        match item {
+            GroupedItem::Folder { children_by_name } => {
+                let mut children: Vec<_> = children_by_name.iter().collect();
+                sort_children(&mut children, options);
+
+                let entries = children
+                    .into_iter()
+                    .filter_map(|(name, child)| {
+                        build_item(child, options).map(|item| (name.into(), item))
+                    })
+                    .collect();
+
+                Some(Expression::table(entries))
+            }
+            GroupedItem::InputGroup {
+                inputs_by_dpi_scale,
+            } => {
+                if inputs_by_dpi_scale.len() == 1 {
+                    // If there is exactly one input in this group, we can
+                    // generate code knowing that there are no high DPI variants
+                    // to choose from.
+
+                    let input = inputs_by_dpi_scale.values().next().unwrap();
+
+                    codegen_input(input)
+                } else {
+                    // In this case, we have the same asset in multiple
+                    // different DPI scales. We can generate code to pick
+                    // between them at runtime.
+                    Some(codegen_with_high_dpi_options(inputs_by_dpi_scale))
+                }
+            }
+        }
+    }
+
+    let root_item = build_item(root, options).unwrap();
+
+    let mut source = String::new();
+    writeln!(source, "{}", codegen_header()).unwrap();
+
+    match options.kind {
+        CodegenKind::Table => {
+            let ast = Statement::Return(root_item);
+            write!(source, "{}", ast).unwrap();
+        }
+        CodegenKind::AssetMap => {
+            let get_content_provider = MethodCall::new(
+                Expression::Raw("game".to_owned()),
+                "GetService",
+                vec!["ContentProvider".into()],
+            );
+
+            let locals = Block {
+                statements: vec![
+                    Statement::Local(
+                        "ContentProvider".to_owned(),
+                        Expression::MethodCall(get_content_provider),
+                    ),
+                    Statement::Local("assets".to_owned(), root_item),
+                ],
+            };
+
+            write!(source, "{}", locals).unwrap();
+            writeln!(source).unwrap();
+            // If Tarmac was built from a checkout with Windows line endings,
+            // they'll have leaked into this string.
+            writeln!(source, "{}", PATH_LOOKUP_HELPER.replace("\r\n", "\n")).unwrap();
+            writeln!(source).unwrap();
+            writeln!(source, "{}", ASSET_MAP_HELPERS.replace("\r\n", "\n")).unwrap();
+        }
+        CodegenKind::Lookup => {
+            let locals = Block::from(Statement::Local("assets".to_owned(), root_item));
+
+            write!(source, "{}", locals).unwrap();
+            writeln!(source).unwrap();
+            writeln!(source, "{}", PATH_LOOKUP_HELPER.replace("\r\n", "\n")).unwrap();
+            writeln!(source).unwrap();
+            writeln!(source, "{}", LOOKUP_HELPERS.replace("\r\n", "\n")).unwrap();
+        }
+    }
+
+    source
+}
+
+/// Perform codegen for a group of inputs that don't have `codegen_path`
+/// defined, and so generate individual files.
+fn codegen_individual(inputs: &[&SyncInput]) -> io::Result<()> {
+    for input in inputs {
+        if !input.config.codegen {
+            continue;
+        }
+
+        let expression = match codegen_input(input) {
+            Some(expression) => expression,
+            None => continue,
+        };
+
+        let ast = Statement::Return(expression);
+
+        let path = input.path.with_extension("lua");
+
+        let mut file = File::create(path)?;
+        writeln!(file, "{}", codegen_header())?;
+        write!(file, "{}", ast)?;
+    }
+
+    Ok(())
+}
+
+/// Generates the expression used to refer to a single input, or `None` if the
+/// input has not been uploaded yet and has no placeholder.
+fn codegen_input(input: &SyncInput) -> Option<Expression> {
+    if input.is_font() {
+        return codegen_font(input);
+    }
+
+    let uploaded = match input.codegen_id() {
+        Some(id) => {
+            let url = codegen_asset_url(id, input.config.codegen_url_format);
+            Some(codegen_asset(input, url, input.slice))
+        }
+
+        // A configured placeholder stands in for inputs without an ID, so that
+        // missing assets are easy to spot instead of missing from the table.
+        None => match input.config.codegen_placeholder_id {
+            Some(placeholder) => {
+                let url = codegen_asset_url(placeholder, input.config.codegen_url_format);
+                Some(codegen_asset(input, url, None))
+            }
+
+            // Inputs rejected by moderation keep their key with an empty
+            // image, so code referring to them keeps working until they're
+            // fixed.
+            None if input.rejected => {
+                let placeholder = match input.config.codegen_url_format {
+                    UrlFormat::Id => Expression::Raw("0".to_owned()),
+                    _ => Expression::String(String::new()),
+                };
+                Some(codegen_asset(input, placeholder, None))
+            }
+
+            None => None,
+        },
+    };
+
+    let dev_url = match &input.dev_url {
+        Some(dev_url) => dev_url,
+        None => return uploaded,
+    };
+
+    // The local copy of an input is never packed, so it's generated without a
+    // slice, but in the same shape as the uploaded version.
+    let local = codegen_asset(input, Expression::String(dev_url.clone()), None);
+    let uploaded = uploaded.unwrap_or_else(|| Expression::Raw("nil".to_owned()));
+
+    Some(Expression::If(IfExpression::new(
+        Expression::Raw(DEV_FLAG.to_owned()),
+        local,
+        uploaded,
+    )))
+}
+
+/// Generates a `Font` for a font input. Fonts can't be loaded from the Studio
+/// content folder, so `dev-content` doesn't apply to them.
+fn codegen_font(input: &SyncInput) -> Option<Expression> {
+    match input.id {
+        Some(id) => Some(Expression::Raw(format!("Font.fromId({})", id))),
+        None if input.rejected => Some(Expression::Raw(
+            "Font.fromEnum(Enum.Font.SourceSans)".to_owned(),
+        )),
+        None => None,
+    }
+}
+
+/// Generates the expression for an asset with the given URL. Inputs that are
+/// packed or include their size are generated as a table of ImageLabel
+/// properties.
+fn codegen_asset(input: &SyncInput, url: Expression, slice: Option<ImageSlice>) -> Expression {
+    if !input.is_image() || (input.slice.is_none() && !input.config.codegen_include_size) {
+        return url;
+    }
+
+    let mut table = Table::new();
+    table.add_entry("Image", url);
+
+    if let Some(slice) = slice {
+        add_slice_entries(&mut table, slice, input.config.codegen_slice_format);
+    }
+
+    if input.config.codegen_include_size {
+        if let Some((width, height)) = native_size(input) {
+            table.add_entry(
+                "Size",
+                Expression::Raw(format!("UDim2.fromOffset({}, {})", width, height)),
+            );
+        }
+    }
+
+    Expression::Table(table)
+}
+
+/// The size that an input should take up on screen, if it can be determined.
+///
+/// High DPI variants should take up the same amount of space on screen as their
+/// 1x counterparts, so their size is scaled down accordingly.
+fn native_size(input: &SyncInput) -> Option<(u32, u32)> {
+    let (width, height) = match input.slice {
+        Some(slice) => slice.size(),
+        None => match &input.contents {
+            Some(contents) => read_png_size(contents.reader().ok()?).ok()?,
+            None => read_png_size(File::open(&input.path).ok()?).ok()?,
+        },
+    };
+
+    let scale = input.dpi_scale;
+
+    Some((width / scale, height / scale))
+}
+
+fn add_slice_entries(table: &mut Table, slice: ImageSlice, format: SliceFormat) {
+    let min = slice.min();
+    let max = slice.max();
+    let size = slice.size();
+
+    match format {
+        SliceFormat::Vector2 => {
+            table.add_entry(
+                "ImageRectOffset",
+                Expression::Raw(format!("Vector2.new({}, {})", min.0, min.1)),
+            );
+
+            table.add_entry(
+                "ImageRectSize",
+                Expression::Raw(format!("Vector2.new({}, {})", size.0, size.1)),
+            );
+        }
+        SliceFormat::Udim2 => {
+            table.add_entry(
+                "ImageRectOffset",
+                Expression::Raw(format!("UDim2.fromOffset({}, {})", min.0, min.1)),
+            );
+
+            table.add_entry(
+                "ImageRectSize",
+                Expression::Raw(format!("UDim2.fromOffset({}, {})", size.0, size.1)),
+            );
+        }
+        SliceFormat::Rect => {
+            table.add_entry(
+                "ImageRect",
+                Expression::Raw(format!(
+                    "Rect.new({}, {}, {}, {})",
+                    min.0, min.1, max.0, max.1
+                )),
+            );
+        }
+    }
+}
+
+fn codegen_asset_url(id: u64, format: UrlFormat) -> Expression {
+    match format {
+        UrlFormat::Id => Expression::Raw(id.to_string()),
+        _ => Expression::String(format.asset_url(id)),
+    }
+}
+
+fn codegen_dpi_option(input: &SyncInput) -> (Expression, Block) {
+    let condition = Expression::Raw(format!("dpiScale >= {}", input.dpi_scale));
+
+    // FIXME: We should probably pull data out of SyncInput at the start of
+    // codegen so that we can handle invariants like this.
+    let value = codegen_input(input).unwrap();
+
+    let body = Statement::Return(value);
+
+    (condition, body.into())
+}
+
+fn codegen_with_high_dpi_options(inputs: &BTreeMap<u32, &SyncInput>) -> Expression {
+    let args = "dpiScale".to_owned();
+
+    let mut options_high_to_low = inputs.values().rev().peekable();
+
+    let highest_dpi_option = options_high_to_low.next().unwrap();
+    let (highest_cond, highest_body) = codegen_dpi_option(highest_dpi_option);
+
+    let mut if_block = IfBlock::new(highest_cond, highest_body);
+
+    while let Some(dpi_option) = options_high_to_low.next() {
+        let (cond, body) = codegen_dpi_option(dpi_option);
+
+        if options_high_to_low.peek().is_some() {
+            if_block.else_if_blocks.push((cond, body));
+        } else {
+            if_block.else_block = Some(body);
+        }
+    }
+
+    let statements = vec![Statement::If(if_block)];
+
+    Expression::Function(Function::new(args, statements))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, process};
+
+    use super::*;
+
+    fn lua(input: &SyncInput) -> Option<String> {
+        codegen_input(input).map(|expression| expression.to_string())
+    }
+
+    #[test]
+    fn placeholders_stand_in_for_missing_ids() {
+        let mut input = SyncInput::new("a.png", "codegen-placeholder-id = 99");
+        assert_eq!(lua(&input).as_deref(), Some("\"rbxassetid://99\""));
+
+        input.rejected = true;
+        assert_eq!(lua(&input).as_deref(), Some("\"rbxassetid://99\""));
+
+        input.id = Some(10);
+        assert_eq!(lua(&input).as_deref(), Some("\"rbxassetid://10\""));
+    }
+
+    #[test]
+    fn rejected_inputs_without_placeholders_are_empty() {
+        let mut input = SyncInput::new("a.png", "");
+        assert_eq!(lua(&input), None);
+
+        input.rejected = true;
+        assert_eq!(lua(&input).as_deref(), Some("\"\""));
+
+        input.config.codegen_url_format = UrlFormat::Id;
+        assert_eq!(lua(&input).as_deref(), Some("0"));
+    }
+
+    #[test]
+    fn lookup_helpers_cant_be_shadowed() {
+        let mut input = SyncInput::new(
+            "byPath.png",
+            "codegen = true\ncodegen-path = \"assets.lua\"\ncodegen-kind = \"lookup\"",
+        );
+        input.id = Some(10);
+
+        let options = CodegenOptions::from_input_config(&input.config);
+        let root = build_tree(&options, &[&input]).unwrap();
+        let source = grouped_source(&options, &root);
+
+        assert!(source.contains("local assets = {\n\tbyPath = \"rbxassetid://10\",\n}"));
+        assert!(source.contains("function Lookup.byPath(path)"));
+        assert!(source.ends_with("return setmetatable(Lookup, {\n\t__index = assets,\n})\n"));
+    }
+
+    #[test]
+    fn asset_maps_wrap_the_tree_in_helpers() {
+        let config = "codegen = true\ncodegen-path = \"assets.lua\"\ncodegen-kind = \"asset-map\"";
+        let mut play = SyncInput::new("ui/play.png", config);
+        play.id = Some(10);
+        let mut stop = SyncInput::new("ui/stop.png", config);
+        stop.id = Some(11);
+
+        let options = CodegenOptions::from_input_config(&play.config);
+        let root = build_tree(&options, &[&play, &stop]).unwrap();
+        let source = grouped_source(&options, &root);
+
+        assert!(source.contains("local ContentProvider = game:GetService(\"ContentProvider\")"));
+        assert!(source.contains(
+            "local assets = {\n\tui = {\n\t\tplay = \"rbxassetid://10\",\n\t\tstop = \"rbxassetid://11\",\n\t},\n}"
+        ));
+        assert!(source.contains("local function lookup(path)"));
+
+        for helper in &["get", "slice", "preloadAll"] {
+            assert!(source.contains(&format!("function AssetMap.{}(", helper)));
+        }
+
+        assert!(source.ends_with("return AssetMap\n"));
+    }
+
+    #[test]
+    fn property_tables_include_the_native_size() {
+        let mut input = SyncInput::new("play@2x.png", "codegen-include-size = true");
+        input.id = Some(10);
+        input.dpi_scale = 2;
+        input.slice = Some(ImageSlice::new((8, 16), (72, 48)));
+
+        assert_eq!(
+            lua(&input).as_deref(),
+            Some(
+                "{\n\tImage = \"rbxassetid://10\",\n\
+                 \tImageRectOffset = Vector2.new(8, 16),\n\
+                 \tImageRectSize = Vector2.new(64, 32),\n\
+                 \tSize = UDim2.fromOffset(32, 16),\n}"
+            )
+        );
+
+        // Inputs that aren't packed still get a table when their size is
+        // included.
+        input.slice = None;
+        input.path = PathBuf::from("missing.png");
+        assert_eq!(
+            lua(&input).as_deref(),
+            Some("{\n\tImage = \"rbxassetid://10\",\n}")
+        );
+
+        input.config.codegen_include_size = false;
+        assert_eq!(lua(&input).as_deref(), Some("\"rbxassetid://10\""));
+    }
+
+    #[test]
+    fn rbxmx_output_wraps_the_module_in_a_module_script() {
+        let mut input = SyncInput::new(
+            "play.png",
+            "codegen = true\ncodegen-path = \"Assets.rbxmx\"",
+        );
+        input.id = Some(10);
+
+        let folder = env::temp_dir().join(format!("tarmac-rbxmx-codegen-{}", process::id()));
+        let output_path = folder.join("Assets.rbxmx");
+        let options = CodegenOptions::from_input_config(&input.config);
+
+        perform_codegen(
+            Some(&output_path),
+            CodegenFormat::Rbxmx,
+            &options,
+            &[&input],
+        )
+        .unwrap();
+
+        let model = fs_err::read_to_string(&output_path).unwrap();
+        assert!(model.contains("<Item class=\"ModuleScript\" referent=\"RBX0\">"));
+        assert!(model.contains("<string name=\"Name\">Assets</string>"));
+        assert!(model.contains(&format!(
+            "<ProtectedString name=\"Source\"><![CDATA[{}]]></ProtectedString>",
+            grouped_source(&options, &build_tree(&options, &[&input]).unwrap())
+        )));
+
+        fs_err::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn fonts_are_loaded_by_id() {
+        let mut input = SyncInput::new("fonts/Title.otf", "codegen-include-size = true");
+        assert!(input.is_font());
+        assert_eq!(lua(&input), None);
+
+        input.id = Some(12);
+        assert_eq!(lua(&input).as_deref(), Some("Font.fromId(12)"));
+
+        input.id = None;
+        input.rejected = true;
+        assert_eq!(
+            lua(&input).as_deref(),
+            Some("Font.fromEnum(Enum.Font.SourceSans)")
+        );
+    }
+
+    #[test]
+    fn natural_numbers() {
+        assert_eq!(natural_cmp("frame_2", "frame_10"), Ordering::Less);
+        assert_eq!(natural_cmp("frame_10", "frame_9"), Ordering::Greater);
+        assert_eq!(natural_cmp("frame_10", "frame_10"), Ordering::Equal);
+    }
+
+    #[test]
+    fn natural_leading_zeros() {
+        assert_eq!(natural_cmp("frame_002", "frame_10"), Ordering::Less);
+        assert_eq!(natural_cmp("frame_01", "frame_1"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_text() {
+        assert_eq!(natural_cmp("a", "b"), Ordering::Less);
+        assert_eq!(natural_cmp("abc", "ab"), Ordering::Greater);
+        assert_eq!(natural_cmp("a2b", "a2a"), Ordering::Greater);
+    }
+}
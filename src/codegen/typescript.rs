@@ -0,0 +1,217 @@
+//! Generates TypeScript declarations that describe the shape of grouped Lua
+//! modules, for projects written with roblox-ts.
+
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    path::Path,
+};
+
+use fs_err::File;
+
+use crate::data::{CodegenKind, SliceFormat, SyncInput, UrlFormat};
+
+use super::{native_size, sort_children, CodegenOptions, GroupedItem};
+
+const TYPESCRIPT_HEADER: &str =
+    "// This file was @generated by Tarmac. It is not intended for manual editing.";
+
+pub(super) fn codegen_typescript(
+    output_path: &Path,
+    options: &CodegenOptions<'_>,
+    root: &GroupedItem<'_>,
+) -> io::Result<()> {
+    // The asset map nests the tree one level deeper, under its `assets` key.
+    let depth = match options.kind {
+        CodegenKind::AssetMap => 2,
+        CodegenKind::Table | CodegenKind::Lookup => 1,
+    };
+    let tree_type = build_type(root, options, depth).unwrap_or_else(|| "{}".to_owned());
+
+    let mut file = File::create(output_path)?;
+    writeln!(file, "{}", TYPESCRIPT_HEADER)?;
+
+    match options.kind {
+        CodegenKind::Table => {
+            writeln!(file, "declare const assets: {};", tree_type)?;
+            writeln!(file)?;
+            writeln!(file, "export = assets;")?;
+        }
+        CodegenKind::AssetMap => {
+            writeln!(file, "declare const AssetMap: {{")?;
+            writeln!(file, "\tassets: {};", tree_type)?;
+            writeln!(file, "\tget(path: string, dpiScale?: number): unknown;")?;
+            writeln!(
+                file,
+                "\tslice(path: string, dpiScale?: number): LuaTuple<[Vector2 | UDim2 | Rect | undefined, Vector2 | UDim2 | undefined]>;"
+            )?;
+            writeln!(file, "\tpreloadAll(): void;")?;
+            writeln!(file, "}};")?;
+            writeln!(file)?;
+            writeln!(file, "export = AssetMap;")?;
+        }
+        CodegenKind::Lookup => {
+            writeln!(
+                file,
+                "declare const assets: {} & {{\n\tbyPath(path: string): unknown;\n}};",
+                tree_type
+            )?;
+            writeln!(file)?;
+            writeln!(file, "export = assets;")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds the type of an item in the tree. `depth` is the indentation level of
+/// the members of the returned type, if it is an object type.
+fn build_type(
+    item: &GroupedItem<'_>,
+    options: &CodegenOptions<'_>,
+    depth: usize,
+) -> Option<String> {
+    match item {
+        GroupedItem::Folder { children_by_name } => {
+            let mut children: Vec<_> = children_by_name.iter().collect();
+            sort_children(&mut children, options);
+
+            let members = children.into_iter().filter_map(|(name, child)| {
+                build_type(child, options, depth + 1)
+                    .map(|child_type| (property_name(name), child_type))
+            });
+
+            Some(object_type(members, depth))
+        }
+        GroupedItem::InputGroup {
+            inputs_by_dpi_scale,
+        } => {
+            if inputs_by_dpi_scale.len() == 1 {
+                let input = inputs_by_dpi_scale.values().next().unwrap();
+
+                input_type(input, depth)
+            } else {
+                // Variants can have different shapes, like when only some of
+                // them are packed into spritesheets.
+                let mut variant_types: Vec<String> = Vec::new();
+
+                for input in inputs_by_dpi_scale.values() {
+                    if let Some(variant_type) = input_type(input, depth) {
+                        if !variant_types.contains(&variant_type) {
+                            variant_types.push(variant_type);
+                        }
+                    }
+                }
+
+                Some(format!(
+                    "(dpiScale: number) => {}",
+                    variant_types.join(" | ")
+                ))
+            }
+        }
+    }
+}
+
+fn input_type(input: &SyncInput, depth: usize) -> Option<String> {
+    if input.id.is_none() && !input.rejected && input.config.codegen_placeholder_id.is_none() {
+        return None;
+    }
+
+    if input.is_font() {
+        return Some("Font".to_owned());
+    }
+
+    let url_type = match input.config.codegen_url_format {
+        UrlFormat::Id => "number",
+        _ => "string",
+    };
+
+    if !input.is_image() || (input.slice.is_none() && !input.config.codegen_include_size) {
+        return Some(url_type.to_owned());
+    }
+
+    let mut members = vec![("Image".to_owned(), url_type.to_owned())];
+
+    if input.slice.is_some() {
+        match input.config.codegen_slice_format {
+            SliceFormat::Vector2 => {
+                members.push(("ImageRectOffset".to_owned(), "Vector2".to_owned()));
+                members.push(("ImageRectSize".to_owned(), "Vector2".to_owned()));
+            }
+            SliceFormat::Udim2 => {
+                members.push(("ImageRectOffset".to_owned(), "UDim2".to_owned()));
+                members.push(("ImageRectSize".to_owned(), "UDim2".to_owned()));
+            }
+            SliceFormat::Rect => {
+                members.push(("ImageRect".to_owned(), "Rect".to_owned()));
+            }
+        }
+    }
+
+    if input.config.codegen_include_size && native_size(input).is_some() {
+        members.push(("Size".to_owned(), "UDim2".to_owned()));
+    }
+
+    Some(object_type(members, depth))
+}
+
+fn object_type<I: IntoIterator<Item = (String, String)>>(members: I, depth: usize) -> String {
+    let indentation = "\t".repeat(depth);
+    let mut output = String::new();
+
+    writeln!(output, "{{").unwrap();
+
+    for (name, member_type) in members {
+        writeln!(output, "{}{}: {};", indentation, name, member_type).unwrap();
+    }
+
+    write!(output, "{}}}", "\t".repeat(depth - 1)).unwrap();
+
+    output
+}
+
+/// Formats a key as a TypeScript property name, quoting it if it isn't a valid
+/// identifier.
+fn property_name(name: &str) -> String {
+    let mut chars = name.chars();
+
+    let is_identifier = match chars.next() {
+        Some(first) => {
+            (first.is_ascii_alphabetic() || first == '_' || first == '$')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        }
+        None => false,
+    };
+
+    if is_identifier {
+        name.to_owned()
+    } else {
+        serde_json::to_string(name).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn placeholders_stand_in_for_missing_ids() {
+        let mut input = SyncInput::new("a.png", "codegen-placeholder-id = 99");
+        assert_eq!(input_type(&input, 1).as_deref(), Some("string"));
+
+        input.rejected = true;
+        assert_eq!(input_type(&input, 1).as_deref(), Some("string"));
+
+        input.config.codegen_url_format = UrlFormat::Id;
+        assert_eq!(input_type(&input, 1).as_deref(), Some("number"));
+    }
+
+    #[test]
+    fn rejected_inputs_without_placeholders_are_declared() {
+        let mut input = SyncInput::new("a.png", "");
+        assert_eq!(input_type(&input, 1), None);
+
+        input.rejected = true;
+        assert_eq!(input_type(&input, 1).as_deref(), Some("string"));
+    }
+}
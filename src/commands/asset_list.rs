@@ -4,21 +4,33 @@ use std::io::{BufWriter, Write};
 
 use fs_err as fs;
 
-use crate::data::Manifest;
+use crate::commands::read_project_configs;
+use crate::data::{select_projects, AssetPackage, Manifest};
 use crate::options::{AssetListOptions, GlobalOptions};
 
-pub fn asset_list(_global: GlobalOptions, options: AssetListOptions) -> anyhow::Result<()> {
+pub fn asset_list(global: GlobalOptions, options: AssetListOptions) -> anyhow::Result<()> {
     let project_path = match options.project_path {
         Some(path) => path,
         None => env::current_dir()?,
     };
 
-    let manifest = Manifest::read_from_folder(&project_path)?;
-
     let mut asset_list = BTreeSet::new();
-    for input_manifest in manifest.inputs.values() {
-        if let Some(id) = input_manifest.id {
-            asset_list.insert(id);
+
+    for project_path in select_projects(&project_path, options.project.as_deref())? {
+        let manifest = Manifest::read_from_folder(&project_path)?;
+
+        for input_manifest in manifest.inputs.values() {
+            if let Some(id) = input_manifest.id {
+                asset_list.insert(id);
+            }
+        }
+
+        // Assets from packages are used by the project too, even though the
+        // project didn't upload them.
+        for config in read_project_configs(&global, &project_path)? {
+            for package_path in &config.packages {
+                asset_list.extend(AssetPackage::read_from_folder(package_path)?.asset_ids());
+            }
         }
     }
 
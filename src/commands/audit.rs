@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+use std::env;
+
+use crate::data::Manifest;
+use crate::options::{AuditOptions, GlobalOptions};
+use crate::roblox_web_api::{CreatedAsset, RobloxApiClient, RobloxApiError};
+use crate::sync_backend::DEFAULT_DESCRIPTION;
+
+pub fn audit(global: GlobalOptions, options: AuditOptions) -> anyhow::Result<()> {
+    let credentials = global.credentials()?;
+    let mut api_client = RobloxApiClient::new(credentials.auth, &global.client_options())?;
+
+    let project_paths = if options.project_paths.is_empty() {
+        vec![env::current_dir()?]
+    } else {
+        options.project_paths.clone()
+    };
+
+    // Legacy uploads create a decal backed by an image, and either of them may
+    // show up in the account's creations.
+    let mut referenced = HashSet::new();
+    for project_path in &project_paths {
+        let manifest = Manifest::read_from_folder(project_path)?;
+
+        for input_manifest in manifest.inputs.values() {
+            let targets = input_manifest.targets.values();
+
+            for synced in std::iter::once(input_manifest).chain(targets) {
+                referenced.extend(synced.id);
+                referenced.extend(synced.decal_id);
+            }
+        }
+    }
+
+    log::info!(
+        "Found {} asset ID(s) referenced by {} project(s)",
+        referenced.len(),
+        project_paths.len()
+    );
+
+    let unreferenced = unreferenced_assets(&mut api_client, &options, &referenced)?;
+
+    for (asset_type, asset) in &unreferenced {
+        println!("{}\t{}\t{}", asset.asset_id, asset_type, asset.name);
+    }
+
+    log::info!(
+        "{} asset(s) are no longer referenced by any project",
+        unreferenced.len()
+    );
+
+    Ok(())
+}
+
+/// Lists the assets of the types in `options` that aren't in `referenced`,
+/// going through every page of the account's creations.
+fn unreferenced_assets<'a>(
+    api_client: &mut RobloxApiClient,
+    options: &'a AuditOptions,
+    referenced: &HashSet<u64>,
+) -> Result<Vec<(&'a str, CreatedAsset)>, RobloxApiError> {
+    let mut unreferenced = Vec::new();
+
+    for asset_type in &options.asset_types {
+        let mut cursor = None;
+
+        loop {
+            let page =
+                api_client.list_created_assets(asset_type, options.group_id, cursor.as_deref())?;
+
+            for asset in page.data {
+                if !referenced.contains(&asset.asset_id)
+                    && (options.all || is_tarmac_upload(&asset))
+                {
+                    unreferenced.push((asset_type.as_str(), asset));
+                }
+            }
+
+            cursor = page.next_page_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+    }
+
+    Ok(unreferenced)
+}
+
+/// Whether the given asset looks like it was uploaded by Tarmac, which we can
+/// only tell from the defaults that Tarmac uploads with.
+fn is_tarmac_upload(asset: &CreatedAsset) -> bool {
+    asset.description.as_deref() == Some(DEFAULT_DESCRIPTION) || asset.name == "spritesheet"
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::http_fixtures::replay_client;
+
+    static ASSETS_URL: &str =
+        "https://itemconfiguration.roblox.com/v1/creations/get-assets?assetType=Decal&limit=100";
+
+    fn audit_options(all: bool) -> AuditOptions {
+        AuditOptions {
+            project_paths: Vec::new(),
+            group_id: None,
+            asset_types: vec!["Decal".to_owned()],
+            all,
+        }
+    }
+
+    #[test]
+    fn only_unreferenced_tarmac_uploads_are_listed() {
+        let page = |url: String, body: &str| {
+            serde_json::json!({
+                "method": "GET",
+                "url": url,
+                "status": 200,
+                "headers": [],
+                "body": body,
+            })
+        };
+
+        let first_page = r#"{
+            "data": [
+                { "assetId": 1, "name": "play", "description": "Uploaded by Tarmac." },
+                { "assetId": 2, "name": "close", "description": "Uploaded by Tarmac." }
+            ],
+            "nextPageCursor": "next"
+        }"#;
+        let second_page = r#"{
+            "data": [
+                { "assetId": 3, "name": "spritesheet", "description": "" },
+                { "assetId": 4, "name": "logo", "description": "Made by hand" }
+            ]
+        }"#;
+
+        let exchanges = || {
+            serde_json::json!([
+                page(ASSETS_URL.to_owned(), first_page),
+                page(format!("{}&cursor=next", ASSETS_URL), second_page),
+            ])
+        };
+
+        let referenced: HashSet<u64> = vec![2].into_iter().collect();
+        let ids = |assets: Vec<(&str, CreatedAsset)>| -> Vec<u64> {
+            assets.iter().map(|(_, asset)| asset.asset_id).collect()
+        };
+
+        let options = audit_options(false);
+        let mut client = replay_client("audit", exchanges());
+        let unreferenced = unreferenced_assets(&mut client, &options, &referenced).unwrap();
+        assert_eq!(unreferenced[0].0, "Decal");
+        assert_eq!(ids(unreferenced), vec![1, 3]);
+
+        let options = audit_options(true);
+        let mut client = replay_client("audit-all", exchanges());
+        let unreferenced = unreferenced_assets(&mut client, &options, &referenced).unwrap();
+        assert_eq!(ids(unreferenced), vec![1, 3, 4]);
+    }
+}
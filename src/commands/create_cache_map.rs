@@ -13,7 +13,8 @@ pub fn create_cache_map(
     global: GlobalOptions,
     options: CreateCacheMapOptions,
 ) -> anyhow::Result<()> {
-    let mut api_client = RobloxApiClient::new(global.auth);
+    let credentials = global.credentials()?;
+    let mut api_client = RobloxApiClient::new(credentials.auth, &global.client_options())?;
 
     let project_path = match options.project_path {
         Some(path) => path.clone(),
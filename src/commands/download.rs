@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::path::Path;
+
+use fs_err as fs;
+use thiserror::Error;
+
+use crate::asset_name::AssetName;
+use crate::data::{InputManifest, Manifest};
+use crate::image::Image;
+use crate::options::{DownloadOptions, GlobalOptions};
+use crate::roblox_web_api::RobloxApiClient;
+
+pub fn download(global: GlobalOptions, options: DownloadOptions) -> anyhow::Result<()> {
+    let credentials = global.credentials()?;
+    let mut api_client = RobloxApiClient::new(credentials.auth, &global.client_options())?;
+
+    let project_path = match options.project_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let manifest = Manifest::read_from_folder(&project_path)?;
+
+    // Packed inputs share an asset, so we group inputs by ID to only download
+    // each spritesheet once.
+    let mut selected: BTreeMap<u64, Vec<(&AssetName, &InputManifest)>> = BTreeMap::new();
+    for (name, input_manifest) in &manifest.inputs {
+        let is_selected =
+            options.only.is_empty() || options.only.iter().any(|glob| glob.is_match(name.as_ref()));
+
+        if !is_selected {
+            continue;
+        }
+
+        match input_manifest.id {
+            Some(id) => selected.entry(id).or_default().push((name, input_manifest)),
+            None => log::warn!("Skipping {} as it has not been uploaded", name),
+        }
+    }
+
+    for (id, inputs) in selected {
+        log::info!("Downloading asset {}", id);
+        let contents = api_client.download_asset(id)?;
+
+        write_inputs(&options.output, id, &contents, &inputs)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the inputs found in the downloaded asset with the given ID to the
+/// output folder, cutting packed inputs out of the spritesheet.
+fn write_inputs(
+    output: &Path,
+    id: u64,
+    contents: &[u8],
+    inputs: &[(&AssetName, &InputManifest)],
+) -> anyhow::Result<()> {
+    // Spritesheets are only decoded when we need to cut inputs out of them.
+    let mut spritesheet = None;
+
+    for (name, input_manifest) in inputs {
+        let path = output.join(name.as_ref());
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        match input_manifest.slice {
+            Some(slice) => {
+                if spritesheet.is_none() {
+                    spritesheet = Some(Image::decode_png_as_rgba8(contents)?);
+                }
+                let spritesheet = spritesheet.as_ref().unwrap();
+
+                // The asset might have been replaced by a smaller one since
+                // the manifest was written, or the manifest edited by hand.
+                let (min, max) = (slice.min(), slice.max());
+                let (width, height) = spritesheet.size();
+                if min.0 > max.0 || min.1 > max.1 || max.0 > width || max.1 > height {
+                    return Err(DownloadError::SliceOutOfBounds {
+                        name: (*name).clone(),
+                        id,
+                        min,
+                        max,
+                        size: (width, height),
+                    }
+                    .into());
+                }
+
+                let image = spritesheet.crop(min, slice.size());
+
+                let mut encoded = Vec::new();
+                image.encode_png(&mut encoded)?;
+                fs::write(&path, encoded)?;
+            }
+            None => fs::write(&path, contents)?,
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum DownloadError {
+    #[error(
+        "{name} is at ({}, {}) to ({}, {}) of asset {id} according to the manifest, but the asset is only {}x{}",
+        .min.0,
+        .min.1,
+        .max.0,
+        .max.1,
+        .size.0,
+        .size.1
+    )]
+    SliceOutOfBounds {
+        name: AssetName,
+        id: u64,
+        min: (u32, u32),
+        max: (u32, u32),
+        size: (u32, u32),
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, process};
+
+    use super::*;
+    use crate::data::ImageSlice;
+
+    fn packed(slice: ImageSlice) -> InputManifest {
+        let mut input_manifest = InputManifest::imported(1);
+        input_manifest.slice = Some(slice);
+        input_manifest
+    }
+
+    #[test]
+    fn slices_must_fit_in_the_spritesheet() {
+        let output = env::temp_dir().join(format!("tarmac-download-{}", process::id()));
+
+        let mut spritesheet = Vec::new();
+        Image::new_empty_rgba8((4, 4))
+            .encode_png(&mut spritesheet)
+            .unwrap();
+
+        let name = AssetName::new("icons/a.png");
+        let inside = packed(ImageSlice::new((2, 0), (4, 2)));
+        write_inputs(&output, 1, &spritesheet, &[(&name, &inside)]).unwrap();
+
+        let written = Image::decode_png(fs::File::open(output.join("icons/a.png")).unwrap());
+        assert_eq!(written.unwrap().size(), (2, 2));
+
+        for slice in &[
+            ImageSlice::new((2, 2), (6, 4)),
+            ImageSlice::new((0, 3), (4, 5)),
+            ImageSlice::new((3, 3), (1, 1)),
+        ] {
+            let outside = packed(*slice);
+            let err = write_inputs(&output, 1, &spritesheet, &[(&name, &outside)]).unwrap_err();
+
+            assert!(matches!(
+                err.downcast_ref(),
+                Some(DownloadError::SliceOutOfBounds { id: 1, .. })
+            ));
+        }
+
+        fs::remove_dir_all(&output).unwrap();
+    }
+}
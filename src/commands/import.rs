@@ -0,0 +1,476 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    env, io,
+    path::{Path, PathBuf},
+};
+
+use fs_err as fs;
+use regex::Regex;
+use serde::Serialize;
+use thiserror::Error;
+use walkdir::WalkDir;
+
+use crate::{
+    asset_name::AssetName,
+    data::{InputManifest, Manifest, ManifestError, CONFIG_FILENAMES},
+    glob::Glob,
+    options::{GlobalOptions, ImportFormat, ImportOptions},
+    roblox_web_api::AssetFormat,
+};
+
+use super::import_ids::parse_csv_mapping;
+
+pub fn import(_global: GlobalOptions, options: ImportOptions) -> Result<(), ImportError> {
+    let project_folder = match &options.project_path {
+        Some(path) => path.clone(),
+        None => env::current_dir()?,
+    };
+
+    if let Some(path) = CONFIG_FILENAMES
+        .iter()
+        .map(|name| project_folder.join(name))
+        .find(|path| path.exists())
+    {
+        return Err(ImportError::ProjectExists { path });
+    }
+
+    let project_folder = project_folder.canonicalize()?;
+
+    let found = match options.format {
+        ImportFormat::Lua => find_lua_ids(&options.source, &project_folder)?,
+        ImportFormat::RojoMeta => find_meta_ids(&options.source)?,
+        ImportFormat::Csv => {
+            let contents = fs::read_to_string(&options.source)?;
+            let mapping =
+                parse_csv_mapping(&contents).map_err(|reason| ImportError::InvalidSource {
+                    path: options.source.clone(),
+                    reason,
+                })?;
+
+            mapping
+                .into_iter()
+                .map(|(path, id)| (project_folder.join(path), id))
+                .collect()
+        }
+    };
+
+    let mut manifest = Manifest::default();
+
+    for (path, id) in found {
+        // Assets are found by following references in other files, which
+        // can lead anywhere.
+        let path = match path.canonicalize() {
+            Ok(path) if path.starts_with(&project_folder) => path,
+            _ => {
+                log::warn!(
+                    "Skipping {}, which isn't a file in the project",
+                    path.display()
+                );
+                continue;
+            }
+        };
+
+        let name = AssetName::from_paths(&project_folder, &path);
+
+        if let Some(existing) = manifest.inputs.get(&name) {
+            if existing.id != Some(id) {
+                log::warn!(
+                    "{} refers to both ID {} and ID {}. Using ID {}.",
+                    name,
+                    existing.id.unwrap_or_default(),
+                    id,
+                    id
+                );
+            }
+        }
+
+        log::debug!("Importing ID {} for {}", id, name);
+        manifest.inputs.insert(name, InputManifest::imported(id));
+    }
+
+    if manifest.inputs.is_empty() {
+        return Err(ImportError::NothingFound {
+            path: options.source,
+        });
+    }
+
+    let names: BTreeSet<&AssetName> = manifest.inputs.keys().collect();
+    let globs = input_globs(&project_folder, &names)?;
+
+    let config = ImportedConfig {
+        name: project_folder
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "imported".to_owned()),
+        inputs: globs
+            .into_iter()
+            .map(|glob| ImportedInput { glob })
+            .collect(),
+    };
+
+    let config_path = project_folder.join(CONFIG_FILENAMES[0]);
+    fs::write(&config_path, toml::to_string(&config)?)?;
+    manifest.write_to_folder(&project_folder)?;
+
+    log::info!(
+        "Imported {} asset(s) into {} input group(s) in {}. The next 'tarmac sync' records the contents of their files without uploading them.",
+        manifest.inputs.len(),
+        config.inputs.len(),
+        config_path.display()
+    );
+
+    Ok(())
+}
+
+/// The smallest config that uploads the imported assets, which can be
+/// extended by hand afterwards.
+#[derive(Debug, Serialize)]
+struct ImportedConfig {
+    name: String,
+    inputs: Vec<ImportedInput>,
+}
+
+#[derive(Debug, Serialize)]
+struct ImportedInput {
+    glob: String,
+}
+
+/// The globs of the input groups that the imported assets belong to. Files in
+/// the same folder with the same extension share a glob like
+/// `icons/*.png` when every file it matches was imported. Otherwise, each
+/// imported file gets a glob of its own, so that the next sync doesn't upload
+/// files that weren't imported.
+fn input_globs(
+    project_folder: &Path,
+    names: &BTreeSet<&AssetName>,
+) -> Result<Vec<String>, ImportError> {
+    let mut groups: BTreeMap<(usize, &str, Option<&str>), Vec<&AssetName>> = BTreeMap::new();
+
+    for name in names {
+        let name_str = name.as_ref();
+        let folder = match name_str.rfind('/') {
+            Some(slash) => &name_str[..=slash],
+            None => "",
+        };
+        let extension = Path::new(name_str)
+            .extension()
+            .and_then(|extension| extension.to_str());
+
+        // Wildcards match across folders, so folders are grouped before the
+        // ones inside of them, whose files might already be matched.
+        let depth = folder.matches('/').count();
+
+        groups
+            .entry((depth, folder, extension))
+            .or_default()
+            .push(name);
+    }
+
+    let mut globs: Vec<Glob> = Vec::new();
+
+    for ((_, folder, extension), group) in groups {
+        let unmatched: Vec<&AssetName> = group
+            .into_iter()
+            .filter(|name| !globs.iter().any(|glob| glob.is_match(name.as_ref())))
+            .collect();
+
+        if unmatched.is_empty() {
+            continue;
+        }
+
+        if let Some(extension) = extension {
+            let glob = escaped_glob(&format!(
+                "{}*.{}",
+                globset::escape(folder),
+                globset::escape(extension)
+            ));
+
+            if only_matches_imported(project_folder, folder, &glob, names)? {
+                globs.push(glob);
+                continue;
+            }
+        }
+
+        for name in unmatched {
+            globs.push(escaped_glob(&globset::escape(name.as_ref())));
+        }
+    }
+
+    Ok(globs.iter().map(ToString::to_string).collect())
+}
+
+/// Makes a glob from a pattern whose parts that come from file names have
+/// been escaped with `globset::escape`, so that names containing characters
+/// like `[` or `*` only match themselves.
+fn escaped_glob(glob: &str) -> Glob {
+    Glob::new(glob).expect("escaped globs are always valid")
+}
+
+/// Whether every file in the given folder of the project that the glob
+/// matches is one of the imported files.
+fn only_matches_imported(
+    project_folder: &Path,
+    folder: &str,
+    glob: &Glob,
+    names: &BTreeSet<&AssetName>,
+) -> Result<bool, ImportError> {
+    for entry in WalkDir::new(project_folder.join(folder)) {
+        let entry = entry.map_err(io::Error::from)?;
+
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let name = AssetName::from_paths(project_folder, entry.path());
+        if glob.is_match(name.as_ref()) && !names.contains(&name) {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Finds constants like `Star = "rbxassetid://123"` in Lua modules, and
+/// matches them to files in the project with the same name, ignoring case.
+fn find_lua_ids(source: &Path, project_folder: &Path) -> Result<Vec<(PathBuf, u64)>, ImportError> {
+    let mut files_by_stem: HashMap<String, Vec<PathBuf>> = HashMap::new();
+
+    for entry in WalkDir::new(project_folder) {
+        let entry = entry.map_err(io::Error::from)?;
+        let path = entry.path();
+
+        if entry.file_type().is_file() && AssetFormat::from_path(path).is_some() {
+            let stem = path.file_stem().unwrap().to_string_lossy().to_lowercase();
+            files_by_stem
+                .entry(stem)
+                .or_default()
+                .push(entry.into_path());
+        }
+    }
+
+    let mut found = Vec::new();
+
+    for entry in WalkDir::new(source) {
+        let entry = entry.map_err(io::Error::from)?;
+        let is_lua = matches!(
+            entry.path().extension().and_then(|ext| ext.to_str()),
+            Some("lua") | Some("luau")
+        );
+
+        if !entry.file_type().is_file() || !is_lua {
+            continue;
+        }
+
+        let contents = fs::read_to_string(entry.path())?;
+
+        for (key, id) in parse_lua_constants(&contents) {
+            match files_by_stem.get(&key.to_lowercase()).map(Vec::as_slice) {
+                Some([path]) => found.push((path.clone(), id)),
+                Some(paths) => log::warn!(
+                    "Skipping {} in {}, which could be any of {} files named {}",
+                    key,
+                    entry.path().display(),
+                    paths.len(),
+                    key
+                ),
+                None => log::warn!(
+                    "Skipping {} in {}, which doesn't match any file in the project",
+                    key,
+                    entry.path().display()
+                ),
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+fn parse_lua_constants(contents: &str) -> Vec<(String, u64)> {
+    lazy_static::lazy_static! {
+        static ref CONSTANT_PATTERN: Regex = Regex::new(
+            r#"(?:\[\s*["']([^"']+)["']\s*\]|([A-Za-z_]\w*))\s*=\s*["']rbxassetid://(\d+)["']"#
+        )
+        .unwrap();
+    }
+
+    CONSTANT_PATTERN
+        .captures_iter(contents)
+        .filter_map(|captures| {
+            let key = captures.get(1).or_else(|| captures.get(2))?.as_str();
+            let id = captures.get(3)?.as_str().parse().ok()?;
+
+            Some((key.to_owned(), id))
+        })
+        .collect()
+}
+
+/// Finds Rojo `.meta.json` files with an `rbxassetid://` property, like
+/// `star.meta.json` setting `Image`, and matches them to the asset next to
+/// them with the same name, like `star.png`.
+fn find_meta_ids(source: &Path) -> Result<Vec<(PathBuf, u64)>, ImportError> {
+    let mut found = Vec::new();
+
+    for entry in WalkDir::new(source) {
+        let entry = entry.map_err(io::Error::from)?;
+        let meta_path = entry.path();
+
+        let stem = match meta_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".meta.json"))
+        {
+            Some(stem) if stem != "init" && entry.file_type().is_file() => stem,
+            _ => continue,
+        };
+
+        let contents = fs::read(meta_path)?;
+        let meta: serde_json::Value =
+            serde_json::from_slice(&contents).map_err(|err| ImportError::InvalidSource {
+                path: meta_path.to_owned(),
+                reason: err.to_string(),
+            })?;
+
+        let id = match meta.get("properties").and_then(find_asset_id) {
+            Some(id) => id,
+            None => continue,
+        };
+
+        let folder = meta_path.parent().unwrap();
+        let asset = fs::read_dir(folder)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .find(|path| {
+                path.file_stem() == Some(stem.as_ref()) && AssetFormat::from_path(path).is_some()
+            });
+
+        match asset {
+            Some(path) => found.push((path, id)),
+            None => log::warn!(
+                "Skipping {}, which doesn't have an asset named {} next to it",
+                meta_path.display(),
+                stem
+            ),
+        }
+    }
+
+    Ok(found)
+}
+
+/// Finds the first `rbxassetid://` URL in a JSON value, which can be nested in
+/// Rojo's explicit property syntax.
+fn find_asset_id(value: &serde_json::Value) -> Option<u64> {
+    match value {
+        serde_json::Value::String(url) => url.strip_prefix("rbxassetid://")?.parse().ok(),
+        serde_json::Value::Object(map) => map.values().find_map(find_asset_id),
+        serde_json::Value::Array(values) => values.iter().find_map(find_asset_id),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("{} already exists. Use 'tarmac import-ids' to add existing uploads to a Tarmac project.", .path.display())]
+    ProjectExists { path: PathBuf },
+
+    #[error("{} can't be imported: {reason}", .path.display())]
+    InvalidSource { path: PathBuf, reason: String },
+
+    #[error("No assets with IDs were found in {}", .path.display())]
+    NothingFound { path: PathBuf },
+
+    #[error(transparent)]
+    SerializeToml {
+        #[from]
+        source: toml::ser::Error,
+    },
+
+    #[error(transparent)]
+    Manifest {
+        #[from]
+        source: ManifestError,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_lua_and_meta() {
+        let lua = r#"
+            local Icons = {
+                Star = "rbxassetid://10",
+                ["close-button"] = 'rbxassetid://20',
+                Missing = "",
+            }
+            Icons.Moon = "rbxassetid://30"
+            return Icons
+        "#;
+
+        assert_eq!(
+            parse_lua_constants(lua),
+            vec![
+                ("Star".to_owned(), 10),
+                ("close-button".to_owned(), 20),
+                ("Moon".to_owned(), 30),
+            ]
+        );
+
+        let meta: serde_json::Value = serde_json::from_str(
+            r#"{ "properties": { "Size": [1, 2], "Image": { "Content": "rbxassetid://40" } } }"#,
+        )
+        .unwrap();
+        assert_eq!(find_asset_id(&meta), Some(40));
+    }
+
+    #[test]
+    fn input_globs_only_match_imported_files() {
+        let project_folder = env::temp_dir().join(format!("tarmac-import-{}", std::process::id()));
+
+        let files = [
+            "logo.png",
+            "icons/star.png",
+            "icons/moon.png",
+            "icons/large/sun.png",
+            "sounds/[click]*.ogg",
+            "sounds/unused.ogg",
+            "misc/{a,b}?",
+        ];
+        for file in &files {
+            let path = project_folder.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"").unwrap();
+        }
+
+        let imported: Vec<AssetName> = files
+            .iter()
+            .filter(|file| **file != "sounds/unused.ogg")
+            .map(AssetName::new)
+            .collect();
+        let names: BTreeSet<&AssetName> = imported.iter().collect();
+
+        let globs = input_globs(&project_folder, &names).unwrap();
+        assert_eq!(
+            globs,
+            vec![
+                "*.png".to_owned(),
+                "misc/[{]a,b[}][?]".to_owned(),
+                "sounds/[[]click[]][*].ogg".to_owned(),
+            ]
+        );
+
+        let globs: Vec<Glob> = globs.iter().map(|glob| Glob::new(glob).unwrap()).collect();
+        for file in &files {
+            let matched = globs.iter().any(|glob| glob.is_match(file));
+            assert_eq!(matched, *file != "sounds/unused.ogg", "{}", file);
+        }
+
+        fs::remove_dir_all(&project_folder).unwrap();
+    }
+}
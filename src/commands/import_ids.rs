@@ -0,0 +1,194 @@
+use std::{
+    collections::BTreeMap,
+    env, io,
+    path::{Component, Path, PathBuf},
+};
+
+use fs_err as fs;
+use thiserror::Error;
+
+use crate::{
+    asset_name::AssetName,
+    data::{AssetNameCase, Config, ConfigError, InputManifest, Manifest, ManifestError},
+    options::{GlobalOptions, ImportIdsOptions},
+};
+
+pub fn import_ids(_global: GlobalOptions, options: ImportIdsOptions) -> Result<(), ImportIdsError> {
+    let fuzzy_config_path = match &options.config_path {
+        Some(path) => path.clone(),
+        None => env::current_dir()?,
+    };
+
+    let root_config = Config::read_from_folder_or_file(&fuzzy_config_path)?;
+    let root_folder = root_config.folder();
+
+    let contents = fs::read_to_string(&options.mapping)?;
+    let is_json = options.mapping.extension() == Some("json".as_ref());
+    let mapping = if is_json {
+        parse_json_mapping(&contents)
+    } else {
+        parse_csv_mapping(&contents)
+    }
+    .map_err(|reason| ImportIdsError::InvalidMapping {
+        path: options.mapping.clone(),
+        reason,
+    })?;
+
+    let mut manifest = match Manifest::read_from_folder(root_folder) {
+        Ok(manifest) => manifest,
+        Err(err) if err.is_not_found() => Manifest::default(),
+        other => other?,
+    };
+
+    let mut imported = 0;
+
+    for (key, id) in mapping {
+        let path = root_folder.join(&key);
+        let leaves_folder = Path::new(&key)
+            .components()
+            .any(|component| component == Component::ParentDir);
+
+        if leaves_folder || !path.starts_with(root_folder) {
+            return Err(ImportIdsError::OutsideProject { path });
+        }
+
+        let name = AssetName::from_paths(root_folder, &path);
+        let name = match root_config.asset_name_case {
+            AssetNameCase::Preserve => name,
+            AssetNameCase::Lower => name.to_lowercase(),
+        };
+
+        // Inputs that aren't files in the project would be dropped by the
+        // next sync anyways.
+        if !path.is_file() {
+            log::warn!("Skipping {}, which isn't a file in the project", name);
+            continue;
+        }
+
+        let existing_id = manifest.inputs.get(&name).and_then(|input| input.id);
+        if let Some(existing_id) = existing_id.filter(|_| !options.overwrite) {
+            log::warn!(
+                "Skipping {}, which already has ID {} in the manifest",
+                name,
+                existing_id
+            );
+            continue;
+        }
+
+        let mut input = InputManifest::imported(id);
+        input.group_id = options.group_id;
+
+        log::debug!("Importing ID {} for {}", id, name);
+        manifest.inputs.insert(name, input);
+        imported += 1;
+    }
+
+    manifest.write_to_folder(root_folder)?;
+
+    log::info!(
+        "Imported {} ID(s). The next 'tarmac sync' records the contents of their files without uploading them.",
+        imported
+    );
+
+    Ok(())
+}
+
+/// Reads a CSV file with an asset's path or name and its ID on each line, like
+/// one exported from a spreadsheet. A header line is skipped, and fields can
+/// be quoted.
+pub(super) fn parse_csv_mapping(contents: &str) -> Result<Vec<(String, u64)>, String> {
+    let mut mapping = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        // Paths can contain commas, but IDs can't.
+        let (key, id) = match line.rfind(',') {
+            Some(comma) => (unquote(&line[..comma]), unquote(&line[comma + 1..])),
+            None => return Err(format!("line {} doesn't have a path and an ID", index + 1)),
+        };
+
+        match id.parse() {
+            Ok(id) => mapping.push((key.to_owned(), id)),
+            Err(_) if index == 0 => continue,
+            Err(_) => return Err(format!("line {} has an invalid ID: {:?}", index + 1, id)),
+        }
+    }
+
+    Ok(mapping)
+}
+
+/// Reads a JSON object whose keys are assets' paths or names and whose values
+/// are their IDs.
+fn parse_json_mapping(contents: &str) -> Result<Vec<(String, u64)>, String> {
+    let mapping: BTreeMap<String, u64> =
+        serde_json::from_str(contents).map_err(|err| err.to_string())?;
+
+    Ok(mapping.into_iter().collect())
+}
+
+fn unquote(field: &str) -> &str {
+    let field = field.trim();
+
+    field
+        .strip_prefix('"')
+        .and_then(|field| field.strip_suffix('"'))
+        .unwrap_or(field)
+}
+
+#[derive(Debug, Error)]
+pub enum ImportIdsError {
+    #[error("{} can't be imported: {reason}", .path.display())]
+    InvalidMapping { path: PathBuf, reason: String },
+
+    #[error("{} isn't in the project's folder, so it can't be imported", .path.display())]
+    OutsideProject { path: PathBuf },
+
+    #[error(transparent)]
+    Config {
+        #[from]
+        source: ConfigError,
+    },
+
+    #[error(transparent)]
+    Manifest {
+        #[from]
+        source: ManifestError,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_mappings() {
+        let csv = "path,id\n\"icons/a, b.png\",10\n\nicons/c.png, 20\n";
+        assert_eq!(
+            parse_csv_mapping(csv).unwrap(),
+            vec![
+                ("icons/a, b.png".to_owned(), 10),
+                ("icons/c.png".to_owned(), 20)
+            ]
+        );
+        assert!(parse_csv_mapping("icons/a.png,10\nicons/b.png,abc").is_err());
+
+        let json = r#"{ "icons/c.png": 20, "icons/a.png": 10 }"#;
+        assert_eq!(
+            parse_json_mapping(json).unwrap(),
+            vec![
+                ("icons/a.png".to_owned(), 10),
+                ("icons/c.png".to_owned(), 20)
+            ]
+        );
+    }
+}
@@ -0,0 +1,456 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, fmt, io,
+    path::{Path, PathBuf},
+};
+
+use fs_err as fs;
+use reqwest::Client;
+use thiserror::Error;
+use walkdir::{DirEntry, WalkDir};
+
+use crate::{
+    data::{
+        is_sidecar_path, select_projects, Config, ConfigError, InputConfig, InputDefaults,
+        SymlinkPolicy, WorkspaceError, CONFIG_FILENAMES,
+    },
+    glob::Glob,
+    ignore_file::{IgnoreError, IgnoreRules},
+    options::{GlobalOptions, LintOptions},
+    remote_include::{RemoteIncludeError, REMOTE_INCLUDES_FOLDER},
+    roblox_web_api::RobloxApiError,
+};
+
+pub fn lint(global: GlobalOptions, options: LintOptions) -> Result<(), LintError> {
+    let fuzzy_config_path = match options.config_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let mut linter = Linter {
+        client: global.client_options().build_client()?,
+        symlinks: SymlinkPolicy::default(),
+        respect_gitignore: false,
+        reached: HashMap::new(),
+        all_reached: HashSet::new(),
+        warnings: Vec::new(),
+    };
+
+    // The projects of a workspace are linted separately, except that configs
+    // reached from any of them aren't unreachable.
+    let mut roots = Vec::new();
+
+    for project_path in select_projects(&fuzzy_config_path, options.project.as_deref())? {
+        let root_config = Config::read_from_folder_or_file(&project_path)?;
+        roots.push((
+            root_config.folder().to_owned(),
+            root_config.symlinks,
+            root_config.respect_gitignore,
+        ));
+
+        linter.symlinks = root_config.symlinks;
+        linter.respect_gitignore = root_config.respect_gitignore;
+        linter.reached.clear();
+        linter.visit_config(root_config, None, &mut Vec::new())?;
+    }
+
+    for (root_folder, symlinks, respect_gitignore) in &roots {
+        linter.symlinks = *symlinks;
+        linter.respect_gitignore = *respect_gitignore;
+        linter.find_unreachable_configs(root_folder)?;
+    }
+
+    for warning in &linter.warnings {
+        log::warn!("{}", warning);
+    }
+
+    if linter.warnings.is_empty() {
+        log::info!(
+            "No problems found in {} config(s)",
+            linter.all_reached.len()
+        );
+        Ok(())
+    } else {
+        Err(LintError::HadWarnings {
+            count: linter.warnings.len(),
+        })
+    }
+}
+
+/// Something about a project's configs that's probably a mistake.
+#[derive(Debug, PartialEq)]
+enum LintWarning {
+    EmptyGlob {
+        config: PathBuf,
+        glob: Glob,
+    },
+    PackableWithoutCodegen {
+        config: PathBuf,
+        glob: Glob,
+    },
+    MissingInclude {
+        config: PathBuf,
+        include: PathBuf,
+    },
+    IncludeCycle {
+        config: PathBuf,
+        included: PathBuf,
+    },
+    DuplicateInclude {
+        config: PathBuf,
+        included: PathBuf,
+        first_included_by: PathBuf,
+    },
+    UnreachableConfig {
+        path: PathBuf,
+    },
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintWarning::EmptyGlob { config, glob } => write!(
+                formatter,
+                "Input group '{}' in {} doesn't match any files. Check the glob for typos, or remove the input group.",
+                glob,
+                config.display()
+            ),
+            LintWarning::PackableWithoutCodegen { config, glob } => write!(
+                formatter,
+                "Input group '{}' in {} is packable but doesn't generate code. Packed images can only be shown with the slices in generated code, so set codegen = true or packable = false.",
+                glob,
+                config.display()
+            ),
+            LintWarning::MissingInclude { config, include } => write!(
+                formatter,
+                "{} includes {}, which doesn't exist. 'tarmac sync' will fail until it's created or removed from includes.",
+                config.display(),
+                include.display()
+            ),
+            LintWarning::IncludeCycle { config, included } => write!(
+                formatter,
+                "{} includes {}, which is one of the configs that includes it. Remove one of the includes to break the cycle.",
+                config.display(),
+                included.display()
+            ),
+            LintWarning::DuplicateInclude {
+                config,
+                included,
+                first_included_by,
+            } => write!(
+                formatter,
+                "{} includes {}, which is already included by {}. It's only used once, with the input defaults of the first config that included it.",
+                config.display(),
+                included.display(),
+                first_included_by.display()
+            ),
+            LintWarning::UnreachableConfig { path } => write!(
+                formatter,
+                "{} isn't included by any config in the project, so it's never used. Add it to the includes of another config, or list it in a .tarmacignore file.",
+                path.display()
+            ),
+        }
+    }
+}
+
+struct Linter {
+    /// The client that remote includes are fetched with.
+    client: Client,
+
+    symlinks: SymlinkPolicy,
+    respect_gitignore: bool,
+
+    /// Every config reached from the current root config by its canonical
+    /// path, along with the config that first included it.
+    reached: HashMap<PathBuf, Option<PathBuf>>,
+
+    /// Every config reached from any root config.
+    all_reached: HashSet<PathBuf>,
+
+    warnings: Vec<LintWarning>,
+}
+
+impl Linter {
+    /// Lints a config and every config that it includes. `chain` holds the
+    /// canonical paths of the configs that included this one, in order.
+    fn visit_config(
+        &mut self,
+        config: Config,
+        included_by: Option<PathBuf>,
+        chain: &mut Vec<PathBuf>,
+    ) -> Result<(), LintError> {
+        let canonical = config.file_path.canonicalize()?;
+        self.reached.insert(canonical.clone(), included_by);
+        self.all_reached.insert(canonical.clone());
+        chain.push(canonical);
+
+        for input_config in &config.inputs {
+            self.warnings.extend(lint_input(&config, input_config));
+
+            if !self.has_matches(&config, input_config)? {
+                self.warnings.push(LintWarning::EmptyGlob {
+                    config: config.file_path.clone(),
+                    glob: input_config.glob.clone(),
+                });
+            }
+        }
+
+        for include in config.resolve_includes(&self.client)? {
+            let include = include.path;
+
+            if !include.exists() {
+                self.warnings.push(LintWarning::MissingInclude {
+                    config: config.file_path.clone(),
+                    include,
+                });
+                continue;
+            }
+
+            let mut included = Vec::new();
+            self.find_configs(&include, &config.defaults, &mut included)?;
+
+            for included in included {
+                let included_canonical = included.file_path.canonicalize()?;
+
+                if chain.contains(&included_canonical) {
+                    self.warnings.push(LintWarning::IncludeCycle {
+                        config: config.file_path.clone(),
+                        included: included.file_path,
+                    });
+                } else if let Some(first) = self.reached.get(&included_canonical) {
+                    self.warnings.push(LintWarning::DuplicateInclude {
+                        config: config.file_path.clone(),
+                        included: included.file_path,
+                        first_included_by: first.clone().unwrap_or_default(),
+                    });
+                } else {
+                    self.visit_config(included, Some(config.file_path.clone()), chain)?;
+                }
+            }
+        }
+
+        chain.pop();
+        Ok(())
+    }
+
+    /// Finds the configs that an include refers to, the same way that
+    /// 'tarmac sync' does: folders without a config are searched for more
+    /// folders that have one.
+    fn find_configs(
+        &self,
+        path: &Path,
+        inherited: &InputDefaults,
+        found: &mut Vec<Config>,
+    ) -> Result<(), LintError> {
+        let mut to_search = vec![path.to_owned()];
+        let mut searched = HashSet::new();
+
+        while let Some(search_path) = to_search.pop() {
+            if !searched.insert(search_path.canonicalize()?) {
+                continue;
+            }
+
+            if fs::metadata(&search_path)?.is_file() {
+                found.push(Config::read_from_file_inheriting(&search_path, inherited)?);
+                continue;
+            }
+
+            match Config::read_from_folder_inheriting(&search_path, inherited) {
+                Ok(config) => found.push(config),
+                Err(err) if err.is_not_found() => {
+                    for entry in fs::read_dir(&search_path)? {
+                        let entry = entry?;
+
+                        if self.symlinks == SymlinkPolicy::Ignore && entry.file_type()?.is_symlink()
+                        {
+                            continue;
+                        }
+
+                        if entry.file_name() == REMOTE_INCLUDES_FOLDER {
+                            continue;
+                        }
+
+                        let entry_path = entry.path();
+                        if entry_path.is_dir() {
+                            to_search.push(entry_path);
+                        }
+                    }
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether any file in the project belongs to the given input group.
+    fn has_matches(&self, config: &Config, input_config: &InputConfig) -> Result<bool, LintError> {
+        let config_path = config.folder();
+        let base_path = config_path.join(input_config.glob.get_prefix());
+        let mut ignore_rules = self.ignore_rules(config_path);
+
+        let walker = WalkDir::new(&base_path)
+            .follow_links(self.symlinks == SymlinkPolicy::Follow)
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !is_remote_includes(entry));
+
+        // Parts of the project that can't be searched are reported by 'tarmac
+        // sync', so they're not a problem for linting.
+        for entry in walker.filter_map(Result::ok) {
+            if !entry.file_type().is_file() || is_sidecar_path(entry.path()) {
+                continue;
+            }
+
+            let match_path = entry.path().strip_prefix(config_path).unwrap();
+            if input_config.matches(match_path) && !ignore_rules.is_ignored(entry.path())? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn ignore_rules(&self, root: &Path) -> IgnoreRules {
+        let rules = IgnoreRules::new(root);
+
+        if self.respect_gitignore {
+            rules.with_gitignore()
+        } else {
+            rules
+        }
+    }
+
+    /// Looks for config files below the root config's folder that no config
+    /// includes.
+    fn find_unreachable_configs(&mut self, root_folder: &Path) -> Result<(), LintError> {
+        let mut ignore_rules = self.ignore_rules(root_folder);
+
+        // Remote includes often contain configs that aren't included, which
+        // aren't part of this project anyways.
+        let walker = WalkDir::new(root_folder)
+            .follow_links(self.symlinks == SymlinkPolicy::Follow)
+            .into_iter()
+            .filter_entry(|entry| !is_remote_includes(entry));
+
+        for entry in walker.filter_map(Result::ok) {
+            let is_config = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| CONFIG_FILENAMES.contains(&name));
+
+            if !is_config || !entry.file_type().is_file() {
+                continue;
+            }
+
+            let canonical = entry.path().canonicalize()?;
+            if self.all_reached.contains(&canonical) || ignore_rules.is_ignored(entry.path())? {
+                continue;
+            }
+
+            self.warnings.push(LintWarning::UnreachableConfig {
+                path: entry.into_path(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+fn is_remote_includes(entry: &DirEntry) -> bool {
+    entry.file_name() == REMOTE_INCLUDES_FOLDER
+}
+
+/// Checks the settings of an input group that don't depend on the files in
+/// the project.
+fn lint_input(config: &Config, input_config: &InputConfig) -> Option<LintWarning> {
+    if input_config.packable && !input_config.codegen {
+        return Some(LintWarning::PackableWithoutCodegen {
+            config: config.file_path.clone(),
+            glob: input_config.glob.clone(),
+        });
+    }
+
+    None
+}
+
+#[derive(Debug, Error)]
+pub enum LintError {
+    #[error("Found {count} problem(s) in the project's configs")]
+    HadWarnings { count: usize },
+
+    #[error(transparent)]
+    Config {
+        #[from]
+        source: ConfigError,
+    },
+
+    #[error(transparent)]
+    Ignore {
+        #[from]
+        source: IgnoreError,
+    },
+
+    #[error(transparent)]
+    RemoteInclude {
+        #[from]
+        source: RemoteIncludeError,
+    },
+
+    #[error(transparent)]
+    Workspace {
+        #[from]
+        source: WorkspaceError,
+    },
+
+    #[error(transparent)]
+    RobloxApi {
+        #[from]
+        source: RobloxApiError,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn packable_without_codegen() {
+        let config = Config::parse(
+            br#"
+            name = "game"
+
+            [[inputs]]
+            glob = "icons/*.png"
+            packable = true
+
+            [[inputs]]
+            glob = "ui/*.png"
+            packable = true
+            codegen = true
+            "#,
+            Path::new("tarmac.toml"),
+            &InputDefaults::default(),
+        )
+        .unwrap();
+
+        let warnings: Vec<_> = config
+            .inputs
+            .iter()
+            .filter_map(|input| lint_input(&config, input))
+            .collect();
+
+        assert_eq!(
+            warnings,
+            vec![LintWarning::PackableWithoutCodegen {
+                config: PathBuf::new(),
+                glob: config.inputs[0].glob.clone(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::{self, BufRead, Write};
+
+use thiserror::Error;
+
+use crate::{
+    data::{
+        remove_credentials, store_credential, CredentialKind, CredentialLocation, UserConfigError,
+    },
+    options::{GlobalOptions, LoginOptions},
+    roblox_web_api::{RobloxApiClient, RobloxApiError},
+};
+
+pub fn login(global: GlobalOptions, options: LoginOptions) -> Result<(), LoginError> {
+    let prompt = if options.open_cloud {
+        "Paste your Open Cloud API key: "
+    } else {
+        "Paste your .ROBLOSECURITY cookie: "
+    };
+
+    let credential = read_credential(prompt)?;
+    let profile = global.profile.as_deref();
+
+    // We validate credentials before storing them so that mistakes show up
+    // now instead of during the next sync.
+    if options.open_cloud {
+        let mut client = RobloxApiClient::new(None, &global.client_options())?;
+        let info = client
+            .introspect_api_key(&credential)
+            .map_err(|source| LoginError::Invalid { source })?;
+
+        if !info.enabled || info.expired {
+            return Err(LoginError::UnusableApiKey { name: info.name });
+        }
+
+        let location = store_credential(profile, CredentialKind::ApiKey, credential)?;
+        eprintln!(
+            "Stored Open Cloud API key {:?} {}",
+            info.name,
+            describe_location(location)
+        );
+    } else {
+        let mut client = RobloxApiClient::new(Some(credential.clone()), &global.client_options())?;
+        let user = client
+            .get_authenticated_user()
+            .map_err(|source| LoginError::Invalid { source })?;
+
+        let location = store_credential(profile, CredentialKind::Auth, credential)?;
+        eprintln!(
+            "Logged in as {} ({}), stored {}",
+            user.name,
+            user.id,
+            describe_location(location)
+        );
+    }
+
+    Ok(())
+}
+
+pub fn logout(global: GlobalOptions) -> Result<(), LoginError> {
+    if remove_credentials(global.profile.as_deref())? {
+        eprintln!("Removed stored credentials");
+    } else {
+        eprintln!("No credentials were stored");
+    }
+
+    Ok(())
+}
+
+fn describe_location(location: CredentialLocation) -> &'static str {
+    match location {
+        CredentialLocation::Keyring => "in the system keychain",
+        CredentialLocation::File => "in credentials.toml",
+    }
+}
+
+/// Reads a credential from stdin, prompting for it if stdin is a terminal.
+/// Reading from stdin keeps credentials out of shell history and process
+/// lists, unlike passing them as arguments.
+fn read_credential(prompt: &str) -> Result<String, LoginError> {
+    if atty::is(atty::Stream::Stdin) {
+        eprint!("{}", prompt);
+        io::stderr().flush()?;
+    }
+
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+
+    let credential = line.trim();
+    if credential.is_empty() {
+        return Err(LoginError::Empty);
+    }
+
+    Ok(credential.to_owned())
+}
+
+#[derive(Debug, Error)]
+pub enum LoginError {
+    #[error("No credential was given")]
+    Empty,
+
+    #[error("Roblox didn't accept the given credential")]
+    Invalid { source: RobloxApiError },
+
+    #[error("API key {name:?} is disabled or expired")]
+    UnusableApiKey { name: String },
+
+    #[error(transparent)]
+    RobloxApi {
+        #[from]
+        source: RobloxApiError,
+    },
+
+    #[error(transparent)]
+    UserConfig {
+        #[from]
+        source: UserConfigError,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
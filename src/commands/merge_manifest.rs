@@ -0,0 +1,47 @@
+use thiserror::Error;
+
+use crate::{
+    data::{Manifest, ManifestError},
+    options::{GlobalOptions, MergeManifestOptions},
+};
+
+pub fn merge_manifest(
+    _global: GlobalOptions,
+    options: MergeManifestOptions,
+) -> Result<(), MergeManifestError> {
+    let base = Manifest::read_from_file(&options.base)?;
+    let ours = Manifest::read_from_file(&options.ours)?;
+    let theirs = Manifest::read_from_file(&options.theirs)?;
+
+    let (merged, conflicts) = Manifest::merge(&base, &ours, &theirs);
+
+    // Git expects the result of a merge driver to replace our side.
+    merged.write_to_file(&options.ours)?;
+
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+
+    for name in &conflicts {
+        log::error!(
+            "{} was uploaded with different contents on each side of the merge",
+            name
+        );
+    }
+
+    Err(MergeManifestError::Conflicts {
+        count: conflicts.len(),
+    })
+}
+
+#[derive(Debug, Error)]
+pub enum MergeManifestError {
+    #[error("{count} input(s) conflict. They were kept without a hash, so the next 'tarmac sync' uploads them again once the conflicting files are resolved.")]
+    Conflicts { count: usize },
+
+    #[error(transparent)]
+    Manifest {
+        #[from]
+        source: ManifestError,
+    },
+}
@@ -1,9 +1,31 @@
 mod asset_list;
+mod audit;
 mod create_cache_map;
+mod download;
+mod import;
+mod import_ids;
+mod lint;
+mod login;
+mod merge_manifest;
+mod package;
+mod preview_model;
+mod report;
+mod schema;
 mod sync;
 mod upload_image;
 
 pub use asset_list::*;
+pub use audit::*;
 pub use create_cache_map::*;
+pub use download::*;
+pub use import::*;
+pub use import_ids::*;
+pub use lint::*;
+pub use login::*;
+pub use merge_manifest::*;
+pub use package::*;
+pub use preview_model::*;
+pub use report::*;
+pub use schema::*;
 pub use sync::*;
 pub use upload_image::*;
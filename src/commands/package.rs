@@ -0,0 +1,127 @@
+use std::{collections::BTreeSet, env, io, path::PathBuf};
+
+use fs_err as fs;
+use thiserror::Error;
+
+use crate::{
+    commands::{read_project_configs, SyncError},
+    data::{AssetPackage, Manifest, ManifestError, PackageError},
+    options::{GlobalOptions, PackageOptions},
+};
+
+pub fn package(global: GlobalOptions, options: PackageOptions) -> Result<(), PackageCommandError> {
+    let fuzzy_config_path = match options.config_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let configs = read_project_configs(&global, &fuzzy_config_path)?;
+    let root_config = &configs[0];
+    let root_folder = root_config.folder();
+
+    let mut manifest = Manifest::read_from_folder(root_folder)?;
+
+    // A project profile can keep the IDs for one destination under their own
+    // manifest key, and those are the IDs that belong in the package.
+    let profile = global
+        .profile
+        .as_ref()
+        .and_then(|name| root_config.profiles.get(name));
+    if let Some(key) = profile.and_then(|profile| profile.manifest_key.as_ref()) {
+        manifest = manifest.for_key(key);
+    }
+
+    let (mut package, unsynced) = AssetPackage::from_manifest(root_config.name.clone(), &manifest);
+
+    if !unsynced.is_empty() {
+        for name in &unsynced {
+            log::error!("Input {} hasn't been uploaded", name);
+        }
+
+        return Err(PackageCommandError::NotSynced {
+            count: unsynced.len(),
+        });
+    }
+
+    let mut modules = BTreeSet::new();
+    for config in &configs {
+        for input in config.inputs.iter().filter(|input| input.codegen) {
+            modules.extend(input.codegen_path.iter().cloned());
+            modules.extend(
+                input
+                    .codegen_outputs
+                    .iter()
+                    .map(|output| output.path.clone()),
+            );
+        }
+    }
+
+    fs::create_dir_all(&options.output)?;
+
+    for module in modules {
+        let relative = module.strip_prefix(root_folder).map_err(|_| {
+            PackageCommandError::ModuleOutsideProject {
+                path: module.clone(),
+            }
+        })?;
+
+        if !module.is_file() {
+            return Err(PackageCommandError::ModuleNotGenerated { path: module });
+        }
+
+        let destination = options.output.join(relative);
+        fs::create_dir_all(destination.parent().unwrap())?;
+        fs::copy(&module, &destination)?;
+
+        package.modules.push(relative.to_owned());
+    }
+
+    package.write_to_folder(&options.output)?;
+
+    log::info!(
+        "Packaged {} asset(s) and {} module(s) into {}",
+        package.assets.len(),
+        package.modules.len(),
+        options.output.display()
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum PackageCommandError {
+    #[error(
+        "{count} input(s) haven't been uploaded. Sync the project to Roblox before packaging it."
+    )]
+    NotSynced { count: usize },
+
+    #[error("Generated module {} isn't in the project's folder, so it can't be packaged", .path.display())]
+    ModuleOutsideProject { path: PathBuf },
+
+    #[error("Generated module {} doesn't exist. Sync the project before packaging it.", .path.display())]
+    ModuleNotGenerated { path: PathBuf },
+
+    #[error(transparent)]
+    Sync {
+        #[from]
+        source: SyncError,
+    },
+
+    #[error(transparent)]
+    Manifest {
+        #[from]
+        source: ManifestError,
+    },
+
+    #[error(transparent)]
+    Package {
+        #[from]
+        source: PackageError,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
@@ -0,0 +1,132 @@
+use std::env;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use fs_err as fs;
+
+use crate::data::Manifest;
+use crate::options::{GlobalOptions, PreviewModelOptions};
+use crate::rbxmx::{self, Instance, Property};
+use crate::roblox_web_api::AssetFormat;
+
+/// The size of each cell in the preview grid, in pixels.
+const CELL_SIZE: (i32, i32) = (128, 148);
+
+/// The height of the label underneath each image, in pixels.
+const LABEL_HEIGHT: i32 = 20;
+
+// Values of Enum.AutomaticSize.Y and Enum.ScaleType.Fit.
+const AUTOMATIC_SIZE_Y: u32 = 2;
+const SCALE_TYPE_FIT: u32 = 3;
+
+pub fn preview_model(_global: GlobalOptions, options: PreviewModelOptions) -> anyhow::Result<()> {
+    let project_path = match options.project_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let manifest = Manifest::read_from_folder(&project_path)?;
+    let gui = Instance::new("ScreenGui", "TarmacPreview").with_child(preview_frame(&manifest));
+
+    let mut file = BufWriter::new(fs::File::create(&options.output)?);
+    rbxmx::write_model(&mut file, &[gui])?;
+    file.flush()?;
+
+    Ok(())
+}
+
+/// Lays out every uploaded image in the manifest in a grid, labelled with its
+/// name and ID. Audio, meshes, fonts, and other assets that aren't images
+/// can't be shown by an ImageLabel, so they're left out.
+fn preview_frame(manifest: &Manifest) -> Instance {
+    let grid = Instance::new("UIGridLayout", "Grid").with_property(
+        "CellSize",
+        Property::UDim2((0.0, CELL_SIZE.0), (0.0, CELL_SIZE.1)),
+    );
+
+    let mut frame = Instance::new("ScrollingFrame", "Assets")
+        .with_property("Size", Property::UDim2((1.0, 0), (1.0, 0)))
+        .with_property("CanvasSize", Property::UDim2((0.0, 0), (0.0, 0)))
+        .with_property("AutomaticCanvasSize", Property::Token(AUTOMATIC_SIZE_Y))
+        .with_child(grid);
+
+    for (name, input_manifest) in &manifest.inputs {
+        let id = match input_manifest.id {
+            Some(id) => id,
+            None => continue,
+        };
+
+        // Inputs in formats Tarmac doesn't know are treated as images, like
+        // when syncing.
+        let is_image =
+            AssetFormat::from_path(Path::new(name.as_ref())).map_or(true, AssetFormat::is_image);
+        if !is_image {
+            continue;
+        }
+
+        let mut image = Instance::new("ImageLabel", name.as_ref())
+            .with_property("Image", Property::Content(format!("rbxassetid://{}", id)))
+            .with_property("ScaleType", Property::Token(SCALE_TYPE_FIT))
+            .with_property("BackgroundTransparency", Property::Float(1.0));
+
+        if let Some(slice) = input_manifest.slice {
+            let (x, y) = slice.min();
+            let (width, height) = slice.size();
+
+            image = image
+                .with_property("ImageRectOffset", Property::Vector2(x as f32, y as f32))
+                .with_property(
+                    "ImageRectSize",
+                    Property::Vector2(width as f32, height as f32),
+                );
+        }
+
+        let label = Instance::new("TextLabel", "Label")
+            .with_property("Text", Property::String(format!("{}\n{}", name, id)))
+            .with_property("TextScaled", Property::Bool(true))
+            .with_property("Size", Property::UDim2((1.0, 0), (0.0, LABEL_HEIGHT)))
+            .with_property("Position", Property::UDim2((0.0, 0), (1.0, -LABEL_HEIGHT)));
+
+        frame = frame.with_child(image.with_child(label));
+    }
+
+    frame
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{asset_name::AssetName, data::InputManifest};
+
+    #[test]
+    fn only_images_are_previewed() {
+        let mut manifest = Manifest::default();
+        for (index, name) in [
+            "icon.png",
+            "photo.jpg",
+            "click.ogg",
+            "sword.fbx",
+            "title.ttf",
+        ]
+        .iter()
+        .enumerate()
+        {
+            manifest.inputs.insert(
+                AssetName::new(name),
+                InputManifest::imported(index as u64 + 1),
+            );
+        }
+
+        let frame = preview_frame(&manifest);
+        let labels: Vec<_> = frame
+            .children
+            .iter()
+            .filter(|child| child.class_name == "ImageLabel")
+            .map(|child| &child.properties[0].1)
+            .collect();
+
+        assert_eq!(labels.len(), 2);
+        assert!(matches!(labels[0], Property::String(name) if name == "icon.png"));
+        assert!(matches!(labels[1], Property::String(name) if name == "photo.jpg"));
+    }
+}
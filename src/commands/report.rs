@@ -0,0 +1,331 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+use fs_err as fs;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::asset_name::AssetName;
+use crate::data::{ImageSlice, Manifest};
+use crate::options::{GlobalOptions, ReportOptions};
+use crate::roblox_web_api::AssetFormat;
+
+const REPORT_STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.5em; text-align: left; vertical-align: middle; }
+img, svg { max-width: 128px; max-height: 128px; background: repeating-conic-gradient(#ddd 0 25%, #fff 0 50%) 0 0 / 16px 16px; }
+svg.spritesheet { max-width: 100%; max-height: none; height: auto; }
+svg.spritesheet rect { fill: none; stroke: #c00; stroke-width: 2; }
+.missing { color: #c00; }
+";
+
+/// Characters that are left as they are in the URLs of copied files.
+const URL_SAFE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+pub fn report(_global: GlobalOptions, options: ReportOptions) -> anyhow::Result<()> {
+    let project_path = match options.project_path {
+        Some(path) => path,
+        None => env::current_dir()?,
+    };
+
+    let manifest = Manifest::read_from_folder(&project_path)?;
+    let html = render_report(&manifest, &project_path, &options.html)?;
+
+    fs::write(options.html.join("index.html"), html)?;
+
+    Ok(())
+}
+
+/// Copies each input's local file into the report folder and describes every
+/// input in an HTML page, along with the layout of each spritesheet.
+fn render_report(manifest: &Manifest, project_path: &Path, html_path: &Path) -> io::Result<String> {
+    // Packed inputs share their asset ID with every other input in the same
+    // spritesheet, so we group them up front to describe each atlas.
+    let mut atlases: BTreeMap<u64, Vec<(&AssetName, ImageSlice)>> = BTreeMap::new();
+    for (name, input_manifest) in &manifest.inputs {
+        if let (Some(id), Some(slice)) = (input_manifest.id, input_manifest.slice) {
+            atlases.entry(id).or_default().push((name, slice));
+        }
+    }
+
+    let images_path = html_path.join("images");
+    fs::create_dir_all(&images_path)?;
+
+    // The URLs of the copies of inputs whose files could be found.
+    let mut copies: BTreeMap<&AssetName, String> = BTreeMap::new();
+    let mut rows = String::new();
+
+    for (name, input_manifest) in &manifest.inputs {
+        let source_path = project_path.join(name.as_ref());
+
+        let image_cell = if source_path.is_file() {
+            let file_name = copied_file_name(name);
+            fs::copy(&source_path, images_path.join(&file_name))?;
+
+            let url = format!("images/{}", utf8_percent_encode(&file_name, URL_SAFE));
+            let cell = if is_image(name) {
+                format!("<img src=\"{}\">", url)
+            } else {
+                format!("<a href=\"{}\">{}</a>", url, escape(&display_name(name)))
+            };
+
+            copies.insert(name, url);
+            cell
+        } else {
+            "<span class=\"missing\">Missing</span>".to_owned()
+        };
+
+        let id_cell = match input_manifest.id {
+            Some(id) => id.to_string(),
+            None if input_manifest.rejected => {
+                "<span class=\"missing\">Rejected by moderation</span>".to_owned()
+            }
+            None => "<span class=\"missing\">Not uploaded</span>".to_owned(),
+        };
+
+        let (slice_cell, atlas_cell) = match (input_manifest.id, input_manifest.slice) {
+            (Some(id), Some(slice)) => {
+                let (x, y) = slice.min();
+                let (width, height) = slice.size();
+
+                (
+                    format!("{}, {} ({}x{})", x, y, width, height),
+                    format!(
+                        "<a href=\"#spritesheet-{}\">{}</a> ({} inputs)",
+                        id,
+                        id,
+                        atlases[&id].len()
+                    ),
+                )
+            }
+            _ => (String::new(), String::new()),
+        };
+
+        writeln!(
+            rows,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>",
+            image_cell,
+            escape(name.as_ref()),
+            id_cell,
+            escape(&input_manifest.hash),
+            slice_cell,
+            atlas_cell,
+        )
+        .unwrap();
+    }
+
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>").unwrap();
+    writeln!(html, "<html>").unwrap();
+    writeln!(html, "<head>").unwrap();
+    writeln!(html, "<meta charset=\"utf-8\">").unwrap();
+    writeln!(html, "<title>Tarmac Report</title>").unwrap();
+    writeln!(html, "<style>{}</style>", REPORT_STYLE).unwrap();
+    writeln!(html, "</head>").unwrap();
+    writeln!(html, "<body>").unwrap();
+    writeln!(html, "<h1>Tarmac Report</h1>").unwrap();
+    writeln!(
+        html,
+        "<p>{} inputs, {} spritesheets</p>",
+        manifest.inputs.len(),
+        atlases.len()
+    )
+    .unwrap();
+    writeln!(html, "<table>").unwrap();
+    writeln!(
+        html,
+        "<tr><th>File</th><th>Name</th><th>Asset ID</th><th>Hash</th><th>Slice</th><th>Spritesheet</th></tr>"
+    )
+    .unwrap();
+    html.push_str(&rows);
+    writeln!(html, "</table>").unwrap();
+
+    for (id, slices) in &atlases {
+        write_spritesheet(&mut html, *id, slices, &copies);
+    }
+
+    writeln!(html, "</body>").unwrap();
+    writeln!(html, "</html>").unwrap();
+
+    Ok(html)
+}
+
+/// Draws the layout of a spritesheet, placing each input's local image in its
+/// slice and outlining every slice.
+fn write_spritesheet(
+    html: &mut String,
+    id: u64,
+    slices: &[(&AssetName, ImageSlice)],
+    copies: &BTreeMap<&AssetName, String>,
+) {
+    // Spritesheets are never downloaded for the report, so their size is the
+    // smallest one that fits every slice.
+    let width = slices
+        .iter()
+        .map(|(_, slice)| slice.max().0)
+        .max()
+        .unwrap_or(0);
+    let height = slices
+        .iter()
+        .map(|(_, slice)| slice.max().1)
+        .max()
+        .unwrap_or(0);
+
+    writeln!(
+        html,
+        "<h2 id=\"spritesheet-{}\">Spritesheet {}</h2>",
+        id, id
+    )
+    .unwrap();
+    writeln!(
+        html,
+        "<svg class=\"spritesheet\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        width, height, width, height
+    )
+    .unwrap();
+
+    for (name, slice) in slices {
+        let (x, y) = slice.min();
+        let (slice_width, slice_height) = slice.size();
+
+        if let Some(url) = copies.get(name) {
+            writeln!(
+                html,
+                "<image href=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" preserveAspectRatio=\"none\"/>",
+                url, x, y, slice_width, slice_height
+            )
+            .unwrap();
+        }
+
+        writeln!(
+            html,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"><title>{}</title></rect>",
+            x,
+            y,
+            slice_width,
+            slice_height,
+            escape(name.as_ref())
+        )
+        .unwrap();
+    }
+
+    writeln!(html, "</svg>").unwrap();
+}
+
+/// The name that an input's file is copied into the report with. Input names
+/// can contain `..` or characters that aren't allowed in file names, so the
+/// copy is named after a hash of the input's name, keeping its extension.
+fn copied_file_name(name: &AssetName) -> String {
+    let hash = blake3::hash(name.as_ref().as_bytes()).to_hex();
+    let extension = Path::new(name.as_ref())
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .filter(|extension| extension.chars().all(|c| c.is_ascii_alphanumeric()));
+
+    match extension {
+        Some(extension) => format!("{}.{}", &hash[..16], extension),
+        None => hash[..16].to_owned(),
+    }
+}
+
+/// Whether an input can be shown with an `<img>` tag. Inputs in formats
+/// Tarmac doesn't know are treated as images, like when syncing.
+fn is_image(name: &AssetName) -> bool {
+    AssetFormat::from_path(Path::new(name.as_ref())).map_or(true, AssetFormat::is_image)
+}
+
+/// The last part of an input's name, which links to its file are labeled with.
+fn display_name(name: &AssetName) -> String {
+    name.as_ref()
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use std::process;
+
+    use super::*;
+    use crate::data::InputManifest;
+
+    #[test]
+    fn report_files_stay_in_the_report_folder() {
+        let root = env::temp_dir().join(format!("tarmac-report-{}", process::id()));
+        let project_path = root.join("project");
+        let html_path = root.join("report");
+        fs::create_dir_all(&project_path).unwrap();
+
+        fs::write(project_path.join("a b#1.png"), b"png").unwrap();
+        fs::write(project_path.join("click.ogg"), b"ogg").unwrap();
+        fs::write(root.join("outside.png"), b"png").unwrap();
+
+        let mut manifest = Manifest::default();
+        let mut packed = InputManifest::imported(10);
+        packed.slice = Some(ImageSlice::new((4, 0), (12, 8)));
+        manifest.inputs.insert(AssetName::new("a b#1.png"), packed);
+        manifest
+            .inputs
+            .insert(AssetName::new("click.ogg"), InputManifest::imported(20));
+        manifest.inputs.insert(
+            AssetName::new("../outside.png"),
+            InputManifest::imported(30),
+        );
+
+        let html = render_report(&manifest, &project_path, &html_path).unwrap();
+
+        // Every copy is named by a hash, so nothing is written next to the
+        // report or outside of it.
+        assert!(!html_path.join("outside.png").exists());
+        assert!(!root.join("images").exists());
+        for entry in fs::read_dir(html_path.join("images")).unwrap() {
+            let file_name = entry.unwrap().file_name();
+            let file_name = file_name.to_str().unwrap();
+            assert!(file_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '.'));
+        }
+
+        let packed_url = format!("images/{}", copied_file_name(&AssetName::new("a b#1.png")));
+        assert!(html.contains(&format!("<img src=\"{}\">", packed_url)));
+        assert!(html.contains(&format!(
+            "<a href=\"images/{}\">click.ogg</a>",
+            copied_file_name(&AssetName::new("click.ogg"))
+        )));
+        assert!(html.contains("<svg class=\"spritesheet\" width=\"12\" height=\"8\""));
+        assert!(html.contains(
+            "<rect x=\"4\" y=\"0\" width=\"8\" height=\"8\"><title>a b#1.png</title></rect>"
+        ));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn copied_file_names_are_url_safe() {
+        let name = copied_file_name(&AssetName::new("../icons/a b#?.PNG"));
+
+        assert!(name.ends_with(".PNG"));
+        assert_eq!(utf8_percent_encode(&name, URL_SAFE).to_string(), name);
+        assert_eq!(
+            copied_file_name(&AssetName::new("weird.p ng")).len(),
+            16,
+            "extensions that aren't alphanumeric are dropped"
+        );
+    }
+}
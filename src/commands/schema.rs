@@ -0,0 +1,27 @@
+use std::io::{self, BufWriter, Write};
+
+use fs_err as fs;
+
+use crate::data::config_schema;
+use crate::options::{GlobalOptions, SchemaOptions};
+
+pub fn schema(_global: GlobalOptions, options: SchemaOptions) -> anyhow::Result<()> {
+    let schema = config_schema();
+
+    match &options.output {
+        Some(path) => {
+            let mut file = BufWriter::new(fs::File::create(path)?);
+            serde_json::to_writer_pretty(&mut file, &schema)?;
+            writeln!(file)?;
+            file.flush()?;
+        }
+        None => {
+            let stdout = io::stdout();
+            let mut handle = stdout.lock();
+            serde_json::to_writer_pretty(&mut handle, &schema)?;
+            writeln!(handle)?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,386 +1,1951 @@
 use std::{
-    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     env,
     io::{self, BufWriter, Write},
     path::{Path, PathBuf},
-    time::Duration,
+    sync::{
+        atomic::{self, AtomicBool},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
 use fs_err as fs;
 use packos::{InputItem, SimplePacker};
+use reqwest::{Client, StatusCode};
 use thiserror::Error;
 use walkdir::WalkDir;
 
 use crate::{
     alpha_bleed::alpha_bleed,
     asset_name::AssetName,
-    auth_cookie::get_auth_cookie,
-    codegen::perform_codegen,
-    data::{Config, ConfigError, ImageSlice, InputManifest, Manifest, ManifestError, SyncInput},
+    codegen::{perform_codegen, perform_preload_codegen, CodegenError, CodegenOptions},
+    data::{
+        is_sidecar_path, select_projects, AssetNameCase, AssetPackage, CachedWalk, CodegenFormat,
+        Config, ConfigError, ConfigProfile, DimensionPolicy, FileStamp, ImageSlice, InputConfig,
+        InputManifest, Manifest, ManifestError, OverlapPolicy, PackageError, SidecarConfig,
+        SymlinkPolicy, SyncIndex, SyncInput, UploadApi, UploadLedger, UserConfigError,
+        WalkErrorPolicy, WorkspaceError, DEFAULT_MANIFEST_KEY,
+    },
     dpi_scale,
-    image::Image,
-    options::{GlobalOptions, SyncOptions, SyncTarget},
-    roblox_web_api::{RobloxApiClient, RobloxApiError},
+    file_contents::FileContents,
+    ignore_file::{IgnoreError, IgnoreRules},
+    image::{read_png_size, Image},
+    interrupt,
+    options::{GlobalOptions, MigrateOwnershipOptions, SyncOptions, SyncTarget},
+    pipeline::{Flow, Pipeline},
+    remote_include::{self, FetchedInclude, RemoteIncludeError, REMOTE_INCLUDES_FOLDER},
+    roblox_web_api::{AssetCreator, AssetFormat, ModerationState, RobloxApiClient, RobloxApiError},
     sync_backend::{
-        DebugSyncBackend, Error as SyncBackendError, NoneSyncBackend, RetryBackend,
-        RobloxSyncBackend, SyncBackend, UploadInfo,
+        CachedBackend, DebugSyncBackend, Error as SyncBackendError, NoneSyncBackend,
+        OpenCloudSyncBackend, RetryBackend, RobloxSyncBackend, SyncBackend, UploadInfo,
+        UploadResponse,
     },
+    upload_cache::{FolderUploadCache, HttpUploadCache},
+    upload_name::{self, UploadNameParts},
 };
 
-fn sync_session<B: SyncBackend>(session: &mut SyncSession, options: &SyncOptions, mut backend: B) {
-    if let Some(retry) = options.retry {
-        let mut retry_backend =
-            RetryBackend::new(backend, retry, Duration::from_secs(options.retry_delay));
+fn sync_session<B: SyncBackend>(
+    session: &mut SyncSession,
+    retry: Option<usize>,
+    retry_delay: u64,
+    mut backend: B,
+) {
+    if let Some(retry) = retry {
+        let mut retry_backend = RetryBackend::new(backend, retry, Duration::from_secs(retry_delay));
         session.sync_with_backend(&mut retry_backend);
     } else {
         session.sync_with_backend(&mut backend);
     }
 }
 
+/// Syncs with a backend that uploads to Roblox, checking the current user's
+/// cache and the project's remote cache before each upload.
+fn sync_session_to_roblox<B: SyncBackend>(
+    session: &mut SyncSession,
+    options: &SyncOptions,
+    client: &Client,
+    default_owner: Option<AssetCreator>,
+    backend: B,
+) {
+    let remote_cache = session
+        .root_config()
+        .remote_cache
+        .as_ref()
+        .map(|remote_cache| HttpUploadCache::new(client.clone(), &remote_cache.url));
+
+    let user_cache = if options.no_user_cache {
+        None
+    } else {
+        FolderUploadCache::for_user()
+    };
+
+    // The user cache is checked first, since it doesn't need a request.
+    let backend = CachedBackend::new(backend, remote_cache, default_owner);
+    let backend = CachedBackend::new(backend, user_cache, default_owner);
+
+    sync_session(session, options.retry, options.retry_delay, backend);
+}
+
 pub fn sync(global: GlobalOptions, options: SyncOptions) -> Result<(), SyncError> {
-    let fuzzy_config_path = match &options.config_path {
-        Some(v) => v.to_owned(),
-        None => env::current_dir()?,
+    let fuzzy_config_paths = if options.config_paths.is_empty() {
+        vec![env::current_dir()?]
+    } else {
+        options.config_paths.clone()
     };
 
-    let mut api_client = RobloxApiClient::new(global.auth.or_else(get_auth_cookie));
+    let mut projects = Vec::new();
+    for fuzzy_config_path in &fuzzy_config_paths {
+        projects.extend(select_projects(
+            fuzzy_config_path,
+            options.project.as_deref(),
+        )?);
+    }
 
-    let mut session = SyncSession::new(&fuzzy_config_path)?;
+    let mut shared = SharedAuth::default();
 
-    session.discover_configs()?;
-    session.discover_inputs()?;
+    if let [project_path] = projects.as_slice() {
+        return sync_project(&global, &options, project_path, &mut shared).map(|_| ());
+    }
 
-    match &options.target {
-        SyncTarget::Roblox => {
-            let group_id = session.root_config().upload_to_group_id;
-            sync_session(
-                &mut session,
-                &options,
-                RobloxSyncBackend::new(&mut api_client, group_id),
-            );
-        }
-        SyncTarget::None => {
-            sync_session(&mut session, &options, NoneSyncBackend);
-        }
-        SyncTarget::Debug => {
-            sync_session(&mut session, &options, DebugSyncBackend::new());
+    // Projects are independent, whether they're in a workspace or were given
+    // separately, so one failing to sync doesn't stop the others. Stopping
+    // with Ctrl-C does, though.
+    let mut summaries = Vec::new();
+    let mut interrupted = false;
+
+    for project_path in &projects {
+        log::info!("Syncing {}", project_path.display());
+
+        match sync_project(&global, &options, project_path, &mut shared) {
+            Ok(summary) => summaries.push((project_path, Some(summary))),
+            Err(err) => {
+                interrupted = matches!(err, SyncError::Interrupted);
+                log::error!("{:?}", anyhow::Error::from(err));
+                summaries.push((project_path, None));
+
+                if interrupted {
+                    break;
+                }
+            }
         }
     }
 
-    session.write_manifest()?;
-    session.codegen()?;
-    session.write_asset_list()?;
-    session.populate_asset_cache(&mut api_client)?;
+    log::info!(
+        "Synced {} of {} project(s):",
+        summaries.len(),
+        projects.len()
+    );
+
+    let mut failed = 0;
+    for (project_path, summary) in summaries {
+        match summary {
+            Some(summary) => log::info!(
+                "  {}: {} input(s), {} upload(s)",
+                project_path.display(),
+                summary.inputs,
+                summary.uploads
+            ),
+            None => {
+                log::info!("  {}: failed", project_path.display());
+                failed += 1;
+            }
+        }
+    }
 
-    if session.sync_errors.is_empty() {
+    if interrupted {
+        Err(SyncError::Interrupted)
+    } else if failed == 0 {
         Ok(())
     } else {
-        Err(SyncError::HadErrors {
-            error_count: session.sync_errors.len(),
+        Err(SyncError::ProjectsHadErrors {
+            failed,
+            total: projects.len(),
         })
     }
 }
 
-/// A sync session holds all of the state for a single run of the 'tarmac sync'
-/// command.
-#[derive(Debug)]
-struct SyncSession {
-    /// The set of all configs known by the sync session.
-    ///
-    /// This list is always at least one element long. The first entry is the
-    /// root config where the sync session was started; use
-    /// SyncSession::root_config to retrieve it.
-    configs: Vec<Config>,
+/// What happened to one project during a run of 'tarmac sync', for the
+/// summary logged after syncing more than one.
+struct SyncSummary {
+    inputs: usize,
+    uploads: usize,
+}
 
-    /// The manifest file that was present as of the beginning of the sync
-    /// operation.
-    original_manifest: Manifest,
+/// The authenticated client shared by every project synced by one run of
+/// 'tarmac sync', so that the projects of a workspace only log in and check
+/// their credentials once.
+#[derive(Default)]
+struct SharedAuth {
+    /// The HTTP client that every request is sent with, including the ones
+    /// that fetch remote includes before the API client is created.
+    http_client: Option<Client>,
 
-    /// All of the inputs discovered so far in the current sync.
-    inputs: BTreeMap<AssetName, SyncInput>,
+    api_client: Option<RobloxApiClient>,
 
-    /// Errors encountered during syncing that we ignored at the time.
-    sync_errors: Vec<anyhow::Error>,
-}
+    /// The upload APIs whose credentials have already been checked.
+    checked: Vec<UploadApi>,
 
-/// Contains information to help Tarmac batch process different kinds of assets.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-struct InputKind {
-    packable: bool,
-    dpi_scale: u32,
+    /// The user that the legacy upload API uploads to, if the credentials
+    /// were checked and belong to a user.
+    user_id: Option<u64>,
 }
 
-struct PackedImage {
-    image: Image,
-    slices: HashMap<AssetName, ImageSlice>,
-}
+fn sync_project(
+    global: &GlobalOptions,
+    options: &SyncOptions,
+    fuzzy_config_path: &Path,
+    shared: &mut SharedAuth,
+) -> Result<SyncSummary, SyncError> {
+    let mut session = SyncSession::new(fuzzy_config_path)?;
 
-impl SyncSession {
-    fn new(fuzzy_config_path: &Path) -> Result<Self, SyncError> {
-        log::trace!("Starting new sync session");
+    if let Some(policy) = options.walk_errors {
+        session.walk_errors = policy;
+    }
 
-        let root_config = Config::read_from_folder_or_file(&fuzzy_config_path)?;
+    session.upload_tags = options.tags.clone();
+    session.jobs = options.jobs;
+    session.map_inputs = options.map_inputs;
 
-        log::trace!("Starting from config \"{}\"", root_config.name);
+    let http_client = match &shared.http_client {
+        Some(http_client) => http_client.clone(),
+        None => shared
+            .http_client
+            .insert(global.client_options().build_client()?)
+            .clone(),
+    };
 
-        let original_manifest = match Manifest::read_from_folder(root_config.folder()) {
-            Ok(manifest) => manifest,
-            Err(err) if err.is_not_found() => Manifest::default(),
-            other => other?,
-        };
+    session.discover_configs(&http_client)?;
+    session.discover_packages()?;
 
-        Ok(Self {
-            configs: vec![root_config],
-            original_manifest,
-            inputs: BTreeMap::new(),
-            sync_errors: Vec::new(),
-        })
+    let profile = match &global.profile {
+        Some(name) => session.apply_profile(name),
+        None => None,
+    };
+
+    if let Some(key) = profile
+        .as_ref()
+        .and_then(|profile| profile.manifest_key.clone())
+    {
+        session.use_manifest_key(key);
     }
 
-    /// Raise a sync error that will fail the sync process at a later point.
-    fn raise_error(&mut self, error: impl Into<anyhow::Error>) {
-        let error = error.into();
-        log::error!("{:?}", error);
-        self.sync_errors.push(error);
+    let credentials = global.credentials_for_project(profile.is_some())?;
+
+    let target = options
+        .target
+        .or_else(|| profile.as_ref().and_then(|profile| profile.target))
+        .ok_or(SyncError::MissingTarget)?;
+
+    let auth = credentials.auth.clone();
+    let api_client = match &mut shared.api_client {
+        Some(api_client) => api_client,
+        None => shared.api_client.insert(RobloxApiClient::with_client(
+            auth.clone(),
+            http_client,
+            &global.client_options(),
+        )?),
+    };
+    let universe_id = options.universe_id.or(credentials.universe_id);
+    let upload_api = session.root_config().upload_api();
+
+    // Only uploading with Open Cloud, checking moderation, and giving
+    // universes access need an API key, so it isn't looked for otherwise.
+    let needs_api_key = matches!(target, SyncTarget::Roblox)
+        && (upload_api == UploadApi::OpenCloud
+            || options.check_moderation
+            || universe_id.is_some());
+    let api_key = if needs_api_key {
+        credentials.api_key()?
+    } else {
+        None
+    };
+
+    // Finding inputs can take a long time in large projects, so we make sure
+    // that uploads can succeed before starting.
+    if let SyncTarget::Roblox = target {
+        if !options.skip_auth_check && !shared.checked.contains(&upload_api) {
+            let user_id =
+                check_credentials(api_client, upload_api, auth.as_deref(), api_key.as_deref())?;
+            shared.user_id = shared.user_id.or(user_id);
+            shared.checked.push(upload_api);
+        }
     }
 
-    /// The config that this sync session was started from.
-    fn root_config(&self) -> &Config {
-        &self.configs[0]
+    // Moderation is checked and universes are given access after uploading,
+    // so a missing API key has to be caught before anything is uploaded, or
+    // the new IDs would be lost.
+    if let SyncTarget::Roblox = target {
+        if (options.check_moderation || universe_id.is_some()) && api_key.is_none() {
+            return Err(SyncError::MissingApiKey);
+        }
     }
 
-    /// Locate all of the configs connected to our root config.
-    ///
-    /// Tarmac config files can include each other via the `includes` field,
-    /// which will search the given path for other config files and use them as
-    /// part of the sync.
-    fn discover_configs(&mut self) -> Result<(), SyncError> {
-        let mut to_search = VecDeque::new();
-        to_search.extend(self.root_config().includes.iter().cloned());
+    session.discover_inputs()?;
 
-        while let Some(search_path) = to_search.pop_front() {
-            let search_meta = fs::metadata(&search_path)?;
+    // Ctrl-C stops the sync between uploads from here on, instead of exiting
+    // and losing the IDs of the assets that were already uploaded.
+    session.interrupted = interrupt::flag();
 
-            if search_meta.is_file() {
-                // This is a file that's explicitly named by a config. We'll
-                // check that it's a Tarmac config and include it.
+    match target {
+        SyncTarget::Roblox => {
+            let root_config = session.root_config();
+            let group_id = options
+                .group_id
+                .or(credentials.group_id)
+                .or(root_config.upload_to_group_id);
+
+            // Requests to the remote cache reuse the API client's connections.
+            let http_client = api_client.http_client().clone();
+
+            match root_config.upload_api() {
+                UploadApi::Legacy => {
+                    if universe_id.is_some() {
+                        return Err(SyncError::UniverseRequiresOpenCloud);
+                    }
 
-                let config = Config::read_from_file(&search_path)?;
+                    // Cached uploads are keyed by the account they were
+                    // uploaded to, so we need to know who we're signed in as
+                    // if the credentials weren't checked.
+                    if group_id.is_none() && shared.user_id.is_none() && auth.is_some() {
+                        match api_client.get_authenticated_user() {
+                            Ok(user) => shared.user_id = Some(user.id),
+                            Err(err) => log::warn!(
+                                "Couldn't find the signed-in user, so cached uploads won't be used: {}",
+                                err
+                            ),
+                        }
+                    }
 
-                // Include any configs that this config references.
-                to_search.extend(config.includes.iter().cloned());
+                    let owner = group_id
+                        .map(AssetCreator::Group)
+                        .or(shared.user_id.map(AssetCreator::User));
 
-                self.configs.push(config);
-            } else {
-                // If this directory contains a config file, we can stop
-                // traversing this branch.
+                    session.set_default_owner(owner);
+                    session.track_uploads(auth.as_deref(), options.daily_upload_limit);
 
-                match Config::read_from_folder(&search_path) {
-                    Ok(config) => {
-                        // We found a config, we're done here.
+                    sync_session_to_roblox(
+                        &mut session,
+                        options,
+                        &http_client,
+                        owner,
+                        RobloxSyncBackend::new(api_client, group_id).uploading_as(shared.user_id),
+                    );
+                }
+                UploadApi::OpenCloud => {
+                    let api_key = api_key.clone().ok_or(SyncError::MissingApiKey)?;
 
-                        // Append config include paths from this config
-                        to_search.extend(config.includes.iter().cloned());
+                    let creator = match (group_id, root_config.upload_to_user_id) {
+                        (Some(group_id), _) => Some(AssetCreator::Group(group_id)),
+                        (None, Some(user_id)) => Some(AssetCreator::User(user_id)),
+                        (None, None) => None,
+                    };
 
-                        self.configs.push(config);
-                    }
+                    session.set_default_owner(creator);
+                    session.track_uploads(Some(&api_key), options.daily_upload_limit);
 
-                    Err(err) if err.is_not_found() => {
-                        // We didn't find a config, keep searching down this
-                        // branch of the filesystem.
+                    sync_session_to_roblox(
+                        &mut session,
+                        options,
+                        &http_client,
+                        creator,
+                        OpenCloudSyncBackend::new(api_client, api_key.clone(), creator),
+                    );
+                }
+            }
+        }
+        SyncTarget::None => {
+            sync_session(
+                &mut session,
+                options.retry,
+                options.retry_delay,
+                NoneSyncBackend,
+            );
+        }
+        SyncTarget::Debug => {
+            sync_session(
+                &mut session,
+                options.retry,
+                options.retry_delay,
+                DebugSyncBackend::new(),
+            );
+        }
+    }
 
-                        let children = fs::read_dir(&search_path)?;
+    session.record_uploads();
 
-                        for entry in children {
-                            let entry = entry?;
-                            let entry_path = entry.path();
+    // Once the sync has been stopped, nothing else is sent to Roblox, but the
+    // uploads that finished are still written to the manifest and codegen.
+    let interrupted = session.is_interrupted();
 
-                            // DirEntry has a metadata method, but in the case
-                            // of symlinks, it returns metadata about the
-                            // symlink and not the file or folder.
-                            let entry_meta = fs::metadata(&entry_path)?;
+    if let (Some(universe_id), SyncTarget::Roblox, false) = (universe_id, target, interrupted) {
+        let api_key = api_key.as_deref().ok_or(SyncError::MissingApiKey)?;
+        session.grant_universe_access(api_client, api_key, universe_id);
+    }
 
-                            if entry_meta.is_dir() {
-                                to_search.push_back(entry_path);
-                            }
-                        }
-                    }
+    if options.check_moderation && !interrupted {
+        if let SyncTarget::Roblox = target {
+            let api_key = api_key.ok_or(SyncError::MissingApiKey)?;
+            let timeout = Duration::from_secs(options.moderation_timeout);
 
-                    Err(err) => {
-                        return Err(err.into());
-                    }
-                }
-            }
+            session.check_moderation(api_client, &api_key, timeout);
         }
-
-        Ok(())
     }
 
-    /// Find all files on the filesystem referenced as inputs by our configs.
-    fn discover_inputs(&mut self) -> Result<(), SyncError> {
-        let inputs = &mut self.inputs;
-        let root_config_path = &self.configs[0].folder();
+    if options.verify_uploads && !interrupted {
+        if let SyncTarget::Roblox = target {
+            session.verify_uploads(api_client)?;
+        }
+    }
 
-        // Starting with our root config, iterate over all configs and find all
-        // relevant inputs
-        for config in &self.configs {
-            let config_path = config.folder();
+    session.write_manifest()?;
 
-            for input_config in &config.inputs {
-                let base_path = config_path.join(input_config.glob.get_prefix());
-                log::trace!(
-                    "Searching for inputs in '{}' matching '{}'",
-                    base_path.display(),
-                    input_config.glob,
-                );
+    if let Some(key) = session.root_config().codegen_manifest_key.clone() {
+        session.use_codegen_ids(&key);
+    }
 
-                let filtered_paths = WalkDir::new(base_path)
-                    .into_iter()
-                    // TODO: Properly handle WalkDir errors
-                    .filter_map(Result::ok)
-                    .filter(|entry| {
-                        let match_path = entry.path().strip_prefix(config_path).unwrap();
-                        input_config.glob.is_match(match_path)
-                    });
+    session.write_dev_content()?;
+    session.codegen()?;
+    session.write_asset_list()?;
+    session.write_preload_list()?;
+    if !interrupted {
+        session.populate_asset_cache(api_client)?;
+    }
 
-                for matching in filtered_paths {
-                    let path = matching.into_path();
+    if interrupted {
+        Err(SyncError::Interrupted)
+    } else if session.sync_errors.is_empty() {
+        Ok(SyncSummary {
+            inputs: session.inputs.len(),
+            uploads: session.upload_count,
+        })
+    } else {
+        Err(SyncError::HadErrors {
+            error_count: session.sync_errors.len(),
+        })
+    }
+}
 
-                    let name = AssetName::from_paths(&root_config_path, &path);
-                    log::trace!("Found input {}", name);
+/// Reads the config at the given path along with every config that it
+/// includes, the same way that 'tarmac sync' finds them.
+pub fn read_project_configs(
+    global: &GlobalOptions,
+    fuzzy_config_path: &Path,
+) -> Result<Vec<Config>, SyncError> {
+    let mut session = SyncSession::new(fuzzy_config_path)?;
+    session.discover_configs(&global.client_options().build_client()?)?;
 
-                    let path_info = dpi_scale::extract_path_info(&path);
+    Ok(session.configs)
+}
 
-                    let contents = fs::read(&path)?;
-                    let hash = generate_asset_hash(&contents);
+/// Uploads every asset in a project again under a new owner, then rewrites
+/// the manifest and generated code to refer to the new assets.
+///
+/// The manifest is only written once every upload has succeeded, so a failed
+/// migration leaves the project referring to the original assets.
+pub fn migrate_ownership(
+    global: GlobalOptions,
+    options: MigrateOwnershipOptions,
+) -> Result<(), SyncError> {
+    let fuzzy_config_path = match &options.config_path {
+        Some(v) => v.to_owned(),
+        None => env::current_dir()?,
+    };
 
-                    // If this input was known during the last sync operation,
-                    // pull the information we knew about it out.
-                    let (id, slice) = match self.original_manifest.inputs.get(&name) {
-                        Some(original) => (original.id, original.slice),
-                        None => (None, None),
-                    };
+    let owner = match (options.group_id, options.user_id) {
+        (Some(group_id), _) => AssetCreator::Group(group_id),
+        (None, Some(user_id)) => AssetCreator::User(user_id),
+        (None, None) => unreachable!("structopt requires --group-id or --user-id"),
+    };
 
-                    let already_found = inputs.insert(
-                        name.clone(),
-                        SyncInput {
-                            name,
-                            path,
-                            path_without_dpi_scale: path_info.path_without_dpi_scale,
-                            dpi_scale: path_info.dpi_scale,
-                            config: input_config.clone(),
-                            contents,
-                            hash,
-                            id,
-                            slice,
-                        },
-                    );
+    let credentials = global.credentials()?;
+    let auth = credentials.auth.clone();
+    let mut api_client = RobloxApiClient::new(auth.clone(), &global.client_options())?;
 
-                    if let Some(existing) = already_found {
-                        return Err(SyncError::OverlappingGlobs {
-                            path: existing.path,
-                        });
-                    }
-                }
-            }
-        }
+    let mut session = SyncSession::new(&fuzzy_config_path)?;
 
-        Ok(())
-    }
+    session.discover_configs(api_client.http_client())?;
+    session.discover_packages()?;
 
-    fn sync_with_backend<S: SyncBackend>(&mut self, backend: &mut S) {
-        let mut compatible_input_groups = BTreeMap::new();
+    let upload_api = session.root_config().upload_api();
+    let api_key = match upload_api {
+        UploadApi::OpenCloud => credentials.api_key()?,
+        UploadApi::Legacy => None,
+    };
+    let user_id = check_credentials(
+        &mut api_client,
+        upload_api,
+        auth.as_deref(),
+        api_key.as_deref(),
+    )?;
 
-        for (input_name, input) in &self.inputs {
-            if !is_image_asset(&input.path) {
-                log::warn!(
-                    "Asset '{}' is not recognized by Tarmac.",
-                    input.path.display()
-                );
+    session.discover_inputs()?;
 
-                continue;
-            }
+    let migrating = session.migrate_to(owner);
+    log::info!("Migrating {} input(s) to {}", migrating, owner);
 
-            let kind = InputKind {
-                packable: input.config.packable,
-                dpi_scale: input.dpi_scale,
+    match upload_api {
+        UploadApi::Legacy => {
+            let group_id = match owner {
+                AssetCreator::Group(group_id) => Some(group_id),
+                AssetCreator::User(_) => None,
             };
 
-            let input_group = compatible_input_groups.entry(kind).or_insert_with(Vec::new);
+            session.track_uploads(auth.as_deref(), None);
 
-            input_group.push(input_name.clone());
+            sync_session(
+                &mut session,
+                options.retry,
+                options.retry_delay,
+                RobloxSyncBackend::new(&mut api_client, group_id).uploading_as(user_id),
+            );
         }
+        UploadApi::OpenCloud => {
+            let api_key = api_key.ok_or(SyncError::MissingApiKey)?;
 
-        'outer: for (kind, group) in compatible_input_groups {
-            if kind.packable {
-                if let Err(err) = self.sync_packable_images(backend, group) {
-                    let rate_limited = err.is_rate_limited();
+            session.track_uploads(Some(&api_key), None);
 
-                    println!("{}: {:#?}", rate_limited, err);
+            sync_session(
+                &mut session,
+                options.retry,
+                options.retry_delay,
+                OpenCloudSyncBackend::new(&mut api_client, api_key, Some(owner)),
+            );
+        }
+    }
 
-                    self.raise_error(err);
+    session.record_uploads();
 
-                    if rate_limited {
-                        break 'outer;
-                    }
-                }
-            } else {
-                for input_name in group {
-                    if let Err(err) = self.sync_unpackable_image(backend, &input_name) {
-                        let rate_limited = err.is_rate_limited();
+    if !session.sync_errors.is_empty() {
+        return Err(SyncError::MigrationFailed {
+            error_count: session.sync_errors.len(),
+        });
+    }
+
+    session.write_manifest()?;
+    session.write_dev_content()?;
+    session.codegen()?;
+    session.write_asset_list()?;
+    session.write_preload_list()?;
+
+    let root_config = session.root_config();
+    let configured_owner = match (
+        root_config.upload_to_group_id,
+        root_config.upload_to_user_id,
+    ) {
+        (Some(group_id), _) => Some(AssetCreator::Group(group_id)),
+        (None, Some(user_id)) => Some(AssetCreator::User(user_id)),
+        (None, None) => None,
+    };
 
-                        self.raise_error(err);
+    if configured_owner != Some(owner) {
+        log::warn!(
+            "Assets were migrated to {}, but {} still uploads elsewhere. Update its \
+             upload-to-group-id or upload-to-user-id so that new assets go to the same owner.",
+            owner,
+            root_config.file_path.display()
+        );
+    }
 
-                        if rate_limited {
-                            break 'outer;
-                        }
-                    }
+    Ok(())
+}
+
+/// Makes a cheap authenticated request to confirm that the credentials used to
+/// upload are valid, and reports which account they belong to. Returns the ID
+/// of the user that legacy uploads go to, if it could be found.
+///
+/// Only rejected credentials are treated as errors; if Roblox can't be reached,
+/// the uploads will report that themselves.
+fn check_credentials(
+    api_client: &mut RobloxApiClient,
+    upload_api: UploadApi,
+    auth: Option<&str>,
+    api_key: Option<&str>,
+) -> Result<Option<u64>, SyncError> {
+    let mut user_id = None;
+
+    match upload_api {
+        UploadApi::Legacy => {
+            if auth.is_none() {
+                return Err(SyncError::MissingAuth);
+            }
+
+            match api_client.get_authenticated_user() {
+                Ok(user) => {
+                    log::info!("Uploading as {} ({})", user.name, user.id);
+                    user_id = Some(user.id);
+                }
+                Err(err) if is_rejected_credential(&err) => {
+                    return Err(SyncError::InvalidAuth { source: err })
                 }
+                Err(err) => log::warn!("Couldn't check the .ROBLOSECURITY cookie: {}", err),
             }
         }
+        UploadApi::OpenCloud => {
+            let api_key = api_key.ok_or(SyncError::MissingApiKey)?;
 
-        // TODO: Clean up output of inputs that were present in the previous
-        // sync but are no longer present.
+            match api_client.introspect_api_key(api_key) {
+                Ok(info) if !info.enabled || info.expired => {
+                    return Err(SyncError::UnusableApiKey { name: info.name })
+                }
+                Ok(info) => log::info!("Uploading with API key {:?}", info.name),
+                Err(err) if is_rejected_credential(&err) => {
+                    return Err(SyncError::InvalidApiKey { source: err })
+                }
+                Err(err) => log::warn!("Couldn't check the Open Cloud API key: {}", err),
+            }
+        }
     }
 
-    fn sync_packable_images<S: SyncBackend>(
-        &mut self,
-        backend: &mut S,
-        group: Vec<AssetName>,
-    ) -> Result<(), SyncError> {
-        if self.are_inputs_unchanged(&group) {
-            log::info!("Skipping image packing as all inputs are unchanged.");
+    Ok(user_id)
+}
 
-            return Ok(());
+fn is_rejected_credential(err: &RobloxApiError) -> bool {
+    match err {
+        RobloxApiError::ResponseError { status, .. } => {
+            *status == StatusCode::UNAUTHORIZED
+                || *status == StatusCode::FORBIDDEN
+                || *status == StatusCode::NOT_FOUND
         }
+        _ => false,
+    }
+}
 
-        log::trace!("Packing images...");
-        let mut packed_images = self.pack_images(&group)?;
+/// A sync session holds all of the state for a single run of the 'tarmac sync'
+/// command.
+#[derive(Debug)]
+struct SyncSession {
+    /// The set of all configs known by the sync session.
+    ///
+    /// This list is always at least one element long. The first entry is the
+    /// root config where the sync session was started; use
+    /// SyncSession::root_config to retrieve it.
+    configs: Vec<Config>,
 
-        log::trace!("Alpha-bleeding {} packed images...", packed_images.len());
+    /// The manifest file that was present as of the beginning of the sync
+    /// operation, including the IDs tracked under every manifest key.
+    stored_manifest: Manifest,
 
-        for (i, packed_image) in packed_images.iter_mut().enumerate() {
-            log::trace!("Bleeding image {}", i);
+    /// The manifest key that this sync reads and records IDs under.
+    manifest_key: String,
 
-            alpha_bleed(&mut packed_image.image);
-        }
+    /// The inputs that the stored manifest knows about under this sync's
+    /// manifest key.
+    original_manifest: Manifest,
 
-        log::trace!("Syncing packed images...");
-        for packed_image in &packed_images {
-            self.sync_packed_image(backend, packed_image)?;
-        }
+    /// All of the inputs discovered so far in the current sync.
+    inputs: BTreeMap<AssetName, SyncInput>,
 
-        Ok(())
-    }
+    /// The number of assets uploaded so far in the current sync, not counting
+    /// ones found in an upload cache.
+    upload_count: usize,
 
-    fn are_inputs_unchanged(&self, group: &[AssetName]) -> bool {
-        for name in group {
-            if let Some(manifest) = self.original_manifest.inputs.get(name) {
-                let input = &self.inputs[name];
-                let unchanged = input.is_unchanged_since_last_sync(manifest);
+    /// If we know which account is uploading, tracks its uploads in the upload
+    /// ledger.
+    upload_tracking: Option<UploadTracking>,
 
-                if !unchanged {
-                    log::trace!("Input {} changed since last sync", name);
+    /// Inputs that must be uploaded again even though they haven't changed.
+    reupload: BTreeSet<AssetName>,
+
+    /// What to do when part of the project can't be searched for inputs.
+    walk_errors: WalkErrorPolicy,
+
+    /// If not empty, only inputs with at least one of these tags are
+    /// uploaded during this sync.
+    upload_tags: Vec<String>,
+
+    /// How many threads prepare assets for upload, if not one for each core.
+    jobs: Option<usize>,
+
+    /// Whether large inputs are mapped into memory instead of being streamed
+    /// from disk, which the user opted into with `--map-inputs`.
+    map_inputs: bool,
+
+    /// The IDs of the assets in the packages that the project depends on.
+    package_ids: BTreeSet<u64>,
+
+    /// Set when the user asks the sync to stop, which stops it once the upload
+    /// in progress is done.
+    interrupted: Arc<AtomicBool>,
+
+    /// The remote includes that our configs fetched, so that their inputs can
+    /// be named as if they were inside the project.
+    fetched_includes: Vec<FetchedInclude>,
+
+    /// Errors encountered during syncing that we ignored at the time.
+    sync_errors: Vec<anyhow::Error>,
+}
+
+#[derive(Debug)]
+struct UploadTracking {
+    path: PathBuf,
+    ledger: UploadLedger,
+    account: String,
+    daily_limit: Option<usize>,
+}
+
+/// How long to wait between checks on assets that are still being moderated.
+const MODERATION_POLL_DELAY: Duration = Duration::from_secs(5);
+
+/// How far each channel of a downloaded image's pixels may be from the local
+/// image when verifying uploads, since Roblox re-encodes uploaded images.
+const VERIFY_TOLERANCE: u8 = 2;
+
+/// What was found while looking for included configs at one path.
+enum ConfigSearch {
+    Config(Box<Config>),
+
+    /// The path is a folder without a config, so its subfolders are searched
+    /// next.
+    Folders(Vec<PathBuf>),
+}
+
+/// Looks for a config at a path named by an include, which is either a config
+/// file or a folder that might contain one. `depth` is how many folders below
+/// the include the path is, and the config that named the include decides how
+/// deep the search goes and which folders it skips.
+fn search_for_config(
+    search_path: &Path,
+    including: &Config,
+    depth: usize,
+    symlinks: SymlinkPolicy,
+) -> Result<ConfigSearch, SyncError> {
+    let search_meta = fs::metadata(search_path)?;
+    let inherited = &including.defaults;
+
+    if search_meta.is_file() {
+        // This is a file that's explicitly named by a config. We'll check that
+        // it's a Tarmac config and include it.
+        let config = Config::read_from_file_inheriting(search_path, inherited)?;
+        return Ok(ConfigSearch::Config(Box::new(config)));
+    }
+
+    // If this directory contains a config file, we can stop traversing this
+    // branch.
+    match Config::read_from_folder_inheriting(search_path, inherited) {
+        Ok(config) => Ok(ConfigSearch::Config(Box::new(config))),
+
+        Err(err) if err.is_not_found() => {
+            if including
+                .include_max_depth
+                .is_some_and(|max_depth| depth >= max_depth)
+            {
+                log::trace!(
+                    "Not searching below {}, which is as deep as {} allows",
+                    search_path.display(),
+                    including.file_path.display()
+                );
+                return Ok(ConfigSearch::Folders(Vec::new()));
+            }
+
+            // We didn't find a config, keep searching down this branch of the
+            // filesystem.
+            let mut folders = Vec::new();
+
+            for entry in fs::read_dir(search_path)? {
+                let entry = entry?;
+                let entry_path = entry.path();
+
+                if symlinks == SymlinkPolicy::Ignore && entry.file_type()?.is_symlink() {
+                    log::trace!("Skipping symlink {}", entry_path.display());
+                    continue;
+                }
+
+                // Remote includes are only used by the configs that include
+                // them.
+                if entry.file_name() == REMOTE_INCLUDES_FOLDER {
+                    continue;
+                }
+
+                // DirEntry has a metadata method, but in the case of symlinks,
+                // it returns metadata about the symlink and not the file or
+                // folder.
+                let entry_meta = fs::metadata(&entry_path)?;
+
+                if !entry_meta.is_dir() {
+                    continue;
+                }
+
+                if including.excludes_from_includes(&entry_path) {
+                    log::trace!("Skipping excluded folder {}", entry_path.display());
+                    continue;
+                }
+
+                folders.push(entry_path);
+            }
+
+            Ok(ConfigSearch::Folders(folders))
+        }
+
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Identifies the search for an input group's files in the sync index, which
+/// changes whenever anything that decides which files it finds does.
+fn walk_key(config_path: &Path, input_config: &InputConfig, follow_symlinks: bool) -> String {
+    let excludes: Vec<String> = input_config
+        .exclude
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let key = format!(
+        "{}\n{}\n{}\n{}",
+        config_path.display(),
+        input_config.glob,
+        follow_symlinks,
+        excludes.join("\n")
+    );
+
+    blake3::hash(key.as_bytes()).to_hex()[..16].to_owned()
+}
+
+/// Finds the files in the folder of the config at the given path that belong
+/// to an input group, skipping folders that can't contain any of them.
+///
+/// If none of the folders searched by the last sync have changed, the files it
+/// found are used without searching again. Otherwise, the files that are found
+/// are returned along with a record of the search for the next sync, unless
+/// part of it failed or changed too recently to be trusted.
+fn find_input_paths(
+    config_path: &Path,
+    input_config: &InputConfig,
+    follow_symlinks: bool,
+    walk_errors: WalkErrorPolicy,
+    cached: Option<&CachedWalk>,
+) -> Result<(Vec<PathBuf>, Option<CachedWalk>), SyncError> {
+    if let Some(paths) = cached.and_then(CachedWalk::unchanged_paths) {
+        log::trace!(
+            "Using the {} file(s) matching '{}' found by the last sync",
+            paths.len(),
+            input_config.glob
+        );
+        return Ok((paths, cached.cloned()));
+    }
+
+    let base_path = config_path.join(input_config.glob.get_prefix());
+    log::trace!(
+        "Searching for inputs in '{}' matching '{}'",
+        base_path.display(),
+        input_config.glob,
+    );
+
+    // Remote includes have inputs of their own, which belong to the configs
+    // that were fetched.
+    let walker = WalkDir::new(&base_path)
+        .follow_links(follow_symlinks)
+        .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+        .into_iter()
+        .filter_entry(|entry| {
+            if entry.depth() == 0 || !entry.file_type().is_dir() {
+                return true;
+            }
+
+            let folder = entry.path().strip_prefix(config_path).unwrap();
+            entry.file_name() != REMOTE_INCLUDES_FOLDER
+                && input_config.glob.could_match_within(folder)
+        });
+
+    let mut matching_paths = Vec::new();
+    let mut walk = Some(CachedWalk::default());
+
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+
+            // When following symlinks, WalkDir reports symlinks that lead back
+            // to one of their own parents as errors instead of searching them
+            // forever. The folder they point to is searched anyways, so they're
+            // not a problem.
+            Err(err) if err.loop_ancestor().is_some() => {
+                log::trace!("Skipping symlink loop: {}", err);
+                continue;
+            }
+
+            // Input groups can point at folders that don't exist yet, which
+            // just means they have no inputs.
+            Err(err)
+                if err.depth() == 0
+                    && err.io_error().map(io::Error::kind) == Some(io::ErrorKind::NotFound) =>
+            {
+                log::trace!("{} does not exist", base_path.display());
+                walk = None;
+                break;
+            }
+
+            Err(source) => {
+                let path = source.path().unwrap_or(&base_path).to_owned();
+                walk = None;
+
+                match walk_errors {
+                    WalkErrorPolicy::Error => {
+                        return Err(SyncError::WalkDir { path, source });
+                    }
+                    WalkErrorPolicy::Warn => {
+                        log::warn!("Skipping part of the project: {}", source);
+                    }
+                    WalkErrorPolicy::Skip => {
+                        log::trace!("Skipping part of the project: {}", source);
+                    }
+                }
+
+                continue;
+            }
+        };
+
+        if !follow_symlinks && entry.path_is_symlink() {
+            continue;
+        }
+
+        if entry.file_type().is_dir() {
+            let recorded = match (&mut walk, entry.metadata()) {
+                (Some(walk), Ok(metadata)) => walk.add_folder(entry.path(), &metadata),
+                _ => false,
+            };
+
+            if !recorded {
+                walk = None;
+            }
+
+            continue;
+        }
+
+        let match_path = entry.path().strip_prefix(config_path).unwrap();
+        if input_config.matches(match_path) {
+            if let Some(walk) = &mut walk {
+                walk.add_path(entry.path());
+            }
+
+            matching_paths.push(entry.into_path());
+        }
+    }
+
+    Ok((matching_paths, walk))
+}
+
+/// Calls the given function with every item, spread across a thread for each
+/// available core, and returns the results in the same order as the items.
+fn map_in_parallel<T, R, F>(items: &[T], function: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    let chunk_size = items.len().div_ceil(threads).max(1);
+    let function = &function;
+
+    thread::scope(|scope| {
+        let chunks: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(function).collect::<Vec<_>>()))
+            .collect();
+
+        chunks
+            .into_iter()
+            .flat_map(|chunk| {
+                chunk
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    })
+}
+
+/// Checks that an image fits within its input group's `max_dimensions`,
+/// returning the contents to upload instead if it had to be scaled down.
+///
+/// `@2x` and `@3x` variants are never scaled down, since shrinking them on
+/// their own would break their scale relative to their 1x image. They're
+/// warned about instead.
+fn enforce_max_dimensions(
+    path: &Path,
+    contents: &[u8],
+    max_dimensions: (u32, u32),
+    dpi_scale: u32,
+    config: &InputConfig,
+) -> Result<Option<Vec<u8>>, SyncError> {
+    // Only PNG images can be decoded, like when packing spritesheets.
+    if AssetFormat::from_path(path) != Some(AssetFormat::Png) {
+        return Ok(None);
+    }
+
+    let size = read_png_size(contents)?;
+    if size.0 <= max_dimensions.0 && size.1 <= max_dimensions.1 {
+        return Ok(None);
+    }
+
+    match config.max_dimensions_policy {
+        DimensionPolicy::Error => Err(SyncError::ImageTooLarge {
+            path: path.to_owned(),
+            size,
+            max_dimensions,
+        }),
+        DimensionPolicy::Warn => {
+            log::warn!(
+                "{} is {}x{}, which is larger than the maximum of {}x{}",
+                path.display(),
+                size.0,
+                size.1,
+                max_dimensions.0,
+                max_dimensions.1
+            );
+            Ok(None)
+        }
+        DimensionPolicy::Resize if dpi_scale > 1 => {
+            log::warn!(
+                "{} is {}x{}, which is larger than the maximum of {}x{}, but isn't scaled down because it's an @{}x variant",
+                path.display(),
+                size.0,
+                size.1,
+                max_dimensions.0,
+                max_dimensions.1,
+                dpi_scale
+            );
+            Ok(None)
+        }
+        DimensionPolicy::Resize => {
+            let scale = f64::min(
+                max_dimensions.0 as f64 / size.0 as f64,
+                max_dimensions.1 as f64 / size.1 as f64,
+            );
+            let new_size = (
+                ((size.0 as f64 * scale).round() as u32).clamp(1, max_dimensions.0),
+                ((size.1 as f64 * scale).round() as u32).clamp(1, max_dimensions.1),
+            );
+
+            log::info!(
+                "Scaling {} down from {}x{} to {}x{}",
+                path.display(),
+                size.0,
+                size.1,
+                new_size.0,
+                new_size.1
+            );
+
+            let image = Image::decode_png_as_rgba8(contents)?;
+            let mut resized = Vec::new();
+            image.downscale(new_size).encode_png(&mut resized)?;
+
+            Ok(Some(resized))
+        }
+    }
+}
+
+/// Decides whether an input found by one input group should replace the same
+/// input found earlier by another, or returns None if the policy can't pick
+/// between them.
+fn should_replace(
+    policy: OverlapPolicy,
+    existing: &InputConfig,
+    new: &InputConfig,
+) -> Option<bool> {
+    let ordering = match policy {
+        OverlapPolicy::Error => return None,
+        OverlapPolicy::LastConfig => return Some(true),
+        OverlapPolicy::MostSpecific => new.glob.specificity().cmp(&existing.glob.specificity()),
+        OverlapPolicy::Priority => new.priority.cmp(&existing.priority),
+    };
+
+    match ordering {
+        Ordering::Greater => Some(true),
+        Ordering::Less => Some(false),
+        Ordering::Equal => None,
+    }
+}
+
+/// Contains information to help Tarmac batch process different kinds of assets.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct InputKind {
+    format: AssetFormat,
+    packable: bool,
+    dpi_scale: u32,
+    owner: Option<AssetCreator>,
+
+    /// Inputs with different tags are never packed into the same spritesheet,
+    /// so that each spritesheet can be preloaded or synced with its tag.
+    tags: Vec<String>,
+}
+
+struct PackedImage {
+    image: Image,
+    slices: HashMap<AssetName, ImageSlice>,
+}
+
+/// Work that's prepared on the upload pipeline's workers, ending in an upload
+/// if there's anything to upload.
+enum UploadJob {
+    Unpackable {
+        input_name: AssetName,
+        format: AssetFormat,
+    },
+
+    /// A spritesheet that's been packed, but still has to be alpha-bled and
+    /// encoded.
+    Spritesheet(PackedImage),
+}
+
+/// An asset that's ready to upload, and the inputs it's uploaded for.
+struct PreparedUpload {
+    info: UploadInfo,
+    target: UploadTarget,
+}
+
+enum UploadTarget {
+    Input(AssetName),
+    Spritesheet(HashMap<AssetName, ImageSlice>),
+}
+
+impl SyncSession {
+    fn new(fuzzy_config_path: &Path) -> Result<Self, SyncError> {
+        log::trace!("Starting new sync session");
+
+        let root_config = Config::read_from_folder_or_file(&fuzzy_config_path)?;
+
+        log::trace!("Starting from config \"{}\"", root_config.name);
+
+        let mut stored_manifest = match Manifest::read_from_folder(root_config.folder()) {
+            Ok(manifest) => manifest,
+            Err(err) if err.is_not_found() => Manifest::default(),
+            other => other?,
+        };
+
+        if root_config.asset_name_case == AssetNameCase::Lower {
+            stored_manifest.lowercase_names();
+        }
+
+        let walk_errors = root_config.walk_errors;
+
+        Ok(Self {
+            configs: vec![root_config],
+            original_manifest: stored_manifest.for_key(DEFAULT_MANIFEST_KEY),
+            stored_manifest,
+            manifest_key: DEFAULT_MANIFEST_KEY.to_owned(),
+            inputs: BTreeMap::new(),
+            upload_count: 0,
+            upload_tracking: None,
+            reupload: BTreeSet::new(),
+            walk_errors,
+            upload_tags: Vec::new(),
+            jobs: None,
+            map_inputs: false,
+            package_ids: BTreeSet::new(),
+            interrupted: Arc::new(AtomicBool::new(false)),
+            fetched_includes: Vec::new(),
+            sync_errors: Vec::new(),
+        })
+    }
+
+    fn is_interrupted(&self) -> bool {
+        self.interrupted.load(atomic::Ordering::SeqCst)
+    }
+
+    /// Raise a sync error that will fail the sync process at a later point.
+    fn raise_error(&mut self, error: impl Into<anyhow::Error>) {
+        let error = error.into();
+        log::error!("{:?}", error);
+        self.sync_errors.push(error);
+    }
+
+    /// Applies the profile with the given name from the root config to every
+    /// config, returning it if the root config defines one.
+    fn apply_profile(&mut self, name: &str) -> Option<ConfigProfile> {
+        let profile = self.root_config().profiles.get(name)?.clone();
+        log::debug!("Applying project profile {}", name);
+
+        for config in &mut self.configs {
+            config.apply_profile(&profile);
+        }
+
+        Some(profile)
+    }
+
+    /// Makes this sync read and record IDs under the given manifest key,
+    /// leaving the IDs stored under other keys alone.
+    fn use_manifest_key(&mut self, key: String) {
+        log::debug!("Using asset IDs stored under manifest key {}", key);
+
+        self.original_manifest = self.stored_manifest.for_key(&key);
+        self.manifest_key = key;
+    }
+
+    /// Points every input at the IDs stored under the given manifest key, so
+    /// that generated code refers to them instead of the ones from this sync.
+    fn use_codegen_ids(&mut self, key: &str) {
+        if key == self.manifest_key {
+            return;
+        }
+
+        log::debug!("Generating code with asset IDs from manifest key {}", key);
+
+        let stored = self.stored_manifest.for_key(key);
+        let mut missing = 0;
+
+        for (name, input) in &mut self.inputs {
+            match stored.inputs.get(name) {
+                Some(manifest) => {
+                    input.id = manifest.id;
+                    input.decal_id = manifest.decal_id;
+                    input.slice = manifest.slice;
+                    input.spritesheet = manifest.spritesheet.clone();
+                }
+                None => {
+                    input.id = None;
+                    input.decal_id = None;
+                    missing += 1;
+                }
+            }
+        }
+
+        if missing > 0 {
+            log::warn!(
+                "{} input(s) have never been synced under manifest key {}, so generated code won't have IDs for them",
+                missing,
+                key
+            );
+        }
+    }
+
+    /// The config that this sync session was started from.
+    fn root_config(&self) -> &Config {
+        &self.configs[0]
+    }
+
+    /// Locate all of the configs connected to our root config.
+    ///
+    /// Tarmac config files can include each other via the `includes` field,
+    /// which will search the given path for other config files and use them as
+    /// part of the sync. Remote includes are fetched with the given client.
+    fn discover_configs(&mut self, client: &Client) -> Result<(), SyncError> {
+        let symlinks = self.root_config().symlinks;
+
+        // Included configs inherit the input defaults of the config that
+        // included them, which also limits how deep its includes are searched,
+        // so we keep track of its index and the depth with each path.
+        let mut to_search: Vec<(PathBuf, usize, usize)> = Vec::new();
+        for include in self.root_config().resolve_includes(client)? {
+            self.fetched_includes.extend(include.fetched);
+            to_search.push((include.path, 0, 0));
+        }
+
+        // Symlinks can make the same folder reachable from more than one path,
+        // or even from inside of itself, so we keep track of where we've been.
+        let mut searched = HashSet::new();
+
+        // Each level of folders is searched in parallel, since a project's
+        // folders might hold a lot of other files.
+        while !to_search.is_empty() {
+            let mut level = Vec::new();
+
+            for (search_path, including, depth) in to_search.drain(..) {
+                if searched.insert(search_path.canonicalize()?) {
+                    level.push((search_path, including, depth));
+                } else {
+                    log::trace!(
+                        "Skipping {}, which was already searched",
+                        search_path.display()
+                    );
+                }
+            }
+
+            let configs = &self.configs;
+            let results = map_in_parallel(&level, |(search_path, including, depth)| {
+                search_for_config(search_path, &configs[*including], *depth, symlinks)
+            });
+
+            for ((_, including, depth), result) in level.into_iter().zip(results) {
+                match result? {
+                    ConfigSearch::Config(config) => {
+                        // Every upload in a sync goes through the same API, so
+                        // an included config can't ask for a different one.
+                        let root_api = self.root_config().upload_api();
+                        if matches!(config.upload_api, Some(api) if api != root_api) {
+                            return Err(SyncError::NestedUploadApi {
+                                path: config.file_path.clone(),
+                                root: self.root_config().file_path.clone(),
+                            });
+                        }
+
+                        // Include any configs that this config references.
+                        let index = self.configs.len();
+                        for include in config.resolve_includes(client)? {
+                            self.fetched_includes.extend(include.fetched);
+                            to_search.push((include.path, index, 0));
+                        }
+
+                        self.configs.push(*config);
+                    }
+
+                    ConfigSearch::Folders(folders) => {
+                        to_search.extend(
+                            folders
+                                .into_iter()
+                                .map(|folder| (folder, including, depth + 1)),
+                        );
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the asset packages that our configs depend on.
+    fn discover_packages(&mut self) -> Result<(), SyncError> {
+        for config in &self.configs {
+            for package_path in &config.packages {
+                let package = AssetPackage::read_from_folder(package_path)?;
+                log::trace!(
+                    "Using {} asset(s) from package {}",
+                    package.assets.len(),
+                    package.name
+                );
+
+                self.package_ids.extend(package.asset_ids());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Find all files on the filesystem referenced as inputs by our configs.
+    fn discover_inputs(&mut self) -> Result<(), SyncError> {
+        let inputs = &mut self.inputs;
+        let root_config_path = &self.configs[0].folder();
+        let follow_symlinks = self.configs[0].symlinks == SymlinkPolicy::Follow;
+        let overlapping_globs = self.configs[0].overlapping_globs;
+        let asset_name_case = self.configs[0].asset_name_case;
+        let respect_gitignore = self.configs[0].respect_gitignore;
+        let fetched_includes = &self.fetched_includes;
+
+        let sync_index_path = SyncIndex::path_for_project(root_config_path);
+        let sync_index = match &sync_index_path {
+            Some(path) => SyncIndex::read_from_file(path).unwrap_or_else(|err| {
+                log::warn!("Couldn't read the sync index: {}", err);
+                SyncIndex::default()
+            }),
+            None => SyncIndex::default(),
+        };
+        let mut stamps = HashMap::new();
+
+        // Included configs can upload their inputs somewhere other than the
+        // root config, unless an input group overrides it.
+        let mut input_groups = Vec::new();
+        for (config_index, config) in self.configs.iter().enumerate() {
+            for input_config in &config.inputs {
+                let mut input_config = input_config.clone();
+                if config_index > 0
+                    && input_config.upload_to_group_id.is_none()
+                    && input_config.upload_to_user_id.is_none()
+                {
+                    input_config.upload_to_group_id = config.upload_to_group_id;
+                    input_config.upload_to_user_id = config.upload_to_user_id;
+                }
+
+                input_groups.push((config_index, input_config));
+            }
+        }
+
+        // Each input group's folder is searched on its own thread, since
+        // searching large projects is mostly spent waiting on the filesystem.
+        let walk_errors = self.walk_errors;
+        let configs = &self.configs;
+        let sync_index = &sync_index;
+        let found_paths = map_in_parallel(&input_groups, |(config_index, input_config)| {
+            let config_path = configs[*config_index].folder();
+            let key = walk_key(config_path, input_config, follow_symlinks);
+
+            find_input_paths(
+                config_path,
+                input_config,
+                follow_symlinks,
+                walk_errors,
+                sync_index.walk(&key),
+            )
+            .map(|(paths, walk)| (paths, walk.map(|walk| (key, walk))))
+        });
+
+        // Starting with our root config, iterate over all configs and find all
+        // relevant inputs
+        let mut ignore_rules: Vec<IgnoreRules> = self
+            .configs
+            .iter()
+            .map(|config| {
+                let rules = IgnoreRules::new(config.folder());
+                if respect_gitignore {
+                    rules.with_gitignore()
+                } else {
+                    rules
+                }
+            })
+            .collect();
+
+        let mut walks = Vec::new();
+
+        for ((config_index, input_config), found) in input_groups.iter().zip(found_paths) {
+            let config_index = *config_index;
+            let config_path = self.configs[config_index].folder();
+            let ignore_rules = &mut ignore_rules[config_index];
+
+            let (matching_paths, walk) = found?;
+            walks.extend(walk);
+
+            for path in matching_paths {
+                // Sidecar files describe other inputs and aren't inputs
+                // themselves.
+                if is_sidecar_path(&path) {
+                    continue;
+                }
+
+                if ignore_rules.is_ignored(&path)? {
+                    log::trace!("Skipping ignored file {}", path.display());
+                    continue;
+                }
+
+                let name_path = remote_include::name_path(fetched_includes, &path);
+                let name = AssetName::from_paths(root_config_path, &name_path);
+                let name = match asset_name_case {
+                    AssetNameCase::Preserve => name,
+                    AssetNameCase::Lower => name.to_lowercase(),
+                };
+                log::trace!("Found input {}", name);
+
+                let path_info = dpi_scale::extract_path_info(&path);
+
+                let metadata = fs::metadata(&path)?;
+
+                // If this input was known during the last sync operation,
+                // pull the information we knew about it out.
+                let original = self.original_manifest.inputs.get(&name);
+                let mut id = original.and_then(|original| original.id);
+                let mut group_id = original.and_then(|original| original.group_id);
+                let mut user_id = original.and_then(|original| original.user_id);
+                let mut decal_id = original.and_then(|original| original.decal_id);
+                let universe_ids = original
+                    .map(|original| original.universe_ids.clone())
+                    .unwrap_or_default();
+                let mut slice = original.and_then(|original| original.slice);
+                let mut spritesheet = original.and_then(|original| original.spritesheet.clone());
+                let mut rejected = original.is_some_and(|original| original.rejected);
+
+                let mut config = input_config.clone();
+                config.apply_overrides(path.strip_prefix(config_path).unwrap());
+
+                let mut codegen_name = None;
+
+                if let Some(sidecar) = SidecarConfig::read_for_input(&path)? {
+                    log::trace!("Applying sidecar overrides to {}", name);
+
+                    sidecar.apply_to(&mut config);
+                    codegen_name = sidecar.codegen_name;
+
+                    if let Some(sidecar_slice) = sidecar.slice {
+                        if config.packable {
+                            log::warn!(
+                                    "Input {} is packable, so the slice from its sidecar will be ignored",
+                                    name
+                                );
+                        } else {
+                            slice = Some(sidecar_slice);
+                            spritesheet = None;
+                        }
+                    }
+                }
+
+                // Pinned inputs refer to an asset that Tarmac didn't
+                // upload, so nothing from earlier uploads applies to them.
+                if let Some(asset_id) = config.asset_id {
+                    if config.packable {
+                        log::warn!(
+                                "Input {} has a pinned asset ID, so it won't be packed into a spritesheet",
+                                name
+                            );
+                        config.packable = false;
+                        slice = None;
+                        spritesheet = None;
+                    }
+
+                    id = Some(asset_id);
+                    group_id = None;
+                    user_id = None;
+                    decal_id = None;
+                    rejected = false;
+                }
+
+                // Resized images are hashed by the contents they're
+                // uploaded with, so they have to be read every time.
+                let file_stamp = FileStamp::from_metadata(&metadata);
+                let stamp = match config.max_dimensions {
+                    Some(_) => None,
+                    None => file_stamp,
+                };
+
+                // Files that haven't been touched since they were last
+                // hashed are only read if they need to be uploaded. Other
+                // hashes are filled in by hash_inputs once every input is
+                // found.
+                let cached_hash = stamp.and_then(|stamp| sync_index.get(&path, stamp));
+                let (contents, hash) = match cached_hash {
+                    Some(hash) => (None, hash.to_owned()),
+                    // Large files are streamed from disk when they're
+                    // hashed and uploaded instead of being kept in memory,
+                    // unless the sync opted into mapping them.
+                    None if config.max_dimensions.is_none()
+                        && !self.map_inputs
+                        && FileContents::is_large(metadata.len()) =>
+                    {
+                        (None, String::new())
+                    }
+                    None => {
+                        let mut contents = match config.max_dimensions {
+                            Some(_) => FileContents::read(&path)?,
+
+                            // SAFETY: The user promised that inputs aren't
+                            // modified or truncated during the sync by
+                            // passing --map-inputs. Mapped inputs are still
+                            // checked against the stamp that was taken before
+                            // mapping them before they're used after hashing,
+                            // and are read again if they changed.
+                            None if self.map_inputs => unsafe { FileContents::map(&path)? },
+                            None => FileContents::read(&path)?,
+                        };
+
+                        if let Some(max_dimensions) = config.max_dimensions {
+                            if let Some(resized) = enforce_max_dimensions(
+                                &path,
+                                &contents,
+                                max_dimensions,
+                                path_info.dpi_scale,
+                                &config,
+                            )? {
+                                contents = resized.into();
+                            }
+                        }
+
+                        (Some(contents.into()), String::new())
+                    }
+                };
+
+                if let Some(stamp) = stamp {
+                    stamps.insert(path.clone(), stamp);
+                }
+
+                let input = SyncInput {
+                    name: name.clone(),
+                    path,
+                    config_path: self.configs[config_index].file_path.clone(),
+                    path_without_dpi_scale: path_info.path_without_dpi_scale,
+                    dpi_scale: path_info.dpi_scale,
+                    discovery_index: inputs.len(),
+                    config,
+                    codegen_name,
+                    dev_url: None,
+                    contents,
+                    stamp: file_stamp,
+                    hash,
+                    id,
+                    group_id,
+                    user_id,
+                    decal_id,
+                    universe_ids,
+                    slice,
+                    spritesheet,
+                    rejected,
+                    owner: None,
+                };
+
+                let existing = match inputs.get_mut(&name) {
+                    Some(existing) => existing,
+                    None => {
+                        inputs.insert(name, input);
+                        continue;
+                    }
+                };
+
+                // Overlapping globs match the same file, but different
+                // files can also end up with the same name, like when
+                // asset names are made lowercase.
+                if existing.path != input.path {
+                    return Err(SyncError::AssetNameCollision {
+                        name,
+                        first_path: existing.path.clone(),
+                        first_config: existing.config_path.clone(),
+                        second_path: input.path,
+                        second_config: input.config_path,
+                    });
+                }
+
+                let replace =
+                    match should_replace(overlapping_globs, &existing.config, &input.config) {
+                        Some(replace) => replace,
+                        None => {
+                            return Err(SyncError::OverlappingGlobs {
+                                path: existing.path.clone(),
+                            })
+                        }
+                    };
+
+                let (winner, loser) = if replace {
+                    (&input.config.glob, &existing.config.glob)
+                } else {
+                    (&existing.config.glob, &input.config.glob)
+                };
+                log::debug!(
+                    "Input {} is matched by '{}' and '{}', using '{}'",
+                    name,
+                    loser,
+                    winner,
+                    winner,
+                );
+
+                if replace {
+                    // The input keeps its place in the discovery order so
+                    // that no two inputs share an index.
+                    let discovery_index = existing.discovery_index;
+                    *existing = SyncInput {
+                        discovery_index,
+                        ..input
+                    };
+                }
+            }
+        }
+
+        self.check_pinned_ids()?;
+        self.hash_inputs()?;
+
+        if let Some(path) = sync_index_path {
+            self.write_sync_index(&path, &stamps, walks);
+        }
+
+        // Imported IDs were uploaded before the project used Tarmac, so each
+        // file is trusted to have the contents that were uploaded, and only
+        // its hash is recorded.
+        for (name, input) in &self.inputs {
+            if let Some(original) = self.original_manifest.inputs.get_mut(name) {
+                if original.has_unknown_hash() && original.id.is_some() {
+                    log::debug!("Using imported ID for {} without uploading it", name);
+                    original.hash = input.hash.clone();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Makes sure that no two inputs are pinned to the same asset. An
+    /// `asset-id` on an input group pins every file its glob matches, which
+    /// would make different files share one ID.
+    fn check_pinned_ids(&self) -> Result<(), SyncError> {
+        let mut pinned: HashMap<u64, &AssetName> = HashMap::new();
+
+        for (name, input) in &self.inputs {
+            if let Some(id) = input.config.asset_id {
+                if let Some(first) = pinned.insert(id, name) {
+                    return Err(SyncError::SharedPinnedId {
+                        id,
+                        first: first.clone(),
+                        second: name.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Hashes the contents of every input, spread across a thread for each
+    /// available core, since hashing thousands of images one at a time takes
+    /// most of the time spent discovering them. Resized images are tracked by
+    /// the contents they're uploaded with, and large inputs that weren't kept
+    /// in memory are hashed as they're streamed from disk.
+    fn hash_inputs(&mut self) -> io::Result<()> {
+        let started = Instant::now();
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+
+        let mut inputs: Vec<&mut SyncInput> = self
+            .inputs
+            .values_mut()
+            .filter(|input| input.hash.is_empty())
+            .collect();
+        let hashed = inputs.len();
+        let chunk_size = hashed.div_ceil(threads).max(1);
+
+        thread::scope(|scope| {
+            let workers: Vec<_> = inputs
+                .chunks_mut(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || -> io::Result<()> {
+                        for input in chunk {
+                            input.hash = match input
+                                .contents
+                                .as_ref()
+                                .and_then(|contents| contents.as_bytes())
+                            {
+                                Some(contents) => generate_asset_hash(contents),
+                                None => generate_file_hash(&input.path)?,
+                            };
+                        }
+
+                        Ok(())
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .try_for_each(|worker| worker.join().expect("hashing thread panicked"))
+        })?;
+
+        log::trace!(
+            "Hashed {} of {} input(s) on {} thread(s) in {:?}",
+            hashed,
+            self.inputs.len(),
+            threads,
+            started.elapsed()
+        );
+
+        Ok(())
+    }
+
+    /// Remembers the hash of every input along with the size and modification
+    /// time its file had when it was discovered, and the files each input group
+    /// matched. Files and input groups that are gone are left out, so the index
+    /// doesn't grow forever.
+    fn write_sync_index(
+        &self,
+        path: &Path,
+        stamps: &HashMap<PathBuf, FileStamp>,
+        walks: Vec<(String, CachedWalk)>,
+    ) {
+        let mut sync_index = SyncIndex::default();
+
+        for input in self.inputs.values() {
+            if let Some(stamp) = stamps.get(&input.path) {
+                sync_index.insert(&input.path, *stamp, input.hash.clone());
+            }
+        }
+
+        for (key, walk) in walks {
+            sync_index.insert_walk(key, walk);
+        }
+
+        if let Err(err) = sync_index.write_to_file(path) {
+            log::warn!("Couldn't save the sync index: {}", err);
+        }
+    }
+
+    fn sync_with_backend<S: SyncBackend>(&mut self, backend: &mut S) {
+        let mut compatible_input_groups = BTreeMap::new();
+
+        for (input_name, input) in &self.inputs {
+            if input.is_pinned() {
+                log::trace!("Skipping pinned input {}", input_name);
+                continue;
+            }
+
+            if !input.config.has_any_tag(&self.upload_tags) {
+                log::trace!("Skipping input {} without a selected tag", input_name);
+                continue;
+            }
+
+            let format = match input.format() {
+                Some(format) => format,
+                None => {
+                    log::warn!(
+                        "Asset '{}' is not recognized by Tarmac.",
+                        input.path.display()
+                    );
+
+                    continue;
+                }
+            };
+
+            if let Some(manifest) = self.original_manifest.inputs.get(input_name) {
+                if input.is_quarantined(manifest) {
+                    log::warn!(
+                        "Skipping '{}' as it was rejected by moderation and hasn't changed.",
+                        input.path.display()
+                    );
+
+                    continue;
+                }
+            }
+
+            let kind = InputKind {
+                format,
+                // Only images can be packed into spritesheets.
+                packable: input.config.packable && format.is_image(),
+                dpi_scale: input.dpi_scale,
+                owner: input.upload_owner(),
+                tags: {
+                    let mut tags = input.config.tags.clone();
+                    tags.sort();
+                    tags.dedup();
+                    tags
+                },
+            };
+
+            let input_group = compatible_input_groups.entry(kind).or_insert_with(Vec::new);
+
+            input_group.push(input_name.clone());
+        }
+
+        self.report_planned_uploads(&compatible_input_groups);
+
+        let mut uploaded = Vec::new();
+        let mut errors = Vec::new();
+
+        // Groups of packable images are only packed once there's room in the
+        // pipeline for their first spritesheet, so on top of the jobs in
+        // flight, at most one group's spritesheets are kept in memory. Packing
+        // happens on the pipeline's own thread for creating jobs, so it doesn't
+        // hold up uploads. Each spritesheet is then encoded and uploaded as a
+        // job of its own.
+        let jobs = compatible_input_groups.into_iter().flat_map(
+            |(kind, group)| -> Vec<Result<UploadJob, SyncError>> {
+                if !kind.packable {
+                    return group
+                        .into_iter()
+                        .map(|input_name| {
+                            Ok(UploadJob::Unpackable {
+                                input_name,
+                                format: kind.format,
+                            })
+                        })
+                        .collect();
+                }
+
+                match self.pack_changed_images(&group) {
+                    Ok(packed_images) => packed_images
+                        .into_iter()
+                        .map(|packed_image| Ok(UploadJob::Spritesheet(packed_image)))
+                        .collect(),
+                    Err(err) => vec![Err(err)],
+                }
+            },
+        );
+
+        // Inputs are read and spritesheets are encoded on the pipeline's
+        // workers while the backend uploads the ones that are ready on this
+        // thread. Results are only applied once every upload is done, since
+        // the workers are still reading the session until then.
+        let mut pipeline = Pipeline::new();
+        if let Some(workers) = self.jobs {
+            pipeline = pipeline.workers(workers).in_flight(workers);
+        }
+        pipeline = pipeline.cancel_on(Arc::clone(&self.interrupted));
+
+        pipeline.run(
+            jobs,
+            |job| job.and_then(|job| self.prepare_upload(job)),
+            |prepared| {
+                let upload = match prepared {
+                    Ok(Some(upload)) => upload,
+                    Ok(None) => return Flow::Continue,
+                    Err(err) => {
+                        errors.push(err);
+                        return Flow::Continue;
+                    }
+                };
+
+                let hash = upload.info.hash.clone();
+
+                match upload_asset(backend, upload.info) {
+                    Ok(response) => uploaded.push((upload.target, hash, response)),
+                    Err(err) => {
+                        let rate_limited = err.is_rate_limited();
+                        errors.push(err);
+
+                        // Every other upload would be rate limited too.
+                        if rate_limited {
+                            return Flow::Stop;
+                        }
+                    }
+                }
+
+                Flow::Continue
+            },
+        );
+
+        for (target, hash, response) in uploaded {
+            self.apply_upload(target, hash, response);
+        }
+
+        for err in errors {
+            self.raise_error(err);
+        }
+
+        // TODO: Clean up output of inputs that were present in the previous
+        // sync but are no longer present.
+    }
+
+    /// Reads or encodes whatever the given job needs to upload, leaving out
+    /// inputs that haven't changed since the last sync.
+    fn prepare_upload(&self, job: UploadJob) -> Result<Option<PreparedUpload>, SyncError> {
+        match job {
+            UploadJob::Unpackable { input_name, format } => {
+                if !self.needs_upload(&input_name) {
+                    log::trace!("Input {} is unchanged.", input_name);
+                    return Ok(None);
+                }
+
+                let input = &self.inputs[&input_name];
+                let info = UploadInfo {
+                    name: self.upload_name(&input_name),
+                    contents: input.upload_contents()?,
+                    hash: input.hash.clone(),
+                    description: input.config.upload_description.clone(),
+                    format,
+                    owner: input.upload_owner(),
+                };
+
+                Ok(Some(PreparedUpload {
+                    info,
+                    target: UploadTarget::Input(input_name),
+                }))
+            }
+
+            UploadJob::Spritesheet(mut packed_image) => {
+                log::trace!(
+                    "Bleeding and encoding a spritesheet of {} image(s)",
+                    packed_image.slices.len()
+                );
+
+                alpha_bleed(&mut packed_image.image);
+                self.prepare_packed_image(packed_image).map(Some)
+            }
+        }
+    }
+
+    /// Packs the given group of images into spritesheets, unless none of them
+    /// changed since the last sync.
+    fn pack_changed_images(&self, group: &[AssetName]) -> Result<Vec<PackedImage>, SyncError> {
+        if self.are_inputs_unchanged(group) {
+            log::info!("Skipping image packing as all inputs are unchanged.");
+
+            return Ok(Vec::new());
+        }
+
+        log::trace!("Packing {} images...", group.len());
+        self.pack_images(group)
+    }
+
+    fn are_inputs_unchanged(&self, group: &[AssetName]) -> bool {
+        for name in group {
+            if self.reupload.contains(name) {
+                log::trace!("Input {} must be uploaded again", name);
+
+                return false;
+            }
+
+            if let Some(manifest) = self.original_manifest.inputs.get(name) {
+                let input = &self.inputs[name];
+                let unchanged = input.is_unchanged_since_last_sync(manifest);
+
+                if !unchanged {
+                    log::trace!("Input {} changed since last sync", name);
 
                     return false;
                 }
@@ -389,142 +1954,603 @@ impl SyncSession {
                     "Input {} was not present last sync, need to re-pack spritesheets",
                     name
                 );
-
-                return false;
+
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn pack_images(&self, group: &[AssetName]) -> Result<Vec<PackedImage>, SyncError> {
+        let mut packos_inputs = Vec::new();
+        let mut images_by_id = HashMap::new();
+
+        for name in group {
+            let input = &self.inputs[&name];
+            let image = Image::decode_png(input.read_contents()?.as_ref())?;
+
+            let input = InputItem::new(image.size());
+
+            images_by_id.insert(input.id(), (name, image));
+            packos_inputs.push(input);
+        }
+
+        let packer = SimplePacker::new()
+            .max_size(self.root_config().max_spritesheet_size)
+            .padding(1);
+
+        let pack_results = packer.pack(packos_inputs);
+        let mut packed_images = Vec::new();
+
+        for bucket in pack_results.buckets() {
+            let mut image = Image::new_empty_rgba8(bucket.size());
+            let mut slices: HashMap<AssetName, _> = HashMap::new();
+
+            for item in bucket.items() {
+                let (name, sprite_image) = &images_by_id[&item.id()];
+
+                image.blit(sprite_image, item.position());
+
+                let slice = ImageSlice::new(item.position(), item.max());
+                slices.insert((*name).clone(), slice);
+            }
+
+            packed_images.push(PackedImage { image, slices });
+        }
+
+        Ok(packed_images)
+    }
+
+    fn prepare_packed_image(&self, packed_image: PackedImage) -> Result<PreparedUpload, SyncError> {
+        let mut encoded_image = Vec::new();
+        packed_image.image.encode_png(&mut encoded_image)?;
+
+        let hash = generate_asset_hash(&encoded_image);
+
+        // Inputs are only packed together if they share an owner, so any of
+        // them can tell us where this spritesheet belongs.
+        let owner = packed_image
+            .slices
+            .keys()
+            .next()
+            .and_then(|name| self.inputs[name].upload_owner());
+
+        let info = UploadInfo {
+            name: "spritesheet".to_owned(),
+            contents: encoded_image.into(),
+            hash,
+            description: None,
+            format: AssetFormat::Png,
+            owner,
+        };
+
+        Ok(PreparedUpload {
+            info,
+            target: UploadTarget::Spritesheet(packed_image.slices),
+        })
+    }
+
+    /// Applies the metadata of an uploaded asset back to the inputs it was
+    /// uploaded for. Assets found in an upload cache weren't uploaded by this
+    /// sync, so they aren't counted as uploads.
+    fn apply_upload(&mut self, target: UploadTarget, hash: String, response: UploadResponse) {
+        if !response.cached {
+            self.upload_count += 1;
+        }
+
+        let (inputs, spritesheet) = match target {
+            UploadTarget::Input(input_name) => (vec![(input_name, None)], None),
+            UploadTarget::Spritesheet(slices) => (
+                slices
+                    .into_iter()
+                    .map(|(input_name, slice)| (input_name, Some(slice)))
+                    .collect(),
+                Some(hash),
+            ),
+        };
+
+        for (input_name, slice) in inputs {
+            let input = self.inputs.get_mut(&input_name).unwrap();
+
+            input.id = Some(response.id);
+            input.group_id = response.group_id;
+            input.user_id = response.user_id;
+            input.decal_id = response.decal_id;
+            input.universe_ids.clear();
+            input.spritesheet = spritesheet.clone();
+            input.rejected = false;
+
+            if slice.is_some() {
+                input.slice = slice;
+            }
+        }
+    }
+
+    /// The name that the given input is uploaded to Roblox with, which is
+    /// independent of the name that Tarmac tracks it by.
+    fn upload_name(&self, input_name: &AssetName) -> String {
+        let input = &self.inputs[input_name];
+        let root_config = self.root_config();
+
+        let template = input
+            .config
+            .upload_name
+            .as_deref()
+            .or(root_config.upload_name_template.as_deref())
+            .unwrap_or(upload_name::DEFAULT_TEMPLATE);
+
+        upload_name::format_upload_name(
+            template,
+            &UploadNameParts {
+                name: &input.human_name(),
+                path: input_name.as_ref(),
+                hash: &input.hash,
+                project: &root_config.name,
+            },
+        )
+    }
+
+    /// Tells whether an input that isn't packed needs to be uploaded, by
+    /// comparing its current state with the one from our last sync.
+    fn needs_upload(&self, input_name: &AssetName) -> bool {
+        if self.reupload.contains(input_name) {
+            return true;
+        }
+
+        let input = &self.inputs[input_name];
+
+        let input_manifest = match self.original_manifest.inputs.get(input_name) {
+            Some(input_manifest) => input_manifest,
+
+            // This input was added since the last sync, if there was one.
+            None => return true,
+        };
+
+        if input_manifest.hash != input.hash {
+            // The file's contents have been edited since the last sync.
+            return true;
+        }
+
+        if input.id.is_none() {
+            // This image has never been uploaded, but its hash is present in
+            // the manifest.
+            return true;
+        }
+
+        // The file's contents are the same as the previous sync and this image
+        // has been uploaded previously, so we only upload it again if its
+        // config changed.
+        //
+        // TODO: We might not need to reupload this image?
+        input_manifest.packable != input.config.packable || input.has_new_owner(input_manifest)
+    }
+
+    /// Records who each input is uploaded for, given the group or user that the
+    /// sync uploads to when an input's config doesn't pick one. Inputs that
+    /// were last uploaded for someone else are then uploaded again.
+    fn set_default_owner(&mut self, default_owner: Option<AssetCreator>) {
+        for input in self.inputs.values_mut() {
+            input.resolve_owner(default_owner);
+        }
+    }
+
+    /// Makes every input belong to the given owner, marking the ones that were
+    /// uploaded elsewhere to be uploaded again. Returns how many were marked.
+    fn migrate_to(&mut self, owner: AssetCreator) -> usize {
+        for (name, input) in &mut self.inputs {
+            let (group_id, user_id) = match owner {
+                AssetCreator::Group(group_id) => (Some(group_id), None),
+                AssetCreator::User(user_id) => (None, Some(user_id)),
+            };
+
+            input.config.upload_to_group_id = group_id;
+            input.config.upload_to_user_id = user_id;
+            input.owner = Some(owner);
+
+            // Assets uploaded before their owner was recorded are always
+            // uploaded again.
+            let already_owned = (group_id.is_some() && input.group_id == group_id)
+                || (user_id.is_some() && input.user_id == user_id);
+
+            if input.id.is_some() && !already_owned && !input.is_pinned() {
+                self.reupload.insert(name.clone());
+            }
+        }
+
+        self.reupload.len()
+    }
+
+    /// Logs how much is about to be uploaded and, if we know which account is
+    /// uploading, warns when that would approach its daily upload limit.
+    fn report_planned_uploads(&self, groups: &BTreeMap<InputKind, Vec<AssetName>>) {
+        let mut assets = 0;
+        let mut spritesheet_groups = 0;
+        let mut bytes = 0;
+
+        for (kind, group) in groups {
+            if kind.packable {
+                if !self.are_inputs_unchanged(group) {
+                    spritesheet_groups += 1;
+                    bytes += group
+                        .iter()
+                        .map(|name| self.inputs[name].content_len())
+                        .sum::<usize>();
+                }
+            } else {
+                for name in group {
+                    if self.needs_upload(name) {
+                        assets += 1;
+                        bytes += self.inputs[name].content_len();
+                    }
+                }
+            }
+        }
+
+        // We only know how many spritesheets each group packs into once it's
+        // packed, so we count at least one for each.
+        let planned = assets + spritesheet_groups;
+        if planned == 0 {
+            return;
+        }
+
+        if spritesheet_groups == 0 {
+            log::info!(
+                "Uploading {} asset(s), {} in total",
+                assets,
+                format_bytes(bytes)
+            );
+        } else {
+            log::info!(
+                "Uploading {} asset(s) and {} group(s) of packed images, about {} in total",
+                assets,
+                spritesheet_groups,
+                format_bytes(bytes)
+            );
+        }
+
+        let tracking = match &self.upload_tracking {
+            Some(tracking) => tracking,
+            None => return,
+        };
+
+        let today = tracking.ledger.uploads_today(&tracking.account);
+        log::info!("This account has uploaded {} asset(s) today", today);
+
+        if let Some(limit) = tracking.daily_limit {
+            if today + planned > limit {
+                log::warn!(
+                    "This sync uploads at least {} asset(s), which would take this account \
+                     past its daily limit of {} uploads. The sync may stop partway through.",
+                    planned,
+                    limit
+                );
+            } else if (today + planned) * 10 >= limit * 9 {
+                log::warn!(
+                    "After this sync, this account will have used {} of its {} daily uploads",
+                    today + planned,
+                    limit
+                );
             }
         }
-
-        true
     }
 
-    fn pack_images(&self, group: &[AssetName]) -> Result<Vec<PackedImage>, SyncError> {
-        let mut packos_inputs = Vec::new();
-        let mut images_by_id = HashMap::new();
+    /// Starts counting uploads made with the given credential in the upload
+    /// ledger. The ledger is only advisory, so problems reading it are logged
+    /// instead of failing the sync.
+    fn track_uploads(&mut self, credential: Option<&str>, daily_limit: Option<usize>) {
+        let credential = match credential {
+            Some(credential) => credential,
+            None => return,
+        };
 
-        for name in group {
-            let input = &self.inputs[&name];
-            let image = Image::decode_png(input.contents.as_slice())?;
+        let path = match UploadLedger::default_path() {
+            Some(path) => path,
+            None => {
+                log::debug!("Couldn't find a home directory to keep the upload ledger in");
+                return;
+            }
+        };
 
-            let input = InputItem::new(image.size());
+        match UploadLedger::read_from_file(&path) {
+            Ok(ledger) => {
+                self.upload_tracking = Some(UploadTracking {
+                    path,
+                    ledger,
+                    account: UploadLedger::account_key(credential),
+                    daily_limit,
+                });
+            }
+            Err(err) => log::warn!("Couldn't read the upload ledger: {}", err),
+        }
+    }
 
-            images_by_id.insert(input.id(), (name, image));
-            packos_inputs.push(input);
+    /// Adds the uploads made during this sync to the upload ledger.
+    fn record_uploads(&mut self) {
+        let tracking = match &mut self.upload_tracking {
+            Some(tracking) => tracking,
+            None => return,
+        };
+
+        if self.upload_count == 0 {
+            return;
         }
 
-        let packer = SimplePacker::new()
-            .max_size(self.root_config().max_spritesheet_size)
-            .padding(1);
+        tracking
+            .ledger
+            .record_uploads(&tracking.account, self.upload_count);
 
-        let pack_results = packer.pack(packos_inputs);
-        let mut packed_images = Vec::new();
+        if let Err(err) = tracking.ledger.write_to_file(&tracking.path) {
+            log::warn!("Couldn't save the upload ledger: {}", err);
+        }
+    }
 
-        for bucket in pack_results.buckets() {
-            let mut image = Image::new_empty_rgba8(bucket.size());
-            let mut slices: HashMap<AssetName, _> = HashMap::new();
+    /// Finds the assets that were uploaded during this sync, along with the
+    /// inputs that each of them contains. Packed inputs share an asset, so
+    /// they're grouped by ID to only visit each asset once.
+    fn uploaded_assets(&self) -> BTreeMap<u64, Vec<AssetName>> {
+        let mut uploaded: BTreeMap<u64, Vec<AssetName>> = BTreeMap::new();
 
-            for item in bucket.items() {
-                let (name, sprite_image) = &images_by_id[&item.id()];
+        for (name, input) in self.inputs.iter().filter(|(_, input)| !input.is_pinned()) {
+            let original_id = self
+                .original_manifest
+                .inputs
+                .get(name)
+                .and_then(|original| original.id);
 
-                image.blit(sprite_image, item.position());
+            if let Some(id) = input.id {
+                if original_id != Some(id) {
+                    uploaded.entry(id).or_default().push(name.clone());
+                }
+            }
+        }
 
-                let slice = ImageSlice::new(item.position(), item.max());
-                slices.insert((*name).clone(), slice);
+        uploaded
+    }
+
+    /// Gives the given universe permission to use every asset in the project
+    /// that it can't use yet. Failures are recorded as sync errors, and the
+    /// assets involved are tried again during the next sync.
+    fn grant_universe_access(
+        &mut self,
+        api_client: &mut RobloxApiClient,
+        api_key: &str,
+        universe_id: u64,
+    ) {
+        let mut needs_access: BTreeMap<u64, Vec<AssetName>> = BTreeMap::new();
+
+        // Pinned assets belong to someone else, who decides which universes
+        // can use them.
+        for (name, input) in self.inputs.iter().filter(|(_, input)| !input.is_pinned()) {
+            if let Some(id) = input.id {
+                if !input.universe_ids.contains(&universe_id) {
+                    needs_access.entry(id).or_default().push(name.clone());
+                }
             }
+        }
 
-            packed_images.push(PackedImage { image, slices });
+        if needs_access.is_empty() {
+            return;
         }
 
-        Ok(packed_images)
+        log::info!(
+            "Giving universe {} access to {} asset(s)",
+            universe_id,
+            needs_access.len()
+        );
+
+        for (id, names) in needs_access {
+            if let Err(err) = api_client.grant_universe_access(id, universe_id, api_key) {
+                self.raise_error(anyhow::Error::new(err).context(format!(
+                    "Couldn't give universe {} access to asset {}",
+                    universe_id, id
+                )));
+                continue;
+            }
+
+            for name in names {
+                let input = self.inputs.get_mut(&name).unwrap();
+                input.universe_ids.push(universe_id);
+                input.universe_ids.sort_unstable();
+            }
+        }
     }
 
-    fn sync_packed_image<S: SyncBackend>(
+    /// Reports the moderation state of every asset uploaded during this sync,
+    /// waiting up to `timeout` for assets that are still being reviewed.
+    ///
+    /// Inputs in rejected assets are quarantined: their ID is dropped and they
+    /// are marked as rejected in the manifest so that they aren't uploaded
+    /// again until they change.
+    fn check_moderation(
         &mut self,
-        backend: &mut S,
-        packed_image: &PackedImage,
-    ) -> Result<(), SyncError> {
-        let mut encoded_image = Vec::new();
-        packed_image.image.encode_png(&mut encoded_image)?;
+        api_client: &mut RobloxApiClient,
+        api_key: &str,
+        timeout: Duration,
+    ) {
+        let uploaded = self.uploaded_assets();
+
+        if uploaded.is_empty() {
+            return;
+        }
 
-        let hash = generate_asset_hash(&encoded_image);
+        log::info!(
+            "Checking moderation of {} uploaded asset(s)",
+            uploaded.len()
+        );
+
+        let deadline = Instant::now() + timeout;
+        let mut pending = uploaded;
+        let mut rejected = Vec::new();
+
+        loop {
+            let mut still_pending = BTreeMap::new();
+
+            for (id, names) in pending {
+                let names_list = names
+                    .iter()
+                    .map(|name| name.as_ref())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                // Failing here would lose the IDs of everything this sync
+                // uploaded, so assets we can't check are treated as pending.
+                match api_client.get_moderation_state(id, api_key) {
+                    Ok(ModerationState::Approved) => {
+                        log::info!("Asset {} ({}) was approved", id, names_list);
+                    }
+                    Ok(ModerationState::Rejected) => {
+                        log::error!("Asset {} ({}) was rejected by moderation", id, names_list);
+                        rejected.extend(names);
+                    }
+                    Ok(ModerationState::Reviewing) | Ok(ModerationState::Unknown) => {
+                        still_pending.insert(id, names);
+                    }
+                    Err(err) => {
+                        log::warn!(
+                            "Couldn't check the moderation of asset {} ({}): {}",
+                            id,
+                            names_list,
+                            err
+                        );
+                        still_pending.insert(id, names);
+                    }
+                }
+            }
 
-        let upload_data = UploadInfo {
-            name: "spritesheet".to_owned(),
-            contents: encoded_image,
-            hash: hash.clone(),
-        };
+            pending = still_pending;
 
-        let id = backend.upload(upload_data)?.id;
+            if pending.is_empty() || Instant::now() >= deadline {
+                break;
+            }
+
+            thread::sleep(MODERATION_POLL_DELAY);
+        }
 
-        // Apply resolved metadata back to the inputs
-        for (asset_name, slice) in &packed_image.slices {
-            let input = self.inputs.get_mut(asset_name).unwrap();
+        for (id, names) in &pending {
+            let names_list = names
+                .iter()
+                .map(|name| name.as_ref())
+                .collect::<Vec<_>>()
+                .join(", ");
 
-            input.id = Some(id);
-            input.slice = Some(*slice);
+            log::warn!("Asset {} ({}) is still pending moderation", id, names_list);
         }
 
-        Ok(())
-    }
+        if rejected.is_empty() {
+            return;
+        }
 
-    fn sync_unpackable_image<S: SyncBackend>(
-        &mut self,
-        backend: &mut S,
-        input_name: &AssetName,
-    ) -> Result<(), SyncError> {
-        let input = self.inputs.get_mut(input_name).unwrap();
-
-        let upload_data = UploadInfo {
-            name: input.human_name(),
-            contents: input.contents.clone(),
-            hash: input.hash.clone(),
-        };
+        let mut report = String::new();
+        for name in &rejected {
+            let input = self.inputs.get_mut(name).unwrap();
 
-        let id = if let Some(input_manifest) = self.original_manifest.inputs.get(&input_name) {
-            // This input existed during our last sync operation. We'll compare
-            // the current state with the previous one to see if we need to take
-            // action.
+            input.id = None;
+            input.group_id = None;
+            input.user_id = None;
+            input.decal_id = None;
+            input.universe_ids.clear();
+            input.slice = None;
+            input.spritesheet = None;
+            input.rejected = true;
 
-            if input_manifest.hash != input.hash {
-                // The file's contents have been edited since the last sync.
+            report.push_str(&format!("\n  {}", input.path.display()));
+        }
+
+        log::error!(
+            "The following files were rejected by moderation. They will be skipped \
+             by codegen and won't be uploaded again until they change:{}",
+            report
+        );
 
-                log::trace!("Contents changed...");
+        self.raise_error(SyncError::AssetsRejected {
+            count: rejected.len(),
+        });
+    }
 
-                backend.upload(upload_data)?.id
-            } else if input.id.is_some() {
-                // The file's contents are the same as the previous sync and
-                // this image has been uploaded previously.
+    /// Downloads every image uploaded during this sync and compares it with
+    /// the local images it was made from, to catch uploads that Roblox
+    /// processed into something else, like by downscaling them.
+    ///
+    /// Assets that can't be downloaded yet, like ones still being moderated,
+    /// are skipped with a warning.
+    fn verify_uploads(&mut self, api_client: &mut RobloxApiClient) -> Result<(), SyncError> {
+        let uploaded: Vec<_> = self
+            .uploaded_assets()
+            .into_iter()
+            .filter(|(_, names)| self.inputs[&names[0]].is_image())
+            .collect();
 
-                if input_manifest.packable != input.config.packable {
-                    // Only the file's config has changed.
-                    //
-                    // TODO: We might not need to reupload this image?
+        if uploaded.is_empty() {
+            return Ok(());
+        }
 
-                    log::trace!("Config changed...");
+        log::info!("Verifying {} uploaded image(s)", uploaded.len());
 
-                    backend.upload(upload_data)?.id
-                } else {
-                    // Nothing has changed, we're good to go!
+        let mut mismatched = Vec::new();
 
-                    log::trace!("Input is unchanged.");
-                    return Ok(());
+        for (id, names) in uploaded {
+            let contents = match api_client.download_asset(id) {
+                Ok(contents) => contents,
+                Err(err) => {
+                    log::warn!("Couldn't download asset {} to verify it: {}", id, err);
+                    continue;
                 }
-            } else {
-                // This image has never been uploaded, but its hash is present
-                // in the manifest.
+            };
 
-                log::trace!("Image has never been uploaded...");
+            let uploaded_image = match Image::decode_png_as_rgba8(contents.as_slice()) {
+                Ok(image) => image,
+                Err(err) => {
+                    log::error!("Asset {} isn't an image that Tarmac can read: {}", id, err);
+                    mismatched.extend(names);
+                    continue;
+                }
+            };
 
-                backend.upload(upload_data)?.id
-            }
-        } else {
-            // This input was added since the last sync, if there was one.
+            for name in names {
+                let input = &self.inputs[&name];
+                let contents = match input.read_contents() {
+                    Ok(contents) => contents,
+                    Err(err) => {
+                        log::warn!("Couldn't verify '{}': {}", input.path.display(), err);
+                        continue;
+                    }
+                };
+                let local_image = match Image::decode_png_as_rgba8(contents.as_ref()) {
+                    Ok(image) => image,
+                    Err(err) => {
+                        log::warn!("Couldn't verify '{}': {}", input.path.display(), err);
+                        continue;
+                    }
+                };
 
-            log::trace!("Image was added since last sync...");
+                let matches = match input.slice {
+                    Some(slice) => {
+                        local_image.matches_region(&uploaded_image, slice.min(), VERIFY_TOLERANCE)
+                    }
+                    None => {
+                        local_image.size() == uploaded_image.size()
+                            && local_image.matches_region(&uploaded_image, (0, 0), VERIFY_TOLERANCE)
+                    }
+                };
 
-            backend.upload(upload_data)?.id
-        };
+                if !matches {
+                    log::error!(
+                        "Asset {} doesn't match '{}' after uploading",
+                        id,
+                        input.path.display()
+                    );
+
+                    mismatched.push(name);
+                }
+            }
+        }
 
-        input.id = Some(id);
+        if !mismatched.is_empty() {
+            self.raise_error(SyncError::VerificationFailed {
+                count: mismatched.len(),
+            });
+        }
 
         Ok(())
     }
@@ -532,24 +2558,45 @@ impl SyncSession {
     fn write_manifest(&self) -> Result<(), SyncError> {
         log::trace!("Generating new manifest");
 
-        let mut manifest = Manifest::default();
+        let mut synced = Manifest::default();
 
-        manifest.inputs = self
+        synced.inputs = self
             .inputs
             .iter()
             .map(|(name, input)| {
+                // Inputs left out of this sync by --tag keep the hash they were
+                // last uploaded with, so that later syncs notice if they've
+                // changed since.
+                let hash = if input.config.has_any_tag(&self.upload_tags) {
+                    input.hash.clone()
+                } else {
+                    self.original_manifest
+                        .inputs
+                        .get(name)
+                        .map(|original| original.hash.clone())
+                        .unwrap_or_default()
+                };
+
                 (
                     name.clone(),
                     InputManifest {
-                        hash: input.hash.clone(),
+                        hash,
                         id: input.id,
+                        group_id: input.group_id,
+                        user_id: input.user_id,
+                        decal_id: input.decal_id,
+                        universe_ids: input.universe_ids.clone(),
                         slice: input.slice,
+                        spritesheet: input.spritesheet.clone(),
+                        rejected: input.rejected,
                         packable: input.config.packable,
+                        targets: BTreeMap::new(),
                     },
                 )
             })
             .collect();
 
+        let manifest = self.stored_manifest.merge_key(&self.manifest_key, synced);
         manifest.write_to_folder(self.root_config().folder())?;
 
         Ok(())
@@ -561,30 +2608,70 @@ impl SyncSession {
         #[derive(Debug, Clone, PartialEq, Eq, Hash)]
         struct CodegenCompatibility<'a> {
             output_path: Option<&'a Path>,
+            format: CodegenFormat,
+            options: CodegenOptions<'a>,
         }
 
         let mut compatible_codegen_groups = HashMap::new();
 
         for (input_name, input) in &self.inputs {
+            // Inputs that opted out of codegen are still uploaded and tracked
+            // in the manifest, but shouldn't show up in any generated files.
+            if !input.config.codegen {
+                continue;
+            }
+
             let output_path = input
                 .config
                 .codegen_path
                 .as_ref()
                 .map(|path| path.as_path());
 
-            let compat = CodegenCompatibility { output_path };
+            let options = CodegenOptions::from_input_config(&input.config);
+
+            let primary = CodegenCompatibility {
+                output_path,
+                format: CodegenFormat::Lua,
+                options: options.clone(),
+            };
+
+            let additional = input
+                .config
+                .codegen_outputs
+                .iter()
+                .filter(|output| input.config.has_any_tag(&output.tags))
+                .map(|output| CodegenCompatibility {
+                    output_path: Some(&output.path),
+                    format: output.format,
+                    options: options.clone(),
+                });
+
+            for compat in std::iter::once(primary).chain(additional) {
+                let group = compatible_codegen_groups
+                    .entry(compat)
+                    .or_insert_with(Vec::new);
+                group.push(input_name.clone());
+            }
+        }
 
-            let group = compatible_codegen_groups
-                .entry(compat)
-                .or_insert_with(Vec::new);
-            group.push(input_name.clone());
+        // Input groups can share an output file as long as they agree on how it
+        // should be generated. Otherwise, they'd overwrite eachother's output.
+        let mut claimed_paths = HashSet::new();
+        for compat in compatible_codegen_groups.keys() {
+            if let Some(output_path) = compat.output_path {
+                if !claimed_paths.insert(output_path) {
+                    return Err(SyncError::ConflictingCodegenOutputs {
+                        path: output_path.to_owned(),
+                    });
+                }
+            }
         }
 
         for (compat, names) in compatible_codegen_groups {
             let inputs: Vec<_> = names.iter().map(|name| &self.inputs[name]).collect();
             let output_path = compat.output_path;
 
-            perform_codegen(output_path, &inputs)?;
+            perform_codegen(output_path, compat.format, &compat.options, &inputs)?;
         }
 
         Ok(())
@@ -603,9 +2690,7 @@ impl SyncSession {
 
         let mut file = BufWriter::new(fs_err::File::create(list_path)?);
 
-        let known_ids: BTreeSet<u64> = self.inputs.values().filter_map(|input| input.id).collect();
-
-        for id in known_ids {
+        for id in self.known_ids() {
             writeln!(file, "rbxassetid://{}", id)?;
         }
 
@@ -613,6 +2698,70 @@ impl SyncSession {
         Ok(())
     }
 
+    /// Copies every input that generates code into the configured development
+    /// content folder, so that generated code can refer to them without
+    /// waiting for an upload.
+    fn write_dev_content(&mut self) -> Result<(), SyncError> {
+        let dev_content = match &self.root_config().dev_content {
+            Some(dev_content) => dev_content.clone(),
+            None => return Ok(()),
+        };
+
+        log::debug!("Writing development content");
+
+        let base_url = dev_content.url.trim_end_matches('/');
+
+        for input in self.inputs.values_mut() {
+            if !input.config.codegen {
+                continue;
+            }
+
+            let path = dev_content.path.join(input.name.as_ref());
+            fs_err::create_dir_all(path.parent().unwrap())?;
+            fs_err::write(&path, input.read_contents()?)?;
+
+            input.dev_url = Some(format!("{}/{}", base_url, input.name.to_url_path()));
+        }
+
+        Ok(())
+    }
+
+    /// The IDs of every asset that the project uses, including the assets of
+    /// the packages it depends on.
+    fn known_ids(&self) -> BTreeSet<u64> {
+        let mut known_ids: BTreeSet<u64> =
+            self.inputs.values().filter_map(|input| input.id).collect();
+
+        known_ids.extend(self.package_ids.iter().copied());
+        known_ids
+    }
+
+    fn write_preload_list(&self) -> Result<(), SyncError> {
+        let preload_path = match &self.root_config().preload_list_path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        log::debug!("Writing preload list");
+
+        // Packages don't know the tags of their assets, so a preload list
+        // limited to some tags only contains the project's own assets.
+        let tags = &self.root_config().preload_list_tags;
+        let ids = if tags.is_empty() {
+            self.known_ids()
+        } else {
+            self.inputs
+                .values()
+                .filter(|input| input.config.has_any_tag(tags))
+                .filter_map(|input| input.id)
+                .collect()
+        };
+
+        perform_preload_codegen(preload_path, &ids)?;
+
+        Ok(())
+    }
+
     fn populate_asset_cache(&self, api_client: &mut RobloxApiClient) -> Result<(), SyncError> {
         let cache_path = match &self.root_config().asset_cache_path {
             Some(path) => path,
@@ -685,12 +2834,35 @@ impl SyncSession {
     }
 }
 
-fn is_image_asset(path: &Path) -> bool {
-    match path.extension().and_then(|ext| ext.to_str()) {
-        // TODO: Expand the definition of images?
-        Some("png") | Some("jpg") => true,
+/// Uploads an asset through the given backend, attaching the asset's name to
+/// any error that comes up.
+fn upload_asset<S: SyncBackend>(
+    backend: &mut S,
+    data: UploadInfo,
+) -> Result<UploadResponse, SyncError> {
+    let name = data.name.clone();
+
+    backend
+        .upload(data)
+        .map_err(|source| SyncError::Upload { name, source })
+}
 
-        _ => false,
+/// Formats a number of bytes for people to read, like `1.5 MB`.
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["bytes", "KB", "MB", "GB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
     }
 }
 
@@ -698,17 +2870,111 @@ fn generate_asset_hash(content: &[u8]) -> String {
     format!("{}", blake3::hash(content).to_hex())
 }
 
+/// Hashes the file at the given path the same way as generate_asset_hash,
+/// without reading the whole file into memory.
+fn generate_file_hash(path: &Path) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut fs_err::File::open(path)?, &mut hasher)?;
+
+    Ok(format!("{}", hasher.finalize().to_hex()))
+}
+
 #[derive(Debug, Error)]
 pub enum SyncError {
-    #[error("Path {} was described by more than one glob", .path.display())]
+    #[error("Inputs {first} and {second} are both pinned to asset {id}. An asset-id applies to every file that its input group matches, so set it in a sidecar file, or in an input group whose glob matches one file.")]
+    SharedPinnedId {
+        id: u64,
+        first: AssetName,
+        second: AssetName,
+    },
+
+    #[error(
+        "{} sets an upload-api that's different from the root config {}. Every asset in a sync is uploaded through the root config's upload-api, so set it there instead.",
+        .path.display(),
+        .root.display()
+    )]
+    NestedUploadApi { path: PathBuf, root: PathBuf },
+
+    #[error("Path {} was described by more than one glob. Set overlapping-globs in tarmac.toml to pick between them.", .path.display())]
     OverlappingGlobs { path: PathBuf },
 
+    #[error(
+        "Image {} is {}x{}, which is larger than its input group's max-dimensions of {}x{}. Scale it down, or set max-dimensions-policy to resize it automatically.",
+        .path.display(),
+        .size.0,
+        .size.1,
+        .max_dimensions.0,
+        .max_dimensions.1
+    )]
+    ImageTooLarge {
+        path: PathBuf,
+        size: (u32, u32),
+        max_dimensions: (u32, u32),
+    },
+
+    #[error(
+        "Asset name {name} is used by both {} from {} and {} from {}. Rename one of the files.",
+        .first_path.display(),
+        .first_config.display(),
+        .second_path.display(),
+        .second_config.display()
+    )]
+    AssetNameCollision {
+        name: AssetName,
+        first_path: PathBuf,
+        first_config: PathBuf,
+        second_path: PathBuf,
+        second_config: PathBuf,
+    },
+
+    #[error(
+        "Path {} is used as a codegen output by input groups with different codegen options",
+        .path.display()
+    )]
+    ConflictingCodegenOutputs { path: PathBuf },
+
+    #[error("No sync target was given. Pass one with --target or select a profile that defines one with --profile.")]
+    MissingTarget,
+
+    #[error("Uploading with Open Cloud requires an API key. Pass one with --api-key or set TARMAC_API_KEY.")]
+    MissingApiKey,
+
+    #[error("Uploading to Roblox requires a .ROBLOSECURITY cookie. Pass one with --auth or --auth-file, set TARMAC_AUTH, run 'tarmac login', or log into Roblox Studio.")]
+    MissingAuth,
+
+    #[error("Roblox rejected the .ROBLOSECURITY cookie. It has probably expired; run 'tarmac login' or log into Roblox Studio again to get a new one.")]
+    InvalidAuth { source: RobloxApiError },
+
+    #[error("Roblox rejected the Open Cloud API key. Check that it hasn't been deleted or had its IP restrictions changed.")]
+    InvalidApiKey { source: RobloxApiError },
+
+    #[error("Open Cloud API key {name:?} is disabled or expired")]
+    UnusableApiKey { name: String },
+
+    #[error("Giving a universe access to assets requires the Open Cloud upload API. Set upload-api to \"open-cloud\".")]
+    UniverseRequiresOpenCloud,
+
+    #[error("{count} input(s) were rejected by moderation")]
+    AssetsRejected { count: usize },
+
+    #[error("{count} input(s) didn't match their uploaded asset")]
+    VerificationFailed { count: usize },
+
+    #[error("The sync was stopped with Ctrl-C. The manifest records the assets that were uploaded, and the next sync uploads the rest.")]
+    Interrupted,
+
     #[error("'tarmac sync' completed, but with {error_count} error(s)")]
     HadErrors { error_count: usize },
 
-    #[error(transparent)]
+    #[error("{failed} of {total} project(s) failed to sync")]
+    ProjectsHadErrors { failed: usize, total: usize },
+
+    #[error("'tarmac migrate-ownership' failed with {error_count} error(s), so the manifest was left unchanged. Assets uploaded before the failure will be uploaded again by the next attempt.")]
+    MigrationFailed { error_count: usize },
+
+    #[error("Couldn't search {} for inputs", .path.display())]
     WalkDir {
-        #[from]
+        path: PathBuf,
         source: walkdir::Error,
     },
 
@@ -719,8 +2985,26 @@ pub enum SyncError {
     },
 
     #[error(transparent)]
-    Backend {
+    Ignore {
+        #[from]
+        source: IgnoreError,
+    },
+
+    #[error(transparent)]
+    RemoteInclude {
+        #[from]
+        source: RemoteIncludeError,
+    },
+
+    #[error(transparent)]
+    Workspace {
         #[from]
+        source: WorkspaceError,
+    },
+
+    #[error("Couldn't upload {name}")]
+    Upload {
+        name: String,
         source: SyncBackendError,
     },
 
@@ -730,6 +3014,24 @@ pub enum SyncError {
         source: ManifestError,
     },
 
+    #[error(transparent)]
+    Package {
+        #[from]
+        source: PackageError,
+    },
+
+    #[error(transparent)]
+    UserConfig {
+        #[from]
+        source: UserConfigError,
+    },
+
+    #[error(transparent)]
+    Codegen {
+        #[from]
+        source: CodegenError,
+    },
+
     #[error(transparent)]
     Io {
         #[from]
@@ -758,10 +3060,269 @@ pub enum SyncError {
 impl SyncError {
     pub fn is_rate_limited(&self) -> bool {
         match self {
-            Self::Backend {
+            Self::Upload {
                 source: SyncBackendError::RateLimited,
+                ..
             } => true,
             _ => false,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::process;
+
+    use super::*;
+
+    use crate::asset_uploader::MemoryUploader;
+
+    fn project(name: &str, config: &str, files: &[&str]) -> PathBuf {
+        let folder = env::temp_dir().join(format!("tarmac-sync-{}-{}", name, process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join("tarmac.toml"), config).unwrap();
+
+        for file in files {
+            fs::write(folder.join(file), b"not really a png").unwrap();
+        }
+
+        folder
+    }
+
+    /// Removes a test project along with the sync index that discovering
+    /// its inputs wrote.
+    fn remove_project(folder: &Path) {
+        if let Some(path) = SyncIndex::path_for_project(folder) {
+            let _ = fs::remove_file(path);
+        }
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    fn discover(folder: &Path) -> Result<SyncSession, SyncError> {
+        let mut session = SyncSession::new(folder)?;
+        session.discover_configs(&Client::new())?;
+        session.discover_packages()?;
+        session.discover_inputs()?;
+
+        Ok(session)
+    }
+
+    #[test]
+    fn pinned_inputs_keep_their_id() {
+        let folder = project(
+            "pinned",
+            "name = \"pinned\"\n\
+             [[inputs]]\n\
+             glob = \"logo.png\"\n\
+             asset-id = 55\n\
+             codegen = true\n\
+             codegen-path = \"Assets.lua\"\n\
+             codegen-base-path = \".\"\n",
+            &["logo.png"],
+        );
+
+        let mut session = discover(&folder).unwrap();
+        sync_session(&mut session, None, 0, NoneSyncBackend);
+        session.write_manifest().unwrap();
+        session.codegen().unwrap();
+
+        let manifest = Manifest::read_from_folder(&folder).unwrap();
+        assert_eq!(manifest.inputs[&AssetName::new("logo.png")].id, Some(55));
+
+        let codegen = fs::read_to_string(folder.join("Assets.lua")).unwrap();
+        assert!(codegen.contains("rbxassetid://55"), "{}", codegen);
+
+        remove_project(&folder);
+    }
+
+    #[test]
+    fn pinned_globs_must_match_one_file() {
+        let folder = project(
+            "pinned-glob",
+            "name = \"pinned\"\n[[inputs]]\nglob = \"*.png\"\nasset-id = 55\n",
+            &["a.png", "b.png"],
+        );
+
+        assert!(matches!(
+            discover(&folder),
+            Err(SyncError::SharedPinnedId { id: 55, .. })
+        ));
+
+        remove_project(&folder);
+    }
+
+    #[test]
+    fn dev_content_urls_are_encoded() {
+        let folder = project(
+            "dev-content",
+            "name = \"game\"\n\
+             dev-content = { path = \"studio\", url = \"rbxasset://tarmac/\" }\n\
+             [[inputs]]\n\
+             glob = \"*.png\"\n\
+             codegen = true\n",
+            &["play button#2.png"],
+        );
+
+        let mut session = discover(&folder).unwrap();
+        session.write_dev_content().unwrap();
+
+        let input = &session.inputs[&AssetName::new("play button#2.png")];
+        assert_eq!(
+            input.dev_url.as_deref(),
+            Some("rbxasset://tarmac/play%20button%232.png")
+        );
+        assert!(folder.join("studio/play button#2.png").is_file());
+
+        remove_project(&folder);
+    }
+
+    #[test]
+    fn included_configs_use_the_root_upload_api() {
+        let folder = project(
+            "upload-api",
+            "name = \"game\"\nupload-api = \"open-cloud\"\nincludes = [\"ui\"]\n",
+            &[],
+        );
+        fs::create_dir_all(folder.join("ui")).unwrap();
+
+        let ui_config = folder.join("ui/tarmac.toml");
+        fs::write(&ui_config, "name = \"ui\"\n").unwrap();
+
+        let session = discover(&folder).unwrap();
+        assert_eq!(session.root_config().upload_api(), UploadApi::OpenCloud);
+
+        fs::write(&ui_config, "name = \"ui\"\nupload-api = \"open-cloud\"\n").unwrap();
+        assert!(discover(&folder).is_ok());
+
+        fs::write(&ui_config, "name = \"ui\"\nupload-api = \"legacy\"\n").unwrap();
+        assert!(matches!(
+            discover(&folder),
+            Err(SyncError::NestedUploadApi { path, .. }) if path == ui_config
+        ));
+
+        remove_project(&folder);
+    }
+
+    #[test]
+    fn preload_lists_only_have_selected_tags() {
+        let folder = project(
+            "preload-list",
+            "name = \"game\"\n\
+             preload-list-path = \"preload.lua\"\n\
+             preload-list-tags = [\"menu\"]\n\
+             [[inputs]]\n\
+             glob = \"menu-*.png\"\n\
+             tags = [\"menu\"]\n\
+             [[inputs]]\n\
+             glob = \"level-*.png\"\n",
+            &["menu-play.png", "level-tree.png"],
+        );
+
+        let mut session = discover(&folder).unwrap();
+        for (name, id) in &[("menu-play.png", 1), ("level-tree.png", 2)] {
+            session.inputs.get_mut(&AssetName::new(*name)).unwrap().id = Some(*id);
+        }
+
+        session.write_preload_list().unwrap();
+        let preload_list = fs::read_to_string(folder.join("preload.lua")).unwrap();
+        assert!(preload_list.contains("\"rbxassetid://1\""));
+        assert!(!preload_list.contains("\"rbxassetid://2\""));
+
+        // Without any tags, every known asset is preloaded.
+        session.configs[0].preload_list_tags.clear();
+        session.write_preload_list().unwrap();
+        let preload_list = fs::read_to_string(folder.join("preload.lua")).unwrap();
+        assert!(preload_list.ends_with("return {\n\t\"rbxassetid://1\",\n\t\"rbxassetid://2\",\n}"));
+
+        remove_project(&folder);
+    }
+
+    #[test]
+    fn input_groups_generate_into_their_own_paths() {
+        let config = "name = \"game\"\n\
+                      [[inputs]]\n\
+                      glob = \"ui-*.png\"\n\
+                      codegen = true\n\
+                      codegen-path = \"src/UI/Assets.lua\"\n\
+                      [[inputs]]\n\
+                      glob = \"icon-*.png\"\n\
+                      codegen = true\n\
+                      codegen-path = \"src/Gameplay/Icons.lua\"\n";
+        let folder = project("codegen-paths", config, &["ui-play.png", "icon-sword.png"]);
+
+        let mut session = discover(&folder).unwrap();
+        for (name, id) in &[("ui-play.png", 1), ("icon-sword.png", 2)] {
+            session.inputs.get_mut(&AssetName::new(*name)).unwrap().id = Some(*id);
+        }
+
+        session.codegen().unwrap();
+
+        let ui = fs::read_to_string(folder.join("src/UI/Assets.lua")).unwrap();
+        assert!(ui.contains("rbxassetid://1"));
+        assert!(!ui.contains("rbxassetid://2"));
+
+        let icons = fs::read_to_string(folder.join("src/Gameplay/Icons.lua")).unwrap();
+        assert!(icons.contains("rbxassetid://2"));
+        assert!(!icons.contains("rbxassetid://1"));
+
+        // Groups that generate the same file differently would overwrite each
+        // other's output.
+        fs::write(
+            folder.join("tarmac.toml"),
+            config.replace("src/Gameplay/Icons.lua", "src/UI/Assets.lua")
+                + "codegen-kind = \"lookup\"\n",
+        )
+        .unwrap();
+
+        let session = discover(&folder).unwrap();
+        assert!(matches!(
+            session.codegen(),
+            Err(SyncError::ConflictingCodegenOutputs { path }) if path.ends_with("src/UI/Assets.lua")
+        ));
+
+        remove_project(&folder);
+    }
+
+    #[test]
+    fn inputs_override_their_upload_name_and_description() {
+        let folder = project(
+            "upload-name",
+            "name = \"game\"\n\
+             upload-name-template = \"{project} {name}\"\n\
+             [[inputs]]\n\
+             glob = \"*.png\"\n\
+             upload-description = \"Menu art\"\n",
+            &["play.png", "close.png"],
+        );
+        fs::write(
+            folder.join("close.png.toml"),
+            "upload-name = \"{name} button\"\nupload-description = \"Closes menus\"\n",
+        )
+        .unwrap();
+
+        let mut session = discover(&folder).unwrap();
+        let mut uploader = MemoryUploader::default();
+        sync_session(
+            &mut session,
+            None,
+            0,
+            RobloxSyncBackend::new(&mut uploader, None),
+        );
+
+        let mut uploads: Vec<_> = uploader
+            .uploads
+            .iter()
+            .map(|upload| (upload.name.as_str(), upload.description.as_str()))
+            .collect();
+        uploads.sort_unstable();
+        uploads.dedup();
+
+        assert_eq!(
+            uploads,
+            vec![("close button", "Closes menus"), ("game play", "Menu art"),]
+        );
+
+        remove_project(&folder);
+    }
+}
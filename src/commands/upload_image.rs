@@ -1,35 +1,68 @@
-use std::borrow::Cow;
+use std::{io, path::PathBuf};
 
-use fs_err as fs;
+use thiserror::Error;
 
 use crate::{
-    auth_cookie::get_auth_cookie,
+    data::UserConfigError,
     options::{GlobalOptions, UploadImageOptions},
-    roblox_web_api::{ImageUploadData, RobloxApiClient},
+    roblox_web_api::{ImageUploadData, RobloxApiClient, RobloxApiError, UploadContents},
 };
 
-pub fn upload_image(global: GlobalOptions, options: UploadImageOptions) {
-    let auth = global
-        .auth
-        .clone()
-        .or_else(get_auth_cookie)
-        .expect("no auth cookie found");
+pub fn upload_image(
+    global: GlobalOptions,
+    options: UploadImageOptions,
+) -> Result<(), UploadImageError> {
+    let credentials = global.credentials()?;
+    let auth = credentials.auth.ok_or(UploadImageError::NoAuthCookie)?;
 
-    let image_data = fs::read(options.path).expect("couldn't read input file");
-
-    let mut client = RobloxApiClient::new(Some(auth));
+    let mut client = RobloxApiClient::new(Some(auth), &global.client_options())?;
 
     let upload_data = ImageUploadData {
-        image_data: Cow::Owned(image_data),
+        image_data: UploadContents::from_file(&options.path, None)?,
         name: &options.name,
         description: &options.description,
-        group_id: None,
+        group_id: options.group_id.or(credentials.group_id),
     };
 
     let response = client
         .upload_image(upload_data)
-        .expect("Roblox API request failed");
+        .map_err(|source| UploadImageError::Upload {
+            path: options.path.clone(),
+            source,
+        })?;
 
     eprintln!("Image uploaded successfully!");
     println!("{}", response.backing_asset_id);
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum UploadImageError {
+    #[error("No Roblox auth cookie was found. Pass one with --auth.")]
+    NoAuthCookie,
+
+    #[error("Couldn't upload {}", .path.display())]
+    Upload {
+        path: PathBuf,
+        source: RobloxApiError,
+    },
+
+    #[error(transparent)]
+    UserConfig {
+        #[from]
+        source: UserConfigError,
+    },
+
+    #[error(transparent)]
+    RobloxApi {
+        #[from]
+        source: RobloxApiError,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
 }
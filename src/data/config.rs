@@ -1,17 +1,29 @@
 use std::{
-    io,
+    collections::BTreeMap,
+    env, io,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
 use fs_err as fs;
-use serde::{Deserialize, Serialize};
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::glob::Glob;
+use walkdir::WalkDir;
 
-static CONFIG_FILENAME: &str = "tarmac.toml";
+use crate::{
+    glob::{self, Glob},
+    options::SyncTarget,
+    remote_include::{self, FetchedInclude, RemoteIncludeError, REMOTE_INCLUDES_FOLDER},
+};
+
+/// The names that a project's config file can have, in the order they're
+/// looked for.
+pub static CONFIG_FILENAMES: &[&str] = &["tarmac.toml", "tarmac.json", "tarmac.yaml", "tarmac.yml"];
 
-/// Configuration for Tarmac, contained in a tarmac.toml file.
+/// Configuration for Tarmac, contained in a tarmac.toml file, or a tarmac.json
+/// or tarmac.yaml file for projects whose configs are generated by other tools.
 ///
 /// Tarmac is started from a top-level tarmac.toml file. Config files can
 /// include other config files.
@@ -34,17 +46,113 @@ pub struct Config {
     /// referred to by this project.
     pub asset_list_path: Option<PathBuf>,
 
+    /// A path to a file where Tarmac will write a Lua module returning a flat
+    /// list of all of the asset URLs referred to by this project, suitable for
+    /// passing to `ContentProvider:PreloadAsync`.
+    pub preload_list_path: Option<PathBuf>,
+
+    /// If not empty, the preload list only contains the assets of inputs with
+    /// at least one of these tags. Only applies if this config is the root
+    /// config file.
+    #[serde(default)]
+    pub preload_list_tags: Vec<String>,
+
+    /// If specified, Tarmac will copy assets into a folder that Roblox Studio
+    /// can load content from, and generated code will refer to those copies
+    /// when `_G.TARMAC_DEV` is set. Only applies if this config is the root
+    /// config file.
+    pub dev_content: Option<DevContentConfig>,
+
+    /// If specified, Tarmac will look up the IDs of assets with the same
+    /// contents in a cache shared between machines before uploading them, and
+    /// record the IDs of new uploads there. Only applies if this config is the
+    /// root config file.
+    pub remote_cache: Option<RemoteCacheConfig>,
+
     /// If specified, requires that all uploaded assets are uploaded to the
     /// given group. Attempting to sync will fail if the authenticated user does
     /// not have access to create assets on the group.
     pub upload_to_group_id: Option<u64>,
 
+    /// Which Roblox API Tarmac should upload assets with. Only the root config
+    /// file chooses the API, and included configs can't choose another one.
+    /// Use `upload_api()` for the API that applies when it isn't set.
+    pub upload_api: Option<UploadApi>,
+
+    /// The user that should own assets uploaded with the Open Cloud API when
+    /// `upload_to_group_id` is not specified.
+    pub upload_to_user_id: Option<u64>,
+
+    /// The template that the names of uploaded assets are built from, unless
+    /// an input defines its own `upload_name`. Only applies if this config is
+    /// the root config file.
+    pub upload_name_template: Option<String>,
+
+    /// Whether symlinks should be followed when searching for configs and
+    /// inputs. Only applies if this config is the root config file.
+    #[serde(default)]
+    pub symlinks: SymlinkPolicy,
+
+    /// What to do when a folder can't be searched for inputs, like when it
+    /// can't be read. Only applies if this config is the root config file.
+    #[serde(default)]
+    pub walk_errors: WalkErrorPolicy,
+
+    /// How to pick which input group a file belongs to when it's matched by
+    /// more than one. Only applies if this config is the root config file.
+    #[serde(default)]
+    pub overlapping_globs: OverlapPolicy,
+
+    /// How the case of the names that assets are tracked by should be
+    /// normalized. Only applies if this config is the root config file.
+    #[serde(default)]
+    pub asset_name_case: AssetNameCase,
+
+    /// Whether files matched by the `.gitignore` files of the repository
+    /// containing the project are left out of input discovery. Only applies
+    /// if this config is the root config file.
+    #[serde(default)]
+    pub respect_gitignore: bool,
+
+    /// Settings used by every input group in this config that doesn't set
+    /// them itself. Configs included by this one inherit them too.
+    #[serde(default)]
+    pub defaults: InputDefaults,
+
+    /// If specified, generated code refers to the asset IDs stored under the
+    /// given manifest key instead of the ones from the current sync. Only
+    /// applies if this config is the root config file.
+    pub codegen_manifest_key: Option<String>,
+
+    /// Named sets of overrides that can be selected with `--profile`, like
+    /// one for each environment a project is synced to. Only applies if this
+    /// config is the root config file.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ConfigProfile>,
+
     /// A list of paths that Tarmac should search in to find other Tarmac
-    /// projects.
+    /// projects, or remote projects to fetch.
     ///
     /// Any found projects will have their inputs merged into this project.
     #[serde(default)]
-    pub includes: Vec<PathBuf>,
+    pub includes: Vec<IncludeConfig>,
+
+    /// How many folders deep Tarmac searches for other projects below each
+    /// of this config's includes. If not specified, the search continues
+    /// until a config is found in every branch.
+    pub include_max_depth: Option<usize>,
+
+    /// Globs matching folders that aren't searched for other projects below
+    /// this config's includes, like `node_modules`. Globs without a slash
+    /// match a folder's name, and other globs match its path relative to this
+    /// config.
+    #[serde(default)]
+    pub include_exclude: Vec<Glob>,
+
+    /// Folders containing asset packages made with `tarmac package`, whose
+    /// assets this project uses without uploading them itself.
+    #[serde(default)]
+    pub packages: Vec<PathBuf>,
 
     /// A list of input glob paths and options that Tarmac should use to
     /// discover assets that it should manage.
@@ -70,31 +178,277 @@ impl Config {
     }
 
     pub fn read_from_folder<P: AsRef<Path>>(folder_path: P) -> Result<Self, ConfigError> {
+        Self::read_from_folder_inheriting(folder_path, &InputDefaults::default())
+    }
+
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        Self::read_from_file_inheriting(path, &InputDefaults::default())
+    }
+
+    /// Reads the config in the given folder, which was included by a config
+    /// with the given input defaults.
+    pub fn read_from_folder_inheriting<P: AsRef<Path>>(
+        folder_path: P,
+        inherited: &InputDefaults,
+    ) -> Result<Self, ConfigError> {
         let folder_path = folder_path.as_ref();
-        let file_path = &folder_path.join(CONFIG_FILENAME);
 
-        Self::read_from_file(file_path)
+        let file_path = CONFIG_FILENAMES
+            .iter()
+            .map(|name| folder_path.join(name))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No Tarmac config found in {}", folder_path.display()),
+                )
+            })?;
+
+        Self::read_from_file_inheriting(file_path, inherited)
     }
 
-    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+    /// Reads the config at the given path, which was included by a config
+    /// with the given input defaults.
+    pub fn read_from_file_inheriting<P: AsRef<Path>>(
+        path: P,
+        inherited: &InputDefaults,
+    ) -> Result<Self, ConfigError> {
         let path = path.as_ref();
         let contents = fs::read(path)?;
 
-        let mut config: Self = toml::from_slice(&contents).map_err(|source| ConfigError::Toml {
-            source,
-            path: path.to_owned(),
-        })?;
+        let mut config = Self::parse(&contents, path, inherited)?;
         config.file_path = path.to_owned();
         config.make_paths_absolute();
 
         Ok(config)
     }
 
+    pub(crate) fn parse(
+        contents: &[u8],
+        path: &Path,
+        inherited: &InputDefaults,
+    ) -> Result<Self, ConfigError> {
+        let format = ConfigFormat::from_path(path);
+        let mut document: toml::Value = format.deserialize(contents, path)?;
+
+        interpolate_env_vars(&mut document, &|name| env::var(name).ok()).map_err(|name| {
+            ConfigError::MissingEnvVar {
+                name,
+                path: path.to_owned(),
+            }
+        })?;
+
+        let mut config: Self = document
+            .clone()
+            .try_into()
+            .map_err(|source| ConfigError::Toml {
+                source,
+                path: path.to_owned(),
+            })?;
+
+        // A config's own upload destination takes precedence over the one its
+        // including config would give it.
+        let mut inherited = inherited.clone();
+        if config.upload_to_group_id.is_some() || config.upload_to_user_id.is_some() {
+            inherited.upload_to_group_id = None;
+            inherited.upload_to_user_id = None;
+        }
+
+        config.defaults.inherit_from(&inherited);
+
+        // Fields that an input group leaves out have already been filled in
+        // with their usual defaults, so we go back to the document to find out
+        // which fields the input defaults should fill in instead.
+        if config.defaults != InputDefaults::default() {
+            if let Some(toml::Value::Array(inputs)) = document.get_mut("inputs") {
+                config.defaults.apply_to_tables(inputs);
+                config.inputs =
+                    toml::Value::Array(inputs.clone())
+                        .try_into()
+                        .map_err(|source| ConfigError::Toml {
+                            source,
+                            path: path.to_owned(),
+                        })?;
+            }
+        }
+
+        for include in &config.includes {
+            if let IncludeConfig::Path(include_path) = include {
+                if glob::contains_pattern(include_path) {
+                    include_glob(include_path).map_err(|source| ConfigError::IncludeGlob {
+                        source,
+                        path: path.to_owned(),
+                    })?;
+                }
+            }
+        }
+
+        // Images can't be scaled down to nothing.
+        let zero_dimensions = |max_dimensions: Option<(u32, u32)>| {
+            max_dimensions.is_some_and(|(width, height)| width == 0 || height == 0)
+        };
+
+        if zero_dimensions(config.defaults.max_dimensions)
+            || config
+                .inputs
+                .iter()
+                .any(|input| zero_dimensions(input.max_dimensions))
+        {
+            return Err(ConfigError::ZeroMaxDimensions {
+                path: path.to_owned(),
+            });
+        }
+
+        // Empty segments would nest generated code under keys named "".
+        for input in &config.inputs {
+            if let Some(namespace) = &input.codegen_namespace {
+                if namespace.split('.').any(str::is_empty) {
+                    return Err(ConfigError::InvalidNamespace {
+                        namespace: namespace.clone(),
+                        path: path.to_owned(),
+                    });
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// The Roblox API that this config uploads assets with.
+    pub fn upload_api(&self) -> UploadApi {
+        self.upload_api.unwrap_or_default()
+    }
+
+    /// Overrides this config's settings with the ones from a profile.
+    pub fn apply_profile(&mut self, profile: &ConfigProfile) {
+        if let Some(upload_api) = profile.upload_api {
+            self.upload_api = Some(upload_api);
+        }
+
+        if let Some(key) = &profile.codegen_manifest_key {
+            self.codegen_manifest_key = Some(key.clone());
+        }
+
+        let replaces_destination =
+            profile.upload_to_group_id.is_some() || profile.upload_to_user_id.is_some();
+
+        if replaces_destination {
+            self.upload_to_group_id = profile.upload_to_group_id;
+            self.upload_to_user_id = profile.upload_to_user_id;
+        }
+
+        for input in &mut self.inputs {
+            if replaces_destination {
+                input.upload_to_group_id = profile.upload_to_group_id;
+                input.upload_to_user_id = profile.upload_to_user_id;
+            }
+
+            if let Some(url_format) = profile.codegen_url_format {
+                input.codegen_url_format = url_format;
+            }
+
+            if let Some(codegen_id) = profile.codegen_id {
+                input.codegen_id = codegen_id;
+            }
+        }
+    }
+
     /// The path that paths in this Config should be considered relative to.
     pub fn folder(&self) -> &Path {
         self.file_path.parent().unwrap()
     }
 
+    /// Everything this config includes, fetching remote includes that haven't
+    /// been fetched yet with the given client.
+    pub fn resolve_includes(
+        &self,
+        client: &Client,
+    ) -> Result<Vec<ResolvedInclude>, RemoteIncludeError> {
+        let mut resolved = Vec::new();
+
+        for include in &self.includes {
+            let include_path = include.resolve(client, self.folder())?;
+
+            match include {
+                IncludeConfig::Path(_) if glob::contains_pattern(&include_path.path) => {
+                    let matches = self.expand_include_glob(&include_path.path)?;
+
+                    if matches.is_empty() {
+                        log::warn!(
+                            "Include {} in {} doesn't match anything",
+                            include_path.path.display(),
+                            self.file_path.display()
+                        );
+                    }
+
+                    resolved.extend(matches.into_iter().map(|path| ResolvedInclude {
+                        path,
+                        fetched: None,
+                    }));
+                }
+                _ => resolved.push(include_path),
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// Whether the given folder, found while searching one of this config's
+    /// includes for other projects, is left out by `include-exclude`.
+    pub fn excludes_from_includes(&self, folder: &Path) -> bool {
+        let relative = folder.strip_prefix(self.folder()).unwrap_or(folder);
+
+        self.include_exclude.iter().any(|glob| {
+            if glob.to_string().contains('/') {
+                glob.is_match(relative)
+            } else {
+                folder.file_name().is_some_and(|name| glob.is_match(name))
+            }
+        })
+    }
+
+    /// Finds the files and folders that an include glob matches, in order of
+    /// their paths. Only the part of the project below the glob's fixed
+    /// prefix is searched, and only as deep as the glob can match.
+    fn expand_include_glob(&self, path: &Path) -> Result<Vec<PathBuf>, RemoteIncludeError> {
+        let folder = self.folder();
+        let pattern = path.strip_prefix(folder).unwrap_or(path);
+
+        // Globs are checked when configs are parsed.
+        let include_glob = include_glob(pattern).unwrap();
+        let base_path = folder.join(include_glob.get_prefix());
+
+        if !base_path.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut walker = WalkDir::new(&base_path)
+            .min_depth(1)
+            .sort_by(|a, b| a.file_name().cmp(b.file_name()));
+        if !pattern.to_string_lossy().contains("**") {
+            let depth =
+                pattern.components().count() - include_glob.get_prefix().components().count();
+            walker = walker.max_depth(depth);
+        }
+
+        let mut matches = Vec::new();
+        let entries = walker.into_iter().filter_entry(|entry| {
+            entry.file_name() != REMOTE_INCLUDES_FOLDER
+                && !(entry.file_type().is_dir() && self.excludes_from_includes(entry.path()))
+        });
+
+        for entry in entries {
+            let entry = entry.map_err(io::Error::from)?;
+            let relative = entry.path().strip_prefix(folder).unwrap_or(entry.path());
+
+            if include_glob.is_match(relative) {
+                matches.push(entry.into_path());
+            }
+        }
+
+        Ok(matches)
+    }
+
     /// Turn all relative paths referenced from this config into absolute paths.
     fn make_paths_absolute(&mut self) {
         let base = self.file_path.parent().unwrap();
@@ -103,12 +457,26 @@ impl Config {
             make_absolute(list_path, base);
         }
 
+        if let Some(preload_path) = self.preload_list_path.as_mut() {
+            make_absolute(preload_path, base);
+        }
+
         if let Some(cache_path) = self.asset_cache_path.as_mut() {
             make_absolute(cache_path, base);
         }
 
+        if let Some(dev_content) = self.dev_content.as_mut() {
+            make_absolute(&mut dev_content.path, base);
+        }
+
         for include in &mut self.includes {
-            make_absolute(include, base);
+            if let IncludeConfig::Path(path) = include {
+                make_absolute(path, base);
+            }
+        }
+
+        for package in &mut self.packages {
+            make_absolute(package, base);
         }
 
         for input in &mut self.inputs {
@@ -117,10 +485,243 @@ impl Config {
             }
 
             make_absolute(&mut input.codegen_base_path, base);
+
+            for output in &mut input.codegen_outputs {
+                make_absolute(&mut output.path, base);
+            }
+
+            for input_override in &mut input.overrides {
+                if let Some(codegen_path) = input_override.codegen_path.as_mut() {
+                    make_absolute(codegen_path, base);
+                }
+            }
         }
     }
 }
 
+/// The Roblox APIs that Tarmac can upload assets with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UploadApi {
+    /// The legacy decal upload endpoint, authenticated with a cookie.
+    #[default]
+    Legacy,
+
+    /// The Open Cloud assets API, authenticated with an API key.
+    OpenCloud,
+}
+
+/// How Tarmac should treat symlinks it finds while searching for configs and
+/// inputs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SymlinkPolicy {
+    /// Symlinks are treated like the file or folder they point to. Folders
+    /// that are reached more than once, like through a symlink pointing to one
+    /// of its own parents, are only searched once.
+    #[default]
+    Follow,
+
+    /// Symlinks are skipped.
+    Ignore,
+}
+
+/// What Tarmac should do when it can't search part of a project for inputs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WalkErrorPolicy {
+    /// The sync fails.
+    #[default]
+    Error,
+
+    /// The folder or file is skipped with a warning.
+    Warn,
+
+    /// The folder or file is skipped silently.
+    Skip,
+}
+
+/// How Tarmac should resolve a file being matched by more than one input
+/// group.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverlapPolicy {
+    /// The sync fails.
+    #[default]
+    Error,
+
+    /// The input group with the most specific glob wins.
+    MostSpecific,
+
+    /// The input group found last wins, so included configs override the
+    /// configs that include them.
+    LastConfig,
+
+    /// The input group with the highest `priority` wins.
+    Priority,
+}
+
+/// How Tarmac should normalize the case of asset names, which are always
+/// normalized to Unicode NFC form.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AssetNameCase {
+    /// Names keep the case of the files they come from.
+    #[default]
+    Preserve,
+
+    /// Names are made lowercase, so that a file produces the same name no
+    /// matter which case it has on a case-insensitive filesystem.
+    Lower,
+}
+
+/// What Tarmac should do with an image that's larger than its input group's
+/// `max_dimensions`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DimensionPolicy {
+    /// The sync fails.
+    #[default]
+    Error,
+
+    /// The image is uploaded anyways, with a warning.
+    Warn,
+
+    /// The image is scaled down to fit before it's uploaded, keeping its
+    /// aspect ratio.
+    Resize,
+}
+
+impl FromStr for WalkErrorPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "error" => Ok(WalkErrorPolicy::Error),
+            "warn" => Ok(WalkErrorPolicy::Warn),
+            "skip" => Ok(WalkErrorPolicy::Skip),
+
+            _ => Err(String::from(
+                "Invalid walk error policy. Valid options are error, warn, and skip.",
+            )),
+        }
+    }
+}
+
+/// A place that a config includes other Tarmac projects from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IncludeConfig {
+    /// A config file, or a folder to search for config files.
+    Path(PathBuf),
+
+    /// A git repository, checked out at a pinned revision.
+    Git(GitInclude),
+
+    /// An archive in tar format, downloaded over HTTPS.
+    Url(UrlInclude),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct GitInclude {
+    /// The URL of the repository, as given to `git fetch`.
+    pub git: String,
+
+    /// The commit, tag, or branch to check out. Commits keep the include from
+    /// changing underneath the project.
+    pub rev: String,
+
+    /// The path inside the repository to search for configs.
+    #[serde(default)]
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct UrlInclude {
+    /// The HTTPS URL of the archive.
+    pub url: String,
+
+    /// The SHA-256 hash of the archive, which the downloaded archive must
+    /// match.
+    pub sha256: String,
+
+    /// The path inside the archive to search for configs.
+    #[serde(default)]
+    pub path: PathBuf,
+}
+
+/// Where an include's projects are found, once it has been fetched if it's
+/// remote.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedInclude {
+    /// The local path to search for projects.
+    pub path: PathBuf,
+
+    /// If the include is remote, where it was fetched into.
+    pub fetched: Option<FetchedInclude>,
+}
+
+impl IncludeConfig {
+    /// Finds the local path that this include refers to, fetching it first if
+    /// it's remote. `config_folder` is the folder of the including config.
+    pub fn resolve(
+        &self,
+        client: &Client,
+        config_folder: &Path,
+    ) -> Result<ResolvedInclude, RemoteIncludeError> {
+        let (fetched, path) = match self {
+            IncludeConfig::Path(path) => {
+                return Ok(ResolvedInclude {
+                    path: path.clone(),
+                    fetched: None,
+                })
+            }
+            IncludeConfig::Git(include) => (
+                remote_include::fetch_git(config_folder, &include.git, &include.rev)?,
+                &include.path,
+            ),
+            IncludeConfig::Url(include) => (
+                remote_include::fetch_archive(
+                    client,
+                    config_folder,
+                    &include.url,
+                    &include.sha256,
+                )?,
+                &include.path,
+            ),
+        };
+
+        Ok(ResolvedInclude {
+            path: remote_include::join_within(&fetched.folder, path)?,
+            fetched: Some(fetched),
+        })
+    }
+}
+
+/// Describes where Tarmac should put local copies of assets for use during
+/// development.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct DevContentConfig {
+    /// The folder to copy assets into, usually inside Roblox Studio's content
+    /// folder.
+    pub path: PathBuf,
+
+    /// The URL that refers to `path` from inside Roblox, like
+    /// `rbxasset://tarmac`.
+    pub url: String,
+}
+
+/// Describes a cache of uploaded asset IDs that's shared between machines.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RemoteCacheConfig {
+    /// The HTTP URL that cache entries are stored under.
+    pub url: String,
+}
+
 fn default_max_spritesheet_size() -> (u32, u32) {
     (1024, 1024)
 }
@@ -132,6 +733,16 @@ pub struct InputConfig {
     /// group of inputs.
     pub glob: Glob,
 
+    /// Globs matching files that should be left out of this group of inputs
+    /// even though they match `glob`, like work-in-progress art.
+    #[serde(default)]
+    pub exclude: Vec<Glob>,
+
+    /// Freeform labels for the inputs in this group, which syncs, packing,
+    /// preload lists, and generated files can be limited to.
+    #[serde(default)]
+    pub tags: Vec<String>,
+
     /// Defines whether Tarmac should generate code to import the assets
     /// associated with this group of inputs.
     #[serde(default)]
@@ -142,9 +753,92 @@ pub struct InputConfig {
     #[serde(default)]
     pub codegen_path: Option<PathBuf>,
 
+    /// The path that maps to the root of the grouped generated table. Keys
+    /// are derived from each input's path relative to this one, independently
+    /// of where the glob starts matching.
     #[serde(default)]
     pub codegen_base_path: PathBuf,
 
+    /// What shape of code Tarmac should generate when `codegen_path` is set.
+    #[serde(default)]
+    pub codegen_kind: CodegenKind,
+
+    /// If specified, nests the grouped generated table under the given
+    /// dot-separated path of keys, like `UI.Icons`.
+    #[serde(default)]
+    pub codegen_namespace: Option<String>,
+
+    /// If specified, prepends the given string to every top-level key in the
+    /// grouped generated table.
+    #[serde(default)]
+    pub codegen_key_prefix: Option<String>,
+
+    /// The order that keys should be generated in within grouped tables.
+    #[serde(default)]
+    pub codegen_order: CodegenOrder,
+
+    /// Keys that should be generated before all others within grouped
+    /// tables, in the given order.
+    #[serde(default)]
+    pub codegen_key_order: Vec<String>,
+
+    /// Additional files to generate alongside `codegen_path`, each describing
+    /// this group of inputs in a given format.
+    #[serde(default)]
+    pub codegen_outputs: Vec<CodegenOutputConfig>,
+
+    /// Whether generated code should describe each asset as a table of
+    /// ImageLabel properties that includes the image's native size.
+    #[serde(default)]
+    pub codegen_include_size: bool,
+
+    /// How generated code should describe the slice of a spritesheet that a
+    /// packed asset is located in.
+    #[serde(default)]
+    pub codegen_slice_format: SliceFormat,
+
+    /// The format of the asset links that generated code should contain.
+    #[serde(default)]
+    pub codegen_url_format: UrlFormat,
+
+    /// Which of an uploaded image's IDs generated code should refer to.
+    #[serde(default)]
+    pub codegen_id: CodegenId,
+
+    /// If specified, the ID that generated code refers to for inputs that
+    /// haven't been uploaded yet or were rejected by moderation, like a
+    /// "missing texture" image, instead of leaving them out.
+    #[serde(default)]
+    pub codegen_placeholder_id: Option<u64>,
+
+    /// If specified, uploads the assets affected by this config to the given
+    /// group instead of the project's default destination.
+    #[serde(default)]
+    pub upload_to_group_id: Option<u64>,
+
+    /// If specified, uploads the assets affected by this config for the given
+    /// user instead of the project's default destination.
+    #[serde(default)]
+    pub upload_to_user_id: Option<u64>,
+
+    /// If specified, the name given to each uploaded asset instead of one
+    /// derived from the project's `upload_name_template`. May use the same
+    /// placeholders as the template.
+    #[serde(default)]
+    pub upload_name: Option<String>,
+
+    /// If specified, the description given to each uploaded asset instead of
+    /// Tarmac's default description.
+    #[serde(default)]
+    pub upload_description: Option<String>,
+
+    /// If specified, the ID of an asset that already exists, like one owned
+    /// by another team, which the inputs affected by this config refer to.
+    /// Tarmac never uploads these inputs, but still tracks them in the
+    /// manifest and generated code.
+    #[serde(default)]
+    pub asset_id: Option<u64>,
+
     /// Whether the assets affected by this config are allowed to be packed into
     /// spritesheets.
     ///
@@ -154,6 +848,365 @@ pub struct InputConfig {
     /// instances.
     #[serde(default)]
     pub packable: bool,
+
+    /// The largest width and height that images in this group can have.
+    #[serde(default)]
+    pub max_dimensions: Option<(u32, u32)>,
+
+    /// What to do with images that are larger than `max_dimensions`.
+    #[serde(default)]
+    pub max_dimensions_policy: DimensionPolicy,
+
+    /// Decides which input group a file belongs to when it's matched by more
+    /// than one and the project's `overlapping_globs` is `priority`. Higher
+    /// priorities win.
+    #[serde(default)]
+    pub priority: i32,
+
+    /// Settings that replace the ones above for the files in this group that
+    /// match another glob. Later overrides take precedence over earlier ones.
+    #[serde(default)]
+    pub overrides: Vec<InputOverride>,
+}
+
+/// Settings that replace those of an input group for some of its files, so
+/// that a few files can be treated differently without splitting the group.
+///
+/// Any field left unspecified keeps the value from the input group.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct InputOverride {
+    /// A glob matching the files in the input group that these settings
+    /// apply to, relative to the config like the group's own glob.
+    pub glob: Glob,
+
+    pub packable: Option<bool>,
+    pub codegen: Option<bool>,
+    pub codegen_path: Option<PathBuf>,
+    pub codegen_kind: Option<CodegenKind>,
+    pub codegen_include_size: Option<bool>,
+    pub codegen_slice_format: Option<SliceFormat>,
+    pub codegen_url_format: Option<UrlFormat>,
+}
+
+impl InputOverride {
+    fn apply_to(&self, config: &mut InputConfig) {
+        fn replace<T: Clone>(value: &mut T, replacement: &Option<T>) {
+            if let Some(replacement) = replacement {
+                *value = replacement.clone();
+            }
+        }
+
+        replace(&mut config.packable, &self.packable);
+        replace(&mut config.codegen, &self.codegen);
+        replace(&mut config.codegen_kind, &self.codegen_kind);
+        replace(&mut config.codegen_include_size, &self.codegen_include_size);
+        replace(&mut config.codegen_slice_format, &self.codegen_slice_format);
+        replace(&mut config.codegen_url_format, &self.codegen_url_format);
+
+        if self.codegen_path.is_some() {
+            config.codegen_path = self.codegen_path.clone();
+        }
+    }
+}
+
+/// The file formats that a config can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Picks the format of a config file from its extension. Files with
+    /// unknown extensions are read as TOML.
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => ConfigFormat::Json,
+            Some("yaml") | Some("yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(
+        self,
+        contents: &[u8],
+        path: &Path,
+    ) -> Result<T, ConfigError> {
+        let path = path.to_owned();
+
+        match self {
+            ConfigFormat::Toml => {
+                toml::from_slice(contents).map_err(|source| ConfigError::Toml { source, path })
+            }
+            ConfigFormat::Json => serde_json::from_slice(contents)
+                .map_err(|source| ConfigError::Json { source, path }),
+            ConfigFormat::Yaml => serde_yaml::from_slice(contents)
+                .map_err(|source| ConfigError::Yaml { source, path }),
+        }
+    }
+}
+
+/// Overrides for a project selected with `--profile`, so that one project can
+/// be synced to different places without keeping a copy of its config for
+/// each.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigProfile {
+    /// Where `tarmac sync` syncs to when `--target` isn't given.
+    pub target: Option<SyncTarget>,
+
+    /// Which Roblox API assets are uploaded with.
+    pub upload_api: Option<UploadApi>,
+
+    /// Uploads every asset in the project to the given group, replacing the
+    /// destination of every config and input group.
+    pub upload_to_group_id: Option<u64>,
+
+    /// Uploads every asset in the project for the given user, replacing the
+    /// destination of every config and input group.
+    pub upload_to_user_id: Option<u64>,
+
+    /// Overrides the format of asset links in generated code.
+    pub codegen_url_format: Option<UrlFormat>,
+
+    /// Overrides which ID generated code refers to for legacy uploads.
+    pub codegen_id: Option<CodegenId>,
+
+    /// The key that asset IDs are stored under in the manifest when syncing
+    /// with this profile, so that syncing to one destination doesn't replace
+    /// the IDs from another. Profiles with the same key share IDs.
+    pub manifest_key: Option<String>,
+
+    /// Overrides the project's `codegen_manifest_key`.
+    pub codegen_manifest_key: Option<String>,
+}
+
+/// Settings that apply to every input group of a config unless the group sets
+/// them itself.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct InputDefaults {
+    pub codegen: Option<bool>,
+    pub codegen_kind: Option<CodegenKind>,
+    pub codegen_slice_format: Option<SliceFormat>,
+    pub codegen_url_format: Option<UrlFormat>,
+    pub codegen_placeholder_id: Option<u64>,
+    pub packable: Option<bool>,
+    pub max_dimensions: Option<(u32, u32)>,
+    pub max_dimensions_policy: Option<DimensionPolicy>,
+    pub upload_to_group_id: Option<u64>,
+    pub upload_to_user_id: Option<u64>,
+    pub upload_description: Option<String>,
+}
+
+/// The settings that pick who an input group's assets are uploaded for.
+static UPLOAD_OWNER_KEYS: &[&str] = &["upload-to-group-id", "upload-to-user-id"];
+
+impl InputDefaults {
+    /// Fills in any settings that these defaults leave out with the ones from
+    /// the defaults of an including config.
+    pub fn inherit_from(&mut self, parent: &InputDefaults) {
+        fn inherit<T: Clone>(value: &mut Option<T>, parent: &Option<T>) {
+            if value.is_none() {
+                *value = parent.clone();
+            }
+        }
+
+        inherit(&mut self.codegen, &parent.codegen);
+        inherit(&mut self.codegen_kind, &parent.codegen_kind);
+        inherit(&mut self.codegen_slice_format, &parent.codegen_slice_format);
+        inherit(&mut self.codegen_url_format, &parent.codegen_url_format);
+        inherit(
+            &mut self.codegen_placeholder_id,
+            &parent.codegen_placeholder_id,
+        );
+        inherit(&mut self.packable, &parent.packable);
+        inherit(&mut self.max_dimensions, &parent.max_dimensions);
+        inherit(
+            &mut self.max_dimensions_policy,
+            &parent.max_dimensions_policy,
+        );
+        inherit(&mut self.upload_description, &parent.upload_description);
+
+        // A group and a user are two ways to pick the same destination, so
+        // they're inherited together.
+        if self.upload_to_group_id.is_none() && self.upload_to_user_id.is_none() {
+            self.upload_to_group_id = parent.upload_to_group_id;
+            self.upload_to_user_id = parent.upload_to_user_id;
+        }
+    }
+
+    /// Adds these defaults to every input group in a list of TOML tables
+    /// that doesn't already set them.
+    fn apply_to_tables(&self, inputs: &mut [toml::Value]) {
+        let defaults = match toml::Value::try_from(self) {
+            Ok(toml::Value::Table(defaults)) => defaults,
+            _ => unreachable!("input defaults always serialize to a table"),
+        };
+
+        for input in inputs {
+            if let toml::Value::Table(input) = input {
+                // An input group that picks a group or a user to upload to
+                // has picked its destination, so it gets neither default.
+                let picks_owner = UPLOAD_OWNER_KEYS.iter().any(|key| input.contains_key(*key));
+
+                for (key, value) in &defaults {
+                    if picks_owner && UPLOAD_OWNER_KEYS.contains(&key.as_str()) {
+                        continue;
+                    }
+
+                    input.entry(key.clone()).or_insert_with(|| value.clone());
+                }
+            }
+        }
+    }
+}
+
+impl InputConfig {
+    /// Whether the file at the given path, relative to the config that defines
+    /// this input group, belongs to it.
+    pub fn matches(&self, path: &Path) -> bool {
+        self.glob.is_match(path) && !self.exclude.iter().any(|glob| glob.is_match(path))
+    }
+
+    /// Whether this input group has at least one of the given tags, or the
+    /// given list of tags is empty, meaning that every input group passes.
+    pub fn has_any_tag(&self, tags: &[String]) -> bool {
+        tags.is_empty() || self.tags.iter().any(|tag| tags.contains(tag))
+    }
+
+    /// Applies the overrides of this input group that match the file at the
+    /// given path, relative to the config that defines this input group.
+    pub fn apply_overrides(&mut self, path: &Path) {
+        for input_override in self.overrides.clone() {
+            if input_override.glob.is_match(path) {
+                input_override.apply_to(self);
+            }
+        }
+    }
+}
+
+/// The shapes of grouped code that Tarmac can generate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CodegenKind {
+    /// A module that returns a nested table of assets.
+    #[default]
+    Table,
+
+    /// A module that contains the same nested table of assets, along with
+    /// runtime helper functions to look up, slice, and preload them.
+    AssetMap,
+
+    /// A module that returns the same nested table of assets, which also has a
+    /// `byPath` function for looking up assets by slash-separated paths.
+    Lookup,
+}
+
+/// An additional file that Tarmac should generate for a group of inputs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct CodegenOutputConfig {
+    /// The path to the file that should be generated.
+    pub path: PathBuf,
+
+    /// The format of the file that should be generated.
+    pub format: CodegenFormat,
+
+    /// If not empty, only inputs with at least one of these tags are
+    /// generated into this file.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// The file formats that Tarmac can generate for a group of inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CodegenFormat {
+    /// A Lua module, shaped according to the input's `codegen_kind`.
+    Lua,
+
+    /// A JSON file describing the same tree of assets as the Lua module.
+    Json,
+
+    /// TypeScript declarations describing the Lua module.
+    Typescript,
+
+    /// A Roblox XML model file containing the Lua module as a ModuleScript,
+    /// for projects that don't use Rojo.
+    Rbxmx,
+}
+
+/// The ways that generated code can describe the slice of a spritesheet that
+/// a packed asset is located in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SliceFormat {
+    /// `ImageRectOffset` and `ImageRectSize` as Vector2 values.
+    #[default]
+    Vector2,
+
+    /// `ImageRectOffset` and `ImageRectSize` as UDim2 offsets.
+    Udim2,
+
+    /// A single `ImageRect` Rect value spanning the slice.
+    Rect,
+}
+
+/// The formats that generated code can use to link to an asset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UrlFormat {
+    /// `rbxassetid://<id>`, usable directly in Roblox.
+    #[default]
+    Rbxassetid,
+
+    /// `https://www.roblox.com/asset/?id=<id>`, for tooling outside of Roblox.
+    Https,
+
+    /// The asset ID on its own, as a number.
+    Id,
+}
+
+impl UrlFormat {
+    /// Formats a link to the given asset ID.
+    pub fn asset_url(self, id: u64) -> String {
+        match self {
+            UrlFormat::Rbxassetid => format!("rbxassetid://{}", id),
+            UrlFormat::Https => format!("https://www.roblox.com/asset/?id={}", id),
+            UrlFormat::Id => id.to_string(),
+        }
+    }
+}
+
+/// The IDs that generated code can refer to for an uploaded image.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CodegenId {
+    /// The image asset itself, which is what image properties expect.
+    #[default]
+    Image,
+
+    /// The decal that the image was uploaded as. Images that weren't uploaded
+    /// as a decal fall back to the image asset.
+    Decal,
+}
+
+/// Strategies for ordering the keys of generated tables.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CodegenOrder {
+    /// Sort keys by comparing them as strings.
+    #[default]
+    Alphabetical,
+
+    /// Sort keys as strings, but compare runs of digits as numbers.
+    Natural,
+
+    /// Order keys in the same order that their inputs were discovered.
+    Discovery,
 }
 
 #[derive(Debug, Error)]
@@ -164,6 +1217,30 @@ pub enum ConfigError {
         source: toml::de::Error,
     },
 
+    #[error("Error deserializing JSON from path {}", .path.display())]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    #[error("Error deserializing YAML from path {}", .path.display())]
+    Yaml {
+        path: PathBuf,
+        source: serde_yaml::Error,
+    },
+
+    #[error("Environment variable {name} is used by {} but isn't set", .path.display())]
+    MissingEnvVar { name: String, path: PathBuf },
+
+    #[error("An include in {} isn't a valid glob", .path.display())]
+    IncludeGlob { path: PathBuf, source: glob::Error },
+
+    #[error("The codegen-namespace {namespace:?} in {} has an empty key. Namespaces are keys separated by dots, like \"UI.Icons\".", .path.display())]
+    InvalidNamespace { namespace: String, path: PathBuf },
+
+    #[error("A max-dimensions in {} has a width or height of 0. Both have to be at least 1.", .path.display())]
+    ZeroMaxDimensions { path: PathBuf },
+
     #[error(transparent)]
     Io {
         #[from]
@@ -185,6 +1262,106 @@ impl ConfigError {
     }
 }
 
+fn include_glob(path: &Path) -> Result<Glob, glob::Error> {
+    Glob::new(&path.to_string_lossy())
+}
+
+/// Replaces `${NAME}` in every string of a config document with the value of
+/// the environment variable `NAME`, or with `fallback` for
+/// `${NAME:-fallback}` if the variable isn't set. `$$` stands for a literal
+/// `$`. Strings that are nothing but one reference become numbers or booleans
+/// if that's what the variable holds, so variables can stand in for IDs too.
+///
+/// Variables are only substituted after the config is parsed, so their values
+/// are never read as part of the config's syntax.
+///
+/// Returns the name of the first variable that isn't set and has no fallback.
+fn interpolate_env_vars(
+    document: &mut toml::Value,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+    match document {
+        toml::Value::String(text) if text.contains('$') => {
+            let interpolated = interpolate_string(text, lookup)?;
+            let is_one_reference = text.starts_with("${") && text.find('}') == Some(text.len() - 1);
+
+            *document = match interpolated.parse() {
+                Ok(number) if is_one_reference => toml::Value::Integer(number),
+                _ => match interpolated.as_str() {
+                    "true" | "false" if is_one_reference => {
+                        toml::Value::Boolean(interpolated == "true")
+                    }
+                    _ => toml::Value::String(interpolated),
+                },
+            };
+        }
+        toml::Value::Array(values) => {
+            for value in values {
+                interpolate_env_vars(value, lookup)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (_, value) in table.iter_mut() {
+                interpolate_env_vars(value, lookup)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Replaces the environment variable references in one string of a config.
+fn interpolate_string(
+    text: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(index) = rest.find('$') {
+        output.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if let Some(after) = rest.strip_prefix("$$") {
+            output.push('$');
+            rest = after;
+            continue;
+        }
+
+        let reference = rest.strip_prefix("${").and_then(|after| {
+            after
+                .find('}')
+                .map(|end| (&after[..end], &after[end + 1..]))
+        });
+
+        match reference {
+            Some((reference, after)) => {
+                let (name, fallback) = match reference.find(":-") {
+                    Some(split) => (&reference[..split], Some(&reference[split + 2..])),
+                    None => (reference, None),
+                };
+
+                match (lookup(name), fallback) {
+                    (Some(value), _) => output.push_str(&value),
+                    (None, Some(fallback)) => output.push_str(fallback),
+                    (None, None) => return Err(name.to_owned()),
+                }
+
+                rest = after;
+            }
+            None => {
+                output.push('$');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    output.push_str(rest);
+
+    Ok(output)
+}
+
 /// Utility to make a path absolute if it is not absolute already.
 fn make_absolute(path: &mut PathBuf, base: &Path) {
     if path.is_relative() {
@@ -192,3 +1369,402 @@ fn make_absolute(path: &mut PathBuf, base: &Path) {
         *path = new_path;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::SidecarConfig;
+
+    #[test]
+    fn input_defaults() {
+        let inherited = InputDefaults {
+            codegen: Some(true),
+            upload_to_group_id: Some(5),
+            ..InputDefaults::default()
+        };
+
+        let config = Config::parse(
+            br#"
+            name = "included"
+
+            [defaults]
+            packable = true
+
+            [[inputs]]
+            glob = "icons/*.png"
+
+            [[inputs]]
+            glob = "decals/*.png"
+            packable = false
+            codegen = false
+            "#,
+            Path::new("tarmac.toml"),
+            &inherited,
+        )
+        .unwrap();
+
+        let icons = &config.inputs[0];
+        assert!(icons.codegen && icons.packable);
+        assert_eq!(icons.upload_to_group_id, Some(5));
+
+        let decals = &config.inputs[1];
+        assert!(!decals.codegen && !decals.packable);
+        assert_eq!(decals.upload_to_group_id, Some(5));
+
+        assert_eq!(config.defaults.packable, Some(true));
+
+        // Picking a user to upload to replaces the default group.
+        let config = Config::parse(
+            br#"
+            name = "included"
+
+            [[inputs]]
+            glob = "avatar/*.png"
+            upload-to-user-id = 9
+            "#,
+            Path::new("tarmac.toml"),
+            &inherited,
+        )
+        .unwrap();
+
+        let avatar = &config.inputs[0];
+        assert_eq!(avatar.upload_to_group_id, None);
+        assert_eq!(avatar.upload_to_user_id, Some(9));
+
+        let mut defaults = InputDefaults {
+            upload_to_user_id: Some(9),
+            ..InputDefaults::default()
+        };
+        defaults.inherit_from(&inherited);
+        assert_eq!(defaults.upload_to_group_id, None);
+        assert_eq!(config.defaults.codegen, Some(true));
+    }
+
+    #[test]
+    fn json_and_yaml() {
+        let json = Config::parse(
+            br#"{
+                "name": "game",
+                "defaults": { "codegen": true },
+                "inputs": [{ "glob": "*.png", "codegen-kind": "asset-map" }]
+            }"#,
+            Path::new("tarmac.json"),
+            &InputDefaults::default(),
+        )
+        .unwrap();
+
+        let yaml = Config::parse(
+            b"name: game\ndefaults:\n  codegen: true\ninputs:\n  - glob: '*.png'\n    codegen-kind: asset-map\n",
+            Path::new("tarmac.yaml"),
+            &InputDefaults::default(),
+        )
+        .unwrap();
+
+        for config in &[json, yaml] {
+            assert!(config.inputs[0].codegen);
+            assert_eq!(config.inputs[0].codegen_kind, CodegenKind::AssetMap);
+        }
+    }
+
+    #[test]
+    fn profiles() {
+        let mut config = Config::parse(
+            br#"
+            name = "game"
+            upload-to-user-id = 1
+
+            [profiles.production]
+            target = "roblox"
+            upload-to-group-id = 2
+            codegen-url-format = "https"
+
+            [[inputs]]
+            glob = "*.png"
+            upload-to-user-id = 3
+            "#,
+            Path::new("tarmac.toml"),
+            &InputDefaults::default(),
+        )
+        .unwrap();
+
+        let profile = config.profiles["production"].clone();
+        assert_eq!(profile.target, Some(SyncTarget::Roblox));
+
+        config.apply_profile(&profile);
+        assert_eq!(config.upload_to_group_id, Some(2));
+        assert_eq!(config.upload_to_user_id, None);
+        assert_eq!(config.inputs[0].upload_to_group_id, Some(2));
+        assert_eq!(config.inputs[0].upload_to_user_id, None);
+        assert_eq!(config.inputs[0].codegen_url_format, UrlFormat::Https);
+    }
+
+    #[test]
+    fn remote_includes() {
+        let config = Config::parse(
+            br#"
+            name = "game"
+            includes = [
+                "shared",
+                { git = "https://github.com/studio/ui-kit.git", rev = "v1.2.0", path = "assets" },
+                { url = "https://example.com/icons-v2.tar.gz", sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08" },
+            ]
+            "#,
+            Path::new("tarmac.toml"),
+            &InputDefaults::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.includes,
+            vec![
+                IncludeConfig::Path(PathBuf::from("shared")),
+                IncludeConfig::Git(GitInclude {
+                    git: "https://github.com/studio/ui-kit.git".to_owned(),
+                    rev: "v1.2.0".to_owned(),
+                    path: PathBuf::from("assets"),
+                }),
+                IncludeConfig::Url(UrlInclude {
+                    url: "https://example.com/icons-v2.tar.gz".to_owned(),
+                    sha256: "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+                        .to_owned(),
+                    path: PathBuf::new(),
+                }),
+            ]
+        );
+
+        // Archives can't be checked without a hash, so one is required.
+        assert!(Config::parse(
+            br#"
+            name = "game"
+            includes = [{ url = "https://example.com/icons-v2.tar.gz" }]
+            "#,
+            Path::new("tarmac.toml"),
+            &InputDefaults::default(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn include_globs() {
+        let parse = |includes: &str| {
+            Config::parse(
+                format!("name = \"game\"\nincludes = [{}]", includes).as_bytes(),
+                Path::new("tarmac.toml"),
+                &InputDefaults::default(),
+            )
+        };
+
+        assert!(parse(r#""packages/*/assets""#).is_ok());
+        assert!(matches!(
+            parse(r#""packages/[a/assets""#),
+            Err(ConfigError::IncludeGlob { .. })
+        ));
+    }
+
+    #[test]
+    fn codegen_namespaces() {
+        let parse = |namespace: &str| {
+            Config::parse(
+                format!(
+                    "name = \"game\"\n[[inputs]]\nglob = \"*.png\"\ncodegen-namespace = \"{}\"",
+                    namespace
+                )
+                .as_bytes(),
+                Path::new("tarmac.toml"),
+                &InputDefaults::default(),
+            )
+        };
+
+        assert!(parse("UI.Icons").is_ok());
+
+        for namespace in &["", "UI..Icons", "UI.", ".UI"] {
+            assert!(matches!(
+                parse(namespace),
+                Err(ConfigError::InvalidNamespace { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn zero_max_dimensions() {
+        let parse = |contents: &str| {
+            Config::parse(
+                contents.as_bytes(),
+                Path::new("tarmac.toml"),
+                &InputDefaults::default(),
+            )
+        };
+
+        assert!(
+            parse("name = \"game\"\n[[inputs]]\nglob = \"*.png\"\nmax-dimensions = [1, 1]").is_ok()
+        );
+
+        for contents in &[
+            "name = \"game\"\n[[inputs]]\nglob = \"*.png\"\nmax-dimensions = [0, 512]",
+            "name = \"game\"\n[[inputs]]\nglob = \"*.png\"\nmax-dimensions = [512, 0]",
+            "name = \"game\"\n[defaults]\nmax-dimensions = [0, 0]\n[[inputs]]\nglob = \"*.png\"",
+        ] {
+            assert!(matches!(
+                parse(contents),
+                Err(ConfigError::ZeroMaxDimensions { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn include_exclusions() {
+        let mut config = Config::parse(
+            br#"
+            name = "game"
+            includes = ["packages"]
+            include-exclude = ["node_modules", "packages/*/build"]
+            "#,
+            Path::new("tarmac.toml"),
+            &InputDefaults::default(),
+        )
+        .unwrap();
+        config.file_path = PathBuf::from("project/tarmac.toml");
+
+        let excluded = |path: &str| config.excludes_from_includes(&Path::new("project").join(path));
+
+        assert!(excluded("packages/ui/node_modules"));
+        assert!(excluded("packages/ui/node_modules/react/node_modules"));
+        assert!(excluded("packages/ui/build"));
+        assert!(!excluded("shared/build"));
+        assert!(!excluded("packages/ui"));
+    }
+
+    #[test]
+    fn env_var_interpolation() {
+        let lookup = |name: &str| match name {
+            "GROUP_ID" => Some("123".to_owned()),
+            "OUT" => Some("build".to_owned()),
+            _ => None,
+        };
+        let interpolate = |text: &str| {
+            let mut document: toml::Value = toml::from_str(text).unwrap();
+            interpolate_env_vars(&mut document, &lookup).map(|_| document)
+        };
+        let expect = |text: &str| Ok(toml::from_str::<toml::Value>(text).unwrap());
+
+        assert_eq!(
+            interpolate(
+                "upload-to-group-id = \"${GROUP_ID}\"\ncodegen-path = \"${OUT}/Assets.lua\"\n"
+            ),
+            expect("upload-to-group-id = 123\ncodegen-path = \"build/Assets.lua\"\n")
+        );
+        assert_eq!(
+            interpolate("[[inputs]]\nname = \"${NAME:-game}\"\ntags = [\"${OUT}\"]"),
+            expect("[[inputs]]\nname = \"game\"\ntags = [\"build\"]")
+        );
+        assert_eq!(
+            interpolate("name = \"$${GROUP_ID} $5\"\n# Set ${MISSING} first"),
+            expect("name = \"${GROUP_ID} $5\"")
+        );
+        assert_eq!(
+            interpolate("name = \"id-${GROUP_ID}\""),
+            expect("name = \"id-123\"")
+        );
+        assert_eq!(
+            interpolate("name = \"${MISSING}\""),
+            Err("MISSING".to_owned())
+        );
+    }
+
+    #[test]
+    fn env_vars_are_not_config_syntax() {
+        let lookup = |name: &str| match name {
+            "QUOTED" => Some("game\"\nupload-to-group-id = 5".to_owned()),
+            "WINDOWS_PATH" => Some("C:\\Users\\dev\\out".to_owned()),
+            _ => None,
+        };
+
+        let mut document: toml::Value =
+            toml::from_str("name = \"${QUOTED}\"\ncodegen-base-path = \"${WINDOWS_PATH}\"")
+                .unwrap();
+        interpolate_env_vars(&mut document, &lookup).unwrap();
+
+        assert_eq!(
+            document["name"].as_str(),
+            Some("game\"\nupload-to-group-id = 5")
+        );
+        assert_eq!(
+            document["codegen-base-path"].as_str(),
+            Some("C:\\Users\\dev\\out")
+        );
+        assert!(document.get("upload-to-group-id").is_none());
+    }
+
+    #[test]
+    fn input_tags() {
+        let config = Config::parse(
+            br#"
+            name = "game"
+
+            [[inputs]]
+            glob = "ui/**/*.png"
+            tags = ["ui", "seasonal"]
+
+            [[inputs]]
+            glob = "world/**/*.png"
+            "#,
+            Path::new("tarmac.toml"),
+            &InputDefaults::default(),
+        )
+        .unwrap();
+
+        let ui = &config.inputs[0];
+        let world = &config.inputs[1];
+
+        assert!(ui.has_any_tag(&["seasonal".to_owned()]));
+        assert!(!world.has_any_tag(&["ui".to_owned()]));
+        assert!(world.has_any_tag(&[]));
+
+        let mut tagged = world.clone();
+        let sidecar: SidecarConfig = toml::from_str(r#"tags = ["ui"]"#).unwrap();
+        sidecar.apply_to(&mut tagged);
+        assert_eq!(tagged.tags, vec!["ui".to_owned()]);
+    }
+
+    #[test]
+    fn input_overrides() {
+        let config = Config::parse(
+            br#"
+            name = "game"
+
+            [[inputs]]
+            glob = "ui/**/*.png"
+            packable = true
+            codegen = true
+            codegen-path = "UI.lua"
+
+            [[inputs.overrides]]
+            glob = "ui/backgrounds/*.png"
+            packable = false
+            codegen-path = "Backgrounds.lua"
+            codegen-kind = "lookup"
+            "#,
+            Path::new("tarmac.toml"),
+            &InputDefaults::default(),
+        )
+        .unwrap();
+
+        let mut background = config.inputs[0].clone();
+        background.apply_overrides(Path::new("ui/backgrounds/sky.png"));
+        assert!(!background.packable);
+        assert_eq!(background.codegen_kind, CodegenKind::Lookup);
+        assert_eq!(
+            background.codegen_path,
+            Some(PathBuf::from("Backgrounds.lua"))
+        );
+
+        let mut button = config.inputs[0].clone();
+        button.apply_overrides(Path::new("ui/buttons/play.png"));
+        assert_eq!(&button, &config.inputs[0]);
+
+        // Sidecar files take precedence over overrides.
+        let sidecar: SidecarConfig = toml::from_str(r#"codegen-kind = "asset-map""#).unwrap();
+        sidecar.apply_to(&mut background);
+        assert_eq!(background.codegen_kind, CodegenKind::AssetMap);
+    }
+}
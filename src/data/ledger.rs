@@ -0,0 +1,159 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::user_folder;
+
+static LEDGER_FILENAME: &str = "upload-ledger.toml";
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Keeps count of how many assets each account has uploaded today, so that
+/// Tarmac can warn before a sync runs into Roblox's upload limits.
+///
+/// The ledger is shared between every project on the machine. Accounts are
+/// identified by a hash of their credentials, so the ledger never contains
+/// the credentials themselves.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UploadLedger {
+    #[serde(default)]
+    accounts: BTreeMap<String, AccountUploads>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct AccountUploads {
+    /// The day these uploads happened on, counted in days since the Unix
+    /// epoch in UTC.
+    day: u64,
+    count: usize,
+}
+
+impl UploadLedger {
+    /// The path of the ledger in the current user's folder, if it can be
+    /// found.
+    pub fn default_path() -> Option<PathBuf> {
+        Some(user_folder()?.join(LEDGER_FILENAME))
+    }
+
+    /// Reads the ledger at the given path, or creates an empty ledger if there
+    /// isn't one yet.
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, LedgerError> {
+        let path = path.as_ref();
+
+        let contents = match fs::read(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        toml::from_slice(&contents).map_err(|source| LedgerError::DeserializeToml {
+            source,
+            file_path: path.to_owned(),
+        })
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), LedgerError> {
+        let path = path.as_ref();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let serialized = toml::to_vec(self)?;
+        fs::write(path, serialized)?;
+
+        log::trace!("Saved upload ledger to {}", path.display());
+
+        Ok(())
+    }
+
+    /// Identifies the account that the given credential belongs to.
+    pub fn account_key(credential: &str) -> String {
+        let hash = blake3::hash(credential.as_bytes()).to_hex();
+        hash.as_str()[..16].to_owned()
+    }
+
+    /// How many assets the given account has uploaded today.
+    pub fn uploads_today(&self, account: &str) -> usize {
+        self.uploads_on(account, today())
+    }
+
+    /// Adds to the number of assets the given account has uploaded today.
+    pub fn record_uploads(&mut self, account: &str, count: usize) {
+        self.record_uploads_on(account, today(), count);
+    }
+
+    fn uploads_on(&self, account: &str, day: u64) -> usize {
+        match self.accounts.get(account) {
+            Some(uploads) if uploads.day == day => uploads.count,
+            _ => 0,
+        }
+    }
+
+    fn record_uploads_on(&mut self, account: &str, day: u64, count: usize) {
+        let previous = self.uploads_on(account, day);
+
+        self.accounts.insert(
+            account.to_owned(),
+            AccountUploads {
+                day,
+                count: previous + count,
+            },
+        );
+    }
+}
+
+fn today() -> u64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    since_epoch.as_secs() / SECONDS_PER_DAY
+}
+
+#[derive(Debug, Error)]
+pub enum LedgerError {
+    #[error("Error deserializing TOML from path {}", .file_path.display())]
+    DeserializeToml {
+        file_path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error(transparent)]
+    SerializeToml {
+        #[from]
+        source: toml::ser::Error,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn counts_reset_each_day() {
+        let mut ledger = UploadLedger::default();
+
+        ledger.record_uploads_on("a", 10, 3);
+        ledger.record_uploads_on("a", 10, 2);
+        assert_eq!(ledger.uploads_on("a", 10), 5);
+        assert_eq!(ledger.uploads_on("b", 10), 0);
+
+        ledger.record_uploads_on("a", 11, 1);
+        assert_eq!(ledger.uploads_on("a", 11), 1);
+        assert_eq!(ledger.uploads_on("a", 10), 0);
+    }
+}
@@ -1,53 +1,556 @@
 use std::{
-    collections::BTreeMap,
-    io,
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    env, io,
     path::{Path, PathBuf},
 };
 
 use fs_err as fs;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use thiserror::Error;
 
-use crate::asset_name::AssetName;
+use crate::{asset_name::AssetName, roblox_web_api::AssetCreator};
 
 static MANIFEST_FILENAME: &str = "tarmac-manifest.toml";
 
+/// The manifest key that refers to the IDs stored directly on each input,
+/// which are used unless a profile picks another key.
+pub static DEFAULT_MANIFEST_KEY: &str = "default";
+
+/// The hash recorded for inputs whose IDs were imported with `tarmac
+/// import-ids`, whose uploaded contents Tarmac never saw. Real hashes are
+/// hexadecimal, so they never look like this.
+pub static UNKNOWN_HASH: &str = "unknown";
+
+/// The environment variable holding the key that manifests are signed with.
+/// When it's set, manifests are signed when they're written, and must have a
+/// valid signature to be read.
+pub static MANIFEST_SIGNING_KEY_VAR: &str = "TARMAC_MANIFEST_SIGNING_KEY";
+
+/// The environment variable that lets unsigned manifests be read while there's
+/// a signing key, which is how a project starts signing its manifest.
+pub static MANIFEST_ALLOW_UNSIGNED_VAR: &str = "TARMAC_MANIFEST_ALLOW_UNSIGNED";
+
+/// The version of the manifest format written by this version of Tarmac.
+/// Manifests written before the format was versioned are version 0.
+pub const MANIFEST_FORMAT_VERSION: u32 = 1;
+
 /// Tracks the status of all configuration, inputs, and outputs as of the last
 /// sync operation.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+///
+/// Manifests are usually checked into version control, so they always
+/// serialize the same way for the same contents: inputs and targets are kept
+/// in maps sorted by name, fields are written in the order they're declared,
+/// and lists are sorted before they're written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct Manifest {
+    /// The version of the format that this manifest was written in. Older
+    /// manifests are migrated to the current version when they're read.
+    #[serde(default)]
+    pub format_version: u32,
+
+    /// A hash of the rest of the manifest as Tarmac last wrote it, which shows
+    /// whether it was edited by hand since. Manifests without one aren't
+    /// checked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+
+    /// The same hash keyed with the signing key from the environment, for
+    /// teams that distribute manifests as artifacts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+
+    #[serde(default)]
     pub inputs: BTreeMap<AssetName, InputManifest>,
 }
 
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            format_version: MANIFEST_FORMAT_VERSION,
+            checksum: None,
+            signature: None,
+            inputs: BTreeMap::new(),
+        }
+    }
+}
+
 impl Manifest {
     pub fn read_from_folder<P: AsRef<Path>>(folder_path: P) -> Result<Self, ManifestError> {
-        let folder_path = folder_path.as_ref();
-        let file_path = &folder_path.join(MANIFEST_FILENAME);
+        Self::read_from_file(folder_path.as_ref().join(MANIFEST_FILENAME))
+    }
+
+    pub fn read_from_file<P: AsRef<Path>>(file_path: P) -> Result<Self, ManifestError> {
+        let file_path = file_path.as_ref();
 
         let contents = fs::read(file_path)?;
-        let config =
-            toml::from_slice(&contents).map_err(|source| ManifestError::DeserializeToml {
-                source,
-                file_path: file_path.to_owned(),
-            })?;
+        Self::parse(&contents, file_path, signing_key().as_ref())
+    }
+
+    fn parse(
+        contents: &[u8],
+        file_path: &Path,
+        signing_key: Option<&SigningKey>,
+    ) -> Result<Self, ManifestError> {
+        let deserialize_error = |source| ManifestError::DeserializeToml {
+            source,
+            file_path: file_path.to_owned(),
+        };
 
-        Ok(config)
+        let mut value: toml::Value = toml::from_slice(contents).map_err(|source| {
+            if has_conflict_markers(contents) {
+                ManifestError::MergeConflict {
+                    file_path: file_path.to_owned(),
+                }
+            } else if is_duplicate_key(&source) {
+                ManifestError::DuplicateKey {
+                    source,
+                    file_path: file_path.to_owned(),
+                }
+            } else {
+                deserialize_error(source)
+            }
+        })?;
+        migrate(&mut value, file_path)?;
+
+        let manifest: Manifest = value.try_into().map_err(deserialize_error)?;
+        manifest.verify(file_path, signing_key)?;
+
+        Ok(manifest)
     }
 
     pub fn write_to_folder<P: AsRef<Path>>(&self, folder_path: P) -> Result<(), ManifestError> {
-        let folder_path = folder_path.as_ref();
-        let file_path = &folder_path.join(MANIFEST_FILENAME);
+        self.write_to_file(folder_path.as_ref().join(MANIFEST_FILENAME))
+    }
 
-        let serialized = toml::to_vec(self)?;
-        fs::write(file_path, serialized)?;
+    pub fn write_to_file<P: AsRef<Path>>(&self, file_path: P) -> Result<(), ManifestError> {
+        let file_path = file_path.as_ref();
+
+        let serialized = self.serialize(signing_key().as_ref().map(|key| &key.key))?;
+
+        // Writing to a temporary file first means that the manifest is never
+        // left half-written, even if Tarmac is interrupted.
+        let temp_path = file_path.with_extension("toml.tmp");
+        fs::write(&temp_path, serialized)?;
+        std::fs::rename(&temp_path, file_path)?;
 
         log::trace!("Saved manifest to {}", file_path.display());
 
         Ok(())
     }
+
+    /// Serializes the manifest along with its checksum, and its signature if
+    /// there's a signing key.
+    fn serialize(
+        &self,
+        signing_key: Option<&[u8; blake3::KEY_LEN]>,
+    ) -> Result<Vec<u8>, ManifestError> {
+        let contents = self.checksummed_contents()?;
+
+        let signed = Manifest {
+            checksum: Some(blake3::hash(&contents).to_hex().to_string()),
+            signature: signing_key
+                .map(|key| blake3::keyed_hash(key, &contents).to_hex().to_string()),
+            ..self.clone()
+        };
+
+        Ok(toml::to_vec(&signed)?)
+    }
+
+    /// The contents that the checksum and signature cover: everything but
+    /// themselves, serialized the same way that the manifest is written.
+    fn checksummed_contents(&self) -> Result<Vec<u8>, ManifestError> {
+        let unsigned = Manifest {
+            checksum: None,
+            signature: None,
+            ..self.clone()
+        };
+
+        Ok(toml::to_vec(&unsigned)?)
+    }
+
+    /// Checks the signature of a manifest that was just read, if there's a
+    /// signing key, and whether it was edited by hand since Tarmac wrote it.
+    /// Edited manifests are fine as long as they're still consistent.
+    fn verify(
+        &self,
+        file_path: &Path,
+        signing_key: Option<&SigningKey>,
+    ) -> Result<(), ManifestError> {
+        let contents = self.checksummed_contents()?;
+
+        match (signing_key, &self.signature) {
+            (Some(key), Some(signature)) => {
+                let expected = blake3::keyed_hash(&key.key, &contents).to_hex();
+
+                if signature.as_str() != expected.as_str() {
+                    return Err(ManifestError::BadSignature {
+                        file_path: file_path.to_owned(),
+                    });
+                }
+            }
+            // Unsigned manifests are only read when that's asked for, so that
+            // a signed manifest can't be swapped for an unsigned one.
+            (Some(key), None) if key.allow_unsigned => log::warn!(
+                "{} isn't signed. It will be signed the next time it's written.",
+                file_path.display()
+            ),
+            (Some(_), None) => {
+                return Err(ManifestError::Unsigned {
+                    file_path: file_path.to_owned(),
+                })
+            }
+            (None, _) => {}
+        }
+
+        let checksum = match &self.checksum {
+            Some(checksum) => checksum,
+            None => return Ok(()),
+        };
+
+        if checksum.as_str() == blake3::hash(&contents).to_hex().as_str() {
+            return Ok(());
+        }
+
+        log::warn!(
+            "{} was edited since Tarmac last wrote it. Checking that it's still consistent...",
+            file_path.display()
+        );
+
+        self.check_consistency(file_path)
+    }
+
+    /// Looks for the mistakes that editing a manifest by hand can make, which
+    /// Tarmac never writes itself.
+    fn check_consistency(&self, file_path: &Path) -> Result<(), ManifestError> {
+        let mut owners_by_id: BTreeMap<u64, (&AssetName, &str)> = BTreeMap::new();
+
+        for (name, input) in &self.inputs {
+            let is_hash = input.hash.len() == 2 * blake3::OUT_LEN
+                && input.hash.bytes().all(|byte| byte.is_ascii_hexdigit());
+
+            if !is_hash && !input.hash.is_empty() && !input.has_unknown_hash() {
+                return Err(ManifestError::InvalidHash {
+                    file_path: file_path.to_owned(),
+                    name: name.clone(),
+                });
+            }
+
+            // Packed inputs share their spritesheet's ID, and inputs without
+            // a known hash could have been uploaded with any contents.
+            let id = match input.id {
+                Some(id) if is_hash && input.slice.is_none() => id,
+                _ => continue,
+            };
+
+            match owners_by_id.get(&id) {
+                Some((first, hash)) if *hash != input.hash => {
+                    return Err(ManifestError::MismatchedId {
+                        file_path: file_path.to_owned(),
+                        id,
+                        first: (*first).clone(),
+                        second: name.clone(),
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    owners_by_id.insert(id, (name, &input.hash));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Makes the name of every input lowercase, for projects that started
+    /// normalizing the case of asset names after they were first synced. If
+    /// more than one input has the same lowercase name, the first one is kept.
+    pub fn lowercase_names(&mut self) {
+        let inputs = std::mem::take(&mut self.inputs);
+
+        for (name, input) in inputs {
+            self.inputs.entry(name.to_lowercase()).or_insert(input);
+        }
+    }
+
+    /// The inputs as they were last synced under the given manifest key.
+    /// Inputs that were never synced under that key are left out.
+    pub fn for_key(&self, key: &str) -> Manifest {
+        if key == DEFAULT_MANIFEST_KEY {
+            return self.clone();
+        }
+
+        let inputs = self
+            .inputs
+            .iter()
+            .filter_map(|(name, input)| Some((name.clone(), input.targets.get(key)?.clone())))
+            .collect();
+
+        Manifest {
+            format_version: MANIFEST_FORMAT_VERSION,
+            checksum: None,
+            signature: None,
+            inputs,
+        }
+    }
+
+    /// Builds the manifest that results from syncing the inputs in `synced`
+    /// under the given manifest key, keeping what this manifest knows about
+    /// other keys. Inputs that aren't in `synced` are dropped.
+    pub fn merge_key(&self, key: &str, synced: Manifest) -> Manifest {
+        let inputs = synced
+            .inputs
+            .into_iter()
+            .map(|(name, mut input)| {
+                let previous = self.inputs.get(&name);
+
+                if key == DEFAULT_MANIFEST_KEY {
+                    input.targets = previous.map(|p| p.targets.clone()).unwrap_or_default();
+                    return (name, input);
+                }
+
+                // An input that was only ever synced under other keys gets an
+                // empty hash here, so that a sync using the default key still
+                // uploads it.
+                let mut merged = previous.cloned().unwrap_or_else(|| InputManifest {
+                    hash: String::new(),
+                    id: None,
+                    group_id: None,
+                    user_id: None,
+                    decal_id: None,
+                    universe_ids: Vec::new(),
+                    slice: None,
+                    spritesheet: None,
+                    rejected: false,
+                    packable: input.packable,
+                    targets: BTreeMap::new(),
+                });
+
+                merged.targets.insert(key.to_owned(), input);
+                (name, merged)
+            })
+            .collect();
+
+        Manifest {
+            format_version: MANIFEST_FORMAT_VERSION,
+            checksum: None,
+            signature: None,
+            inputs,
+        }
+    }
+
+    /// Merges two manifests that were changed separately from a common base,
+    /// like on two git branches. Inputs that only one side changed keep that
+    /// side's changes, and inputs that both sides uploaded with the same
+    /// contents keep the newer upload. Inputs whose contents differ between
+    /// the sides are returned as conflicts, and are kept from `ours` with no
+    /// hash so that the next sync uploads them again.
+    pub fn merge(
+        base: &Manifest,
+        ours: &Manifest,
+        theirs: &Manifest,
+    ) -> (Manifest, Vec<AssetName>) {
+        let mut conflicts = Vec::new();
+        let inputs = merge_maps(&base.inputs, &ours.inputs, &theirs.inputs, |name| {
+            conflicts.push(name.clone())
+        });
+
+        let manifest = Manifest {
+            format_version: MANIFEST_FORMAT_VERSION,
+            checksum: None,
+            signature: None,
+            inputs,
+        };
+
+        (manifest, conflicts)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn merge_maps<K: Ord + Clone>(
+    base: &BTreeMap<K, InputManifest>,
+    ours: &BTreeMap<K, InputManifest>,
+    theirs: &BTreeMap<K, InputManifest>,
+    mut on_conflict: impl FnMut(&K),
+) -> BTreeMap<K, InputManifest> {
+    let keys: BTreeSet<&K> = ours.keys().chain(theirs.keys()).collect();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let merged = merge_input(base.get(key), ours.get(key), theirs.get(key));
+
+            match merged {
+                MergedInput::Kept(input) => Some((key.clone(), input)),
+                MergedInput::Removed => None,
+                MergedInput::Conflict(input) => {
+                    on_conflict(key);
+                    Some((key.clone(), input))
+                }
+            }
+        })
+        .collect()
+}
+
+enum MergedInput {
+    Kept(InputManifest),
+    Removed,
+    Conflict(InputManifest),
+}
+
+fn merge_input(
+    base: Option<&InputManifest>,
+    ours: Option<&InputManifest>,
+    theirs: Option<&InputManifest>,
+) -> MergedInput {
+    let keep = |input: Option<&InputManifest>| match input {
+        Some(input) => MergedInput::Kept(input.clone()),
+        None => MergedInput::Removed,
+    };
+
+    if ours == theirs || theirs == base {
+        return keep(ours);
+    }
+
+    if ours == base {
+        return keep(theirs);
+    }
+
+    // Both sides changed the input. If one side stopped using it, the other
+    // side's changes are kept, and the next sync drops it if it's unused.
+    let (ours, theirs) = match (ours, theirs) {
+        (Some(ours), Some(theirs)) => (ours, theirs),
+        (ours, theirs) => return keep(ours.or(theirs)),
+    };
+
+    if ours.hash != theirs.hash {
+        let mut conflict = ours.clone();
+        conflict.hash = String::new();
+        return MergedInput::Conflict(conflict);
+    }
+
+    // Both sides uploaded the same contents, so either upload works. Asset IDs
+    // only go up, so the larger one is the newer upload.
+    let mut merged = if theirs.id > ours.id {
+        theirs.clone()
+    } else {
+        ours.clone()
+    };
+
+    let empty = BTreeMap::new();
+    let base_targets = base.map_or(&empty, |base| &base.targets);
+    let mut had_conflict = false;
+    merged.targets = merge_maps(base_targets, &ours.targets, &theirs.targets, |_| {
+        had_conflict = true
+    });
+
+    if had_conflict {
+        merged.hash = String::new();
+        MergedInput::Conflict(merged)
+    } else {
+        MergedInput::Kept(merged)
+    }
+}
+
+/// Upgrades a manifest read from disk to the current format version, one
+/// version at a time, so that manifests written by older versions of Tarmac
+/// keep working.
+fn migrate(manifest: &mut toml::Value, file_path: &Path) -> Result<(), ManifestError> {
+    let version = match manifest.get("format-version") {
+        None => 0,
+        Some(version) => version
+            .as_integer()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| ManifestError::InvalidVersion {
+                file_path: file_path.to_owned(),
+            })?,
+    };
+
+    if version > MANIFEST_FORMAT_VERSION {
+        return Err(ManifestError::TooNew {
+            file_path: file_path.to_owned(),
+            version,
+        });
+    }
+
+    if version < 1 {
+        // Unversioned manifests can come from any older version of Tarmac, so
+        // fields that are required now are filled in if they're missing. An
+        // empty hash means that the input is uploaded again on the next sync.
+        if let Some(inputs) = manifest.get_mut("inputs").and_then(|i| i.as_table_mut()) {
+            for input in inputs.iter_mut().filter_map(|(_, i)| i.as_table_mut()) {
+                fill_required_fields(input);
+
+                if let Some(targets) = input.get_mut("targets").and_then(|t| t.as_table_mut()) {
+                    for target in targets.iter_mut().filter_map(|(_, t)| t.as_table_mut()) {
+                        fill_required_fields(target);
+                    }
+                }
+            }
+        }
+    }
+
+    if version < MANIFEST_FORMAT_VERSION {
+        log::debug!(
+            "Migrating {} from manifest format version {} to {}",
+            file_path.display(),
+            version,
+            MANIFEST_FORMAT_VERSION
+        );
+    }
+
+    if let Some(table) = manifest.as_table_mut() {
+        table.insert(
+            "format-version".to_owned(),
+            toml::Value::Integer(MANIFEST_FORMAT_VERSION.into()),
+        );
+    }
+
+    Ok(())
+}
+
+/// The key that manifests are signed with, derived from the signing key in the
+/// environment.
+struct SigningKey {
+    key: [u8; blake3::KEY_LEN],
+
+    /// Whether manifests without a signature can be read anyways.
+    allow_unsigned: bool,
+}
+
+fn signing_key() -> Option<SigningKey> {
+    let key = env::var(MANIFEST_SIGNING_KEY_VAR).ok()?;
+
+    Some(SigningKey {
+        key: *blake3::hash(key.as_bytes()).as_bytes(),
+        allow_unsigned: env::var_os(MANIFEST_ALLOW_UNSIGNED_VAR).is_some(),
+    })
+}
+
+/// Whether the manifest still has the markers that git leaves in a file when
+/// it can't merge it.
+fn has_conflict_markers(contents: &[u8]) -> bool {
+    contents
+        .split(|&byte| byte == b'\n')
+        .any(|line| line.starts_with(b"<<<<<<< ") || line.starts_with(b">>>>>>> "))
+}
+
+/// Whether TOML failed to parse because a key or table was defined twice.
+/// The TOML library only describes its errors with their messages.
+fn is_duplicate_key(error: &toml::de::Error) -> bool {
+    let message = error.to_string();
+    message.starts_with("duplicate key") || message.starts_with("redefinition of table")
+}
+
+fn fill_required_fields(input: &mut toml::value::Table) {
+    input
+        .entry("hash")
+        .or_insert_with(|| toml::Value::String(String::new()));
+    input
+        .entry("packable")
+        .or_insert(toml::Value::Boolean(false));
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct InputManifest {
     /// The hexadecimal encoded hash of the contents of this input the last time
@@ -57,16 +560,99 @@ pub struct InputManifest {
     /// The asset ID that contains this input the last time it was uploaded.
     pub id: Option<u64>,
 
+    /// The group that owns the asset containing this input, if it was uploaded
+    /// to a group instead of a user's inventory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<u64>,
+
+    /// The user that owns the asset containing this input, if it was uploaded
+    /// to a user's inventory. Assets uploaded with the legacy upload API
+    /// before the uploading user was recorded have neither owner.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<u64>,
+
+    /// If the asset containing this input is an image that was uploaded as a
+    /// decal, the ID of that decal. `id` refers to the image itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decal_id: Option<u64>,
+
+    /// The universes that have been given permission to use the asset
+    /// containing this input.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_sorted"
+    )]
+    pub universe_ids: Vec<u64>,
+
     /// If the asset is an image that was packed into a spritesheet, contains
     /// the portion of the uploaded image that contains this input.
     pub slice: Option<ImageSlice>,
 
+    /// If the asset is an image that was packed into a spritesheet, the
+    /// hexadecimal encoded hash of that spritesheet. Inputs with the same
+    /// spritesheet hash were packed into the same image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spritesheet: Option<String>,
+
+    /// Whether the asset containing this input was rejected by moderation the
+    /// last time it was uploaded. Rejected inputs aren't uploaded again until
+    /// their contents change.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub rejected: bool,
+
     /// Whether the config applied to this input asked for it to be packed into
     /// a spritesheet.
     pub packable: bool,
+
+    /// The state of this input as synced under each manifest key besides the
+    /// default one, for projects that sync to more than one destination.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub targets: BTreeMap<String, InputManifest>,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+impl InputManifest {
+    /// An input that was uploaded to the given asset before the project used
+    /// Tarmac, with contents that are unknown until the next sync.
+    pub fn imported(id: u64) -> Self {
+        Self {
+            hash: UNKNOWN_HASH.to_owned(),
+            id: Some(id),
+            group_id: None,
+            user_id: None,
+            decal_id: None,
+            universe_ids: Vec::new(),
+            slice: None,
+            spritesheet: None,
+            rejected: false,
+            packable: false,
+            targets: BTreeMap::new(),
+        }
+    }
+
+    /// Whether this input's ID was imported and it hasn't been synced since,
+    /// so the contents that were uploaded aren't known.
+    pub fn has_unknown_hash(&self) -> bool {
+        self.hash == UNKNOWN_HASH
+    }
+
+    /// The group or user that this input was last uploaded for, if it was
+    /// recorded.
+    pub fn owner(&self) -> Option<AssetCreator> {
+        match (self.group_id, self.user_id) {
+            (Some(group_id), _) => Some(AssetCreator::Group(group_id)),
+            (None, Some(user_id)) => Some(AssetCreator::User(user_id)),
+            (None, None) => None,
+        }
+    }
+}
+
+fn serialize_sorted<S: Serializer>(ids: &[u64], serializer: S) -> Result<S::Ok, S::Error> {
+    let sorted: BTreeSet<u64> = ids.iter().copied().collect();
+    sorted.serialize(serializer)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct ImageSlice {
     coordinates: ((u32, u32), (u32, u32)),
@@ -103,6 +689,42 @@ pub enum ManifestError {
         source: toml::de::Error,
     },
 
+    #[error(
+        "{} was written by a newer version of Tarmac (manifest format version {version}, but this version supports up to {}). Update Tarmac to use this project.",
+        .file_path.display(),
+        MANIFEST_FORMAT_VERSION
+    )]
+    TooNew { file_path: PathBuf, version: u32 },
+
+    #[error("The format-version of {} isn't a valid version number", .file_path.display())]
+    InvalidVersion { file_path: PathBuf },
+
+    #[error("{} defines the same input or field more than once, which usually comes from editing it by hand or a bad merge. Remove the duplicate, or restore the manifest from version control.", .file_path.display())]
+    DuplicateKey {
+        file_path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("{} was edited by hand, and the hash of input {name} isn't one that Tarmac could have written. Restore the manifest from version control, or set the hash to \"\" to upload the input again.", .file_path.display())]
+    InvalidHash { file_path: PathBuf, name: AssetName },
+
+    #[error("{} was edited by hand, and inputs {first} and {second} both have ID {id} but different contents. Restore the manifest from version control, or fix the ID of the input that's wrong.", .file_path.display())]
+    MismatchedId {
+        file_path: PathBuf,
+        id: u64,
+        first: AssetName,
+        second: AssetName,
+    },
+
+    #[error("{} has a signature that doesn't match its contents and the key in {}. It was changed after it was signed, or signed with another key.", .file_path.display(), MANIFEST_SIGNING_KEY_VAR)]
+    BadSignature { file_path: PathBuf },
+
+    #[error("{} isn't signed, but {} is set. If this project's manifest hasn't been signed before, set {} for one sync to sign it.", .file_path.display(), MANIFEST_SIGNING_KEY_VAR, MANIFEST_ALLOW_UNSIGNED_VAR)]
+    Unsigned { file_path: PathBuf },
+
+    #[error("{} has unresolved merge conflicts. Manifests should be merged with 'tarmac merge-manifest', which can be set up as a git merge driver; see the README.", .file_path.display())]
+    MergeConflict { file_path: PathBuf },
+
     #[error(transparent)]
     SerializeToml {
         #[from]
@@ -124,3 +746,257 @@ impl ManifestError {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn synced(hash: &str, id: u64) -> InputManifest {
+        InputManifest {
+            hash: hash.to_owned(),
+            id: Some(id),
+            group_id: None,
+            user_id: None,
+            decal_id: None,
+            universe_ids: Vec::new(),
+            slice: None,
+            spritesheet: None,
+            rejected: false,
+            packable: false,
+            targets: BTreeMap::new(),
+        }
+    }
+
+    fn manifest(inputs: Vec<(&str, InputManifest)>) -> Manifest {
+        Manifest {
+            format_version: MANIFEST_FORMAT_VERSION,
+            checksum: None,
+            signature: None,
+            inputs: inputs
+                .into_iter()
+                .map(|(name, input)| (AssetName::new(name), input))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn ids_per_manifest_key() {
+        let stored = manifest(vec![("a.png", synced("a", 1))]);
+
+        let production = stored.merge_key(
+            "production",
+            manifest(vec![("a.png", synced("a", 2)), ("b.png", synced("b", 3))]),
+        );
+
+        let a = &production.inputs[&AssetName::new("a.png")];
+        assert_eq!(a.id, Some(1));
+        assert_eq!(a.targets["production"].id, Some(2));
+
+        let b = &production.inputs[&AssetName::new("b.png")];
+        assert_eq!(b.id, None);
+        assert_eq!(b.hash, "");
+
+        let view = production.for_key("production");
+        assert_eq!(view.inputs[&AssetName::new("b.png")].id, Some(3));
+        assert!(production.for_key("staging").inputs.is_empty());
+
+        let default = production.merge_key(
+            DEFAULT_MANIFEST_KEY,
+            manifest(vec![("a.png", synced("a", 4))]),
+        );
+        let a = &default.inputs[&AssetName::new("a.png")];
+        assert_eq!(a.id, Some(4));
+        assert_eq!(a.targets["production"].id, Some(2));
+    }
+
+    #[test]
+    fn migrate_unversioned_manifest() {
+        let mut value: toml::Value = toml::from_str(
+            r#"
+            [inputs."a.png"]
+            id = 1
+
+            [inputs."a.png".targets.production]
+            id = 2
+            "#,
+        )
+        .unwrap();
+
+        migrate(&mut value, Path::new("tarmac-manifest.toml")).unwrap();
+        let migrated: Manifest = value.try_into().unwrap();
+
+        assert_eq!(migrated.format_version, MANIFEST_FORMAT_VERSION);
+        let a = &migrated.inputs[&AssetName::new("a.png")];
+        assert_eq!(a.hash, "");
+        assert!(!a.packable);
+        assert_eq!(a.targets["production"].id, Some(2));
+    }
+
+    #[test]
+    fn reject_newer_manifest() {
+        let mut value: toml::Value = toml::from_str("format-version = 1000\ninputs = {}").unwrap();
+
+        let err = migrate(&mut value, Path::new("tarmac-manifest.toml")).unwrap_err();
+        assert!(matches!(err, ManifestError::TooNew { version: 1000, .. }));
+    }
+
+    #[test]
+    fn stable_serialization() {
+        let mut a = synced("a", 1);
+        a.universe_ids = vec![30, 10, 20, 10];
+        a.targets.insert("staging".to_owned(), synced("a", 3));
+        a.targets.insert("production".to_owned(), synced("a", 2));
+
+        let forward = manifest(vec![("a.png", a.clone()), ("b.png", synced("b", 4))]);
+        let backward = manifest(vec![("b.png", synced("b", 4)), ("a.png", a)]);
+
+        let serialized = toml::to_string(&forward).unwrap();
+        assert_eq!(serialized, toml::to_string(&backward).unwrap());
+
+        let position = |needle: &str| serialized.find(needle).unwrap();
+        assert!(position("format-version") < position("[inputs.\"a.png\"]"));
+        assert!(position("[inputs.\"a.png\"]") < position("[inputs.\"b.png\"]"));
+        assert!(position("targets.production") < position("targets.staging"));
+        assert!(serialized.contains("universe-ids = [10, 20, 30]"));
+    }
+
+    #[test]
+    fn merge_branches() {
+        let base = manifest(vec![
+            ("kept.png", synced("k", 1)),
+            ("ours.png", synced("o", 2)),
+            ("removed.png", synced("r", 3)),
+            ("both.png", synced("b", 4)),
+            ("conflict.png", synced("c", 5)),
+        ]);
+        let ours = manifest(vec![
+            ("kept.png", synced("k", 1)),
+            ("ours.png", synced("o2", 10)),
+            ("both.png", synced("b2", 11)),
+            ("conflict.png", synced("c2", 12)),
+            ("new-ours.png", synced("n", 13)),
+        ]);
+        let theirs = manifest(vec![
+            ("kept.png", synced("k", 1)),
+            ("ours.png", synced("o", 2)),
+            ("both.png", synced("b2", 20)),
+            ("conflict.png", synced("c3", 21)),
+            ("new-theirs.png", synced("m", 22)),
+        ]);
+
+        let (merged, conflicts) = Manifest::merge(&base, &ours, &theirs);
+        let id = |name: &str| merged.inputs.get(&AssetName::new(name)).and_then(|i| i.id);
+
+        assert_eq!(id("kept.png"), Some(1));
+        assert_eq!(id("ours.png"), Some(10));
+        assert_eq!(id("removed.png"), None);
+        assert_eq!(id("both.png"), Some(20));
+        assert_eq!(id("new-ours.png"), Some(13));
+        assert_eq!(id("new-theirs.png"), Some(22));
+
+        assert_eq!(conflicts, vec![AssetName::new("conflict.png")]);
+        assert_eq!(merged.inputs[&AssetName::new("conflict.png")].hash, "");
+    }
+
+    #[test]
+    fn lowercase_names() {
+        let mut lowercased = manifest(vec![
+            ("UI/Close.png", synced("a", 1)),
+            ("ui/close.png", synced("b", 2)),
+            ("ui/Open.png", synced("c", 3)),
+        ]);
+        lowercased.lowercase_names();
+
+        let names: Vec<_> = lowercased.inputs.keys().map(|name| name.as_ref()).collect();
+        assert_eq!(names, vec!["ui/close.png", "ui/open.png"]);
+        assert_eq!(
+            lowercased.inputs[&AssetName::new("ui/close.png")].id,
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn recorded_owners() {
+        let mut input = synced("a", 1);
+        assert_eq!(input.owner(), None);
+
+        input.user_id = Some(5);
+        assert_eq!(input.owner(), Some(AssetCreator::User(5)));
+
+        input.group_id = Some(7);
+        assert_eq!(input.owner(), Some(AssetCreator::Group(7)));
+    }
+
+    #[test]
+    fn detect_hand_edits() {
+        let hash_a = "a".repeat(64);
+        let hash_b = "b".repeat(64);
+        let written = manifest(vec![
+            ("a.png", synced(&hash_a, 1)),
+            ("b.png", synced(&hash_b, 2)),
+        ]);
+        let path = Path::new("tarmac-manifest.toml");
+        let key = SigningKey {
+            key: [7; blake3::KEY_LEN],
+            allow_unsigned: false,
+        };
+
+        let serialized = String::from_utf8(written.serialize(Some(&key.key)).unwrap()).unwrap();
+        assert!(Manifest::parse(serialized.as_bytes(), path, Some(&key)).is_ok());
+
+        // Edits that leave the manifest consistent are allowed, but not when
+        // it's signed.
+        let edited = serialized.replace("id = 2", "id = 3");
+        assert!(Manifest::parse(edited.as_bytes(), path, None).is_ok());
+        assert!(matches!(
+            Manifest::parse(edited.as_bytes(), path, Some(&key)),
+            Err(ManifestError::BadSignature { .. })
+        ));
+
+        let edited = serialized.replace("id = 2", "id = 1");
+        assert!(matches!(
+            Manifest::parse(edited.as_bytes(), path, None),
+            Err(ManifestError::MismatchedId { id: 1, .. })
+        ));
+
+        let edited = serialized.replace(&hash_b, "oops");
+        assert!(matches!(
+            Manifest::parse(edited.as_bytes(), path, None),
+            Err(ManifestError::InvalidHash { .. })
+        ));
+
+        let edited = format!("{}\n[inputs.\"a.png\"]\nhash = \"\"\n", serialized);
+        assert!(matches!(
+            Manifest::parse(edited.as_bytes(), path, None),
+            Err(ManifestError::DuplicateKey { .. })
+        ));
+
+        let edited = format!(
+            "<<<<<<< HEAD\n{}=======\n{}>>>>>>> main\n",
+            serialized, serialized
+        );
+        assert!(matches!(
+            Manifest::parse(edited.as_bytes(), path, None),
+            Err(ManifestError::MergeConflict { .. })
+        ));
+    }
+
+    #[test]
+    fn reject_unsigned_manifests() {
+        let unsigned = manifest(vec![("a.png", synced(&"a".repeat(64), 1))]);
+        let serialized = unsigned.serialize(None).unwrap();
+        let path = Path::new("tarmac-manifest.toml");
+
+        let mut key = SigningKey {
+            key: [7; blake3::KEY_LEN],
+            allow_unsigned: false,
+        };
+        assert!(matches!(
+            Manifest::parse(&serialized, path, Some(&key)),
+            Err(ManifestError::Unsigned { .. })
+        ));
+
+        key.allow_unsigned = true;
+        assert!(Manifest::parse(&serialized, path, Some(&key)).is_ok());
+    }
+}
@@ -1,7 +1,21 @@
 mod config;
+mod ledger;
 mod manifest;
+mod package;
+mod schema;
+mod sidecar;
 mod sync;
+mod sync_index;
+mod user_config;
+mod workspace;
 
 pub use config::*;
+pub use ledger::*;
 pub use manifest::*;
+pub use package::*;
+pub use schema::*;
+pub use sidecar::*;
 pub use sync::*;
+pub use sync_index::*;
+pub use user_config::*;
+pub use workspace::*;
@@ -0,0 +1,174 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+    path::{Path, PathBuf},
+};
+
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::asset_name::AssetName;
+
+use super::{ImageSlice, Manifest};
+
+pub static PACKAGE_FILENAME: &str = "tarmac-package.toml";
+
+/// A set of assets that were already uploaded, along with the code generated
+/// for them, which other projects can depend on without uploading the assets
+/// again or having their source images. Created by `tarmac package`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct AssetPackage {
+    /// The name of the project that the package was made from.
+    pub name: String,
+
+    /// The generated modules in the package, relative to the package's folder.
+    #[serde(default)]
+    pub modules: Vec<PathBuf>,
+
+    /// The uploaded assets, by their names in the project that the package
+    /// was made from.
+    #[serde(default)]
+    pub assets: BTreeMap<AssetName, PackagedAsset>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct PackagedAsset {
+    /// The ID of the asset containing this input.
+    pub id: u64,
+
+    /// If the asset is an image that was uploaded as a decal, the ID of that
+    /// decal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decal_id: Option<u64>,
+
+    /// If the asset is an image that was packed into a spritesheet, the
+    /// portion of the spritesheet that contains this input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slice: Option<ImageSlice>,
+}
+
+impl AssetPackage {
+    /// Packages every input of a manifest that has been uploaded, returning
+    /// the package along with the names of inputs that haven't been.
+    pub fn from_manifest(name: String, manifest: &Manifest) -> (Self, Vec<AssetName>) {
+        let mut assets = BTreeMap::new();
+        let mut unsynced = Vec::new();
+
+        for (input_name, input) in &manifest.inputs {
+            match input.id {
+                Some(id) => {
+                    assets.insert(
+                        input_name.clone(),
+                        PackagedAsset {
+                            id,
+                            decal_id: input.decal_id,
+                            slice: input.slice,
+                        },
+                    );
+                }
+                None => unsynced.push(input_name.clone()),
+            }
+        }
+
+        let package = Self {
+            name,
+            modules: Vec::new(),
+            assets,
+        };
+
+        (package, unsynced)
+    }
+
+    pub fn read_from_folder<P: AsRef<Path>>(folder_path: P) -> Result<Self, PackageError> {
+        let file_path = folder_path.as_ref().join(PACKAGE_FILENAME);
+
+        let contents = fs::read(&file_path)?;
+        toml::from_slice(&contents)
+            .map_err(|source| PackageError::DeserializeToml { source, file_path })
+    }
+
+    pub fn write_to_folder<P: AsRef<Path>>(&self, folder_path: P) -> Result<(), PackageError> {
+        let file_path = folder_path.as_ref().join(PACKAGE_FILENAME);
+
+        let serialized = toml::to_vec(self)?;
+        fs::write(&file_path, serialized)?;
+
+        Ok(())
+    }
+
+    /// The IDs of every asset in the package, as used by asset lists.
+    pub fn asset_ids(&self) -> BTreeSet<u64> {
+        self.assets.values().map(|asset| asset.id).collect()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PackageError {
+    #[error("Error deserializing TOML from path {}", .file_path.display())]
+    DeserializeToml {
+        file_path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error(transparent)]
+    SerializeToml {
+        #[from]
+        source: toml::ser::Error,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn package_synced_inputs() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+            [inputs."icons/star.png"]
+            hash = "a"
+            id = 10
+            decal-id = 11
+            packable = false
+
+            [inputs."icons/moon.png"]
+            hash = "b"
+            id = 20
+            slice = [[0, 0], [16, 16]]
+            packable = true
+
+            [inputs."icons/new.png"]
+            hash = "c"
+            packable = false
+            "#,
+        )
+        .unwrap();
+
+        let (package, unsynced) = AssetPackage::from_manifest("icons".to_owned(), &manifest);
+
+        assert_eq!(unsynced, vec![AssetName::new("icons/new.png")]);
+        assert_eq!(
+            package.asset_ids().into_iter().collect::<Vec<_>>(),
+            vec![10, 20]
+        );
+
+        let serialized = toml::to_string(&package).unwrap();
+        let parsed: AssetPackage = toml::from_str(&serialized).unwrap();
+        assert_eq!(
+            parsed.assets[&AssetName::new("icons/star.png")].decal_id,
+            Some(11)
+        );
+        assert!(parsed.assets[&AssetName::new("icons/moon.png")]
+            .slice
+            .is_some());
+    }
+}
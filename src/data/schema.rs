@@ -0,0 +1,604 @@
+//! A JSON Schema describing Tarmac project configs, for editors and CI to
+//! validate `tarmac.toml` files with.
+//!
+//! The schema is written out by hand, but the names of fields and enum values
+//! come from the serde types in this module's siblings, and the tests below
+//! check that every field is described.
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::options::SyncTarget;
+
+use super::{
+    AssetNameCase, CodegenFormat, CodegenId, CodegenKind, CodegenOrder, DimensionPolicy,
+    OverlapPolicy, SliceFormat, SymlinkPolicy, UploadApi, UrlFormat, WalkErrorPolicy,
+};
+
+static SCHEMA_URL: &str = "http://json-schema.org/draft-07/schema#";
+
+/// Builds the JSON Schema for Tarmac project configs.
+pub fn config_schema() -> Value {
+    let mut schema = config_object();
+    let root = schema.as_object_mut().unwrap();
+
+    root.insert("$schema".to_owned(), json!(SCHEMA_URL));
+    root.insert("title".to_owned(), json!("Tarmac project"));
+    root.insert(
+        "definitions".to_owned(),
+        json!({
+            "InputConfig": input_object(),
+            "InputDefaults": input_defaults_object(),
+            "Profile": profile_object(),
+        }),
+    );
+
+    schema
+}
+
+fn config_object() -> Value {
+    object(
+        "A Tarmac project, described by a tarmac.toml file.",
+        &["name"],
+        vec![
+            ("name", string("The name of the project, used in logging and error reporting.")),
+            (
+                "max-spritesheet-size",
+                json!({
+                    "description": "The largest size that packed spritesheets can be. Defaults to [1024, 1024].",
+                    "type": "array",
+                    "items": [
+            { "type": "integer", "minimum": 1 },
+            { "type": "integer", "minimum": 1 },
+        ],
+                    "minItems": 2,
+                    "maxItems": 2,
+                }),
+            ),
+            ("asset-cache-path", string("A folder to download every uploaded asset into.")),
+            ("asset-list-path", string("A file to write the URL of every asset in the project to.")),
+            (
+                "preload-list-path",
+                string("A Lua module to generate that returns the URL of every asset in the project."),
+            ),
+            (
+                "preload-list-tags",
+                array(
+                    "If given, the preload list only contains assets with at least one of these tags.",
+                    string(""),
+                ),
+            ),
+            (
+                "dev-content",
+                object(
+                    "A folder that Roblox Studio can load content from, which assets are copied into for use during development.",
+                    &["path", "url"],
+                    vec![
+                        ("path", string("The folder to copy assets into.")),
+                        ("url", string("The URL that refers to the folder from Roblox, like rbxasset://tarmac.")),
+                    ],
+                ),
+            ),
+            (
+                "remote-cache",
+                object(
+                    "A cache of uploaded asset IDs shared between machines, which is checked before uploading an asset.",
+                    &["url"],
+                    vec![("url", string("The HTTP URL that cache entries are stored under."))],
+                ),
+            ),
+            ("upload-to-group-id", integer("The group to upload every asset to.")),
+            (
+                "upload-api",
+                variants(
+                    "The Roblox API to upload assets with. Defaults to legacy.",
+                    &[UploadApi::Legacy, UploadApi::OpenCloud],
+                ),
+            ),
+            (
+                "upload-to-user-id",
+                integer("The user that owns assets uploaded with Open Cloud when no group is given."),
+            ),
+            (
+                "upload-name-template",
+                string("The template that uploaded assets are named with. Supports {name}, {path}, {folder}, {hash}, and {project}."),
+            ),
+            (
+                "symlinks",
+                variants(
+                    "Whether symlinks are followed while searching for projects and inputs. Defaults to follow.",
+                    &[SymlinkPolicy::Follow, SymlinkPolicy::Ignore],
+                ),
+            ),
+            (
+                "walk-errors",
+                variants(
+                    "What to do when part of the project can't be searched for inputs. Defaults to error.",
+                    &[
+                        WalkErrorPolicy::Error,
+                        WalkErrorPolicy::Warn,
+                        WalkErrorPolicy::Skip,
+                    ],
+                ),
+            ),
+            (
+                "overlapping-globs",
+                variants(
+                    "Which input group a file belongs to when more than one matches it. Defaults to error.",
+                    &[
+                        OverlapPolicy::Error,
+                        OverlapPolicy::MostSpecific,
+                        OverlapPolicy::LastConfig,
+                        OverlapPolicy::Priority,
+                    ],
+                ),
+            ),
+            (
+                "asset-name-case",
+                variants(
+                    "How the case of the names that assets are tracked by is normalized. Defaults to preserve.",
+                    &[AssetNameCase::Preserve, AssetNameCase::Lower],
+                ),
+            ),
+            (
+                "respect-gitignore",
+                boolean("Whether files matched by the repository's .gitignore files are left out of input discovery."),
+            ),
+            ("defaults", reference("InputDefaults")),
+            (
+                "codegen-manifest-key",
+                string("The manifest key whose asset IDs generated code refers to."),
+            ),
+            (
+                "profiles",
+                json!({
+                    "description": "Named sets of overrides that can be selected with --profile.",
+                    "type": "object",
+                    "additionalProperties": reference("Profile"),
+                }),
+            ),
+            (
+                "includes",
+                array(
+                    "Paths to search for other Tarmac projects, or remote projects to fetch, whose inputs are merged into this one.",
+                    json!({
+                        "oneOf": [
+                            string("A config file, or a folder to search for config files. Can be a glob matching more than one."),
+                            object(
+                                "A git repository, checked out at a pinned revision.",
+                                &["git", "rev"],
+                                vec![
+                                    ("git", string("The URL of the repository.")),
+                                    ("rev", string("The commit, tag, or branch to check out.")),
+                                    ("path", string("The path inside the repository to search for configs.")),
+                                ],
+                            ),
+                            object(
+                                "An archive in tar format, downloaded over HTTPS.",
+                                &["url", "sha256"],
+                                vec![
+                                    ("url", string("The HTTPS URL of the archive.")),
+                                    ("sha256", string("The SHA-256 hash of the archive, as 64 hexadecimal digits.")),
+                                    ("path", string("The path inside the archive to search for configs.")),
+                                ],
+                            ),
+                        ],
+                    }),
+                ),
+            ),
+            (
+                "include-max-depth",
+                integer("How many folders deep projects are searched for below each include. Unlimited by default."),
+            ),
+            (
+                "include-exclude",
+                array(
+                    "Folders that aren't searched for projects below this project's includes, like node_modules.",
+                    string("A glob matching a folder's name, or its path relative to this project if it contains a slash."),
+                ),
+            ),
+            (
+                "packages",
+                array(
+                    "Folders containing asset packages made with `tarmac package`, whose assets this project uses without uploading them.",
+                    string("A folder containing a tarmac-package.toml file."),
+                ),
+            ),
+            (
+                "inputs",
+                array("The groups of files that Tarmac manages.", reference("InputConfig")),
+            ),
+        ],
+    )
+}
+
+fn input_object() -> Value {
+    object(
+        "A group of files that Tarmac manages.",
+        &["glob"],
+        vec![
+            ("glob", string("A glob matching the files in this input group.")),
+            (
+                "exclude",
+                array("Globs matching files to leave out of this input group.", string("")),
+            ),
+            (
+                "tags",
+                array("Freeform labels that syncs, packing, preload lists, and generated files can be limited to.", string("")),
+            ),
+            ("codegen", boolean("Whether to generate code for the assets in this input group.")),
+            ("codegen-path", string("The Lua module to generate for this input group.")),
+            (
+                "codegen-base-path",
+                string("The folder that the paths of assets in generated code are relative to."),
+            ),
+            ("codegen-kind", codegen_kind()),
+            (
+                "codegen-namespace",
+                string("A slash-separated path that the assets are nested under in generated code."),
+            ),
+            (
+                "codegen-key-prefix",
+                string("A prefix added to every key in generated code."),
+            ),
+            (
+                "codegen-order",
+                variants(
+                    "The order that keys appear in generated code. Defaults to alphabetical.",
+                    &[
+                        CodegenOrder::Alphabetical,
+                        CodegenOrder::Natural,
+                        CodegenOrder::Discovery,
+                    ],
+                ),
+            ),
+            (
+                "codegen-key-order",
+                array("Keys to place first in generated code, in order.", string("")),
+            ),
+            (
+                "codegen-outputs",
+                array(
+                    "Additional files to generate for this input group.",
+                    object(
+                        "An additional file to generate.",
+                        &["path", "format"],
+                        vec![
+                            ("path", string("The file to generate.")),
+                            (
+                                "format",
+                                variants(
+                                    "The format of the file.",
+                                    &[
+                                        CodegenFormat::Lua,
+                                        CodegenFormat::Json,
+                                        CodegenFormat::Typescript,
+                                        CodegenFormat::Rbxmx,
+                                    ],
+                                ),
+                            ),
+                            (
+                                "tags",
+                                array(
+                                    "If given, only inputs with at least one of these tags are generated into the file.",
+                                    string(""),
+                                ),
+                            ),
+                        ],
+                    ),
+                ),
+            ),
+            (
+                "codegen-include-size",
+                boolean("Whether generated code includes the native size of each image."),
+            ),
+            ("codegen-slice-format", slice_format()),
+            ("codegen-url-format", url_format()),
+            ("codegen-id", codegen_id()),
+            (
+                "codegen-placeholder-id",
+                integer("The ID generated code refers to for inputs that haven't been uploaded or were rejected."),
+            ),
+            ("upload-to-group-id", integer("The group to upload this input group's assets to.")),
+            ("upload-to-user-id", integer("The user to upload this input group's assets for.")),
+            (
+                "upload-name",
+                string("The name to upload each asset with, instead of the project's upload-name-template."),
+            ),
+            ("upload-description", string("The description to upload each asset with.")),
+            (
+                "asset-id",
+                integer("The ID of an existing asset that these inputs refer to instead of being uploaded."),
+            ),
+            (
+                "packable",
+                boolean("Whether the images in this input group can be packed into spritesheets."),
+            ),
+            ("max-dimensions", max_dimensions()),
+            ("max-dimensions-policy", dimension_policy()),
+            (
+                "priority",
+                integer("Decides which input group wins when overlapping-globs is priority. Higher wins."),
+            ),
+            (
+                "overrides",
+                array(
+                    "Settings that replace this input group's for the files that match another glob.",
+                    object(
+                        "Settings for some of the input group's files.",
+                        &["glob"],
+                        vec![
+                            ("glob", string("A glob matching the files these settings apply to.")),
+                            ("packable", boolean("Whether the images can be packed into spritesheets.")),
+                            ("codegen", boolean("Whether to generate code for the assets.")),
+                            ("codegen-path", string("A file to generate the code for the assets into.")),
+                            ("codegen-kind", codegen_kind()),
+                            (
+                                "codegen-include-size",
+                                boolean("Whether generated code includes the native size of each image."),
+                            ),
+                            ("codegen-slice-format", slice_format()),
+                            ("codegen-url-format", url_format()),
+                        ],
+                    ),
+                ),
+            ),
+        ],
+    )
+}
+
+fn input_defaults_object() -> Value {
+    object(
+        "Settings used by every input group that doesn't set them itself. Included projects inherit them.",
+        &[],
+        vec![
+            ("codegen", boolean("Whether to generate code for assets.")),
+            ("codegen-kind", codegen_kind()),
+            ("codegen-slice-format", slice_format()),
+            ("codegen-url-format", url_format()),
+            (
+                "codegen-placeholder-id",
+                integer("The ID generated code refers to for inputs that haven't been uploaded or were rejected."),
+            ),
+            ("packable", boolean("Whether images can be packed into spritesheets.")),
+            ("max-dimensions", max_dimensions()),
+            ("max-dimensions-policy", dimension_policy()),
+            ("upload-to-group-id", integer("The group to upload assets to.")),
+            ("upload-to-user-id", integer("The user to upload assets for.")),
+            ("upload-description", string("The description to upload assets with.")),
+        ],
+    )
+}
+
+fn profile_object() -> Value {
+    object(
+        "Overrides applied to the project when it's selected with --profile.",
+        &[],
+        vec![
+            (
+                "target",
+                variants(
+                    "The sync target used when --target isn't given.",
+                    &[SyncTarget::Roblox, SyncTarget::None, SyncTarget::Debug],
+                ),
+            ),
+            (
+                "upload-api",
+                variants(
+                    "The Roblox API to upload assets with.",
+                    &[UploadApi::Legacy, UploadApi::OpenCloud],
+                ),
+            ),
+            (
+                "upload-to-group-id",
+                integer("The group to upload every asset to."),
+            ),
+            (
+                "upload-to-user-id",
+                integer("The user to upload every asset for."),
+            ),
+            ("codegen-url-format", url_format()),
+            ("codegen-id", codegen_id()),
+            (
+                "manifest-key",
+                string("The key that this profile's asset IDs are stored under in the manifest."),
+            ),
+            (
+                "codegen-manifest-key",
+                string("The manifest key whose asset IDs generated code refers to."),
+            ),
+        ],
+    )
+}
+
+fn codegen_kind() -> Value {
+    variants(
+        "The shape of generated code. Defaults to table.",
+        &[
+            CodegenKind::Table,
+            CodegenKind::AssetMap,
+            CodegenKind::Lookup,
+        ],
+    )
+}
+
+fn slice_format() -> Value {
+    variants(
+        "How generated code describes the slice of a spritesheet. Defaults to vector2.",
+        &[SliceFormat::Vector2, SliceFormat::Udim2, SliceFormat::Rect],
+    )
+}
+
+fn url_format() -> Value {
+    variants(
+        "The format of asset links in generated code. Defaults to rbxassetid.",
+        &[UrlFormat::Rbxassetid, UrlFormat::Https, UrlFormat::Id],
+    )
+}
+
+fn codegen_id() -> Value {
+    variants(
+        "Which ID generated code refers to for images uploaded as decals. Defaults to image.",
+        &[CodegenId::Image, CodegenId::Decal],
+    )
+}
+
+fn max_dimensions() -> Value {
+    json!({
+        "description": "The largest width and height that images can have.",
+        "type": "array",
+        "items": [
+            { "type": "integer", "minimum": 1 },
+            { "type": "integer", "minimum": 1 },
+        ],
+        "minItems": 2,
+        "maxItems": 2,
+    })
+}
+
+fn dimension_policy() -> Value {
+    variants(
+        "What to do with images larger than max-dimensions. Defaults to error.",
+        &[
+            DimensionPolicy::Error,
+            DimensionPolicy::Warn,
+            DimensionPolicy::Resize,
+        ],
+    )
+}
+
+fn object(description: &str, required: &[&str], properties: Vec<(&str, Value)>) -> Value {
+    let properties: Map<String, Value> = properties
+        .into_iter()
+        .map(|(name, schema)| (name.to_owned(), schema))
+        .collect();
+
+    let mut schema = json!({
+        "description": description,
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    });
+
+    if !required.is_empty() {
+        schema["required"] = json!(required);
+    }
+
+    schema
+}
+
+fn array(description: &str, items: Value) -> Value {
+    json!({ "description": description, "type": "array", "items": items })
+}
+
+fn reference(definition: &str) -> Value {
+    json!({ "$ref": format!("#/definitions/{}", definition) })
+}
+
+fn string(description: &str) -> Value {
+    described(description, json!({ "type": "string" }))
+}
+
+fn integer(description: &str) -> Value {
+    described(description, json!({ "type": "integer" }))
+}
+
+fn boolean(description: &str) -> Value {
+    described(description, json!({ "type": "boolean" }))
+}
+
+/// Describes a string enum, using the names that serde gives its variants.
+fn variants<T: Serialize>(description: &str, variants: &[T]) -> Value {
+    let names: Vec<Value> = variants
+        .iter()
+        .map(|variant| serde_json::to_value(variant).unwrap())
+        .collect();
+
+    described(description, json!({ "type": "string", "enum": names }))
+}
+
+fn described(description: &str, mut schema: Value) -> Value {
+    if !description.is_empty() {
+        schema["description"] = json!(description);
+    }
+
+    schema
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeSet, path::Path};
+
+    use super::*;
+    use crate::data::{Config, InputDefaults};
+
+    fn keys(value: &Value) -> BTreeSet<String> {
+        value.as_object().unwrap().keys().cloned().collect()
+    }
+
+    #[test]
+    fn describes_every_field() {
+        let config = Config::parse(
+            br#"
+            name = "game"
+            dev-content = { path = "content", url = "rbxasset://tarmac" }
+            remote-cache = { url = "https://cache.example.com/tarmac" }
+            includes = [
+                { git = "https://github.com/studio/ui-kit.git", rev = "v1", path = "assets" },
+                { url = "https://example.com/icons.tar", sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08", path = "icons" },
+            ]
+
+            [profiles.release]
+
+            [[inputs]]
+            glob = "*.png"
+            codegen-outputs = [{ path = "Assets.json", format = "json" }]
+            overrides = [{ glob = "bg/*.png", packable = false }]
+            "#,
+            Path::new("tarmac.toml"),
+            &InputDefaults::default(),
+        )
+        .unwrap();
+
+        let config = serde_json::to_value(&config).unwrap();
+        let schema = config_schema();
+        let definitions = &schema["definitions"];
+        let input = &definitions["InputConfig"]["properties"];
+        let include = &schema["properties"]["includes"]["items"]["oneOf"];
+
+        let pairs = [
+            (&config, &schema["properties"]),
+            (
+                &config["dev-content"],
+                &schema["properties"]["dev-content"]["properties"],
+            ),
+            (
+                &config["remote-cache"],
+                &schema["properties"]["remote-cache"]["properties"],
+            ),
+            (
+                &config["defaults"],
+                &definitions["InputDefaults"]["properties"],
+            ),
+            (
+                &config["profiles"]["release"],
+                &definitions["Profile"]["properties"],
+            ),
+            (&config["includes"][0], &include[1]["properties"]),
+            (&config["includes"][1], &include[2]["properties"]),
+            (&config["inputs"][0], input),
+            (
+                &config["inputs"][0]["codegen-outputs"][0],
+                &input["codegen-outputs"]["items"]["properties"],
+            ),
+            (
+                &config["inputs"][0]["overrides"][0],
+                &input["overrides"]["items"]["properties"],
+            ),
+        ];
+
+        for (value, properties) in &pairs {
+            assert_eq!(keys(value), keys(properties));
+        }
+    }
+}
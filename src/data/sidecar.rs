@@ -0,0 +1,125 @@
+use std::{io, path::Path};
+
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
+
+use crate::data::{CodegenKind, ConfigError, ImageSlice, InputConfig};
+
+static SIDECAR_EXTENSION: &str = "toml";
+
+/// Overrides for a single input, read from a file next to it with an extra
+/// `.toml` extension, like `foo.png.toml`.
+///
+/// Any field left unspecified keeps the value from the input's glob config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SidecarConfig {
+    /// Overrides whether Tarmac should generate code for this input.
+    pub codegen: Option<bool>,
+
+    /// Overrides the key used to refer to this input in grouped codegen,
+    /// which is normally derived from the input's file name.
+    pub codegen_name: Option<String>,
+
+    /// Overrides whether generated code for this input should include its
+    /// native size.
+    pub codegen_include_size: Option<bool>,
+
+    /// Overrides what kind of code is generated for this input.
+    pub codegen_kind: Option<CodegenKind>,
+
+    /// Overrides the name that this input is uploaded with.
+    pub upload_name: Option<String>,
+
+    /// Overrides the description that this input is uploaded with.
+    pub upload_description: Option<String>,
+
+    /// Overrides whether this input can be packed into spritesheets.
+    pub packable: Option<bool>,
+
+    /// The ID of an existing asset that this input refers to instead of
+    /// being uploaded.
+    pub asset_id: Option<u64>,
+
+    /// A region of this image that generated code should refer to. Only
+    /// applies to images that aren't packed into spritesheets.
+    pub slice: Option<ImageSlice>,
+
+    /// Tags to give this input in addition to the ones from its glob config.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl SidecarConfig {
+    /// Reads the sidecar file for the input at the given path, if one exists.
+    pub fn read_for_input<P: AsRef<Path>>(input_path: P) -> Result<Option<Self>, ConfigError> {
+        let input_path = input_path.as_ref();
+        let mut sidecar_path = input_path.as_os_str().to_owned();
+        sidecar_path.push(".");
+        sidecar_path.push(SIDECAR_EXTENSION);
+        let sidecar_path = Path::new(&sidecar_path);
+
+        let contents = match fs::read(sidecar_path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let sidecar = toml::from_slice(&contents).map_err(|source| ConfigError::Toml {
+            source,
+            path: sidecar_path.to_owned(),
+        })?;
+
+        Ok(Some(sidecar))
+    }
+
+    /// Applies the overrides from this sidecar that correspond to fields in
+    /// `InputConfig`.
+    pub fn apply_to(&self, config: &mut InputConfig) {
+        if let Some(codegen) = self.codegen {
+            config.codegen = codegen;
+        }
+
+        if let Some(include_size) = self.codegen_include_size {
+            config.codegen_include_size = include_size;
+        }
+
+        if let Some(codegen_kind) = self.codegen_kind {
+            config.codegen_kind = codegen_kind;
+        }
+
+        if let Some(upload_name) = &self.upload_name {
+            config.upload_name = Some(upload_name.clone());
+        }
+
+        if let Some(upload_description) = &self.upload_description {
+            config.upload_description = Some(upload_description.clone());
+        }
+
+        if let Some(packable) = self.packable {
+            config.packable = packable;
+        }
+
+        if let Some(asset_id) = self.asset_id {
+            config.asset_id = Some(asset_id);
+        }
+
+        for tag in &self.tags {
+            if !config.tags.contains(tag) {
+                config.tags.push(tag.clone());
+            }
+        }
+    }
+}
+
+/// Tells whether the given path is a sidecar file describing another input,
+/// rather than an input itself.
+pub fn is_sidecar_path(path: &Path) -> bool {
+    match path.extension() {
+        Some(extension) if extension == SIDECAR_EXTENSION => {
+            let described = path.with_extension("");
+            described.extension().is_some() && described.is_file()
+        }
+        _ => false,
+    }
+}
@@ -1,8 +1,11 @@
-use std::path::PathBuf;
+use std::{borrow::Cow, io, path::PathBuf};
+
+use fs_err as fs;
 
 use crate::{
     asset_name::AssetName,
-    data::{ImageSlice, InputConfig, InputManifest},
+    data::{CodegenId, FileStamp, ImageSlice, InputConfig, InputManifest},
+    roblox_web_api::{AssetCreator, AssetFormat, AssetKind, UploadContents},
 };
 
 /// In-memory representation of a Tarmac Input during the sync process.
@@ -17,6 +20,9 @@ pub struct SyncInput {
     /// The path on disk to the file this input originated from.
     pub path: PathBuf,
 
+    /// The path of the config file whose input group this input belongs to.
+    pub config_path: PathBuf,
+
     /// The input's path with DPI scale information stripped away. This is used
     /// to group inputs that are just DPI variations of eachother.
     pub path_without_dpi_scale: PathBuf,
@@ -24,27 +30,247 @@ pub struct SyncInput {
     /// The DPI scale of this input, if it makes sense for this input type.
     pub dpi_scale: u32,
 
+    /// The position of this input in discovery order, starting from the root
+    /// config's first input group.
+    pub discovery_index: usize,
+
     /// The configuration that applied to this input when it was discovered.
     pub config: InputConfig,
 
-    /// The contents of the file this input originated from.
-    pub contents: Vec<u8>,
+    /// If specified, the key used to refer to this input in grouped codegen
+    /// instead of its file name.
+    pub codegen_name: Option<String>,
+
+    /// If the project has `dev-content` configured, the URL of this input's
+    /// local copy in the Roblox Studio content folder.
+    pub dev_url: Option<String>,
+
+    /// The contents of the file this input originated from, unless its hash
+    /// was cached and the file hasn't been read.
+    pub contents: Option<UploadContents>,
 
-    /// A hash of `contents`.
+    /// The size and modification time that this input's file had when it was
+    /// discovered, if they're known.
+    pub stamp: Option<FileStamp>,
+
+    /// A hash of this input's contents.
     pub hash: String,
 
     /// If this input has been part of an upload to Roblox.com, contains the
     /// asset ID that contains the data from this input.
     pub id: Option<u64>,
 
+    /// If this input was uploaded to a group, contains the ID of that group.
+    pub group_id: Option<u64>,
+
+    /// If this input was uploaded to a user's inventory, contains the ID of
+    /// that user, if it's known.
+    pub user_id: Option<u64>,
+
+    /// If this input was uploaded as a decal, contains the ID of that decal.
+    /// `id` always refers to the image the decal resolved to.
+    pub decal_id: Option<u64>,
+
+    /// The universes that have been given permission to use the asset that
+    /// contains this input.
+    pub universe_ids: Vec<u64>,
+
     /// If this input has been packed into a spritesheet, contains the slice of
     /// the spritesheet that this input is located in.
     pub slice: Option<ImageSlice>,
+
+    /// If this input has been packed into a spritesheet, contains the hash of
+    /// that spritesheet.
+    pub spritesheet: Option<String>,
+
+    /// Whether the last upload containing this input was rejected by
+    /// moderation. Rejected inputs have no ID.
+    pub rejected: bool,
+
+    /// The group or user that this input is uploaded for: the one from its
+    /// config, or else the one the whole sync uploads to. Filled in once the
+    /// sync's destination is known, and None if it isn't.
+    pub owner: Option<AssetCreator>,
 }
 
 impl SyncInput {
+    /// Creates an input for the file with the given name, configured by an
+    /// input group with the given extra config lines.
+    #[cfg(test)]
+    pub(crate) fn new(name: &str, config: &str) -> Self {
+        use crate::data::{Config, InputDefaults};
+
+        let config = Config::parse(
+            format!("name = \"game\"\n[[inputs]]\nglob = \"**\"\n{}", config).as_bytes(),
+            std::path::Path::new("tarmac.toml"),
+            &InputDefaults::default(),
+        )
+        .unwrap();
+
+        Self {
+            name: AssetName::new(name),
+            path: PathBuf::from(name),
+            config_path: PathBuf::from("tarmac.toml"),
+            path_without_dpi_scale: PathBuf::from(name),
+            dpi_scale: 1,
+            discovery_index: 0,
+            config: config.inputs[0].clone(),
+            codegen_name: None,
+            dev_url: None,
+            contents: None,
+            stamp: None,
+            hash: "hash".to_owned(),
+            id: None,
+            group_id: None,
+            user_id: None,
+            decal_id: None,
+            universe_ids: Vec::new(),
+            slice: None,
+            spritesheet: None,
+            rejected: false,
+            owner: None,
+        }
+    }
+
+    /// The ID that generated code should use to refer to this input, as
+    /// chosen by its `codegen-id` config.
+    pub fn codegen_id(&self) -> Option<u64> {
+        match self.config.codegen_id {
+            CodegenId::Image => self.id,
+            CodegenId::Decal => self.decal_id.or(self.id),
+        }
+    }
+
+    /// Whether this input refers to an existing asset given by its config,
+    /// which Tarmac never uploads or modifies.
+    pub fn is_pinned(&self) -> bool {
+        self.config.asset_id.is_some()
+    }
+
+    /// This input's contents, read from its file if they weren't kept when it
+    /// was discovered.
+    pub fn read_contents(&self) -> io::Result<Cow<'_, [u8]>> {
+        match &self.contents {
+            Some(contents) if contents.is_mapped() && self.changed_since_discovery()? => {
+                self.read_again().map(Cow::Owned)
+            }
+            Some(contents) => contents.read(),
+            None => fs::read(&self.path).map(Cow::Owned),
+        }
+    }
+
+    /// This input's contents to upload, which are streamed from its file if
+    /// they weren't kept when it was discovered.
+    pub fn upload_contents(&self) -> io::Result<UploadContents> {
+        match &self.contents {
+            Some(contents) if contents.is_mapped() && self.changed_since_discovery()? => {
+                self.read_again().map(UploadContents::from)
+            }
+            Some(contents) => Ok(contents.clone()),
+            None if self.changed_since_discovery()? => self.read_again().map(UploadContents::from),
+            None => UploadContents::from_file(&self.path, Some(&self.hash)),
+        }
+    }
+
+    /// Whether this input's file has a different size or modification time
+    /// than when it was discovered. Mapped contents of a file that changed
+    /// can't be trusted anymore, and neither can the length of a file that
+    /// would be streamed.
+    fn changed_since_discovery(&self) -> io::Result<bool> {
+        let stamp = FileStamp::from_metadata(&fs::metadata(&self.path)?);
+
+        Ok(stamp != self.stamp)
+    }
+
+    /// Reads this input's file again after it changed, which is only allowed
+    /// if its contents still match the hash it was discovered with.
+    fn read_again(&self) -> io::Result<Vec<u8>> {
+        log::debug!(
+            "{} changed since it was discovered, reading it again",
+            self.path.display()
+        );
+
+        let contents = fs::read(&self.path)?;
+
+        // Input hashes are the blake3 hashes of their contents.
+        if blake3::hash(&contents).to_hex().as_str() != self.hash {
+            return Err(io::Error::other(format!(
+                "{} changed during the sync, so it has to be synced again",
+                self.path.display()
+            )));
+        }
+
+        Ok(contents)
+    }
+
+    /// The size of this input's contents in bytes, or 0 if its file can't be
+    /// read anymore.
+    pub fn content_len(&self) -> usize {
+        match &self.contents {
+            Some(contents) => contents.len() as usize,
+            None => fs::metadata(&self.path).map_or(0, |metadata| metadata.len() as usize),
+        }
+    }
+
     pub fn is_unchanged_since_last_sync(&self, old_manifest: &InputManifest) -> bool {
-        self.hash == old_manifest.hash && self.config.packable == old_manifest.packable
+        self.hash == old_manifest.hash
+            && self.config.packable == old_manifest.packable
+            && !self.has_new_owner(old_manifest)
+    }
+
+    /// Whether this input is uploaded for a different group or user than it
+    /// was last uploaded for. Inputs whose owner wasn't recorded, or whose
+    /// owner isn't known now, are assumed to be where they belong.
+    pub fn has_new_owner(&self, old_manifest: &InputManifest) -> bool {
+        match (self.owner, old_manifest.owner()) {
+            (Some(owner), Some(recorded)) => owner != recorded,
+            _ => false,
+        }
+    }
+
+    /// Whether this input was rejected by moderation and hasn't been changed
+    /// since, meaning uploading it again would be rejected too.
+    pub fn is_quarantined(&self, old_manifest: &InputManifest) -> bool {
+        self.rejected && self.hash == old_manifest.hash
+    }
+
+    /// The format of this input's file, if it's one that Tarmac can upload.
+    pub fn format(&self) -> Option<AssetFormat> {
+        AssetFormat::from_path(&self.path)
+    }
+
+    /// Whether this input is an image, as opposed to another kind of asset like
+    /// audio. Only images are generated with ImageLabel properties.
+    pub fn is_image(&self) -> bool {
+        match self.format() {
+            Some(format) => format.is_image(),
+            None => true,
+        }
+    }
+
+    /// Whether this input is a font, which is referred to with a `Font` object
+    /// instead of a content URL.
+    pub fn is_font(&self) -> bool {
+        self.format().map(AssetFormat::kind) == Some(AssetKind::Font)
+    }
+
+    /// Who this input should be uploaded for, if its config overrides the
+    /// project's default destination.
+    pub fn upload_owner(&self) -> Option<AssetCreator> {
+        match (
+            self.config.upload_to_group_id,
+            self.config.upload_to_user_id,
+        ) {
+            (Some(group_id), _) => Some(AssetCreator::Group(group_id)),
+            (None, Some(user_id)) => Some(AssetCreator::User(user_id)),
+            (None, None) => None,
+        }
+    }
+
+    /// Works out who this input is uploaded for, given the group or user that
+    /// the sync uploads to when its config doesn't pick one.
+    pub fn resolve_owner(&mut self, default_owner: Option<AssetCreator>) {
+        self.owner = self.upload_owner().or(default_owner);
     }
 
     /// Creates a non-unique, human-friendly name to refer to this input.
@@ -63,3 +289,40 @@ impl SyncInput {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn input_uploaded_to_group(group_id: u64) -> (SyncInput, InputManifest) {
+        let mut input = SyncInput::new("a.png", "");
+        input.id = Some(10);
+        input.group_id = Some(group_id);
+
+        let mut manifest = InputManifest::imported(10);
+        manifest.hash = "hash".to_owned();
+        manifest.group_id = Some(group_id);
+
+        (input, manifest)
+    }
+
+    #[test]
+    fn changing_the_root_group_changes_owners() {
+        let (mut input, manifest) = input_uploaded_to_group(5);
+
+        // Without a known destination, nothing is assumed to have moved.
+        assert!(input.is_unchanged_since_last_sync(&manifest));
+
+        input.resolve_owner(Some(AssetCreator::Group(5)));
+        assert!(input.is_unchanged_since_last_sync(&manifest));
+
+        input.resolve_owner(Some(AssetCreator::Group(6)));
+        assert!(input.has_new_owner(&manifest));
+        assert!(!input.is_unchanged_since_last_sync(&manifest));
+
+        // An input's own destination takes precedence over the root's.
+        input.config.upload_to_group_id = Some(5);
+        input.resolve_owner(Some(AssetCreator::Group(6)));
+        assert!(input.is_unchanged_since_last_sync(&manifest));
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    collections::BTreeMap,
+    fs::Metadata,
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::user_folder;
+
+static SYNC_INDEX_FOLDER: &str = "sync-index";
+
+/// Folders modified this recently might be modified again without their
+/// modification time changing, since file systems only keep it so precisely.
+const RECENT_CHANGE: Duration = Duration::from_secs(2);
+
+/// What the last sync of a project found, so that the next one only needs to
+/// look at the parts of the project that changed since.
+///
+/// The index remembers the hash of each input file along with its size and
+/// modification time, so that files that haven't been touched don't need to
+/// be read and hashed again. It also remembers the files each input group
+/// matched, along with the modification time of every folder that was
+/// searched, so that input groups whose folders haven't changed don't need to
+/// be searched again.
+///
+/// Each project has its own index in the current user's folder, since the
+/// modification times it depends on are different on every machine.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncIndex {
+    #[serde(default)]
+    files: BTreeMap<String, CachedHash>,
+
+    #[serde(default)]
+    walks: BTreeMap<String, CachedWalk>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedHash {
+    #[serde(flatten)]
+    stamp: FileStamp,
+    hash: String,
+}
+
+/// The files that an input group matched, and the folders that were searched
+/// to find them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CachedWalk {
+    paths: Vec<String>,
+
+    /// The modification time of each folder, in nanoseconds since the Unix
+    /// epoch. Adding, removing, or renaming anything in a folder changes it.
+    folders: BTreeMap<String, u64>,
+}
+
+/// The size and modification time of a file, which change whenever its
+/// contents do.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FileStamp {
+    size: u64,
+
+    /// Nanoseconds since the Unix epoch.
+    modified: u64,
+}
+
+impl FileStamp {
+    /// The stamp of a file with the given metadata, if its modification time
+    /// is known.
+    pub fn from_metadata(metadata: &Metadata) -> Option<Self> {
+        Some(Self {
+            size: metadata.len(),
+            modified: modified_nanos(metadata)?,
+        })
+    }
+}
+
+impl CachedWalk {
+    /// Records that the folder with the given metadata was searched. Returns
+    /// false if its modification time can't be trusted, in which case the
+    /// walk shouldn't be cached.
+    pub fn add_folder(&mut self, path: &Path, metadata: &Metadata) -> bool {
+        let is_recent = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .map_or(true, |age| age < RECENT_CHANGE);
+
+        match modified_nanos(metadata) {
+            Some(modified) if !is_recent => {
+                self.folders
+                    .insert(path.to_string_lossy().into_owned(), modified);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn add_path(&mut self, path: &Path) {
+        self.paths.push(path.to_string_lossy().into_owned());
+    }
+
+    /// The files that were found, if none of the searched folders have
+    /// changed since.
+    pub fn unchanged_paths(&self) -> Option<Vec<PathBuf>> {
+        for (folder, modified) in &self.folders {
+            let metadata = fs::metadata(folder).ok()?;
+
+            if modified_nanos(&metadata) != Some(*modified) {
+                log::trace!("{} changed since the last sync", folder);
+                return None;
+            }
+        }
+
+        Some(self.paths.iter().map(PathBuf::from).collect())
+    }
+}
+
+impl SyncIndex {
+    /// The path of the index for the project in the given folder, if the
+    /// current user's folder can be found.
+    pub fn path_for_project(project_folder: &Path) -> Option<PathBuf> {
+        let key = blake3::hash(project_folder.to_string_lossy().as_bytes()).to_hex();
+
+        Some(
+            user_folder()?
+                .join(SYNC_INDEX_FOLDER)
+                .join(format!("{}.toml", &key.as_str()[..16])),
+        )
+    }
+
+    /// Reads the index at the given path, or creates an empty index if there
+    /// isn't one yet.
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, SyncIndexError> {
+        let path = path.as_ref();
+
+        let contents = match fs::read(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        toml::from_slice(&contents).map_err(|source| SyncIndexError::DeserializeToml {
+            source,
+            file_path: path.to_owned(),
+        })
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), SyncIndexError> {
+        let path = path.as_ref();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let serialized = toml::to_vec(self)?;
+        fs::write(path, serialized)?;
+
+        log::trace!("Saved sync index to {}", path.display());
+
+        Ok(())
+    }
+
+    /// The hash of the file at the given path, if it was cached when the file
+    /// had the same stamp.
+    pub fn get(&self, path: &Path, stamp: FileStamp) -> Option<&str> {
+        let cached = self.files.get(path.to_string_lossy().as_ref())?;
+
+        if cached.stamp == stamp {
+            Some(&cached.hash)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&mut self, path: &Path, stamp: FileStamp, hash: String) {
+        self.files.insert(
+            path.to_string_lossy().into_owned(),
+            CachedHash { stamp, hash },
+        );
+    }
+
+    /// The files that the walk with the given key found during the last sync.
+    pub fn walk(&self, key: &str) -> Option<&CachedWalk> {
+        self.walks.get(key)
+    }
+
+    pub fn insert_walk(&mut self, key: String, walk: CachedWalk) {
+        self.walks.insert(key, walk);
+    }
+}
+
+fn modified_nanos(metadata: &Metadata) -> Option<u64> {
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+    Some(modified.as_nanos() as u64)
+}
+
+#[derive(Debug, Error)]
+pub enum SyncIndexError {
+    #[error("Error deserializing TOML from path {}", .file_path.display())]
+    DeserializeToml {
+        file_path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error(transparent)]
+    SerializeToml {
+        #[from]
+        source: toml::ser::Error,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn stamps_must_match() {
+        let stamp = FileStamp {
+            size: 10,
+            modified: 1000,
+        };
+        let mut index = SyncIndex::default();
+        index.insert(Path::new("a.png"), stamp, "hash".to_owned());
+
+        let serialized = toml::to_string(&index).unwrap();
+        let index: SyncIndex = toml::from_str(&serialized).unwrap();
+
+        assert_eq!(index.get(Path::new("a.png"), stamp), Some("hash"));
+        assert_eq!(index.get(Path::new("b.png"), stamp), None);
+
+        let touched = FileStamp {
+            modified: 2000,
+            ..stamp
+        };
+        assert_eq!(index.get(Path::new("a.png"), touched), None);
+    }
+
+    #[test]
+    fn walks_check_their_folders() {
+        let folder = env::temp_dir().join(format!("tarmac-sync-index-{}", process::id()));
+        fs::create_dir_all(&folder).unwrap();
+
+        let mut walk = CachedWalk::default();
+        walk.add_path(&folder.join("a.png"));
+
+        // Folders that were just modified could change again unnoticed.
+        assert!(!walk.add_folder(&folder, &fs::metadata(&folder).unwrap()));
+
+        walk.folders
+            .insert(folder.to_string_lossy().into_owned(), 1000);
+        assert_eq!(walk.unchanged_paths(), None);
+
+        let modified = modified_nanos(&fs::metadata(&folder).unwrap()).unwrap();
+        walk.folders
+            .insert(folder.to_string_lossy().into_owned(), modified);
+        assert_eq!(walk.unchanged_paths(), Some(vec![folder.join("a.png")]));
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
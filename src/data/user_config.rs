@@ -0,0 +1,382 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use fs_err as fs;
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+static USER_CONFIG_FILENAME: &str = "config.toml";
+static CREDENTIALS_FILENAME: &str = "credentials.toml";
+
+/// The service that every credential in the system keychain is filed under.
+static KEYRING_SERVICE: &str = "tarmac";
+
+/// The name that credentials are stored under when no profile is selected.
+pub static DEFAULT_PROFILE: &str = "default";
+
+/// The folder that Tarmac keeps files shared by every project of the current
+/// user in, if the user's home directory can be found.
+pub fn user_folder() -> Option<PathBuf> {
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+
+    Some(Path::new(&home).join(".tarmac"))
+}
+
+/// Settings that apply to every project of the current user, read from
+/// `config.toml` in the user folder.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct UserConfig {
+    /// Named sets of credentials that can be picked with `--profile`.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// A named set of credentials, for users that upload with more than one
+/// account.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    /// The `.ROBLOSECURITY` cookie to authenticate with.
+    #[serde(default)]
+    pub auth: Option<String>,
+
+    /// The Open Cloud API key to authenticate with.
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    /// The group to upload assets to, overriding the project's
+    /// `upload-to-group-id`.
+    #[serde(default)]
+    pub group_id: Option<u64>,
+
+    /// The universe to give permission to use uploaded assets, so that each
+    /// environment of an experience can have its own profile.
+    #[serde(default)]
+    pub universe_id: Option<u64>,
+}
+
+impl UserConfig {
+    /// Reads the current user's config, or an empty config if they don't
+    /// have one.
+    pub fn read() -> Result<Self, UserConfigError> {
+        match user_folder() {
+            Some(folder) => Self::read_from_file(folder.join(USER_CONFIG_FILENAME)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, UserConfigError> {
+        let path = path.as_ref();
+
+        let contents = match fs::read(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        toml::from_slice(&contents).map_err(|source| UserConfigError::Toml {
+            source,
+            path: path.to_owned(),
+        })
+    }
+
+    /// Finds the profile with the given name.
+    pub fn profile(&self, name: &str) -> Result<&Profile, UserConfigError> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| UserConfigError::UnknownProfile {
+                name: name.to_owned(),
+            })
+    }
+}
+
+/// Credentials saved with `tarmac login`, kept in `credentials.toml` in the
+/// user folder, separately from the user config so that the config can be
+/// shared without leaking them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CredentialStore {
+    #[serde(flatten)]
+    profiles: BTreeMap<String, StoredCredentials>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct StoredCredentials {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+}
+
+impl CredentialStore {
+    fn path() -> Result<PathBuf, UserConfigError> {
+        let folder = user_folder().ok_or(UserConfigError::NoUserFolder)?;
+        Ok(folder.join(CREDENTIALS_FILENAME))
+    }
+
+    /// Reads the current user's stored credentials, which are empty if they
+    /// haven't logged in yet.
+    pub fn read() -> Result<Self, UserConfigError> {
+        let path = match user_folder() {
+            Some(folder) => folder.join(CREDENTIALS_FILENAME),
+            None => return Ok(Self::default()),
+        };
+
+        let contents = match fs::read(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        toml::from_slice(&contents).map_err(|source| UserConfigError::Toml { source, path })
+    }
+
+    /// Saves the stored credentials. The file is only readable by the current
+    /// user on platforms that support it.
+    pub fn write(&self) -> Result<(), UserConfigError> {
+        let path = Self::path()?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let serialized = toml::to_vec(self)?;
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = fs::File::from_options(&path, &options)?;
+        file.write_all(&serialized)?;
+
+        Ok(())
+    }
+
+    /// The credentials stored for the given profile, or for the default
+    /// profile if none is given.
+    pub fn get(&self, profile: Option<&str>) -> Option<&StoredCredentials> {
+        self.profiles.get(profile.unwrap_or(DEFAULT_PROFILE))
+    }
+
+    pub fn get_mut(&mut self, profile: Option<&str>) -> &mut StoredCredentials {
+        self.profiles
+            .entry(profile.unwrap_or(DEFAULT_PROFILE).to_owned())
+            .or_default()
+    }
+
+    /// Forgets the credentials stored for the given profile, returning whether
+    /// there were any.
+    pub fn remove(&mut self, profile: Option<&str>) -> bool {
+        self.profiles
+            .remove(profile.unwrap_or(DEFAULT_PROFILE))
+            .is_some()
+    }
+}
+
+/// The kinds of credential that can be stored with `tarmac login`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialKind {
+    Auth,
+    ApiKey,
+}
+
+impl CredentialKind {
+    const ALL: [CredentialKind; 2] = [CredentialKind::Auth, CredentialKind::ApiKey];
+
+    /// The system keychain entry that this kind of credential is kept in for
+    /// the given profile.
+    fn keyring_entry(self, profile: Option<&str>) -> keyring::Result<Entry> {
+        let kind = match self {
+            CredentialKind::Auth => "auth",
+            CredentialKind::ApiKey => "api-key",
+        };
+
+        let account = format!("{}/{}", profile.unwrap_or(DEFAULT_PROFILE), kind);
+        Entry::new(KEYRING_SERVICE, &account)
+    }
+}
+
+impl StoredCredentials {
+    fn get_mut(&mut self, kind: CredentialKind) -> &mut Option<String> {
+        match kind {
+            CredentialKind::Auth => &mut self.auth,
+            CredentialKind::ApiKey => &mut self.api_key,
+        }
+    }
+}
+
+/// Where a credential ended up after being stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialLocation {
+    Keyring,
+    File,
+}
+
+/// Reads one credential stored for the given profile. The system keychain
+/// takes priority over `credentials.toml`, which is only used when there's no
+/// keychain or it didn't have the credential.
+pub fn stored_credential(
+    profile: Option<&str>,
+    kind: CredentialKind,
+) -> Result<Option<String>, UserConfigError> {
+    match kind
+        .keyring_entry(profile)
+        .and_then(|entry| entry.get_password())
+    {
+        Ok(secret) => return Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => {}
+        Err(err) if is_keyring_unavailable(&err) => {
+            log::debug!(
+                "No system keychain available, using credentials.toml: {}",
+                err
+            );
+        }
+        Err(err) => log::warn!("Couldn't read credentials from the keychain: {}", err),
+    }
+
+    let mut credentials = CredentialStore::read()?
+        .get(profile)
+        .cloned()
+        .unwrap_or_default();
+
+    Ok(credentials.get_mut(kind).take())
+}
+
+/// Whether an error from the system keychain means that there's no keychain to
+/// use, like on a machine without a Secret Service provider.
+fn is_keyring_unavailable(err: &keyring::Error) -> bool {
+    matches!(
+        err,
+        keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_)
+    )
+}
+
+/// Stores a credential for the given profile in the system keychain, falling
+/// back to `credentials.toml` if the keychain can't be used.
+pub fn store_credential(
+    profile: Option<&str>,
+    kind: CredentialKind,
+    secret: String,
+) -> Result<CredentialLocation, UserConfigError> {
+    let mut store = CredentialStore::read()?;
+
+    match kind
+        .keyring_entry(profile)
+        .and_then(|entry| entry.set_password(&secret))
+    {
+        Ok(()) => {
+            // Plaintext copies left behind by older versions of Tarmac would
+            // otherwise outlive the credential they belong to.
+            if let Some(stored) = store.profiles.get_mut(profile.unwrap_or(DEFAULT_PROFILE)) {
+                if stored.get_mut(kind).take().is_some() {
+                    store.write()?;
+                }
+            }
+
+            Ok(CredentialLocation::Keyring)
+        }
+        Err(err) => {
+            log::warn!("Couldn't store credentials in the keychain: {}", err);
+
+            *store.get_mut(profile).get_mut(kind) = Some(secret);
+            store.write()?;
+
+            Ok(CredentialLocation::File)
+        }
+    }
+}
+
+/// Forgets every credential stored for the given profile, returning whether
+/// there were any.
+pub fn remove_credentials(profile: Option<&str>) -> Result<bool, UserConfigError> {
+    let mut removed = false;
+
+    for &kind in &CredentialKind::ALL {
+        match kind
+            .keyring_entry(profile)
+            .and_then(|entry| entry.delete_credential())
+        {
+            Ok(()) => removed = true,
+            Err(keyring::Error::NoEntry) => {}
+            Err(err) if is_keyring_unavailable(&err) => break,
+            Err(err) => log::warn!("Couldn't remove credentials from the keychain: {}", err),
+        }
+    }
+
+    let mut store = CredentialStore::read()?;
+    if store.remove(profile) {
+        store.write()?;
+        removed = true;
+    }
+
+    Ok(removed)
+}
+
+#[derive(Debug, Error)]
+pub enum UserConfigError {
+    #[error("Couldn't find a home directory to store Tarmac's user files in")]
+    NoUserFolder,
+
+    #[error("No profile named {name:?} is defined in the Tarmac user config")]
+    UnknownProfile { name: String },
+
+    #[error("Auth file {} is empty", .path.display())]
+    EmptyAuthFile { path: PathBuf },
+
+    #[error("Error deserializing TOML from path {}", .path.display())]
+    Toml {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error(transparent)]
+    SerializeToml {
+        #[from]
+        source: toml::ser::Error,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_profiles() {
+        let config: UserConfig = toml::from_str(
+            r#"
+            [profiles.studio-alt]
+            auth = "cookie"
+
+            [profiles.group-bot]
+            api-key = "key"
+            group-id = 5
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.profile("studio-alt").unwrap().auth.as_deref(),
+            Some("cookie")
+        );
+        assert_eq!(config.profile("group-bot").unwrap().group_id, Some(5));
+        assert!(config.profile("missing").is_err());
+    }
+}
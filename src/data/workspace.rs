@@ -0,0 +1,172 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{Config, ConfigError};
+
+pub static WORKSPACE_FILENAME: &str = "tarmac-workspace.toml";
+
+/// A set of independent Tarmac projects that commands can run across at once,
+/// described by a tarmac-workspace.toml file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Workspace {
+    /// The folders of the projects in this workspace, relative to the
+    /// workspace file.
+    pub members: Vec<PathBuf>,
+
+    /// The path that this workspace came from.
+    #[serde(skip)]
+    pub file_path: PathBuf,
+}
+
+impl Workspace {
+    /// Reads the workspace at the given path, which can be a workspace file or
+    /// a folder containing one, or returns None if there's no workspace there.
+    pub fn find(path: &Path) -> Result<Option<Self>, WorkspaceError> {
+        let file_path = if path.is_dir() {
+            path.join(WORKSPACE_FILENAME)
+        } else if path.file_name() == Some(WORKSPACE_FILENAME.as_ref()) {
+            path.to_owned()
+        } else {
+            return Ok(None);
+        };
+
+        let contents = match fs::read(&file_path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut workspace: Self =
+            toml::from_slice(&contents).map_err(|source| WorkspaceError::Toml {
+                source,
+                path: file_path.clone(),
+            })?;
+
+        let folder = file_path.parent().unwrap();
+        for member in &mut workspace.members {
+            if member.is_relative() {
+                *member = folder.join(&*member);
+            }
+        }
+
+        workspace.file_path = file_path;
+
+        Ok(Some(workspace))
+    }
+
+    /// The paths of the members of this workspace by the names of their
+    /// projects.
+    pub fn projects(&self) -> Result<BTreeMap<String, PathBuf>, WorkspaceError> {
+        let mut projects = BTreeMap::new();
+
+        for member in &self.members {
+            let config = Config::read_from_folder_or_file(member)?;
+
+            if projects
+                .insert(config.name.clone(), member.clone())
+                .is_some()
+            {
+                return Err(WorkspaceError::DuplicateProject { name: config.name });
+            }
+        }
+
+        Ok(projects)
+    }
+}
+
+/// Finds the projects that a command given the path should run on: every
+/// project in the workspace at the path, or only the one named `only`, or the
+/// path itself if there's no workspace there.
+pub fn select_projects(path: &Path, only: Option<&str>) -> Result<Vec<PathBuf>, WorkspaceError> {
+    let workspace = match Workspace::find(path)? {
+        Some(workspace) => workspace,
+        None => {
+            return match only {
+                Some(name) => Err(WorkspaceError::NotAWorkspace {
+                    name: name.to_owned(),
+                    path: path.to_owned(),
+                }),
+                None => Ok(vec![path.to_owned()]),
+            }
+        }
+    };
+
+    log::trace!("Using workspace {}", workspace.file_path.display());
+
+    let mut projects = workspace.projects()?;
+
+    match only {
+        Some(name) => match projects.remove(name) {
+            Some(path) => Ok(vec![path]),
+            None => Err(WorkspaceError::UnknownProject {
+                name: name.to_owned(),
+                available: projects.into_keys().collect(),
+            }),
+        },
+
+        // Members are used in the order that the workspace lists them.
+        None => Ok(workspace.members),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum WorkspaceError {
+    #[error("Error deserializing TOML from path {}", .path.display())]
+    Toml {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("More than one project in the workspace is named {name:?}")]
+    DuplicateProject { name: String },
+
+    #[error("No project named {name:?} is in the workspace. Its projects are: {}", .available.join(", "))]
+    UnknownProject {
+        name: String,
+        available: Vec<String>,
+    },
+
+    #[error("--project {name} was given, but {} isn't a Tarmac workspace", .path.display())]
+    NotAWorkspace { name: String, path: PathBuf },
+
+    #[error(transparent)]
+    Config {
+        #[from]
+        source: ConfigError,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_members() {
+        let workspace: Workspace = toml::from_str(
+            r#"
+            members = ["games/main", "games/lobby"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            workspace.members,
+            vec![PathBuf::from("games/main"), PathBuf::from("games/lobby")]
+        );
+        assert!(toml::from_str::<Workspace>("projects = []").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! Contents of input files that are kept in memory while they're hashed and
+//! uploaded. Small files are read. Large files are only ever mapped into
+//! memory when a sync opts into it with `--map-inputs`, since a mapped file
+//! that's modified or truncated while it's in use can crash the process or
+//! change underneath its hash. Otherwise, large files aren't kept at all, and
+//! are streamed from disk instead.
+
+use std::{
+    fmt,
+    io::{self, Read},
+    ops::Deref,
+    path::Path,
+};
+
+use fs_err as fs;
+use memmap2::Mmap;
+
+/// Files at least this large are mapped or streamed instead of being read,
+/// since mapping or streaming them saves more than it costs.
+const LARGE_FILE_LEN: u64 = 1024 * 1024;
+
+pub struct FileContents(Inner);
+
+enum Inner {
+    Read(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl FileContents {
+    /// Whether a file of the given length is large enough that it shouldn't
+    /// be read into memory all at once.
+    pub fn is_large(len: u64) -> bool {
+        len >= LARGE_FILE_LEN
+    }
+
+    /// Reads the whole file at the given path.
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let mut file = fs::File::open(path)?;
+        let len = file.metadata()?.len();
+
+        let mut contents = Vec::with_capacity(len as usize);
+        file.read_to_end(&mut contents)?;
+
+        Ok(Self(Inner::Read(contents)))
+    }
+
+    /// Maps the file at the given path into memory, falling back to reading it
+    /// if it's small or can't be mapped.
+    ///
+    /// # Safety
+    ///
+    /// If the file is mapped, nothing may modify or truncate it until the
+    /// returned contents are dropped. Changes to a mapped file show through
+    /// the contents, and reading past the end of a truncated file crashes the
+    /// process.
+    pub unsafe fn map(path: &Path) -> io::Result<Self> {
+        let file = fs::File::open(path)?;
+        let len = file.metadata()?.len();
+
+        if Self::is_large(len) {
+            match Mmap::map(file.file()) {
+                Ok(mapping) => return Ok(Self(Inner::Mapped(mapping))),
+                Err(err) => log::debug!(
+                    "Couldn't map {} into memory, reading it instead: {}",
+                    path.display(),
+                    err
+                ),
+            }
+        }
+
+        Self::read(path)
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.0, Inner::Mapped(_))
+    }
+}
+
+impl From<Vec<u8>> for FileContents {
+    fn from(contents: Vec<u8>) -> Self {
+        Self(Inner::Read(contents))
+    }
+}
+
+impl Deref for FileContents {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.0 {
+            Inner::Read(contents) => contents,
+            Inner::Mapped(mapping) => mapping,
+        }
+    }
+}
+
+impl PartialEq for FileContents {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for FileContents {}
+
+impl fmt::Debug for FileContents {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("FileContents")
+            .field("len", &self.len())
+            .field("mapped", &self.is_mapped())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn only_large_files_are_mapped() {
+        let folder = env::temp_dir().join(format!("tarmac-file-contents-{}", process::id()));
+        fs::create_dir_all(&folder).unwrap();
+
+        let small = folder.join("small");
+        fs::write(&small, b"small").unwrap();
+
+        let large = folder.join("large");
+        let large_contents: Vec<u8> = (0..LARGE_FILE_LEN).map(|i| i as u8).collect();
+        fs::write(&large, &large_contents).unwrap();
+
+        let read = FileContents::read(&large).unwrap();
+        assert!(!read.is_mapped());
+        assert_eq!(*read, *large_contents);
+
+        let small_mapped = unsafe { FileContents::map(&small) }.unwrap();
+        assert!(!small_mapped.is_mapped());
+        assert_eq!(&*small_mapped, b"small");
+
+        let mapped = unsafe { FileContents::map(&large) }.unwrap();
+        assert!(mapped.is_mapped());
+        assert_eq!(*mapped, *large_contents);
+
+        drop(mapped);
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -32,6 +32,62 @@ impl Glob {
     pub fn get_prefix(&self) -> PathBuf {
         get_non_pattern_prefix(Path::new(self.inner.glob()))
     }
+
+    /// Whether files inside the folder at the given path, relative to the
+    /// glob's root, could match this glob. Wildcards can match across
+    /// folders, so only folders ruled out by a fixed part of the glob, like
+    /// `icons` in `ui/icons/*.png` or `{icons,images}` in
+    /// `ui/{icons,images}/*.png`, can be skipped.
+    pub fn could_match_within(&self, folder: &Path) -> bool {
+        let glob_components: Vec<&str> = self.inner.glob().split('/').collect();
+
+        for (index, component) in folder.iter().enumerate() {
+            let glob_component = match glob_components.get(index) {
+                Some(glob_component) => *glob_component,
+                None => return false,
+            };
+
+            if glob_component.contains(|c| "*?[\\".contains(c)) {
+                return true;
+            }
+
+            // The last part of the glob names files, not folders.
+            if index == glob_components.len() - 1 {
+                return false;
+            }
+
+            let alternatives = match glob_component
+                .strip_prefix('{')
+                .and_then(|rest| rest.strip_suffix('}'))
+            {
+                Some(inner) if !inner.contains(['{', '}']) => inner.split(',').collect(),
+                Some(_) => return true,
+                None => vec![glob_component],
+            };
+
+            let component = component.to_string_lossy();
+            if !alternatives.contains(&component.as_ref()) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// How narrowly this glob describes the files it matches, used to pick
+    /// between globs that match the same file. Globs with a longer fixed
+    /// prefix are more specific, followed by globs with more fixed characters.
+    pub fn specificity(&self) -> (usize, usize) {
+        let prefix_length = self.get_prefix().components().count();
+        let fixed_characters = self
+            .inner
+            .glob()
+            .chars()
+            .filter(|&c| !GLOB_PATTERN_CHARACTERS.contains(c))
+            .count();
+
+        (prefix_length, fixed_characters)
+    }
 }
 
 impl PartialEq for Glob {
@@ -50,9 +106,9 @@ impl Serialize for Glob {
 
 impl<'de> Deserialize<'de> for Glob {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        let glob = <&str as Deserialize>::deserialize(deserializer)?;
+        let glob = String::deserialize(deserializer)?;
 
-        Glob::new(glob).map_err(D::Error::custom)
+        Glob::new(&glob).map_err(D::Error::custom)
     }
 }
 
@@ -72,6 +128,11 @@ impl fmt::Display for Glob {
 // Glob syntax described here: https://docs.rs/globset/0.4.4/globset/#syntax
 const GLOB_PATTERN_CHARACTERS: &str = "*?{}[]";
 
+/// Whether any part of the given path uses glob pattern syntax.
+pub fn contains_pattern(path: &Path) -> bool {
+    get_non_pattern_prefix(path) != path
+}
+
 fn get_non_pattern_prefix(glob_path: &Path) -> PathBuf {
     let mut prefix = PathBuf::new();
 
@@ -119,6 +180,37 @@ mod test {
         );
     }
 
+    #[test]
+    fn specificity() {
+        let specificity = |glob: &str| Glob::new(glob).unwrap().specificity();
+
+        assert!(specificity("a/b/*.png") > specificity("a/**/*.png"));
+        assert!(specificity("a/**/icon-*.png") > specificity("a/**/*.png"));
+        assert!(specificity("a/b/foo.png") > specificity("a/b/*.png"));
+    }
+
+    #[test]
+    fn could_match_within() {
+        let could_match = |glob: &str, folder: &str| {
+            Glob::new(glob)
+                .unwrap()
+                .could_match_within(Path::new(folder))
+        };
+
+        assert!(could_match("ui/{icons,images}/*.png", "ui/icons"));
+        assert!(!could_match("ui/{icons,images}/*.png", "ui/sounds"));
+        assert!(!could_match("ui/icons/*.png", "ui/sounds"));
+        assert!(!could_match("ui/icons/star.png", "ui/icons/old"));
+
+        // Wildcards match across folders, so anything below them could match.
+        assert!(could_match("ui/*/icons/*.png", "ui/a/b/c"));
+        assert!(could_match("ui/icons/*.png", "ui/icons/old"));
+        assert!(could_match("**/*.png", "anything"));
+        assert!(Glob::new("ui/icons/*.png")
+            .unwrap()
+            .is_match("ui/icons/old/star.png"));
+    }
+
     #[test]
     fn whole_path() {
         assert_eq!(
@@ -0,0 +1,289 @@
+//! Recording and replaying of HTTP exchanges with Roblox, so that code that
+//! talks to Roblox can be exercised without credentials or network access.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use fs_err as fs;
+use reqwest::{header::SET_COOKIE, Client, Request, Response};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Whether fixtures should be captured from real requests or served back in
+/// place of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixtureMode {
+    Record,
+    Replay,
+}
+
+/// A request that was sent and the response that it received.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Exchange {
+    method: String,
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+
+    /// Bodies that aren't valid UTF-8, like downloaded images, are stored as
+    /// base64 instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_base64: Option<String>,
+}
+
+impl Exchange {
+    fn body(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        match (&self.body, &self.body_base64) {
+            (Some(body), _) => Ok(body.clone().into_bytes()),
+            (None, Some(encoded)) => base64::decode(encoded),
+            (None, None) => Ok(Vec::new()),
+        }
+    }
+}
+
+/// A file of HTTP exchanges that is either being recorded or replayed.
+#[derive(Debug)]
+pub struct Fixtures {
+    mode: FixtureMode,
+    path: PathBuf,
+    exchanges: Mutex<Vec<Exchange>>,
+
+    /// When replaying, which of `exchanges` have already been served.
+    used: Mutex<Vec<bool>>,
+}
+
+impl Fixtures {
+    pub fn new(mode: FixtureMode, path: &Path) -> Result<Self, FixtureError> {
+        let exchanges: Vec<Exchange> = match mode {
+            FixtureMode::Record => Vec::new(),
+            FixtureMode::Replay => {
+                let contents = fs::read(path)?;
+
+                serde_json::from_slice(&contents).map_err(|source| FixtureError::Json {
+                    path: path.to_owned(),
+                    source,
+                })?
+            }
+        };
+
+        let used = vec![false; exchanges.len()];
+
+        Ok(Self {
+            mode,
+            path: path.to_owned(),
+            exchanges: Mutex::new(exchanges),
+            used: Mutex::new(used),
+        })
+    }
+
+    /// Sends a request, recording the exchange or serving it from the fixture
+    /// file depending on the mode.
+    pub fn execute(&self, client: &Client, request: Request) -> Result<Response, FixtureError> {
+        match self.mode {
+            FixtureMode::Record => self.record(client, request),
+            FixtureMode::Replay => self.replay(&request),
+        }
+    }
+
+    fn record(&self, client: &Client, request: Request) -> Result<Response, FixtureError> {
+        let method = request.method().as_str().to_owned();
+        let url = request.url().as_str().to_owned();
+
+        let mut response = client.execute(request)?;
+
+        let mut body = Vec::new();
+        response.copy_to(&mut body)?;
+
+        // Cookies are left out so that recordings can be checked in without
+        // leaking anything about the account they were made with.
+        let headers = response
+            .headers()
+            .iter()
+            .filter(|(name, _)| *name != SET_COOKIE)
+            .filter_map(|(name, value)| {
+                let value = value.to_str().ok()?;
+                Some((name.as_str().to_owned(), value.to_owned()))
+            })
+            .collect();
+
+        let (body, body_base64) = match String::from_utf8(body) {
+            Ok(body) => (Some(body), None),
+            Err(err) => (None, Some(base64::encode(err.as_bytes()))),
+        };
+
+        let exchange = Exchange {
+            method,
+            url,
+            status: response.status().as_u16(),
+            headers,
+            body,
+            body_base64,
+        };
+
+        let response = build_response(&exchange)?;
+
+        let mut exchanges = self.exchanges.lock().unwrap();
+        exchanges.push(exchange);
+
+        // The file is rewritten after every exchange so that a recording is
+        // still usable if Tarmac exits partway through a sync.
+        let serialized = serde_json::to_vec_pretty(&*exchanges).unwrap();
+        fs::write(&self.path, serialized)?;
+
+        Ok(response)
+    }
+
+    fn replay(&self, request: &Request) -> Result<Response, FixtureError> {
+        let method = request.method().as_str();
+        let url = request.url().as_str();
+
+        let exchanges = self.exchanges.lock().unwrap();
+        let mut used = self.used.lock().unwrap();
+
+        // Identical requests are served their recorded responses in order.
+        let index = exchanges
+            .iter()
+            .enumerate()
+            .position(|(index, exchange)| {
+                !used[index] && exchange.method == method && exchange.url == url
+            })
+            .ok_or_else(|| FixtureError::NotRecorded {
+                method: method.to_owned(),
+                url: url.to_owned(),
+            })?;
+
+        used[index] = true;
+        log::trace!("Replaying {} {}", method, url);
+
+        build_response(&exchanges[index])
+    }
+}
+
+fn build_response(exchange: &Exchange) -> Result<Response, FixtureError> {
+    let mut builder = http::Response::builder();
+    builder.status(exchange.status);
+
+    for (name, value) in &exchange.headers {
+        builder.header(name.as_str(), value.as_str());
+    }
+
+    let body = exchange.body().map_err(|_| FixtureError::BadBody {
+        url: exchange.url.clone(),
+    })?;
+
+    let response = builder.body(body).map_err(|_| FixtureError::BadBody {
+        url: exchange.url.clone(),
+    })?;
+
+    Ok(Response::from(response))
+}
+
+#[derive(Debug, Error)]
+pub enum FixtureError {
+    #[error("No recorded response is left for {method} {url}")]
+    NotRecorded { method: String, url: String },
+
+    #[error("The recorded response for {url} is malformed")]
+    BadBody { url: String },
+
+    #[error("Fixture file {} is not valid JSON", .path.display())]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    #[error(transparent)]
+    Http {
+        #[from]
+        source: reqwest::Error,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+/// Creates an API client that replays the given exchanges instead of talking
+/// to Roblox.
+#[cfg(test)]
+pub fn replay_client(
+    name: &str,
+    exchanges: serde_json::Value,
+) -> crate::roblox_web_api::RobloxApiClient {
+    use crate::roblox_web_api::{ClientOptions, RobloxApiClient};
+
+    let path = std::env::temp_dir().join(format!("tarmac-{}-{}.json", name, std::process::id()));
+    fs::write(&path, exchanges.to_string()).unwrap();
+
+    let options = ClientOptions {
+        fixtures: Some((FixtureMode::Replay, path)),
+        ..ClientOptions::default()
+    };
+
+    RobloxApiClient::new(None, &options).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::roblox_web_api::{ImageUploadData, RobloxApiClient, RobloxApiError};
+
+    const UPLOAD_URL: &str =
+        "https://data.roblox.com/data/upload/json?assetTypeId=13&name=foo&description=bar";
+
+    fn upload(client: &mut RobloxApiClient) -> Result<u64, RobloxApiError> {
+        let response = client.upload_image(ImageUploadData {
+            image_data: b"image".to_vec().into(),
+            name: "foo",
+            description: "bar",
+            group_id: None,
+        })?;
+
+        Ok(response.backing_asset_id)
+    }
+
+    #[test]
+    fn replay_csrf_retry() {
+        let mut client = replay_client(
+            "csrf",
+            serde_json::json!([
+                {
+                    "method": "POST",
+                    "url": UPLOAD_URL,
+                    "status": 403,
+                    "headers": [["x-csrf-token", "token"]],
+                },
+                {
+                    "method": "POST",
+                    "url": UPLOAD_URL,
+                    "status": 200,
+                    "headers": [],
+                    "body": r#"{"Success":true,"AssetId":1,"BackingAssetId":2}"#,
+                },
+            ]),
+        );
+
+        assert_eq!(upload(&mut client).unwrap(), 2);
+    }
+
+    #[test]
+    fn replay_unrecorded_request() {
+        let mut client = replay_client("unrecorded", serde_json::json!([]));
+
+        match upload(&mut client) {
+            Err(RobloxApiError::Fixtures {
+                source: FixtureError::NotRecorded { method, .. },
+            }) => assert_eq!(method, "POST"),
+            other => panic!("expected a missing fixture error, got {:?}", other),
+        }
+    }
+}
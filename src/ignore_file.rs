@@ -0,0 +1,255 @@
+//! Support for `.tarmacignore` files, which exclude files from input discovery
+//! using the same syntax as `.gitignore` files. Projects can opt into using
+//! their repository's `.gitignore` files the same way.
+//!
+//! Each ignore file applies to the folder it's in and everything below it.
+//! Rules from deeper ignore files take precedence over shallower ones, and
+//! within one file, later rules take precedence over earlier ones, so `!` can
+//! re-include files that an earlier rule excluded.
+
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use fs_err as fs;
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+use thiserror::Error;
+
+pub static IGNORE_FILENAME: &str = ".tarmacignore";
+
+static GITIGNORE_FILENAME: &str = ".gitignore";
+
+/// The rules from every ignore file in a project, loaded as they're needed.
+#[derive(Debug)]
+pub struct IgnoreRules {
+    root: PathBuf,
+
+    /// The root of the git repository whose `.gitignore` files also apply, if
+    /// they're used.
+    git_root: Option<PathBuf>,
+
+    /// Every ignore file that was looked for by its path.
+    files: HashMap<PathBuf, Option<IgnoreFile>>,
+}
+
+impl IgnoreRules {
+    /// Creates a set of rules for the project rooted at the given folder. Ignore
+    /// files above that folder aren't used.
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_owned(),
+            git_root: None,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Also applies the `.gitignore` files of the git repository containing
+    /// the project, including ones above the project's folder. Rules from
+    /// `.tarmacignore` files take precedence over them.
+    pub fn with_gitignore(mut self) -> Self {
+        let git_root = self
+            .root
+            .ancestors()
+            .find(|folder| folder.join(".git").exists())
+            .unwrap_or(&self.root);
+
+        self.git_root = Some(git_root.to_owned());
+
+        self
+    }
+
+    /// Whether the file at the given path is excluded by any ignore file
+    /// between the project root and the file.
+    pub fn is_ignored(&mut self, path: &Path) -> Result<bool, IgnoreError> {
+        let mut ignored = false;
+
+        if let Some(git_root) = self.git_root.clone() {
+            if let Some(decision) = self.decide(&git_root, GITIGNORE_FILENAME, path)? {
+                ignored = decision;
+            }
+        }
+
+        let root = self.root.clone();
+        if let Some(decision) = self.decide(&root, IGNORE_FILENAME, path)? {
+            ignored = decision;
+        }
+
+        Ok(ignored)
+    }
+
+    /// Decides whether the file at the given path is excluded by the ignore
+    /// files with the given name between `root` and the file, or returns None
+    /// if none of them have a rule for it.
+    fn decide(
+        &mut self,
+        root: &Path,
+        file_name: &str,
+        path: &Path,
+    ) -> Result<Option<bool>, IgnoreError> {
+        let relative = match path.strip_prefix(root) {
+            Ok(relative) => relative.to_owned(),
+            Err(_) => return Ok(None),
+        };
+
+        let mut decision = None;
+        let mut folder = root.to_owned();
+
+        for component in relative.parent().into_iter().flat_map(Path::iter) {
+            if let Some(folder_decision) = self.check_folder(&folder, file_name, path)? {
+                decision = Some(folder_decision);
+            }
+
+            folder.push(component);
+        }
+
+        if let Some(folder_decision) = self.check_folder(&folder, file_name, path)? {
+            decision = Some(folder_decision);
+        }
+
+        Ok(decision)
+    }
+
+    fn check_folder(
+        &mut self,
+        folder: &Path,
+        file_name: &str,
+        path: &Path,
+    ) -> Result<Option<bool>, IgnoreError> {
+        let file_path = folder.join(file_name);
+
+        if !self.files.contains_key(&file_path) {
+            let file = IgnoreFile::read_from_file(&file_path)?;
+            self.files.insert(file_path.clone(), file);
+        }
+
+        Ok(match &self.files[&file_path] {
+            Some(file) => file.decide(path.strip_prefix(folder).unwrap()),
+            None => None,
+        })
+    }
+}
+
+/// The rules from a single ignore file.
+#[derive(Debug)]
+struct IgnoreFile {
+    gitignore: Gitignore,
+}
+
+impl IgnoreFile {
+    fn read_from_file(path: &Path) -> Result<Option<Self>, IgnoreError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        log::trace!("Applying ignore file {}", path.display());
+
+        Self::parse(&contents)
+            .map(Some)
+            .map_err(|source| IgnoreError::BadPattern {
+                path: path.to_owned(),
+                source,
+            })
+    }
+
+    /// Parses the contents of an ignore file, whose patterns are matched
+    /// against paths relative to the file.
+    fn parse(contents: &str) -> Result<Self, ignore::Error> {
+        let mut builder = GitignoreBuilder::new("");
+
+        for line in contents.lines() {
+            builder.add_line(None, line)?;
+        }
+
+        Ok(Self {
+            gitignore: builder.build()?,
+        })
+    }
+
+    /// Decides whether the given path, relative to this ignore file, is
+    /// excluded, or returns None if no rule applies to it. Matching a folder
+    /// excludes everything inside of it.
+    fn decide(&self, path: &Path) -> Option<bool> {
+        match self.gitignore.matched_path_or_any_parents(path, false) {
+            Match::None => None,
+            Match::Ignore(_) => Some(true),
+            Match::Whitelist(_) => Some(false),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum IgnoreError {
+    #[error("Invalid pattern in ignore file {}", .path.display())]
+    BadPattern {
+        path: PathBuf,
+        source: ignore::Error,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn gitignore_rules() {
+        let file = IgnoreFile::parse(concat!(
+            "# Scratch files\n",
+            "*.psd\n",
+            "raw/\n",
+            "/exports/*.png\n",
+            "!exports/keep.png\n",
+            "\\#notes.png\n",
+            "docs/**/draft.png\n",
+        ))
+        .unwrap();
+
+        let decide = |path: &str| file.decide(Path::new(path));
+
+        assert_eq!(decide("art/icon.psd"), Some(true));
+        assert_eq!(decide("raw/deep/icon.png"), Some(true));
+        assert_eq!(decide("art/raw/icon.png"), Some(true));
+        assert_eq!(decide("raw"), None);
+        assert_eq!(decide("exports/icon.png"), Some(true));
+        assert_eq!(decide("art/exports/icon.png"), None);
+        assert_eq!(decide("exports/keep.png"), Some(false));
+        assert_eq!(decide("icon.png"), None);
+        assert_eq!(decide("art/#notes.png"), Some(true));
+        assert_eq!(decide("docs/draft.png"), Some(true));
+        assert_eq!(decide("docs/a/b/draft.png"), Some(true));
+        assert_eq!(decide("art/docs/draft.png"), None);
+    }
+
+    #[test]
+    fn gitignore_files() {
+        let repo = std::env::temp_dir().join(format!("tarmac-gitignore-{}", std::process::id()));
+        let project = repo.join("project");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(&project).unwrap();
+        fs::write(repo.join(".gitignore"), "build/\n*.bak\n").unwrap();
+        fs::write(project.join(IGNORE_FILENAME), "!build/keep.png\n").unwrap();
+
+        let mut plain = IgnoreRules::new(&project);
+        assert!(!plain.is_ignored(&project.join("build/icon.png")).unwrap());
+
+        let mut rules = IgnoreRules::new(&project).with_gitignore();
+        assert!(rules.is_ignored(&project.join("build/icon.png")).unwrap());
+        assert!(rules.is_ignored(&project.join("art/icon.png.bak")).unwrap());
+        assert!(!rules.is_ignored(&project.join("build/keep.png")).unwrap());
+        assert!(!rules.is_ignored(&project.join("art/icon.png")).unwrap());
+
+        fs::remove_dir_all(&repo).unwrap();
+    }
+}
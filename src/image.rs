@@ -2,6 +2,14 @@
 
 use std::io::{Read, Write};
 
+/// Reads the dimensions of a PNG image without decoding its pixel data.
+pub(crate) fn read_png_size<R: Read>(input: R) -> Result<(u32, u32), png::DecodingError> {
+    let decoder = png::Decoder::new(input);
+    let (info, _reader) = decoder.read_info()?;
+
+    Ok((info.width, info.height))
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ImageFormat {
     Rgba8,
@@ -77,6 +85,45 @@ impl Image {
         Ok(Self::new_rgba8(size, data))
     }
 
+    /// Decodes an 8-bit PNG of any color type, converting it to RGBA. Images
+    /// that Roblox serves back to us may have been re-encoded with a different
+    /// color type than the one they were uploaded with.
+    pub fn decode_png_as_rgba8<R: Read>(input: R) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(input);
+        decoder.set_transformations(png::Transformations::EXPAND);
+
+        let (info, mut reader) = decoder.read_info()?;
+
+        let mut buffer = vec![0; info.buffer_size()];
+        reader.next_frame(&mut buffer)?;
+
+        if info.bit_depth != png::BitDepth::Eight {
+            return Err(png::DecodingError::Other(
+                format!("Bit depth {:?} is not supported by Tarmac.", info.bit_depth).into(),
+            ));
+        }
+
+        let data = match info.color_type {
+            png::ColorType::RGBA => buffer,
+            png::ColorType::RGB => buffer
+                .chunks_exact(3)
+                .flat_map(|rgb| vec![rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|ga| vec![ga[0], ga[0], ga[0], ga[1]])
+                .collect(),
+            png::ColorType::Grayscale => buffer.iter().flat_map(|&g| vec![g, g, g, 255]).collect(),
+            png::ColorType::Indexed => {
+                return Err(png::DecodingError::Other(
+                    "Indexed images could not be expanded.".into(),
+                ))
+            }
+        };
+
+        Ok(Self::new_rgba8((info.width, info.height), data))
+    }
+
     pub fn encode_png<W: Write>(&self, output: W) -> Result<(), png::EncodingError> {
         let mut encoder = png::Encoder::new(output, self.size.0, self.size.1);
 
@@ -119,6 +166,23 @@ impl Image {
         }
     }
 
+    /// Copies the region of this image with its top left corner at `pos` into
+    /// a new image.
+    pub fn crop(&self, pos: (u32, u32), size: (u32, u32)) -> Image {
+        assert!(pos.0 + size.0 <= self.size.0);
+        assert!(pos.1 + size.1 <= self.size.1);
+
+        let mut cropped = Image::new_empty_rgba8(size);
+
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                cropped.set_pixel((x, y), self.get_pixel((pos.0 + x, pos.1 + y)));
+            }
+        }
+
+        cropped
+    }
+
     pub fn get_pixel(&self, pos: (u32, u32)) -> Pixel {
         assert!(pos.0 < self.size.0);
         assert!(pos.1 < self.size.1);
@@ -146,6 +210,100 @@ impl Image {
         self.data[start + 2] = pixel.b;
         self.data[start + 3] = pixel.a;
     }
+
+    /// Scales this image down to the given size by averaging the pixels that
+    /// each new pixel covers. Colors are weighted by their alpha, so that the
+    /// invisible color of transparent pixels doesn't bleed into their
+    /// neighbors.
+    pub fn downscale(&self, size: (u32, u32)) -> Image {
+        assert!(size.0 > 0 && size.0 <= self.size.0);
+        assert!(size.1 > 0 && size.1 <= self.size.1);
+
+        let mut scaled = Image::new_empty_rgba8(size);
+
+        // The range of source pixels that the given destination pixel covers
+        // along one axis.
+        let covered = |pos: u32, size: u32, source_size: u32| {
+            let start = (pos as u64 * source_size as u64 / size as u64) as u32;
+            let end = ((pos as u64 + 1) * source_size as u64).div_ceil(size as u64) as u32;
+            start..end
+        };
+
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                let mut color = [0u64; 3];
+                let mut alpha = 0u64;
+                let mut count = 0u64;
+
+                for source_y in covered(y, size.1, self.size.1) {
+                    for source_x in covered(x, size.0, self.size.0) {
+                        let pixel = self.get_pixel((source_x, source_y));
+                        let a = pixel.a as u64;
+
+                        color[0] += pixel.r as u64 * a;
+                        color[1] += pixel.g as u64 * a;
+                        color[2] += pixel.b as u64 * a;
+                        alpha += a;
+                        count += 1;
+                    }
+                }
+
+                let channel = |total: u64| match alpha {
+                    0 => 0,
+                    _ => ((total + alpha / 2) / alpha) as u8,
+                };
+
+                scaled.set_pixel(
+                    (x, y),
+                    Pixel::new(
+                        channel(color[0]),
+                        channel(color[1]),
+                        channel(color[2]),
+                        ((alpha + count / 2) / count) as u8,
+                    ),
+                );
+            }
+        }
+
+        scaled
+    }
+
+    /// Tells whether this image matches the region of `other` with its top
+    /// left corner at `pos`, allowing each channel to be off by `tolerance`.
+    ///
+    /// The color of fully transparent pixels is ignored, since it's invisible
+    /// and isn't always preserved when images are re-encoded.
+    pub fn matches_region(&self, other: &Image, pos: (u32, u32), tolerance: u8) -> bool {
+        if pos.0 + self.size.0 > other.size.0 || pos.1 + self.size.1 > other.size.1 {
+            return false;
+        }
+
+        let close = |a: u8, b: u8| (a as i16 - b as i16).abs() <= tolerance as i16;
+
+        for y in 0..self.size.1 {
+            for x in 0..self.size.0 {
+                let expected = self.get_pixel((x, y));
+                let actual = other.get_pixel((pos.0 + x, pos.1 + y));
+
+                if !close(expected.a, actual.a) {
+                    return false;
+                }
+
+                if expected.a == 0 && actual.a == 0 {
+                    continue;
+                }
+
+                if !close(expected.r, actual.r)
+                    || !close(expected.g, actual.g)
+                    || !close(expected.b, actual.b)
+                {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
 }
 
 #[cfg(test)]
@@ -189,4 +347,52 @@ mod test {
         assert_eq!(&source.data[0..4], &[1, 2, 3, 4]);
         assert_eq!(&source.data[(source.data.len() - 4)..], &[5, 6, 7, 8]);
     }
+
+    #[test]
+    fn crop() {
+        let mut source = Image::new_empty_rgba8((4, 4));
+        source.set_pixel((2, 1), Pixel::new(1, 2, 3, 4));
+
+        let cropped = source.crop((1, 1), (2, 3));
+
+        assert_eq!(cropped.size(), (2, 3));
+        assert_eq!(cropped.get_pixel((1, 0)), Pixel::new(1, 2, 3, 4));
+        assert_eq!(cropped.get_pixel((0, 0)), Pixel::new(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn downscale() {
+        let mut source = Image::new_empty_rgba8((4, 2));
+        source.set_pixel((0, 0), Pixel::new(200, 100, 0, 255));
+        source.set_pixel((1, 0), Pixel::new(0, 0, 0, 0));
+        source.set_pixel((0, 1), Pixel::new(100, 100, 100, 255));
+        source.set_pixel((1, 1), Pixel::new(100, 100, 100, 255));
+
+        let scaled = source.downscale((2, 1));
+
+        assert_eq!(scaled.size(), (2, 1));
+        assert_eq!(scaled.get_pixel((0, 0)), Pixel::new(133, 100, 67, 191));
+        assert_eq!(scaled.get_pixel((1, 0)), Pixel::new(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn matches_region() {
+        let mut source = Image::new_empty_rgba8((2, 2));
+        source.set_pixel((1, 1), Pixel::new(100, 100, 100, 255));
+
+        let mut target = Image::new_empty_rgba8((4, 4));
+        target.blit(&source, (2, 2));
+
+        assert!(source.matches_region(&target, (2, 2), 0));
+        assert!(!source.matches_region(&target, (1, 1), 0));
+        assert!(!source.matches_region(&target, (3, 3), 0));
+
+        target.set_pixel((3, 3), Pixel::new(102, 99, 100, 255));
+        assert!(!source.matches_region(&target, (2, 2), 0));
+        assert!(source.matches_region(&target, (2, 2), 2));
+
+        // The color of transparent pixels doesn't matter.
+        target.set_pixel((2, 2), Pixel::new(255, 0, 0, 0));
+        assert!(source.matches_region(&target, (2, 2), 2));
+    }
 }
@@ -0,0 +1,43 @@
+//! Lets a sync be stopped with Ctrl-C without losing the uploads that already
+//! finished. The first Ctrl-C asks the sync to stop once the upload in progress
+//! is done, so that the manifest can still be written. A second one exits
+//! immediately.
+
+use std::{
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+};
+
+/// The exit code of a process that was stopped by Ctrl-C.
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Returns a flag that's set once the user presses Ctrl-C, listening for it
+/// the first time this is called.
+pub fn flag() -> Arc<AtomicBool> {
+    static FLAG: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+    FLAG.get_or_init(|| {
+        let flag = Arc::new(AtomicBool::new(false));
+        let handler_flag = Arc::clone(&flag);
+
+        let listening = ctrlc::set_handler(move || {
+            if handler_flag.swap(true, Ordering::SeqCst) {
+                process::exit(INTERRUPTED_EXIT_CODE);
+            }
+
+            log::warn!(
+                "Stopping after the upload in progress. Press Ctrl-C again to stop immediately."
+            );
+        });
+
+        if let Err(err) = listening {
+            log::debug!("Couldn't listen for Ctrl-C: {}", err);
+        }
+
+        flag
+    })
+    .clone()
+}
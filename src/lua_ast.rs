@@ -65,6 +65,13 @@ proxy_display!(Block);
 pub(crate) enum Statement {
     Return(Expression),
     If(IfBlock),
+
+    /// A line comment. Comments with multiple lines are written as multiple
+    /// line comments.
+    Comment(String),
+
+    /// Declares a local variable with the given name and value.
+    Local(String, Expression),
 }
 
 impl FmtLua for Statement {
@@ -74,6 +81,23 @@ impl FmtLua for Statement {
                 write!(output, "return ")?;
                 literal.fmt_lua(output)
             }
+            Self::Comment(comment) => {
+                let mut lines = comment.split('\n');
+
+                if let Some(first) = lines.next() {
+                    write!(output, "-- {}", first)?;
+                }
+
+                for line in lines {
+                    write!(output, "\n-- {}", line)?;
+                }
+
+                Ok(())
+            }
+            Self::Local(name, value) => {
+                write!(output, "local {} = ", name)?;
+                value.fmt_lua(output)
+            }
             Self::If(if_block) => {
                 write!(output, "if ")?;
                 if_block.condition.fmt_lua(output)?;
@@ -128,6 +152,8 @@ pub(crate) enum Expression {
     String(String),
     Table(Table),
     Function(Function),
+    MethodCall(MethodCall),
+    If(IfExpression),
 
     /// Used as a catch-all for when this module doesn't define a primitive we
     /// need for codegen.
@@ -136,6 +162,17 @@ pub(crate) enum Expression {
 
 impl Expression {
     pub fn table(entries: Vec<(Expression, Expression)>) -> Self {
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| (Some(key), value))
+            .collect();
+
+        Self::Table(Table { entries })
+    }
+
+    pub fn array(values: Vec<Expression>) -> Self {
+        let entries = values.into_iter().map(|value| (None, value)).collect();
+
         Self::Table(Table { entries })
     }
 }
@@ -146,6 +183,8 @@ impl FmtLua for Expression {
             Self::Table(inner) => inner.fmt_lua(output),
             Self::String(inner) => inner.fmt_lua(output),
             Self::Function(inner) => inner.fmt_lua(output),
+            Self::MethodCall(inner) => inner.fmt_lua(output),
+            Self::If(inner) => inner.fmt_lua(output),
             Self::Raw(inner) => output.write_str(inner),
         }
     }
@@ -155,11 +194,15 @@ impl FmtLua for Expression {
             Self::Table(inner) => inner.fmt_table_key(output),
             Self::String(inner) => inner.fmt_table_key(output),
             Self::Function(inner) => inner.fmt_table_key(output),
+            Self::MethodCall(inner) => inner.fmt_table_key(output),
+            Self::If(inner) => inner.fmt_table_key(output),
             Self::Raw(inner) => output.write_str(inner),
         }
     }
 }
 
+proxy_display!(Expression);
+
 impl From<String> for Expression {
     fn from(value: String) -> Self {
         Self::String(value)
@@ -199,7 +242,9 @@ impl FmtLua for String {
 }
 
 pub(crate) struct Table {
-    pub entries: Vec<(Expression, Expression)>,
+    /// The entries in this table, in order. Entries without a key are written
+    /// as positional (array-like) entries.
+    pub entries: Vec<(Option<Expression>, Expression)>,
 }
 
 impl Table {
@@ -210,7 +255,7 @@ impl Table {
     }
 
     pub fn add_entry<K: Into<Expression>, V: Into<Expression>>(&mut self, key: K, value: V) {
-        self.entries.push((key.into(), value.into()));
+        self.entries.push((Some(key.into()), value.into()));
     }
 }
 
@@ -220,8 +265,11 @@ impl FmtLua for Table {
         output.indent();
 
         for (key, value) in &self.entries {
-            key.fmt_table_key(output)?;
-            write!(output, " = ")?;
+            if let Some(key) = key {
+                key.fmt_table_key(output)?;
+                write!(output, " = ")?;
+            }
+
             value.fmt_lua(output)?;
             writeln!(output, ",")?;
         }
@@ -281,6 +329,73 @@ impl FmtLua for Function {
     }
 }
 
+/// A call to a method on a value, like `game:GetService("Players")`.
+pub(crate) struct MethodCall {
+    pub target: Box<Expression>,
+    pub method: String,
+    pub args: Vec<Expression>,
+}
+
+impl MethodCall {
+    pub fn new<E: Into<Expression>>(target: E, method: &str, args: Vec<Expression>) -> Self {
+        Self {
+            target: Box::new(target.into()),
+            method: method.to_owned(),
+            args,
+        }
+    }
+}
+
+impl FmtLua for MethodCall {
+    fn fmt_lua(&self, output: &mut LuaStream<'_>) -> fmt::Result {
+        self.target.fmt_lua(output)?;
+        write!(output, ":{}(", self.method)?;
+
+        for (i, arg) in self.args.iter().enumerate() {
+            if i > 0 {
+                write!(output, ", ")?;
+            }
+
+            arg.fmt_lua(output)?;
+        }
+
+        write!(output, ")")
+    }
+}
+
+/// A Luau if-then-else expression, like `if a then b else c`.
+pub(crate) struct IfExpression {
+    pub condition: Box<Expression>,
+    pub if_true: Box<Expression>,
+    pub if_false: Box<Expression>,
+}
+
+impl IfExpression {
+    pub fn new<C, T, F>(condition: C, if_true: T, if_false: F) -> Self
+    where
+        C: Into<Expression>,
+        T: Into<Expression>,
+        F: Into<Expression>,
+    {
+        Self {
+            condition: Box::new(condition.into()),
+            if_true: Box::new(if_true.into()),
+            if_false: Box::new(if_false.into()),
+        }
+    }
+}
+
+impl FmtLua for IfExpression {
+    fn fmt_lua(&self, output: &mut LuaStream<'_>) -> fmt::Result {
+        write!(output, "if ")?;
+        self.condition.fmt_lua(output)?;
+        write!(output, " then ")?;
+        self.if_true.fmt_lua(output)?;
+        write!(output, " else ")?;
+        self.if_false.fmt_lua(output)
+    }
+}
+
 /// Wraps a `fmt::Write` with additional tracking to do pretty-printing of Lua.
 ///
 /// Behaves similarly to `fmt::Formatter`. This trait's relationship to `LuaFmt`
@@ -345,3 +460,64 @@ impl<'a> LuaStream<'a> {
         self.inner.write_str("\n")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn comment() {
+        let statement = Statement::Comment("first\nsecond".to_owned());
+        assert_eq!(statement.to_string(), "-- first\n-- second");
+    }
+
+    #[test]
+    fn local() {
+        let statement = Statement::Local("foo".to_owned(), "bar".into());
+        assert_eq!(statement.to_string(), "local foo = \"bar\"");
+    }
+
+    #[test]
+    fn method_call() {
+        let call = MethodCall::new(
+            Expression::Raw("game".to_owned()),
+            "GetService",
+            vec!["Players".into()],
+        );
+        let statement = Statement::Local("Players".to_owned(), Expression::MethodCall(call));
+
+        assert_eq!(
+            statement.to_string(),
+            "local Players = game:GetService(\"Players\")"
+        );
+    }
+
+    #[test]
+    fn if_expression() {
+        let expression = IfExpression::new(
+            Expression::Raw("DEV".to_owned()),
+            "a",
+            Expression::Raw("nil".to_owned()),
+        );
+        let statement = Statement::Return(Expression::If(expression));
+
+        assert_eq!(statement.to_string(), "return if DEV then \"a\" else nil");
+    }
+
+    #[test]
+    fn array() {
+        let statement = Statement::Return(Expression::array(vec!["a".into(), "b".into()]));
+        assert_eq!(statement.to_string(), "return {\n\t\"a\",\n\t\"b\",\n}");
+    }
+
+    #[test]
+    fn nested_table() {
+        let inner = Expression::table(vec![("bar".into(), "baz".into())]);
+        let statement = Statement::Return(Expression::table(vec![("foo".into(), inner)]));
+
+        assert_eq!(
+            statement.to_string(),
+            "return {\n\tfoo = {\n\t\tbar = \"baz\",\n\t},\n}"
+        );
+    }
+}
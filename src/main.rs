@@ -1,16 +1,27 @@
 mod alpha_bleed;
 mod asset_name;
+mod asset_uploader;
 mod auth_cookie;
 mod codegen;
 mod commands;
 mod data;
 mod dpi_scale;
+mod file_contents;
 mod glob;
+mod http_fixtures;
+mod ignore_file;
 mod image;
+mod interrupt;
 mod lua_ast;
 mod options;
+mod pipeline;
+mod rbxmx;
+mod remote_include;
 mod roblox_web_api;
 mod sync_backend;
+mod upload_cache;
+mod upload_name;
+mod upload_progress;
 
 use std::{env, panic, process};
 
@@ -22,13 +33,32 @@ use crate::options::{Options, Subcommand};
 fn run(options: Options) -> Result<(), anyhow::Error> {
     match options.command {
         Subcommand::UploadImage(upload_options) => {
-            commands::upload_image(options.global, upload_options)
+            commands::upload_image(options.global, upload_options)?
         }
         Subcommand::Sync(sync_options) => commands::sync(options.global, sync_options)?,
         Subcommand::CreateCacheMap(sub_options) => {
             commands::create_cache_map(options.global, sub_options)?
         }
         Subcommand::AssetList(sub_options) => commands::asset_list(options.global, sub_options)?,
+        Subcommand::PreviewModel(sub_options) => {
+            commands::preview_model(options.global, sub_options)?
+        }
+        Subcommand::Report(sub_options) => commands::report(options.global, sub_options)?,
+        Subcommand::Download(sub_options) => commands::download(options.global, sub_options)?,
+        Subcommand::Audit(sub_options) => commands::audit(options.global, sub_options)?,
+        Subcommand::MigrateOwnership(sub_options) => {
+            commands::migrate_ownership(options.global, sub_options)?
+        }
+        Subcommand::Login(sub_options) => commands::login(options.global, sub_options)?,
+        Subcommand::Logout => commands::logout(options.global)?,
+        Subcommand::Lint(sub_options) => commands::lint(options.global, sub_options)?,
+        Subcommand::Schema(sub_options) => commands::schema(options.global, sub_options)?,
+        Subcommand::Package(sub_options) => commands::package(options.global, sub_options)?,
+        Subcommand::MergeManifest(sub_options) => {
+            commands::merge_manifest(options.global, sub_options)?
+        }
+        Subcommand::ImportIds(sub_options) => commands::import_ids(options.global, sub_options)?,
+        Subcommand::Import(sub_options) => commands::import(options.global, sub_options)?,
     }
 
     Ok(())
@@ -1,7 +1,19 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{env, path::PathBuf, str::FromStr, time::Duration};
 
+use fs_err as fs;
+use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
+use crate::{
+    auth_cookie::get_auth_cookie,
+    data::{
+        stored_credential, CredentialKind, Profile, UserConfig, UserConfigError, WalkErrorPolicy,
+    },
+    glob::Glob,
+    http_fixtures::FixtureMode,
+    roblox_web_api::{ClientOptions, ProxyOptions},
+};
+
 #[derive(Debug, StructOpt)]
 #[structopt(about = env!("CARGO_PKG_DESCRIPTION"))]
 pub struct Options {
@@ -15,16 +27,239 @@ pub struct Options {
 #[derive(Debug, StructOpt)]
 pub struct GlobalOptions {
     /// The authentication cookie for Tarmac to use. If not specified, Tarmac
-    /// will attempt to use the cookie from the Roblox Studio installation on
-    /// the system.
+    /// will read it from the TARMAC_AUTH or ROBLOSECURITY environment
+    /// variables, use the one stored with `tarmac login`, or attempt to use
+    /// the cookie from the Roblox Studio installation on the system.
     #[structopt(long, global(true))]
     pub auth: Option<String>,
 
+    /// A file containing the authentication cookie for Tarmac to use, like a
+    /// secret mounted by a CI system.
+    #[structopt(long, global(true), conflicts_with = "auth")]
+    pub auth_file: Option<PathBuf>,
+
+    /// The Open Cloud API key for Tarmac to use when a project uploads with
+    /// the `open-cloud` upload API. If not specified, Tarmac will read it from
+    /// the TARMAC_API_KEY environment variable.
+    #[structopt(long, global(true))]
+    pub api_key: Option<String>,
+
+    /// The name of a profile from the Tarmac user config to take credentials
+    /// and a default group from. Credentials passed with `--auth` or
+    /// `--api-key` still take precedence. When syncing, a profile with the
+    /// same name in the project's `tarmac.toml` is applied too.
+    #[structopt(long, global(true))]
+    pub profile: Option<String>,
+
+    /// The number of seconds to wait for each request to Roblox before giving
+    /// up on it.
+    #[structopt(long, global(true), default_value = "30")]
+    pub request_timeout: u64,
+
+    /// The total number of times Tarmac may retry requests that were rate
+    /// limited by Roblox during one run.
+    #[structopt(long, global(true), default_value = "50")]
+    pub request_retries: usize,
+
+    /// The User-Agent header to send with every request. Defaults to Tarmac's
+    /// name and version.
+    #[structopt(long, global(true))]
+    pub user_agent: Option<String>,
+
+    /// Sends requests to this URL instead of Roblox, like a proxy or a mock
+    /// server. Each request's path is prefixed with the Roblox service it was
+    /// meant for, like `/apis`. If not specified, Tarmac will read it from the
+    /// TARMAC_API_BASE_URL environment variable.
+    #[structopt(long, global(true))]
+    pub api_base_url: Option<String>,
+
+    /// Sends every request through the given HTTP proxy. If not specified,
+    /// Tarmac uses the proxies from the HTTP_PROXY and HTTPS_PROXY environment
+    /// variables. Hosts listed in NO_PROXY are always connected to directly.
+    #[structopt(long, global(true))]
+    pub proxy: Option<String>,
+
+    /// Records every request made to Roblox and its response to the given
+    /// JSON file, for replaying later with `--replay-fixtures`.
+    #[structopt(long, global(true), conflicts_with = "replay-fixtures")]
+    pub record_fixtures: Option<PathBuf>,
+
+    /// Serves responses from a file recorded with `--record-fixtures` instead
+    /// of sending requests to Roblox. Requests that weren't recorded fail.
+    #[structopt(long, global(true))]
+    pub replay_fixtures: Option<PathBuf>,
+
     /// Sets verbosity level. Can be specified multiple times.
     #[structopt(long = "verbose", short, global(true), parse(from_occurrences))]
     pub verbosity: u8,
 }
 
+impl GlobalOptions {
+    /// The credentials and default group that commands should use.
+    ///
+    /// When a profile is selected, its credentials and the ones stored for it
+    /// with `tarmac login` come before the ones Tarmac usually looks for.
+    /// Credentials are only looked up until one is found, so passing `--auth`
+    /// never reads the system keychain for a cookie.
+    pub fn credentials(&self) -> Result<Credentials, UserConfigError> {
+        self.credentials_for_project(false)
+    }
+
+    /// Like `credentials`, but for a project that may define the selected
+    /// profile itself, in which case the user config doesn't need to.
+    pub fn credentials_for_project(
+        &self,
+        project_defines_profile: bool,
+    ) -> Result<Credentials, UserConfigError> {
+        let profile_name = self.profile.as_deref();
+
+        let profile = match profile_name {
+            Some(name) => {
+                let user_config = UserConfig::read()?;
+
+                // Profiles only need to be defined in the user config if they
+                // have settings besides the credentials stored for them.
+                match user_config.profile(name) {
+                    Ok(profile) => profile.clone(),
+                    Err(_)
+                        if project_defines_profile
+                            || stored_credential(profile_name, CredentialKind::Auth)?.is_some()
+                            || stored_credential(profile_name, CredentialKind::ApiKey)?
+                                .is_some() =>
+                    {
+                        Profile::default()
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+            None => Profile::default(),
+        };
+
+        let auth = match self.explicit_auth()?.or(profile.auth) {
+            Some(auth) => Some(auth),
+            None => find_credential(
+                profile_name,
+                CredentialKind::Auth,
+                &["TARMAC_AUTH", "ROBLOSECURITY"],
+                get_auth_cookie,
+            )?,
+        };
+
+        Ok(Credentials {
+            auth,
+            api_key: self.api_key.clone().or(profile.api_key),
+            profile: self.profile.clone(),
+            group_id: profile.group_id,
+            universe_id: profile.universe_id,
+        })
+    }
+
+    /// The cookie passed with `--auth` or read from `--auth-file`, if any.
+    fn explicit_auth(&self) -> Result<Option<String>, UserConfigError> {
+        if let Some(path) = &self.auth_file {
+            let contents = fs::read_to_string(path)?;
+            let cookie = contents.trim();
+
+            if cookie.is_empty() {
+                return Err(UserConfigError::EmptyAuthFile { path: path.clone() });
+            }
+
+            return Ok(Some(cookie.to_owned()));
+        }
+
+        Ok(self.auth.clone())
+    }
+
+    /// The connection settings that API clients should use.
+    pub fn client_options(&self) -> ClientOptions {
+        let mut options = ClientOptions {
+            timeout: Duration::from_secs(self.request_timeout),
+            retry_budget: self.request_retries,
+            base_url: self
+                .api_base_url
+                .clone()
+                .or_else(|| env::var("TARMAC_API_BASE_URL").ok()),
+            ..ClientOptions::default()
+        };
+
+        options.proxy = ProxyOptions::from_env();
+        if let Some(proxy) = &self.proxy {
+            options.proxy.http = Some(proxy.clone());
+            options.proxy.https = Some(proxy.clone());
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            options.user_agent = user_agent.clone();
+        }
+
+        if let Some(path) = &self.record_fixtures {
+            options.fixtures = Some((FixtureMode::Record, path.clone()));
+        } else if let Some(path) = &self.replay_fixtures {
+            options.fixtures = Some((FixtureMode::Replay, path.clone()));
+        }
+
+        options
+    }
+}
+
+/// The credentials that commands should authenticate with, along with the
+/// group that the selected profile uploads to.
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    pub auth: Option<String>,
+    pub group_id: Option<u64>,
+    pub universe_id: Option<u64>,
+
+    /// The API key passed with `--api-key` or set by the selected profile.
+    api_key: Option<String>,
+
+    /// The name of the selected profile, to find the API key stored for it.
+    profile: Option<String>,
+}
+
+impl Credentials {
+    /// The Open Cloud API key to use. Only commands that need one look for
+    /// it, so the others never read the system keychain for it.
+    pub fn api_key(&self) -> Result<Option<String>, UserConfigError> {
+        match &self.api_key {
+            Some(api_key) => Ok(Some(api_key.clone())),
+            None => find_credential(
+                self.profile.as_deref(),
+                CredentialKind::ApiKey,
+                &["TARMAC_API_KEY"],
+                || None,
+            ),
+        }
+    }
+}
+
+/// Looks for a credential that wasn't given on the command line or by the
+/// selected profile: the one stored for the profile, then the given
+/// environment variables, then the one stored with `tarmac login` without a
+/// profile, and finally `fallback`. Stops at the first one that's found.
+fn find_credential(
+    profile: Option<&str>,
+    kind: CredentialKind,
+    env_vars: &[&str],
+    fallback: impl FnOnce() -> Option<String>,
+) -> Result<Option<String>, UserConfigError> {
+    if profile.is_some() {
+        if let Some(credential) = stored_credential(profile, kind)? {
+            return Ok(Some(credential));
+        }
+    }
+
+    if let Some(credential) = env_vars.iter().find_map(|name| env::var(name).ok()) {
+        return Ok(Some(credential));
+    }
+
+    if let Some(credential) = stored_credential(None, kind)? {
+        return Ok(Some(credential));
+    }
+
+    Ok(fallback())
+}
+
 #[derive(Debug, StructOpt)]
 pub enum Subcommand {
     /// Upload a single image to the Roblox cloud. Prints the asset ID of the
@@ -40,6 +275,61 @@ pub enum Subcommand {
 
     /// Creates a file that lists all assets required by the project.
     AssetList(AssetListOptions),
+
+    /// Creates a Roblox model file containing an ImageLabel for every synced
+    /// asset in the project, for previewing uploads inside Roblox Studio.
+    PreviewModel(PreviewModelOptions),
+
+    /// Generates a report describing every asset in the project as of the last
+    /// sync.
+    Report(ReportOptions),
+
+    /// Downloads the uploaded version of every asset in the project's manifest
+    /// into a folder.
+    Download(DownloadOptions),
+
+    /// Lists assets created by the authenticated user or a group that look
+    /// like Tarmac uploads but aren't referenced by any of the given projects.
+    Audit(AuditOptions),
+
+    /// Uploads every asset in the project's manifest again under a new owner,
+    /// like when moving a project from a personal account to a group.
+    MigrateOwnership(MigrateOwnershipOptions),
+
+    /// Validates a `.ROBLOSECURITY` cookie or Open Cloud API key and stores it
+    /// for later commands. The credential is read from stdin.
+    Login(LoginOptions),
+
+    /// Forgets the credentials stored with `tarmac login`.
+    Logout,
+
+    /// Checks the project's configs for likely mistakes, like input groups
+    /// that don't match any files and includes that don't exist. Exits with
+    /// an error if any are found.
+    Lint(LintOptions),
+
+    /// Prints a JSON Schema describing Tarmac project configs, which editors
+    /// and CI can validate `tarmac.toml` files with.
+    Schema(SchemaOptions),
+
+    /// Bundles the project's uploaded asset IDs and generated modules into a
+    /// package that other projects can depend on without uploading the assets
+    /// again.
+    Package(PackageOptions),
+
+    /// Merges two versions of a manifest from a common base, for use as a git
+    /// merge driver. The result replaces `ours`.
+    MergeManifest(MergeManifestOptions),
+
+    /// Records the IDs of assets that were uploaded before the project used
+    /// Tarmac in its manifest, so that syncing doesn't upload them again. The
+    /// IDs are read from a CSV or JSON file mapping paths to IDs.
+    ImportIds(ImportIdsOptions),
+
+    /// Creates a Tarmac project from assets managed some other way, like Lua
+    /// modules of asset IDs or Rojo `.meta.json` files, keeping their
+    /// existing IDs so that nothing is uploaded again.
+    Import(ImportOptions),
 }
 
 #[derive(Debug, StructOpt)]
@@ -54,6 +344,11 @@ pub struct UploadImageOptions {
     /// The description to give to the resulting Decal asset.
     #[structopt(long, default_value = "Uploaded by Tarmac.")]
     pub description: String,
+
+    /// If specified, uploads the image to the given Roblox group instead of
+    /// the authenticated user's inventory.
+    #[structopt(long)]
+    pub group_id: Option<u64>,
 }
 
 #[derive(Debug, StructOpt)]
@@ -68,8 +363,10 @@ pub struct SyncOptions {
     ///   unsynced assets.
     ///
     /// - debug: Copy to local debug directory for debugging output
+    ///
+    /// Required unless the selected project profile defines a target.
     #[structopt(long)]
-    pub target: SyncTarget,
+    pub target: Option<SyncTarget>,
 
     /// When provided, Tarmac will upload again at most the given number of times
     /// when it encounters rate limitation errors.
@@ -80,11 +377,86 @@ pub struct SyncOptions {
     #[structopt(long, default_value = "60")]
     pub retry_delay: u64,
 
-    /// The path to a Tarmac config, or a folder containing a Tarmac project.
-    pub config_path: Option<PathBuf>,
+    /// When syncing to Roblox, checks the moderation state of every uploaded
+    /// asset afterwards. Requires an Open Cloud API key. Rejected assets are
+    /// reported as errors.
+    #[structopt(long)]
+    pub check_moderation: bool,
+
+    /// The number of seconds to wait for assets that are still being reviewed
+    /// when using `--check-moderation`.
+    #[structopt(long, default_value = "0")]
+    pub moderation_timeout: u64,
+
+    /// When syncing to Roblox, downloads every uploaded image afterwards and
+    /// compares it with the local image, reporting any that Roblox changed.
+    #[structopt(long)]
+    pub verify_uploads: bool,
+
+    /// The number of assets the uploading account may upload each day. When
+    /// given, Tarmac warns before a sync that would approach or exceed it,
+    /// based on the uploads it has recorded for that account today.
+    #[structopt(long)]
+    pub daily_upload_limit: Option<usize>,
+
+    /// When syncing to Roblox, skips checking that the credentials are valid
+    /// before looking for assets to upload.
+    #[structopt(long)]
+    pub skip_auth_check: bool,
+
+    /// When syncing to Roblox, doesn't reuse assets that were uploaded for the
+    /// same owner from other projects on this machine, or record new uploads
+    /// for them.
+    #[structopt(long)]
+    pub no_user_cache: bool,
+
+    /// If specified, uploads assets to the given Roblox group, overriding the
+    /// project's `upload-to-group-id`.
+    #[structopt(long)]
+    pub group_id: Option<u64>,
+
+    /// If specified, gives the given universe permission to use every synced
+    /// asset. Requires the `open-cloud` upload API.
+    #[structopt(long)]
+    pub universe_id: Option<u64>,
+
+    /// What to do when part of the project can't be searched for inputs,
+    /// overriding the project's `walk-errors`.
+    ///
+    /// Options: error, warn, skip
+    #[structopt(long)]
+    pub walk_errors: Option<WalkErrorPolicy>,
+
+    /// The number of threads that read, pack, and encode assets while other
+    /// assets are being uploaded. Defaults to the number of cores.
+    #[structopt(long)]
+    pub jobs: Option<usize>,
+
+    /// Maps large input files into memory instead of streaming them from disk
+    /// while they're hashed and uploaded. Only safe if nothing modifies or
+    /// truncates the project's inputs while the sync runs.
+    #[structopt(long)]
+    pub map_inputs: bool,
+
+    /// If given, only uploads inputs with this tag. Other inputs keep the
+    /// assets from earlier syncs. Can be given more than once to upload inputs
+    /// with any of the tags.
+    #[structopt(long = "tag")]
+    pub tags: Vec<String>,
+
+    /// The paths to Tarmac configs, or folders containing Tarmac projects or
+    /// workspaces. Each one is synced separately, in the given order. Defaults
+    /// to the current directory.
+    pub config_paths: Vec<PathBuf>,
+
+    /// In a workspace, the name of the only project to use instead of every
+    /// project in the workspace.
+    #[structopt(long)]
+    pub project: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum SyncTarget {
     Roblox,
     None,
@@ -122,9 +494,210 @@ pub struct CreateCacheMapOptions {
 
 #[derive(Debug, StructOpt)]
 pub struct AssetListOptions {
+    /// The folder of a Tarmac project or workspace. In a workspace, the asset
+    /// list includes the assets of every project.
     pub project_path: Option<PathBuf>,
 
+    /// In a workspace, the name of the only project to list the assets of.
+    #[structopt(long)]
+    pub project: Option<String>,
+
     /// A path to a file to put the asset list.
     #[structopt(long = "output")]
     pub output: PathBuf,
 }
+
+#[derive(Debug, StructOpt)]
+pub struct LintOptions {
+    /// The path to a Tarmac config, or a folder containing a Tarmac project
+    /// or workspace.
+    pub config_path: Option<PathBuf>,
+
+    /// In a workspace, the name of the only project to use instead of every
+    /// project in the workspace.
+    #[structopt(long)]
+    pub project: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct SchemaOptions {
+    /// A path to a file to write the schema to, instead of printing it.
+    #[structopt(long = "output")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct PackageOptions {
+    /// The path to a Tarmac config, or a folder containing a Tarmac project.
+    pub config_path: Option<PathBuf>,
+
+    /// The folder to write the package to.
+    #[structopt(long = "output")]
+    pub output: PathBuf,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct MergeManifestOptions {
+    /// The manifest from the common ancestor of both sides, `%O` in git.
+    pub base: PathBuf,
+
+    /// Our side's manifest, which the merged manifest is written to. `%A` in
+    /// git.
+    pub ours: PathBuf,
+
+    /// Their side's manifest, `%B` in git.
+    pub theirs: PathBuf,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ImportIdsOptions {
+    /// A CSV file with a path and an ID on each line, or a JSON file with an
+    /// object mapping paths to IDs. Paths are relative to the project's
+    /// folder.
+    #[structopt(long = "from")]
+    pub mapping: PathBuf,
+
+    /// The path to a Tarmac config, or a folder containing a Tarmac project.
+    pub config_path: Option<PathBuf>,
+
+    /// The group that owns the imported assets, if they aren't owned by a
+    /// user.
+    #[structopt(long)]
+    pub group_id: Option<u64>,
+
+    /// Replaces the IDs of inputs that are already in the manifest, instead of
+    /// skipping them.
+    #[structopt(long)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ImportOptions {
+    /// What kind of files the asset IDs are imported from: lua, rojo-meta, or
+    /// csv.
+    #[structopt(long)]
+    pub format: ImportFormat,
+
+    /// The file or folder to import asset IDs from.
+    #[structopt(long = "from")]
+    pub source: PathBuf,
+
+    /// The folder to create the project in, which contains the assets.
+    /// Defaults to the current directory.
+    pub project_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    /// Lua modules with constants like `Star = "rbxassetid://123"`.
+    Lua,
+
+    /// Rojo `.meta.json` files with an `rbxassetid://` property, next to
+    /// the asset they describe.
+    RojoMeta,
+
+    /// A CSV file with a path and an ID on each line.
+    Csv,
+}
+
+impl FromStr for ImportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<ImportFormat, Self::Err> {
+        match value {
+            "lua" => Ok(ImportFormat::Lua),
+            "rojo-meta" => Ok(ImportFormat::RojoMeta),
+            "csv" => Ok(ImportFormat::Csv),
+
+            _ => Err(String::from(
+                "Invalid import format. Valid options are lua, rojo-meta, and csv.",
+            )),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+pub struct PreviewModelOptions {
+    pub project_path: Option<PathBuf>,
+
+    /// A path to the `.rbxmx` file to create.
+    #[structopt(long = "output")]
+    pub output: PathBuf,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ReportOptions {
+    pub project_path: Option<PathBuf>,
+
+    /// A path to a directory to put a static HTML report in. The directory
+    /// will be created if it does not exist.
+    #[structopt(long = "html")]
+    pub html: PathBuf,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct DownloadOptions {
+    pub project_path: Option<PathBuf>,
+
+    /// A path to a directory to put the downloaded assets in. The directory
+    /// will be created if it does not exist.
+    #[structopt(long = "output")]
+    pub output: PathBuf,
+
+    /// Only downloads inputs whose names match the given glob. Can be
+    /// specified multiple times.
+    #[structopt(long = "only", parse(try_from_str = Glob::new))]
+    pub only: Vec<Glob>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct AuditOptions {
+    /// The projects whose manifests list the assets that are still in use.
+    /// Defaults to the current directory.
+    pub project_paths: Vec<PathBuf>,
+
+    /// Lists the assets of the given group instead of the authenticated
+    /// user's.
+    #[structopt(long)]
+    pub group_id: Option<u64>,
+
+    /// The types of asset to list, as named by Roblox.
+    #[structopt(long = "asset-type", default_value = "Decal")]
+    pub asset_types: Vec<String>,
+
+    /// Lists every unreferenced asset, instead of only the ones that have the
+    /// name or description that Tarmac uploads with by default.
+    #[structopt(long)]
+    pub all: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct MigrateOwnershipOptions {
+    /// The group that should own every asset after the migration.
+    #[structopt(long, required_unless = "user-id", conflicts_with = "user-id")]
+    pub group_id: Option<u64>,
+
+    /// The user that should own every asset after the migration. The legacy
+    /// upload API always uploads to the authenticated user.
+    #[structopt(long)]
+    pub user_id: Option<u64>,
+
+    /// When provided, Tarmac will upload again at most the given number of times
+    /// when it encounters rate limitation errors.
+    #[structopt(long)]
+    pub retry: Option<usize>,
+
+    /// The number of seconds to wait between each re-upload attempts.
+    #[structopt(long, default_value = "60")]
+    pub retry_delay: u64,
+
+    /// The path to a Tarmac config, or a folder containing a Tarmac project.
+    pub config_path: Option<PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct LoginOptions {
+    /// Stores an Open Cloud API key instead of a `.ROBLOSECURITY` cookie.
+    #[structopt(long)]
+    pub open_cloud: bool,
+}
@@ -0,0 +1,411 @@
+//! A pipeline that prepares jobs on a pool of threads while the calling thread
+//! consumes them in order, so that work like reading, packing, and encoding
+//! assets overlaps with uploading the ones that are already prepared.
+//!
+//! Only a few jobs are allowed to be prepared ahead of the one being consumed,
+//! which keeps slow uploads from letting prepared assets pile up in memory.
+//! Jobs are also only created once there's room for them to be in flight, so
+//! jobs that are expensive to hold onto can be produced lazily. They're created
+//! on a thread of their own, so a job that's slow to create doesn't hold up the
+//! workers or the consuming thread.
+//!
+//! The pipeline uses threads rather than an async runtime because every
+//! request Tarmac makes blocks the thread that makes it. A pipeline can still
+//! be cancelled from another thread, like one that handles Ctrl-C: no more
+//! jobs are started, and the results that were already prepared are dropped
+//! instead of being consumed.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Condvar, Mutex, PoisonError,
+    },
+    thread,
+};
+
+/// Whether the pipeline should keep going after a job is consumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+
+    /// No more jobs are started, and jobs that were already prepared are
+    /// dropped without being consumed.
+    Stop,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    workers: usize,
+    in_flight: usize,
+    cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl Pipeline {
+    /// Creates a pipeline with a worker for each available core, which keeps
+    /// as many jobs in flight as it has workers.
+    pub fn new() -> Self {
+        let workers = thread::available_parallelism().map_or(1, |threads| threads.get());
+
+        Self {
+            workers,
+            in_flight: workers,
+            cancelled: None,
+        }
+    }
+
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// How many jobs can be started before the oldest of them is consumed.
+    pub fn in_flight(mut self, in_flight: usize) -> Self {
+        self.in_flight = in_flight.max(1);
+        self
+    }
+
+    /// Stops the pipeline as if `consume` had asked to once the given flag is
+    /// set. The job being consumed when it's set is still finished.
+    pub fn cancel_on(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.cancelled = Some(cancelled);
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        matches!(&self.cancelled, Some(cancelled) if cancelled.load(Ordering::SeqCst))
+    }
+
+    /// Prepares every job with `prepare` on the pipeline's workers, and calls
+    /// `consume` with the results on this thread in the same order as the
+    /// jobs, until every job is consumed, `consume` asks to stop, or the
+    /// pipeline is cancelled.
+    ///
+    /// Jobs are taken from `jobs` on a thread of their own, one at a time as
+    /// room opens up for another job to be in flight, and are handed to
+    /// whichever worker is ready for one. Creating a job never holds up the
+    /// workers or this thread.
+    pub fn run<I, T, P, C>(&self, jobs: I, prepare: P, mut consume: C)
+    where
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: Send,
+        T: Send,
+        P: Fn(I::Item) -> T + Sync,
+        C: FnMut(T) -> Flow,
+    {
+        let jobs = jobs.into_iter();
+        let workers = match jobs.size_hint() {
+            (_, Some(max_jobs)) => self.workers.min(max_jobs),
+            (_, None) => self.workers,
+        };
+        let schedule = Schedule {
+            pipeline: self,
+            state: Mutex::new(ScheduleState {
+                started: 0,
+                consumed: 0,
+                stopped: false,
+            }),
+            changed: Condvar::new(),
+        };
+
+        // Only as many jobs as can be in flight are ever created without being
+        // taken by a worker, so sending a job never blocks.
+        let (job_sender, job_receiver) = mpsc::sync_channel(self.in_flight);
+        let job_receiver = Mutex::new(job_receiver);
+        let (sender, receiver) = mpsc::channel();
+
+        thread::scope(|scope| {
+            let schedule = &schedule;
+
+            scope.spawn(move || {
+                let _guard = StopOnPanic(schedule);
+
+                let mut jobs = jobs.enumerate();
+
+                while schedule.reserve() {
+                    let job = match jobs.next() {
+                        Some(job) => job,
+                        None => break,
+                    };
+
+                    if job_sender.send(job).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            for _ in 0..workers {
+                let sender = sender.clone();
+                let job_receiver = &job_receiver;
+                let prepare = &prepare;
+
+                scope.spawn(move || {
+                    let _guard = StopOnPanic(schedule);
+
+                    loop {
+                        let next = job_receiver.lock().unwrap().recv();
+                        let (index, job) = match next {
+                            Ok(next) if !schedule.is_stopped() => next,
+                            _ => break,
+                        };
+
+                        if sender.send((index, prepare(job))).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+
+            // Workers that panic drop their senders, so once every worker has
+            // exited, we stop waiting for results that won't arrive.
+            drop(sender);
+
+            let _guard = StopOnPanic(schedule);
+            let mut prepared = BTreeMap::new();
+            let mut next = 0;
+
+            for (index, result) in &receiver {
+                prepared.insert(index, result);
+
+                while let Some(result) = prepared.remove(&next) {
+                    next += 1;
+
+                    if self.is_cancelled() || consume(result) == Flow::Stop {
+                        schedule.stop();
+                        return;
+                    }
+
+                    schedule.consumed();
+                }
+            }
+        });
+    }
+}
+
+struct Schedule<'a> {
+    pipeline: &'a Pipeline,
+    state: Mutex<ScheduleState>,
+    changed: Condvar,
+}
+
+struct ScheduleState {
+    started: usize,
+    consumed: usize,
+    stopped: bool,
+}
+
+impl Schedule<'_> {
+    /// Waits until there's room for another job to be in flight, then counts
+    /// one more job as started. Returns false instead if the pipeline stopped.
+    fn reserve(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            if state.stopped || self.pipeline.is_cancelled() {
+                return false;
+            }
+
+            if state.started < state.consumed + self.pipeline.in_flight {
+                state.started += 1;
+                return true;
+            }
+
+            state = self.changed.wait(state).unwrap();
+        }
+    }
+
+    fn consumed(&self) {
+        self.state.lock().unwrap().consumed += 1;
+        self.changed.notify_all();
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .stopped
+    }
+
+    fn stop(&self) {
+        // The lock is poisoned if a thread panicked while holding it, but the
+        // pipeline still has to be stopped so that the panic can reach the
+        // caller.
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .stopped = true;
+        self.changed.notify_all();
+    }
+}
+
+/// Stops the pipeline if the thread holding it panics, so that the threads
+/// waiting on it can exit and the panic can reach the caller.
+struct StopOnPanic<'a, 'b>(&'a Schedule<'b>);
+
+impl Drop for StopOnPanic<'_, '_> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[test]
+    fn results_are_consumed_in_order() {
+        let jobs: Vec<u64> = (0..20).collect();
+        let started = AtomicUsize::new(0);
+        let mut consumed = Vec::new();
+
+        Pipeline::new().workers(4).in_flight(3).run(
+            jobs.clone(),
+            |job| {
+                started.fetch_add(1, Ordering::SeqCst);
+
+                // Later jobs finish first, so results arrive out of order.
+                thread::sleep(Duration::from_millis(20 - job));
+                job
+            },
+            |job| {
+                // Jobs can only be started a few ahead of the one we're on.
+                assert!(started.load(Ordering::SeqCst) <= consumed.len() + 3);
+
+                consumed.push(job);
+                Flow::Continue
+            },
+        );
+
+        assert_eq!(consumed, jobs);
+    }
+
+    #[test]
+    fn stopping_skips_remaining_jobs() {
+        let prepared = AtomicUsize::new(0);
+        let mut consumed = Vec::new();
+
+        Pipeline::new().workers(2).in_flight(2).run(
+            0..100,
+            |job: usize| {
+                prepared.fetch_add(1, Ordering::SeqCst);
+                job
+            },
+            |job| {
+                consumed.push(job);
+
+                if job == 5 {
+                    Flow::Stop
+                } else {
+                    Flow::Continue
+                }
+            },
+        );
+
+        assert_eq!(consumed, (0..=5).collect::<Vec<_>>());
+        assert!(prepared.load(Ordering::SeqCst) <= 7);
+    }
+
+    #[test]
+    fn creating_jobs_doesnt_block_consuming() {
+        let (sender, receiver) = mpsc::channel();
+        let mut consumed = Vec::new();
+
+        // The last job can't be created until the one before it is consumed.
+        let jobs = (0..3).inspect(move |&job| {
+            if job == 2 {
+                receiver
+                    .recv_timeout(Duration::from_secs(10))
+                    .expect("creating a job kept the others from being consumed");
+            }
+        });
+
+        Pipeline::new().workers(2).in_flight(3).run(
+            jobs,
+            |job| job,
+            |job| {
+                consumed.push(job);
+
+                if job == 1 {
+                    sender.send(()).unwrap();
+                }
+
+                Flow::Continue
+            },
+        );
+
+        assert_eq!(consumed, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn cancelling_skips_remaining_jobs() {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let mut consumed = Vec::new();
+
+        Pipeline::new()
+            .workers(2)
+            .in_flight(2)
+            .cancel_on(Arc::clone(&cancelled))
+            .run(
+                0..100,
+                |job: usize| job,
+                |job| {
+                    consumed.push(job);
+
+                    // The job being consumed when the pipeline is cancelled is
+                    // still finished.
+                    if job == 3 {
+                        cancelled.store(true, Ordering::SeqCst);
+                    }
+
+                    Flow::Continue
+                },
+            );
+
+        assert_eq!(consumed, vec![0, 1, 2, 3]);
+
+        // Pipelines that are already cancelled don't start any jobs.
+        let prepared = AtomicUsize::new(0);
+        Pipeline::new().cancel_on(cancelled).run(
+            0..100,
+            |job: usize| {
+                prepared.fetch_add(1, Ordering::SeqCst);
+                job
+            },
+            |_| panic!("no jobs should be consumed"),
+        );
+
+        assert_eq!(prepared.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn jobs_are_created_when_needed() {
+        let created = AtomicUsize::new(0);
+        let mut consumed = 0;
+
+        let jobs = (0..50).inspect(|_| {
+            created.fetch_add(1, Ordering::SeqCst);
+        });
+
+        Pipeline::new().workers(2).in_flight(2).run(
+            jobs,
+            |job| job,
+            |_| {
+                // Only jobs that are in flight or consumed have been created.
+                assert!(created.load(Ordering::SeqCst) <= consumed + 2);
+
+                consumed += 1;
+                Flow::Continue
+            },
+        );
+
+        assert_eq!(consumed, 50);
+    }
+}
@@ -0,0 +1,187 @@
+//! Minimal support for writing Roblox XML model files (`.rbxmx`), which can be
+//! inserted directly into Roblox Studio.
+
+use std::io::{self, Write};
+
+/// An instance to write into a model file.
+pub(crate) struct Instance {
+    pub class_name: String,
+    pub properties: Vec<(String, Property)>,
+    pub children: Vec<Instance>,
+}
+
+impl Instance {
+    pub fn new(class_name: &str, name: &str) -> Self {
+        Self {
+            class_name: class_name.to_owned(),
+            properties: vec![("Name".to_owned(), Property::String(name.to_owned()))],
+            children: Vec::new(),
+        }
+    }
+
+    pub fn with_property(mut self, name: &str, value: Property) -> Self {
+        self.properties.push((name.to_owned(), value));
+        self
+    }
+
+    pub fn with_child(mut self, child: Instance) -> Self {
+        self.children.push(child);
+        self
+    }
+}
+
+/// The subset of property types that Tarmac needs to write.
+pub(crate) enum Property {
+    String(String),
+    ProtectedString(String),
+    Content(String),
+    Bool(bool),
+    Float(f32),
+    Token(u32),
+    Vector2(f32, f32),
+    UDim2((f32, i32), (f32, i32)),
+}
+
+/// Writes a model file containing the given instances at its root.
+pub(crate) fn write_model<W: Write>(output: &mut W, instances: &[Instance]) -> io::Result<()> {
+    writeln!(output, "<roblox version=\"4\">")?;
+
+    let mut next_referent = 0;
+    for instance in instances {
+        write_instance(output, instance, 1, &mut next_referent)?;
+    }
+
+    writeln!(output, "</roblox>")
+}
+
+fn write_instance<W: Write>(
+    output: &mut W,
+    instance: &Instance,
+    depth: usize,
+    next_referent: &mut usize,
+) -> io::Result<()> {
+    let indent = "\t".repeat(depth);
+
+    writeln!(
+        output,
+        "{}<Item class=\"{}\" referent=\"RBX{}\">",
+        indent,
+        escape(&instance.class_name),
+        next_referent
+    )?;
+    *next_referent += 1;
+
+    writeln!(output, "{}\t<Properties>", indent)?;
+
+    for (name, value) in &instance.properties {
+        write!(output, "{}\t\t", indent)?;
+        write_property(output, name, value)?;
+        writeln!(output)?;
+    }
+
+    writeln!(output, "{}\t</Properties>", indent)?;
+
+    for child in &instance.children {
+        write_instance(output, child, depth + 1, next_referent)?;
+    }
+
+    writeln!(output, "{}</Item>", indent)
+}
+
+fn write_property<W: Write>(output: &mut W, name: &str, value: &Property) -> io::Result<()> {
+    let name = escape(name);
+
+    match value {
+        Property::String(value) => write!(
+            output,
+            "<string name=\"{}\">{}</string>",
+            name,
+            escape(value)
+        ),
+        Property::ProtectedString(value) => write!(
+            output,
+            "<ProtectedString name=\"{}\"><![CDATA[{}]]></ProtectedString>",
+            name,
+            // CDATA sections can't contain their own terminator, so we split
+            // it across two sections.
+            value.replace("]]>", "]]]]><![CDATA[>")
+        ),
+        Property::Content(url) => write!(
+            output,
+            "<Content name=\"{}\"><url>{}</url></Content>",
+            name,
+            escape(url)
+        ),
+        Property::Bool(value) => write!(output, "<bool name=\"{}\">{}</bool>", name, value),
+        Property::Float(value) => write!(output, "<float name=\"{}\">{}</float>", name, value),
+        Property::Token(value) => write!(output, "<token name=\"{}\">{}</token>", name, value),
+        Property::Vector2(x, y) => write!(
+            output,
+            "<Vector2 name=\"{}\"><X>{}</X><Y>{}</Y></Vector2>",
+            name, x, y
+        ),
+        Property::UDim2((xs, xo), (ys, yo)) => write!(
+            output,
+            "<UDim2 name=\"{}\"><XS>{}</XS><XO>{}</XO><YS>{}</YS><YO>{}</YO></UDim2>",
+            name, xs, xo, ys, yo
+        ),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn module_script() {
+        let module = Instance::new("ModuleScript", "Assets")
+            .with_property("Source", Property::ProtectedString("return {}".to_owned()));
+
+        let mut output = Vec::new();
+        write_model(&mut output, &[module]).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "<roblox version=\"4\">\n\
+             \t<Item class=\"ModuleScript\" referent=\"RBX0\">\n\
+             \t\t<Properties>\n\
+             \t\t\t<string name=\"Name\">Assets</string>\n\
+             \t\t\t<ProtectedString name=\"Source\"><![CDATA[return {}]]></ProtectedString>\n\
+             \t\t</Properties>\n\
+             \t</Item>\n\
+             </roblox>\n"
+        );
+    }
+
+    #[test]
+    fn nested_instances() {
+        let frame = Instance::new("Frame", "Parent")
+            .with_property("Size", Property::UDim2((1.0, 0), (0.0, 20)))
+            .with_child(
+                Instance::new("ImageLabel", "Child")
+                    .with_property("Image", Property::Content("rbxassetid://1".to_owned())),
+            );
+
+        let mut output = Vec::new();
+        write_model(&mut output, &[frame]).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output
+            .contains("<UDim2 name=\"Size\"><XS>1</XS><XO>0</XO><YS>0</YS><YO>20</YO></UDim2>"));
+        assert!(output.contains("\t\t<Item class=\"ImageLabel\" referent=\"RBX1\">"));
+        assert!(output.contains("<Content name=\"Image\"><url>rbxassetid://1</url></Content>"));
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
@@ -0,0 +1,567 @@
+//! Includes that refer to Tarmac projects kept outside of the current one,
+//! like a UI kit maintained in its own git repository.
+//!
+//! Remote includes are fetched into the user's cache directory, so they're
+//! shared between projects and never written into the project that includes
+//! them. Their assets are still named as if they were in a `.tarmac-includes`
+//! folder next to the config that includes them, so that the names don't
+//! depend on where the cache is.
+
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    path::{Component, Path, PathBuf},
+    process::{Command, Output},
+};
+
+use flate2::read::GzDecoder;
+use fs_err as fs;
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+use tar::Archive;
+use thiserror::Error;
+
+/// The folder next to an including config that the assets of its remote
+/// includes are named as if they were in. Nothing is written there, but input
+/// discovery doesn't search folders with this name, since older versions of
+/// Tarmac fetched remote includes into them.
+pub static REMOTE_INCLUDES_FOLDER: &str = ".tarmac-includes";
+
+/// The folder in the platform's cache directory that remote includes are
+/// fetched into, like `~/.cache/tarmac/includes` on Linux.
+static CACHE_FOLDER: &str = "tarmac/includes";
+
+/// The file in a git include's checkout that records which commit was checked
+/// out, so that includes pinned to a commit don't need to be fetched again.
+static GIT_REV_FILENAME: &str = "tarmac-rev";
+
+/// The kinds of git URLs that includes can use. Other transports, like `ext::`
+/// or `file://`, can run commands or read files on the machine that syncs.
+static GIT_URL_PREFIXES: &[&str] = &["https://", "ssh://", "git@"];
+
+/// The first bytes of a gzip stream.
+static GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// A remote include that has been fetched into the cache.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchedInclude {
+    /// The folder in the cache that the include was fetched into.
+    pub folder: PathBuf,
+
+    /// The folder that the include's files are named as if they were in,
+    /// inside the `.tarmac-includes` folder next to the including config.
+    pub name_folder: PathBuf,
+}
+
+impl FetchedInclude {
+    fn new(config_folder: &Path, url: &str, folder: PathBuf) -> Self {
+        Self {
+            folder,
+            name_folder: config_folder
+                .join(REMOTE_INCLUDES_FOLDER)
+                .join(cache_name(url)),
+        }
+    }
+
+    /// The path that a file inside this include is named as if it were at, or
+    /// None if the file isn't inside of it.
+    pub fn name_path(&self, path: &Path) -> Option<PathBuf> {
+        let relative = path.strip_prefix(&self.folder).ok()?;
+        Some(self.name_folder.join(relative))
+    }
+}
+
+/// The path that the file at the given path is named as if it were at, once
+/// every remote include it's in has been accounted for.
+pub fn name_path(fetched: &[FetchedInclude], path: &Path) -> PathBuf {
+    let mut path = path.to_owned();
+
+    // The name folders of includes fetched by other remote includes are inside
+    // those includes' folders, so a path can need more than one step.
+    while let Some(named) = fetched.iter().find_map(|include| include.name_path(&path)) {
+        path = named;
+    }
+
+    path
+}
+
+/// Fetches the given revision of a git repository for the config in the given
+/// folder.
+///
+/// Revisions that are commit hashes are only fetched once. Branches and tags
+/// can move, so they're fetched again every time.
+pub fn fetch_git(
+    config_folder: &Path,
+    url: &str,
+    rev: &str,
+) -> Result<FetchedInclude, RemoteIncludeError> {
+    check_git_source(url, rev)?;
+
+    let checkout = cache_folder()?
+        .join(cache_name(url))
+        .join(&hash(rev.as_bytes())[..16]);
+    let git_folder = checkout.join(".git");
+    let rev_path = git_folder.join(GIT_REV_FILENAME);
+
+    if is_commit_hash(rev) {
+        match fs::read_to_string(&rev_path) {
+            Ok(checked_out) if checked_out.eq_ignore_ascii_case(rev) => {
+                log::trace!("Using {} at {} from {}", url, rev, checkout.display());
+                return Ok(FetchedInclude::new(config_folder, url, checkout));
+            }
+            Ok(_) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    log::info!("Fetching {} at {}", url, rev);
+
+    if !git_folder.is_dir() {
+        fs::create_dir_all(&checkout)?;
+        git(&checkout, &["init", "--quiet"], url, rev)?;
+    }
+
+    let fetched = git(
+        &checkout,
+        &["fetch", "--quiet", "--depth", "1", "--", url, rev],
+        url,
+        rev,
+    )
+    .and_then(|()| {
+        git(
+            &checkout,
+            &["checkout", "--quiet", "--force", "FETCH_HEAD"],
+            url,
+            rev,
+        )
+    });
+
+    // A branch or tag that was checked out before can still be used when it
+    // can't be fetched again, like when working offline.
+    match fetched {
+        Ok(()) => fs::write(&rev_path, rev)?,
+        Err(err) if rev_path.is_file() => log::warn!(
+            "{}. Using the version of {} that was fetched before.",
+            err,
+            url
+        ),
+        Err(err) => return Err(err),
+    }
+
+    Ok(FetchedInclude::new(config_folder, url, checkout))
+}
+
+/// Downloads and extracts an archive in tar format for the config in the
+/// given folder. The archive's contents must have the given SHA-256 hash.
+///
+/// Archives are kept by their hash, so each version of an archive is only
+/// downloaded once.
+pub fn fetch_archive(
+    client: &Client,
+    config_folder: &Path,
+    url: &str,
+    sha256: &str,
+) -> Result<FetchedInclude, RemoteIncludeError> {
+    if !url.starts_with("https://") {
+        return Err(RemoteIncludeError::InsecureUrl {
+            url: url.to_owned(),
+        });
+    }
+
+    if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(RemoteIncludeError::InvalidSha256 {
+            url: url.to_owned(),
+            sha256: sha256.to_owned(),
+        });
+    }
+
+    let sha256 = sha256.to_ascii_lowercase();
+    let includes_folder = cache_folder()?.join(cache_name(url));
+    let target = includes_folder.join(&sha256[..16]);
+
+    if target.is_dir() {
+        log::trace!("Using {} from {}", url, target.display());
+        return Ok(FetchedInclude::new(config_folder, url, target));
+    }
+
+    log::info!("Downloading {}", url);
+
+    let download_error = |source| RemoteIncludeError::Download {
+        url: url.to_owned(),
+        source,
+    };
+
+    let mut response = client
+        .get(url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(download_error)?;
+
+    fs::create_dir_all(&includes_folder)?;
+
+    let archive_path = includes_folder.join(format!("{}.tar", &sha256[..16]));
+    let mut archive = fs::File::create(&archive_path)?;
+    response.copy_to(&mut archive).map_err(download_error)?;
+    drop(archive);
+
+    let extracted = extract_archive(&archive_path, &sha256, &target);
+    fs::remove_file(&archive_path)?;
+
+    extracted.map_err(|source| match source {
+        ExtractError::Checksum { actual } => RemoteIncludeError::ChecksumMismatch {
+            url: url.to_owned(),
+            expected: sha256.clone(),
+            actual,
+        },
+        ExtractError::Io(source) => RemoteIncludeError::Extract {
+            url: url.to_owned(),
+            source,
+        },
+    })?;
+
+    Ok(FetchedInclude::new(config_folder, url, target))
+}
+
+enum ExtractError {
+    Checksum { actual: String },
+    Io(io::Error),
+}
+
+impl From<io::Error> for ExtractError {
+    fn from(err: io::Error) -> Self {
+        ExtractError::Io(err)
+    }
+}
+
+/// Checks that the archive at the given path has the given SHA-256 hash, then
+/// extracts it into the given folder, which is only created once the archive
+/// has been extracted completely. Archives can be compressed with gzip.
+fn extract_archive(archive_path: &Path, sha256: &str, target: &Path) -> Result<(), ExtractError> {
+    let mut file = fs::File::open(archive_path)?;
+
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    let actual = format!("{:x}", hasher.finalize());
+
+    if actual != sha256 {
+        return Err(ExtractError::Checksum { actual });
+    }
+
+    file.seek(SeekFrom::Start(0))?;
+    let mut magic = [0; 2];
+    let gzipped = file.read_exact(&mut magic).is_ok() && magic == GZIP_MAGIC;
+    file.seek(SeekFrom::Start(0))?;
+
+    // The archive is extracted next to its final location, which it's only
+    // moved into once it has been extracted completely.
+    let mut partial = target.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+
+    if partial.exists() {
+        fs::remove_dir_all(&partial)?;
+    }
+    fs::create_dir_all(&partial)?;
+
+    // Entries that would be written outside of the folder are skipped.
+    if gzipped {
+        Archive::new(GzDecoder::new(file)).unpack(&partial)?;
+    } else {
+        Archive::new(file).unpack(&partial)?;
+    }
+
+    std::fs::rename(&partial, target)?;
+
+    Ok(())
+}
+
+/// Joins a path from an include onto the folder that the include was fetched
+/// into, making sure that it stays inside of that folder.
+pub fn join_within(folder: &Path, path: &Path) -> Result<PathBuf, RemoteIncludeError> {
+    let stays_inside = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+
+    if stays_inside {
+        Ok(folder.join(path))
+    } else {
+        Err(RemoteIncludeError::PathOutsideInclude {
+            path: path.to_owned(),
+        })
+    }
+}
+
+/// The folder that remote includes are fetched into.
+fn cache_folder() -> Result<PathBuf, RemoteIncludeError> {
+    dirs::cache_dir()
+        .map(|folder| folder.join(CACHE_FOLDER))
+        .ok_or(RemoteIncludeError::NoCacheFolder)
+}
+
+/// Makes sure that a git include's URL and revision can't be mistaken for
+/// options by git, and that the URL uses a transport we allow.
+fn check_git_source(url: &str, rev: &str) -> Result<(), RemoteIncludeError> {
+    if url.starts_with('-')
+        || !GIT_URL_PREFIXES
+            .iter()
+            .any(|prefix| url.starts_with(prefix))
+    {
+        return Err(RemoteIncludeError::UnsupportedGitUrl {
+            url: url.to_owned(),
+        });
+    }
+
+    if rev.is_empty() || rev.starts_with('-') {
+        return Err(RemoteIncludeError::InvalidRev {
+            url: url.to_owned(),
+            rev: rev.to_owned(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Whether a revision is the full hash of a commit, which always refers to the
+/// same files, as opposed to a branch or tag that can move.
+fn is_commit_hash(rev: &str) -> bool {
+    (rev.len() == 40 || rev.len() == 64) && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn git(checkout: &Path, args: &[&str], url: &str, rev: &str) -> Result<(), RemoteIncludeError> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(checkout)
+        .args(args)
+        .output()
+        .map_err(|source| RemoteIncludeError::MissingGit { source })?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(RemoteIncludeError::Git {
+            url: url.to_owned(),
+            rev: rev.to_owned(),
+            message: stderr_message(&output),
+        })
+    }
+}
+
+fn stderr_message(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).trim().to_owned()
+}
+
+fn hash(contents: &[u8]) -> String {
+    blake3::hash(contents).to_hex().as_str().to_owned()
+}
+
+/// The name of the folder that the include from the given URL is kept in: the
+/// last part of the URL for people to recognize it by, followed by a hash of
+/// the whole URL to tell apart includes whose URLs end the same way.
+fn cache_name(url: &str) -> String {
+    let last_part = url
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+
+    let readable: String = last_part
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+
+    let hash = hash(url.as_bytes());
+
+    if readable.is_empty() {
+        hash[..16].to_owned()
+    } else {
+        format!("{}-{}", readable, &hash[..16])
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RemoteIncludeError {
+    #[error("Couldn't fetch {url} at revision {rev}: {message}")]
+    Git {
+        url: String,
+        rev: String,
+        message: String,
+    },
+
+    #[error("Couldn't download {url}")]
+    Download { url: String, source: reqwest::Error },
+
+    #[error("Couldn't extract the archive downloaded from {url}")]
+    Extract { url: String, source: io::Error },
+
+    #[error("The archive downloaded from {url} has the SHA-256 hash {actual}, but its include expects {expected}")]
+    ChecksumMismatch {
+        url: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error(
+        "The sha256 of the include from {url} must be 64 hexadecimal digits, but it's {sha256}"
+    )]
+    InvalidSha256 { url: String, sha256: String },
+
+    #[error("Included archives must be downloaded over HTTPS, but {url} isn't an HTTPS URL")]
+    InsecureUrl { url: String },
+
+    #[error(
+        "Included git repositories must use an https://, ssh://, or git@ URL, but {url} doesn't"
+    )]
+    UnsupportedGitUrl { url: String },
+
+    #[error("{rev} isn't a valid revision of {url}")]
+    InvalidRev { url: String, rev: String },
+
+    #[error("The path {} of a remote include must be relative and stay inside of the include", .path.display())]
+    PathOutsideInclude { path: PathBuf },
+
+    #[error("Couldn't find a cache directory to fetch remote includes into")]
+    NoCacheFolder,
+
+    #[error("Fetching git includes requires git to be installed")]
+    MissingGit { source: io::Error },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, process};
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    #[test]
+    fn cache_names() {
+        let git = cache_name("https://github.com/studio/ui-kit.git");
+        assert!(git.starts_with("ui-kit-"));
+        assert_eq!(git.len(), "ui-kit-".len() + 16);
+
+        let archive = cache_name("https://example.com/icons/v2/icons.tar.gz");
+        assert!(archive.starts_with("icons-"));
+        assert_ne!(
+            archive,
+            cache_name("https://example.com/icons/v3/icons.tar.gz")
+        );
+
+        assert_eq!(
+            cache_name("https://example.com/").len(),
+            "example".len() + 17
+        );
+    }
+
+    #[test]
+    fn git_sources_are_checked() {
+        let url = "https://github.com/studio/ui-kit.git";
+        assert!(check_git_source(url, "v1.2.0").is_ok());
+        assert!(check_git_source("git@github.com:studio/ui-kit.git", "main").is_ok());
+        assert!(check_git_source("ssh://git@github.com/studio/ui-kit.git", "main").is_ok());
+
+        for bad_url in &[
+            "--upload-pack=touch /tmp/pwned",
+            "ext::sh -c touch% /tmp/pwned",
+            "file:///etc",
+            "http://github.com/studio/ui-kit.git",
+        ] {
+            assert!(matches!(
+                check_git_source(bad_url, "main"),
+                Err(RemoteIncludeError::UnsupportedGitUrl { .. })
+            ));
+        }
+
+        assert!(matches!(
+            check_git_source(url, "--output=/tmp/pwned"),
+            Err(RemoteIncludeError::InvalidRev { .. })
+        ));
+
+        let folder = Path::new("checkout");
+        assert_eq!(
+            join_within(folder, Path::new("assets/ui")).unwrap(),
+            folder.join("assets/ui")
+        );
+        assert!(join_within(folder, Path::new("../secrets")).is_err());
+        assert!(join_within(folder, Path::new("/etc")).is_err());
+    }
+
+    #[test]
+    fn only_commit_hashes_are_pinned() {
+        assert!(is_commit_hash("0123456789abcdef0123456789abcdef01234567"));
+        assert!(is_commit_hash("0123456789ABCDEF0123456789ABCDEF01234567"));
+        assert!(!is_commit_hash("0123456"));
+        assert!(!is_commit_hash("v1.4.0"));
+        assert!(!is_commit_hash("main"));
+    }
+
+    #[test]
+    fn fetched_files_are_named_inside_the_project() {
+        let kit = FetchedInclude {
+            folder: PathBuf::from("/cache/ui-kit"),
+            name_folder: PathBuf::from("/game/.tarmac-includes/ui-kit"),
+        };
+        let icons = FetchedInclude {
+            folder: PathBuf::from("/cache/icons"),
+            name_folder: PathBuf::from("/cache/ui-kit/.tarmac-includes/icons"),
+        };
+        let fetched = [kit, icons];
+
+        assert_eq!(
+            name_path(&fetched, Path::new("/cache/ui-kit/button.png")),
+            Path::new("/game/.tarmac-includes/ui-kit/button.png")
+        );
+        assert_eq!(
+            name_path(&fetched, Path::new("/cache/icons/play.png")),
+            Path::new("/game/.tarmac-includes/ui-kit/.tarmac-includes/icons/play.png")
+        );
+        assert_eq!(
+            name_path(&fetched, Path::new("/game/logo.png")),
+            Path::new("/game/logo.png")
+        );
+    }
+
+    #[test]
+    fn archives_are_checked_and_extracted() {
+        let folder = env::temp_dir().join(format!("tarmac-archive-{}", process::id()));
+        fs::create_dir_all(&folder).unwrap();
+
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let contents = b"name = \"icons\"\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "icons/tarmac.toml", &contents[..])
+            .unwrap();
+        let archive = builder.into_inner().unwrap().finish().unwrap();
+
+        let archive_path = folder.join("icons.tar.gz");
+        fs::write(&archive_path, &archive).unwrap();
+        let sha256 = format!("{:x}", Sha256::digest(&archive));
+
+        let target = folder.join("extracted");
+        assert!(matches!(
+            extract_archive(&archive_path, &"0".repeat(64), &target),
+            Err(ExtractError::Checksum { actual }) if actual == sha256
+        ));
+        assert!(!target.exists());
+
+        assert!(extract_archive(&archive_path, &sha256, &target).is_ok());
+        assert_eq!(
+            fs::read(target.join("icons/tarmac.toml")).unwrap(),
+            contents
+        );
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
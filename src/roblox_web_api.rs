@@ -1,23 +1,342 @@
 use std::{
     borrow::Cow,
+    env,
     fmt::{self, Write},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
 };
 
 use reqwest::{
-    header::{HeaderValue, COOKIE},
-    Client, Request, Response, StatusCode,
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE, COOKIE, RETRY_AFTER, USER_AGENT},
+    multipart::Form,
+    Client, Proxy, Request, Response, StatusCode, Url,
 };
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::{
+    file_contents::FileContents,
+    http_fixtures::{FixtureError, FixtureMode, Fixtures},
+    upload_progress::{upload_body, upload_part},
+};
+
+/// The contents of an asset to upload. Every clone shares the same bytes, so
+/// retrying a request or passing an upload between sync backends doesn't copy
+/// the whole asset each time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadContents {
+    /// Contents that are already in memory, like a packed spritesheet, or
+    /// mapped into memory, like a large input file.
+    Memory(Arc<FileContents>),
+
+    /// Contents that are streamed from a file as they're sent, so large
+    /// assets are never held in memory all at once.
+    ///
+    /// The file is read again for every request, so it's checked against the
+    /// length it had when the upload was prepared, and against the hash of
+    /// the contents it's expected to have if there is one. Files that changed
+    /// fail to be read instead of sending something else.
+    File {
+        path: PathBuf,
+        len: u64,
+        hash: Option<String>,
+    },
+}
+
+impl UploadContents {
+    /// Refers to the current contents of the file at the given path, which
+    /// must have the given blake3 hash when they're read, if there is one.
+    pub fn from_file(path: &Path, hash: Option<&str>) -> io::Result<Self> {
+        let len = fs_err::metadata(path)?.len();
+
+        Ok(UploadContents::File {
+            path: path.to_owned(),
+            len,
+            hash: hash.map(ToOwned::to_owned),
+        })
+    }
+
+    pub fn len(&self) -> u64 {
+        match self {
+            UploadContents::Memory(contents) => contents.len() as u64,
+            UploadContents::File { len, .. } => *len,
+        }
+    }
+
+    /// Whether the contents are mapped from a file, which might change
+    /// underneath them.
+    pub fn is_mapped(&self) -> bool {
+        match self {
+            UploadContents::Memory(contents) => contents.is_mapped(),
+            UploadContents::File { .. } => false,
+        }
+    }
+
+    /// The contents, if they're in memory.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            UploadContents::Memory(contents) => Some(contents),
+            UploadContents::File { .. } => None,
+        }
+    }
+
+    /// All of the contents at once, reading them if they're in a file.
+    pub fn read(&self) -> io::Result<Cow<'_, [u8]>> {
+        match self {
+            UploadContents::Memory(contents) => Ok(Cow::Borrowed(contents)),
+            UploadContents::File { path, len, hash } => {
+                let contents = fs_err::read(path)?;
+                check_file_contents(path, *len, hash.as_deref(), &contents)?;
+
+                Ok(Cow::Owned(contents))
+            }
+        }
+    }
+
+    /// Opens a reader that streams the contents from the start.
+    pub fn reader(&self) -> io::Result<Box<dyn Read + Send>> {
+        match self {
+            UploadContents::Memory(contents) => Ok(Box::new(SharedReader {
+                contents: Arc::clone(contents),
+                position: 0,
+            })),
+            UploadContents::File { path, len, hash } => Ok(Box::new(CheckedFileReader {
+                file: fs_err::File::open(path)?,
+                path: path.clone(),
+                len: *len,
+                hash: hash.clone(),
+                hasher: blake3::Hasher::new(),
+                read: 0,
+            })),
+        }
+    }
+}
+
+/// Makes sure that a file that was read for an upload still has the contents
+/// the upload was prepared with.
+fn check_file_contents(
+    path: &Path,
+    len: u64,
+    hash: Option<&str>,
+    contents: &[u8],
+) -> io::Result<()> {
+    let hash_matches = hash.map_or(true, |hash| {
+        blake3::hash(contents).to_hex().as_str() == hash
+    });
+
+    if contents.len() as u64 == len && hash_matches {
+        Ok(())
+    } else {
+        Err(changed_file_error(path))
+    }
+}
+
+fn changed_file_error(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} changed while it was being uploaded", path.display()),
+    )
+}
+
+/// Streams a file while hashing it, failing at the end of the file if it
+/// doesn't have the contents the upload was prepared with.
+struct CheckedFileReader {
+    file: fs_err::File,
+    path: PathBuf,
+    len: u64,
+    hash: Option<String>,
+    hasher: blake3::Hasher,
+    read: u64,
+}
+
+impl Read for CheckedFileReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.file.read(buf)?;
+        self.read += read as u64;
+
+        // Requests are sent with the length the file had when the upload was
+        // prepared, so a file that grew can't be sent either.
+        if self.read > self.len {
+            return Err(changed_file_error(&self.path));
+        }
+
+        if read > 0 {
+            self.hasher.update(&buf[..read]);
+            return Ok(read);
+        }
+
+        let hash_matches = self.hash.as_deref().map_or(true, |hash| {
+            self.hasher.finalize().to_hex().as_str() == hash
+        });
+
+        if self.read == self.len && hash_matches {
+            Ok(0)
+        } else {
+            Err(changed_file_error(&self.path))
+        }
+    }
+}
+
+impl From<Vec<u8>> for UploadContents {
+    fn from(contents: Vec<u8>) -> Self {
+        UploadContents::Memory(Arc::new(contents.into()))
+    }
+}
+
+impl From<FileContents> for UploadContents {
+    fn from(contents: FileContents) -> Self {
+        UploadContents::Memory(Arc::new(contents))
+    }
+}
+
+/// Reads contents that are in memory without taking them away from other
+/// requests that send them.
+struct SharedReader {
+    contents: Arc<FileContents>,
+    position: usize,
+}
+
+impl Read for SharedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = (&self.contents[self.position..]).read(buf)?;
+        self.position += read;
+
+        Ok(read)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ImageUploadData<'a> {
-    pub image_data: Cow<'a, [u8]>,
+    pub image_data: UploadContents,
     pub name: &'a str,
     pub description: &'a str,
     pub group_id: Option<u64>,
 }
 
+/// An asset of any supported format to upload through an endpoint that isn't
+/// specific to images.
+#[derive(Debug, Clone)]
+pub struct AssetUploadData<'a> {
+    pub contents: UploadContents,
+    pub name: &'a str,
+    pub description: &'a str,
+    pub format: AssetFormat,
+}
+
+/// The kinds of assets that Tarmac can create on Roblox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AssetKind {
+    Image,
+    Audio,
+    Mesh,
+    Model,
+    Video,
+    Font,
+}
+
+/// The file formats that Tarmac knows how to upload to Roblox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AssetFormat {
+    Png,
+    Jpeg,
+    Ogg,
+    Mp3,
+    Fbx,
+    Obj,
+    Rbxm,
+    Rbxmx,
+    Webm,
+    Mp4,
+    Ttf,
+    Otf,
+}
+
+impl AssetFormat {
+    /// Picks the format of a file based on its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "png" => Some(AssetFormat::Png),
+            "jpg" | "jpeg" => Some(AssetFormat::Jpeg),
+            "ogg" => Some(AssetFormat::Ogg),
+            "mp3" => Some(AssetFormat::Mp3),
+            "fbx" => Some(AssetFormat::Fbx),
+            "obj" => Some(AssetFormat::Obj),
+            "rbxm" => Some(AssetFormat::Rbxm),
+            "rbxmx" => Some(AssetFormat::Rbxmx),
+            "webm" => Some(AssetFormat::Webm),
+            "mp4" => Some(AssetFormat::Mp4),
+            "ttf" => Some(AssetFormat::Ttf),
+            "otf" => Some(AssetFormat::Otf),
+            _ => None,
+        }
+    }
+
+    pub fn kind(self) -> AssetKind {
+        match self {
+            AssetFormat::Png | AssetFormat::Jpeg => AssetKind::Image,
+            AssetFormat::Ogg | AssetFormat::Mp3 => AssetKind::Audio,
+            AssetFormat::Fbx | AssetFormat::Obj => AssetKind::Mesh,
+            AssetFormat::Rbxm | AssetFormat::Rbxmx => AssetKind::Model,
+            AssetFormat::Webm | AssetFormat::Mp4 => AssetKind::Video,
+            AssetFormat::Ttf | AssetFormat::Otf => AssetKind::Font,
+        }
+    }
+
+    pub fn is_image(self) -> bool {
+        self.kind() == AssetKind::Image
+    }
+
+    /// The asset type that Open Cloud creates for files in this format.
+    fn asset_type(self) -> &'static str {
+        match self.kind() {
+            AssetKind::Image => "Image",
+            AssetKind::Audio => "Audio",
+            AssetKind::Mesh | AssetKind::Model => "Model",
+            AssetKind::Video => "Video",
+            AssetKind::Font => "Font",
+        }
+    }
+
+    fn file_name(self) -> &'static str {
+        match self {
+            AssetFormat::Png => "image.png",
+            AssetFormat::Jpeg => "image.jpg",
+            AssetFormat::Ogg => "audio.ogg",
+            AssetFormat::Mp3 => "audio.mp3",
+            AssetFormat::Fbx => "mesh.fbx",
+            AssetFormat::Obj => "mesh.obj",
+            AssetFormat::Rbxm => "model.rbxm",
+            AssetFormat::Rbxmx => "model.rbxmx",
+            AssetFormat::Webm => "video.webm",
+            AssetFormat::Mp4 => "video.mp4",
+            AssetFormat::Ttf => "font.ttf",
+            AssetFormat::Otf => "font.otf",
+        }
+    }
+
+    fn mime_type(self) -> &'static str {
+        match self {
+            AssetFormat::Png => "image/png",
+            AssetFormat::Jpeg => "image/jpeg",
+            AssetFormat::Ogg => "audio/ogg",
+            AssetFormat::Mp3 => "audio/mpeg",
+            AssetFormat::Fbx => "model/fbx",
+            AssetFormat::Obj => "model/obj",
+            AssetFormat::Rbxm => "model/x-rbxm",
+            AssetFormat::Rbxmx => "model/x-rbxmx",
+            AssetFormat::Webm => "video/webm",
+            AssetFormat::Mp4 => "video/mp4",
+            AssetFormat::Ttf => "font/ttf",
+            AssetFormat::Otf => "font/otf",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct UploadResponse {
@@ -36,10 +355,379 @@ struct RawUploadResponse {
     backing_asset_id: Option<u64>,
 }
 
+impl RawUploadResponse {
+    /// Some errors are reported inside the response, even though we received a
+    /// successful HTTP response. This turns those into proper errors.
+    fn into_result(self) -> Result<UploadResponse, RobloxApiError> {
+        match (self.success, self.asset_id, self.backing_asset_id) {
+            (true, Some(asset_id), Some(backing_asset_id)) => Ok(UploadResponse {
+                asset_id,
+                backing_asset_id,
+            }),
+            (true, _, _) => Err(RobloxApiError::ApiError {
+                message: "Upload succeeded, but no asset ID was returned".to_owned(),
+            }),
+            (false, _, _) => Err(RobloxApiError::ApiError {
+                message: self
+                    .message
+                    .unwrap_or_else(|| "Upload failed without an error message".to_owned()),
+            }),
+        }
+    }
+}
+
+/// The body of a request to the legacy audio upload endpoint.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AudioUploadRequest<'a> {
+    name: &'a str,
+    file: String,
+    payment_source: &'static str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group_id: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AudioUploadResponse {
+    id: u64,
+}
+
+/// The user or group that should own an uploaded asset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AssetCreator {
+    User(u64),
+    Group(u64),
+}
+
+impl fmt::Display for AssetCreator {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetCreator::User(id) => write!(formatter, "user {}", id),
+            AssetCreator::Group(id) => write!(formatter, "group {}", id),
+        }
+    }
+}
+
+/// The metadata sent alongside an image to the Open Cloud assets endpoint.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenCloudAssetRequest<'a> {
+    asset_type: &'static str,
+    display_name: &'a str,
+    description: &'a str,
+    creation_context: OpenCloudCreationContext,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenCloudCreationContext {
+    creator: OpenCloudCreator,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenCloudCreator {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group_id: Option<String>,
+}
+
+/// A long-running operation returned by the Open Cloud assets endpoints.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenCloudOperation {
+    path: String,
+
+    #[serde(default)]
+    done: bool,
+
+    response: Option<OpenCloudAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenCloudAsset {
+    asset_id: String,
+}
+
+/// The moderation state of an uploaded asset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ModerationState {
+    Reviewing,
+    Approved,
+    Rejected,
+
+    /// A state that Roblox added after this version of Tarmac, which is
+    /// treated like an asset that's still being reviewed.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenCloudModerationAsset {
+    moderation_result: OpenCloudModerationResult,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenCloudModerationResult {
+    moderation_state: ModerationState,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AssetPermissionsRequest {
+    requests: Vec<AssetPermissionRequest>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AssetPermissionRequest {
+    subject_type: &'static str,
+    subject_id: String,
+    action: &'static str,
+}
+
+/// One page of the assets that a user or group has created.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedAssetsPage {
+    pub data: Vec<CreatedAsset>,
+    pub next_page_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedAsset {
+    pub asset_id: u64,
+    pub name: String,
+
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// The user that a `.ROBLOSECURITY` cookie belongs to.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatedUser {
+    pub id: u64,
+    pub name: String,
+}
+
+/// A description of an Open Cloud API key, as returned by introspecting it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyInfo {
+    pub name: String,
+
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default)]
+    pub expired: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiKeyIntrospectRequest<'a> {
+    api_key: &'a str,
+}
+
+/// How long to wait between checks on an Open Cloud upload operation.
+const OPEN_CLOUD_POLL_DELAY: Duration = Duration::from_secs(1);
+
+/// How many times a single request that was rate limited is sent again before
+/// the rate limit is reported as an error.
+const RATE_LIMIT_RETRIES: usize = 5;
+
+/// The shortest and longest delays kept between requests once Roblox has
+/// started rate limiting us.
+const MIN_THROTTLE: Duration = Duration::from_millis(250);
+const MAX_THROTTLE: Duration = Duration::from_secs(30);
+
+/// How many times to check on an Open Cloud upload operation before giving up.
+const OPEN_CLOUD_POLL_ATTEMPTS: usize = 30;
+
+/// Connection settings for a `RobloxApiClient`.
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// How long to wait for each request before giving up on it.
+    pub timeout: Duration,
+
+    /// The total number of times that requests may be retried after being
+    /// rate limited over the lifetime of the client.
+    pub retry_budget: usize,
+
+    /// The `User-Agent` header sent with every request.
+    pub user_agent: String,
+
+    /// If specified, requests are sent to this URL instead of Roblox, with
+    /// the Roblox service they were meant for as the first segment of their
+    /// path, like `<base-url>/apis/assets/v1/assets`.
+    pub base_url: Option<String>,
+
+    /// The proxies that requests should be sent through.
+    pub proxy: ProxyOptions,
+
+    /// If specified, requests and their responses are recorded to or replayed
+    /// from this fixture file.
+    pub fixtures: Option<(FixtureMode, PathBuf)>,
+}
+
+/// Proxies that a `RobloxApiClient` should send its requests through.
+#[derive(Debug, Clone, Default)]
+pub struct ProxyOptions {
+    /// The proxy used for `http` URLs.
+    pub http: Option<String>,
+
+    /// The proxy used for `https` URLs.
+    pub https: Option<String>,
+
+    /// Hosts that are connected to directly, even if a proxy is set. Entries
+    /// also match subdomains of the given host, and `*` matches every host.
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyOptions {
+    /// Reads proxy settings from the conventional `HTTP_PROXY`, `HTTPS_PROXY`,
+    /// and `NO_PROXY` environment variables, in either upper or lower case.
+    pub fn from_env() -> Self {
+        let var = |name: &str| {
+            env::var(name.to_ascii_uppercase())
+                .or_else(|_| env::var(name))
+                .ok()
+                .filter(|value| !value.is_empty())
+        };
+
+        let no_proxy = var("no_proxy")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(|host| host.trim().to_owned())
+                    .filter(|host| !host.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            http: var("http_proxy"),
+            https: var("https_proxy"),
+            no_proxy,
+        }
+    }
+
+    /// Whether requests to the given host should skip the proxy.
+    fn bypasses(&self, host: &str) -> bool {
+        self.no_proxy.iter().any(|entry| {
+            let entry = entry.trim_start_matches('.');
+
+            entry == "*"
+                || host.eq_ignore_ascii_case(entry)
+                || host
+                    .to_ascii_lowercase()
+                    .ends_with(&format!(".{}", entry.to_ascii_lowercase()))
+        })
+    }
+
+    fn to_proxy(&self) -> Result<Option<Proxy>, RobloxApiError> {
+        let parse = |url: &Option<String>| match url {
+            Some(url) => Url::parse(url)
+                .map(Some)
+                .map_err(|_| RobloxApiError::InvalidProxy { url: url.clone() }),
+            None => Ok(None),
+        };
+
+        let http = parse(&self.http)?;
+        let https = parse(&self.https)?;
+
+        if http.is_none() && https.is_none() {
+            return Ok(None);
+        }
+
+        let options = self.clone();
+        Ok(Some(Proxy::custom(move |url| {
+            if options.bypasses(url.host_str().unwrap_or_default()) {
+                return None;
+            }
+
+            match url.scheme() {
+                "http" => http.clone(),
+                "https" => https.clone(),
+                _ => None,
+            }
+        })))
+    }
+}
+
+impl ClientOptions {
+    /// Builds an HTTP client with these options' timeout, user agent, and
+    /// proxies.
+    pub fn build_client(&self) -> Result<Client, RobloxApiError> {
+        let user_agent = HeaderValue::from_str(&self.user_agent).map_err(|_| {
+            RobloxApiError::InvalidUserAgent {
+                user_agent: self.user_agent.clone(),
+            }
+        })?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, user_agent);
+
+        let mut builder = Client::builder()
+            .timeout(self.timeout)
+            .default_headers(headers);
+
+        if let Some(proxy) = self.proxy.to_proxy()? {
+            builder = builder.proxy(proxy);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            retry_budget: 50,
+            user_agent: format!("tarmac/{}", env!("CARGO_PKG_VERSION")),
+            base_url: None,
+            proxy: ProxyOptions::default(),
+            fixtures: None,
+        }
+    }
+}
+
+/// A client for the Roblox web APIs.
+///
+/// Requests block the thread that makes them. Concurrent uploads come from
+/// threads that each use a clone of one client instead of from an async
+/// runtime, and clones share its connection pool, CSRF token, and rate limiting
+/// state, so those threads still reuse open TLS connections.
+#[derive(Clone)]
 pub struct RobloxApiClient {
     auth_token: Option<String>,
-    csrf_token: Option<HeaderValue>,
     client: Client,
+    base_url: Option<String>,
+    fixtures: Option<Arc<Fixtures>>,
+    state: Arc<Mutex<ClientState>>,
+}
+
+/// The parts of a `RobloxApiClient` that change as requests are made.
+struct ClientState {
+    csrf_token: Option<HeaderValue>,
+
+    /// The delay to keep between requests. This grows whenever we're rate
+    /// limited and shrinks again as requests succeed.
+    throttle: Duration,
+    last_request: Option<Instant>,
+
+    /// How many more times requests may be retried after being rate limited.
+    retry_budget: usize,
 }
 
 impl fmt::Debug for RobloxApiClient {
@@ -49,16 +737,49 @@ impl fmt::Debug for RobloxApiClient {
 }
 
 impl RobloxApiClient {
-    pub fn new(auth_token: Option<String>) -> Self {
-        Self {
+    pub fn new(
+        auth_token: Option<String>,
+        options: &ClientOptions,
+    ) -> Result<Self, RobloxApiError> {
+        Self::with_client(auth_token, options.build_client()?, options)
+    }
+
+    /// Creates a client that sends its requests with the given HTTP client,
+    /// which should have been built from the same options, so that it shares
+    /// a connection pool with requests made before it was created.
+    pub fn with_client(
+        auth_token: Option<String>,
+        client: Client,
+        options: &ClientOptions,
+    ) -> Result<Self, RobloxApiError> {
+        let fixtures = match &options.fixtures {
+            Some((mode, path)) => Some(Arc::new(Fixtures::new(*mode, path)?)),
+            None => None,
+        };
+
+        Ok(Self {
             auth_token,
-            csrf_token: None,
-            client: Client::new(),
-        }
+            client,
+            base_url: options.base_url.clone(),
+            fixtures,
+            state: Arc::new(Mutex::new(ClientState {
+                csrf_token: None,
+                throttle: Duration::from_secs(0),
+                last_request: None,
+                retry_budget: options.retry_budget,
+            })),
+        })
+    }
+
+    /// The HTTP client that this client sends requests with. Requests to
+    /// services other than Roblox, like upload caches, can be sent with it to
+    /// reuse its connection pool, proxies, timeout, and user agent.
+    pub fn http_client(&self) -> &Client {
+        &self.client
     }
 
     pub fn download_image(&mut self, id: u64) -> Result<Vec<u8>, RobloxApiError> {
-        let url = format!("https://roblox.com/asset?id={}", id);
+        let url = self.url("www", &format!("/asset?id={}", id));
 
         let mut response =
             self.execute_with_csrf_retry(|client| Ok(client.get(&url).build()?))?;
@@ -69,6 +790,27 @@ impl RobloxApiClient {
         Ok(buffer)
     }
 
+    /// Download the latest version of an asset from the asset delivery
+    /// service, as it would be served to a game.
+    pub fn download_asset(&mut self, id: u64) -> Result<Vec<u8>, RobloxApiError> {
+        let url = self.url("assetdelivery", &format!("/v1/asset/?id={}", id));
+
+        let mut response =
+            self.execute_with_csrf_retry(|client| Ok(client.get(&url).build()?))?;
+
+        if !response.status().is_success() {
+            return Err(RobloxApiError::ResponseError {
+                status: response.status(),
+                body: response.text()?,
+            });
+        }
+
+        let mut buffer = Vec::new();
+        response.copy_to(&mut buffer)?;
+
+        Ok(buffer)
+    }
+
     /// Upload an image, retrying if the asset endpoint determines that the
     /// asset's name is inappropriate. The asset's name will be replaced with a
     /// generic known-good string.
@@ -78,41 +820,29 @@ impl RobloxApiClient {
     ) -> Result<UploadResponse, RobloxApiError> {
         let response = self.upload_image_raw(&data)?;
 
-        // Some other errors will be reported inside the response, even
-        // though we received a successful HTTP response.
-        if response.success {
-            let asset_id = response.asset_id.unwrap();
-            let backing_asset_id = response.backing_asset_id.unwrap();
+        // There are no status codes for this API, so we pattern match on the
+        // returned error message.
+        //
+        // If the error message text mentions something being inappropriate,
+        // we assume the title was problematic and attempt to re-upload.
+        let name_moderated = !response.success
+            && matches!(&response.message, Some(message) if message.contains("inappropriate"));
 
-            Ok(UploadResponse {
-                asset_id,
-                backing_asset_id,
-            })
-        } else {
-            let message = response.message.unwrap();
-
-            // There are no status codes for this API, so we pattern match
-            // on the returned error message.
-            //
-            // If the error message text mentions something being
-            // inappropriate, we assume the title was problematic and
-            // attempt to re-upload.
-            if message.contains("inappropriate") {
-                log::warn!(
-                    "Image name '{}' was moderated, retrying with different name...",
-                    data.name
-                );
-
-                let new_data = ImageUploadData {
-                    name: "image",
-                    ..data
-                };
+        if name_moderated {
+            log::warn!(
+                "Image name '{}' was moderated, retrying with different name...",
+                data.name
+            );
 
-                return self.upload_image(new_data);
-            } else {
-                Err(RobloxApiError::ApiError { message })
-            }
+            let new_data = ImageUploadData {
+                name: "image",
+                ..data
+            };
+
+            return self.upload_image(new_data);
         }
+
+        response.into_result()
     }
 
     /// Upload an image, returning an error if anything goes wrong.
@@ -120,23 +850,7 @@ impl RobloxApiClient {
         &mut self,
         data: ImageUploadData,
     ) -> Result<UploadResponse, RobloxApiError> {
-        let response = self.upload_image_raw(&data)?;
-
-        // Some other errors will be reported inside the response, even
-        // though we received a successful HTTP response.
-        if response.success {
-            let asset_id = response.asset_id.unwrap();
-            let backing_asset_id = response.backing_asset_id.unwrap();
-
-            Ok(UploadResponse {
-                asset_id,
-                backing_asset_id,
-            })
-        } else {
-            let message = response.message.unwrap();
-
-            Err(RobloxApiError::ApiError { message })
-        }
+        self.upload_image_raw(&data)?.into_result()
     }
 
     /// Upload an image, returning the raw response returned by the endpoint,
@@ -145,7 +859,7 @@ impl RobloxApiClient {
         &mut self,
         data: &ImageUploadData,
     ) -> Result<RawUploadResponse, RobloxApiError> {
-        let mut url = "https://data.roblox.com/data/upload/json?assetTypeId=13".to_owned();
+        let mut url = self.url("data", "/data/upload/json?assetTypeId=13");
 
         if let Some(group_id) = data.group_id {
             write!(url, "&groupId={}", group_id).unwrap();
@@ -155,7 +869,7 @@ impl RobloxApiClient {
             Ok(client
                 .post(&url)
                 .query(&[("name", data.name), ("description", data.description)])
-                .body(data.image_data.clone().into_owned())
+                .body(upload_body(data.name, &data.image_data)?)
                 .build()?)
         })?;
 
@@ -175,28 +889,365 @@ impl RobloxApiClient {
         }
     }
 
+    /// Upload an audio file through the legacy audio endpoint, returning the
+    /// ID of the new audio asset.
+    pub fn upload_audio(
+        &mut self,
+        data: &AssetUploadData,
+        group_id: Option<u64>,
+    ) -> Result<u64, RobloxApiError> {
+        // Audio is embedded in the request as base64, so it can't be streamed.
+        let contents = data
+            .contents
+            .read()
+            .map_err(|source| RobloxApiError::ReadContents {
+                name: data.name.to_owned(),
+                source,
+            })?;
+
+        let request = AudioUploadRequest {
+            name: data.name,
+            file: base64::encode(&contents),
+            payment_source: if group_id.is_some() { "Group" } else { "User" },
+            group_id,
+        };
+
+        // Audio is sent inside a JSON body, so that whole body is what we
+        // report the progress of.
+        let request = UploadContents::from(serde_json::to_vec(&request).unwrap());
+
+        let url = self.url("publish", "/v1/audio");
+        let mut response = self.execute_with_csrf_retry(|client| {
+            Ok(client
+                .post(&url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(upload_body(data.name, &request)?)
+                .build()?)
+        })?;
+
+        let body = response.text()?;
+
+        if response.status().is_success() {
+            match serde_json::from_str::<AudioUploadResponse>(&body) {
+                Ok(response) => Ok(response.id),
+                Err(source) => Err(RobloxApiError::BadResponseJson { body, source }),
+            }
+        } else {
+            Err(RobloxApiError::ResponseError {
+                status: response.status(),
+                body,
+            })
+        }
+    }
+
+    /// Upload a model file through the legacy model endpoint, returning the ID
+    /// of the new model asset.
+    pub fn upload_model(
+        &mut self,
+        data: &AssetUploadData,
+        group_id: Option<u64>,
+    ) -> Result<u64, RobloxApiError> {
+        let mut url = self.url("data", "/Data/Upload.ashx?assetid=0&type=Model");
+
+        if let Some(group_id) = group_id {
+            write!(url, "&groupId={}", group_id).unwrap();
+        }
+
+        let mut response = self.execute_with_csrf_retry(|client| {
+            Ok(client
+                .post(&url)
+                .query(&[
+                    ("name", data.name),
+                    ("description", data.description),
+                    ("ispublic", "false"),
+                    ("allowComments", "false"),
+                ])
+                .body(upload_body(data.name, &data.contents)?)
+                .build()?)
+        })?;
+
+        let body = response.text()?;
+
+        // This endpoint responds with the new asset's ID as plain text.
+        if response.status().is_success() {
+            body.trim().parse().map_err(|_| RobloxApiError::ApiError {
+                message: format!("Model upload returned invalid asset ID {}", body),
+            })
+        } else {
+            Err(RobloxApiError::ResponseError {
+                status: response.status(),
+                body,
+            })
+        }
+    }
+
+    /// Upload an asset through the Open Cloud assets API, waiting for the
+    /// upload operation to finish. Returns the ID of the new asset.
+    pub fn upload_asset_open_cloud(
+        &mut self,
+        data: &AssetUploadData,
+        api_key: &str,
+        creator: AssetCreator,
+    ) -> Result<u64, RobloxApiError> {
+        let creator = match creator {
+            AssetCreator::User(id) => OpenCloudCreator {
+                user_id: Some(id.to_string()),
+                group_id: None,
+            },
+            AssetCreator::Group(id) => OpenCloudCreator {
+                user_id: None,
+                group_id: Some(id.to_string()),
+            },
+        };
+
+        let request = OpenCloudAssetRequest {
+            asset_type: data.format.asset_type(),
+            display_name: data.name,
+            description: data.description,
+            creation_context: OpenCloudCreationContext { creator },
+        };
+
+        let request = serde_json::to_string(&request).unwrap();
+
+        let url = self.url("apis", "/assets/v1/assets");
+        let response = self.execute_with_rate_limit(false, |client| {
+            let file = upload_part(data.name, &data.contents)?
+                .file_name(data.format.file_name())
+                .mime_str(data.format.mime_type())?;
+
+            let form = Form::new()
+                .text("request", request.clone())
+                .part("fileContent", file);
+
+            Ok(client
+                .post(&url)
+                .header("x-api-key", api_key)
+                .multipart(form)
+                .build()?)
+        })?;
+
+        let mut operation = read_open_cloud_operation(response)?;
+
+        // Uploads are processed asynchronously, so we need to wait for the
+        // operation to finish before we know the asset's ID.
+        for _ in 0..OPEN_CLOUD_POLL_ATTEMPTS {
+            if operation.done {
+                break;
+            }
+
+            thread::sleep(OPEN_CLOUD_POLL_DELAY);
+
+            let url = self.url("apis", &format!("/assets/v1/{}", operation.path));
+            let response = self.execute_with_rate_limit(false, |client| {
+                Ok(client.get(&url).header("x-api-key", api_key).build()?)
+            })?;
+
+            operation = read_open_cloud_operation(response)?;
+        }
+
+        let asset = match operation.response {
+            Some(asset) if operation.done => asset,
+            _ => {
+                return Err(RobloxApiError::OperationTimedOut {
+                    path: operation.path,
+                })
+            }
+        };
+
+        asset
+            .asset_id
+            .parse()
+            .map_err(|_| RobloxApiError::ApiError {
+                message: format!("Open Cloud returned invalid asset ID {}", asset.asset_id),
+            })
+    }
+
+    /// Retrieve the moderation state of an asset through the Open Cloud assets
+    /// API.
+    pub fn get_moderation_state(
+        &mut self,
+        asset_id: u64,
+        api_key: &str,
+    ) -> Result<ModerationState, RobloxApiError> {
+        let url = self.url("apis", &format!("/assets/v1/assets/{}", asset_id));
+
+        let mut response = self.execute_with_rate_limit(false, |client| {
+            Ok(client
+                .get(&url)
+                .query(&[("readMask", "moderationResult")])
+                .header("x-api-key", api_key)
+                .build()?)
+        })?;
+
+        let body = response.text()?;
+
+        if response.status().is_success() {
+            match serde_json::from_str::<OpenCloudModerationAsset>(&body) {
+                Ok(asset) => Ok(asset.moderation_result.moderation_state),
+                Err(source) => Err(RobloxApiError::BadResponseJson { body, source }),
+            }
+        } else {
+            Err(RobloxApiError::ResponseError {
+                status: response.status(),
+                body,
+            })
+        }
+    }
+
+    /// List one page of the assets of the given type created by the
+    /// authenticated user, or by the given group.
+    pub fn list_created_assets(
+        &mut self,
+        asset_type: &str,
+        group_id: Option<u64>,
+        cursor: Option<&str>,
+    ) -> Result<CreatedAssetsPage, RobloxApiError> {
+        let url = self.url("itemconfiguration", "/v1/creations/get-assets");
+
+        let mut query = vec![
+            ("assetType", asset_type.to_owned()),
+            ("limit", "100".to_owned()),
+        ];
+
+        if let Some(group_id) = group_id {
+            query.push(("groupId", group_id.to_string()));
+        }
+
+        if let Some(cursor) = cursor {
+            query.push(("cursor", cursor.to_owned()));
+        }
+
+        let mut response =
+            self.execute_with_csrf_retry(|client| Ok(client.get(&url).query(&query).build()?))?;
+
+        let body = response.text()?;
+
+        if response.status().is_success() {
+            match serde_json::from_str(&body) {
+                Ok(page) => Ok(page),
+                Err(source) => Err(RobloxApiError::BadResponseJson { body, source }),
+            }
+        } else {
+            Err(RobloxApiError::ResponseError {
+                status: response.status(),
+                body,
+            })
+        }
+    }
+
+    /// Give a universe permission to use an asset, which is required for
+    /// experiences to load private assets like audio and video.
+    pub fn grant_universe_access(
+        &mut self,
+        asset_id: u64,
+        universe_id: u64,
+        api_key: &str,
+    ) -> Result<(), RobloxApiError> {
+        let url = self.url(
+            "apis",
+            &format!("/asset-permissions-api/v1/assets/{}/permissions", asset_id),
+        );
+
+        let request = AssetPermissionsRequest {
+            requests: vec![AssetPermissionRequest {
+                subject_type: "Universe",
+                subject_id: universe_id.to_string(),
+                action: "Use",
+            }],
+        };
+
+        let mut response = self.execute_with_rate_limit(false, |client| {
+            Ok(client
+                .patch(&url)
+                .header("x-api-key", api_key)
+                .json(&request)
+                .build()?)
+        })?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(RobloxApiError::ResponseError {
+                status: response.status(),
+                body: response.text()?,
+            })
+        }
+    }
+
+    /// Find out which user the client's authentication cookie belongs to, which
+    /// fails if the cookie isn't valid.
+    pub fn get_authenticated_user(&mut self) -> Result<AuthenticatedUser, RobloxApiError> {
+        let url = self.url("users", "/v1/users/authenticated");
+
+        let mut response =
+            self.execute_with_csrf_retry(|client| Ok(client.get(&url).build()?))?;
+
+        let body = response.text()?;
+
+        if response.status().is_success() {
+            match serde_json::from_str(&body) {
+                Ok(user) => Ok(user),
+                Err(source) => Err(RobloxApiError::BadResponseJson { body, source }),
+            }
+        } else {
+            Err(RobloxApiError::ResponseError {
+                status: response.status(),
+                body,
+            })
+        }
+    }
+
+    /// Describe an Open Cloud API key, which fails if the key doesn't exist.
+    pub fn introspect_api_key(&mut self, api_key: &str) -> Result<ApiKeyInfo, RobloxApiError> {
+        let url = self.url("apis", "/api-keys/v1/introspect");
+        let request = ApiKeyIntrospectRequest { api_key };
+
+        let mut response = self.execute_with_rate_limit(false, |client| {
+            Ok(client.post(&url).json(&request).build()?)
+        })?;
+
+        let body = response.text()?;
+
+        if response.status().is_success() {
+            match serde_json::from_str(&body) {
+                Ok(info) => Ok(info),
+                Err(source) => Err(RobloxApiError::BadResponseJson { body, source }),
+            }
+        } else {
+            Err(RobloxApiError::ResponseError {
+                status: response.status(),
+                body,
+            })
+        }
+    }
+
+    /// Builds the URL of a path on one of Roblox's services, like `apis`. When
+    /// a base URL is configured, the service becomes the first segment of the
+    /// path instead, so that one proxy or mock server can stand in for all of
+    /// them.
+    fn url(&self, service: &str, path: &str) -> String {
+        match &self.base_url {
+            Some(base_url) => format!("{}/{}{}", base_url.trim_end_matches('/'), service, path),
+            None => format!("https://{}.roblox.com{}", service, path),
+        }
+    }
+
     /// Execute a request generated by the given function, retrying if the
     /// endpoint requests that the user refreshes their CSRF token.
     fn execute_with_csrf_retry<F>(&mut self, make_request: F) -> Result<Response, RobloxApiError>
     where
         F: Fn(&Client) -> Result<Request, RobloxApiError>,
     {
-        let mut request = make_request(&self.client)?;
-        self.attach_headers(&mut request);
-
-        let response = self.client.execute(request)?;
+        let response = self.execute_with_rate_limit(true, &make_request)?;
 
         match response.status() {
             StatusCode::FORBIDDEN => {
                 if let Some(csrf) = response.headers().get("X-CSRF-Token") {
                     log::debug!("Retrying request with X-CSRF-Token...");
 
-                    self.csrf_token = Some(csrf.clone());
+                    self.state.lock().unwrap().csrf_token = Some(csrf.clone());
 
-                    let mut new_request = make_request(&self.client)?;
-                    self.attach_headers(&mut new_request);
-
-                    Ok(self.client.execute(new_request)?)
+                    self.execute_with_rate_limit(true, &make_request)
                 } else {
                     // If the response did not return a CSRF token for us to
                     // retry with, this request was likely forbidden for other
@@ -209,6 +1260,92 @@ impl RobloxApiClient {
         }
     }
 
+    /// Execute a request generated by the given function, waiting and retrying
+    /// if Roblox responds that we're being rate limited.
+    ///
+    /// Rate limited requests are retried after the delay given by the
+    /// response's `Retry-After` header. Each time we're rate limited, we also
+    /// leave more time between all following requests, which is slowly reduced
+    /// again as requests succeed.
+    fn execute_with_rate_limit<F>(
+        &mut self,
+        authenticated: bool,
+        make_request: F,
+    ) -> Result<Response, RobloxApiError>
+    where
+        F: Fn(&Client) -> Result<Request, RobloxApiError>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            // Reserve our slot before sleeping, so that clones of this client
+            // on other threads space their requests out after ours.
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                let next_request = match state.last_request {
+                    Some(last_request) => (last_request + state.throttle).max(now),
+                    None => now,
+                };
+
+                state.last_request = Some(next_request);
+                next_request - now
+            };
+
+            if wait > Duration::from_secs(0) {
+                thread::sleep(wait);
+            }
+
+            let mut request = make_request(&self.client)?;
+            if authenticated {
+                self.attach_headers(&mut request);
+            }
+
+            let response = match &self.fixtures {
+                Some(fixtures) => fixtures.execute(&self.client, request)?,
+                None => self.client.execute(request)?,
+            };
+            let mut state = self.state.lock().unwrap();
+
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                state.throttle /= 2;
+                if state.throttle < MIN_THROTTLE {
+                    state.throttle = Duration::from_secs(0);
+                }
+
+                return Ok(response);
+            }
+
+            state.throttle = (state.throttle * 2).max(MIN_THROTTLE).min(MAX_THROTTLE);
+
+            if attempt >= RATE_LIMIT_RETRIES || state.retry_budget == 0 {
+                return Ok(response);
+            }
+            attempt += 1;
+            state.retry_budget -= 1;
+
+            let throttle = state.throttle;
+            drop(state);
+
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(throttle);
+
+            log::warn!(
+                "Rate limited by Roblox, retrying in {:.1}s ({}/{})",
+                retry_after.as_secs_f32(),
+                attempt,
+                RATE_LIMIT_RETRIES
+            );
+
+            thread::sleep(retry_after);
+        }
+    }
+
     /// Attach required headers to a request object before sending it to a
     /// Roblox API, like authentication and CSRF protection.
     fn attach_headers(&self, request: &mut Request) {
@@ -221,12 +1358,28 @@ impl RobloxApiClient {
             );
         }
 
-        if let Some(csrf) = &self.csrf_token {
+        if let Some(csrf) = &self.state.lock().unwrap().csrf_token {
             request.headers_mut().insert("X-CSRF-Token", csrf.clone());
         }
     }
 }
 
+fn read_open_cloud_operation(mut response: Response) -> Result<OpenCloudOperation, RobloxApiError> {
+    let body = response.text()?;
+
+    if response.status().is_success() {
+        match serde_json::from_str(&body) {
+            Ok(operation) => Ok(operation),
+            Err(source) => Err(RobloxApiError::BadResponseJson { body, source }),
+        }
+    } else {
+        Err(RobloxApiError::ResponseError {
+            status: response.status(),
+            body,
+        })
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum RobloxApiError {
     #[error("Roblox API HTTP error")]
@@ -246,4 +1399,225 @@ pub enum RobloxApiError {
 
     #[error("Roblox API returned HTTP {status} with body: {body}")]
     ResponseError { status: StatusCode, body: String },
+
+    #[error("{url:?} is not a valid proxy URL")]
+    InvalidProxy { url: String },
+
+    #[error("{user_agent:?} is not a valid User-Agent header")]
+    InvalidUserAgent { user_agent: String },
+
+    #[error("Couldn't read the contents of {name} to upload them")]
+    ReadContents { name: String, source: io::Error },
+
+    #[error("Open Cloud upload operation {path} did not finish in time")]
+    OperationTimedOut { path: String },
+
+    #[error(transparent)]
+    Fixtures {
+        #[from]
+        source: FixtureError,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::http_fixtures::replay_client;
+
+    fn upload_data(format: AssetFormat) -> AssetUploadData<'static> {
+        AssetUploadData {
+            contents: b"contents".to_vec().into(),
+            name: "foo",
+            description: "bar",
+            format,
+        }
+    }
+
+    #[test]
+    fn no_proxy_matches_hosts_and_subdomains() {
+        let options = ProxyOptions {
+            no_proxy: vec!["roblox.com".to_owned(), ".internal".to_owned()],
+            ..ProxyOptions::default()
+        };
+
+        assert!(options.bypasses("roblox.com"));
+        assert!(options.bypasses("apis.roblox.com"));
+        assert!(options.bypasses("mirror.internal"));
+        assert!(!options.bypasses("notroblox.com"));
+        assert!(!options.bypasses("example.com"));
+    }
+
+    #[test]
+    fn no_proxy_wildcard() {
+        let options = ProxyOptions {
+            no_proxy: vec!["*".to_owned()],
+            ..ProxyOptions::default()
+        };
+
+        assert!(options.bypasses("apis.roblox.com"));
+    }
+
+    #[test]
+    fn upload_contents_are_shared() {
+        let contents = UploadContents::from(vec![1, 2, 3]);
+        let retry = contents.clone();
+
+        let mut sent = Vec::new();
+        retry.reader().unwrap().read_to_end(&mut sent).unwrap();
+        assert_eq!(sent, vec![1, 2, 3]);
+        assert_eq!(
+            retry.as_bytes().unwrap().as_ptr(),
+            contents.as_bytes().unwrap().as_ptr()
+        );
+
+        let path = env::temp_dir().join(format!("tarmac-upload-{}", std::process::id()));
+        fs_err::write(&path, b"streamed").unwrap();
+
+        let hash = blake3::hash(b"streamed").to_hex();
+        let streamed = UploadContents::from_file(&path, Some(hash.as_str())).unwrap();
+        assert_eq!(streamed.len(), 8);
+        assert_eq!(streamed.as_bytes(), None);
+        assert_eq!(streamed.read().unwrap().as_ref(), b"streamed");
+
+        let mut sent = Vec::new();
+        streamed.reader().unwrap().read_to_end(&mut sent).unwrap();
+        assert_eq!(sent, b"streamed");
+
+        // Files that change after the upload was prepared aren't sent.
+        for changed in &[&b"Streamed"[..], b"streamed!", b"stream"] {
+            fs_err::write(&path, changed).unwrap();
+
+            assert!(streamed.read().is_err());
+            assert!(streamed
+                .reader()
+                .unwrap()
+                .read_to_end(&mut Vec::new())
+                .is_err());
+        }
+
+        fs_err::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn unknown_moderation_states() {
+        let parse = |state: &str| {
+            serde_json::from_str::<OpenCloudModerationAsset>(&format!(
+                r#"{{ "moderationResult": {{ "moderationState": "{}" }} }}"#,
+                state
+            ))
+            .unwrap()
+            .moderation_result
+            .moderation_state
+        };
+
+        assert_eq!(parse("Approved"), ModerationState::Approved);
+        assert_eq!(parse("Escalated"), ModerationState::Unknown);
+    }
+
+    #[test]
+    fn audio_is_published_through_the_audio_endpoint() {
+        let format = AssetFormat::from_path(Path::new("theme.MP3")).unwrap();
+        assert_eq!(format, AssetFormat::Mp3);
+        assert_eq!(format.kind(), AssetKind::Audio);
+        assert_eq!(format.asset_type(), "Audio");
+
+        let mut client = replay_client(
+            "audio",
+            serde_json::json!([
+                {
+                    "method": "POST",
+                    "url": "https://publish.roblox.com/v1/audio",
+                    "status": 200,
+                    "headers": [],
+                    "body": r#"{"Id":5,"Name":"foo"}"#,
+                },
+            ]),
+        );
+
+        let id = client
+            .upload_audio(&upload_data(AssetFormat::Ogg), None)
+            .unwrap();
+        assert_eq!(id, 5);
+    }
+
+    #[test]
+    fn meshes_are_uploaded_through_open_cloud_as_models() {
+        let format = AssetFormat::from_path(Path::new("rock.obj")).unwrap();
+        assert_eq!(format.kind(), AssetKind::Mesh);
+        assert_eq!(format.asset_type(), "Model");
+        assert_eq!(AssetFormat::Fbx.mime_type(), "model/fbx");
+
+        let mut client = replay_client(
+            "mesh",
+            serde_json::json!([
+                {
+                    "method": "POST",
+                    "url": "https://apis.roblox.com/assets/v1/assets",
+                    "status": 200,
+                    "headers": [],
+                    "body": r#"{"path":"operations/1","done":true,"response":{"assetId":"6"}}"#,
+                },
+            ]),
+        );
+
+        let id = client
+            .upload_asset_open_cloud(&upload_data(AssetFormat::Fbx), "key", AssetCreator::User(1))
+            .unwrap();
+        assert_eq!(id, 6);
+    }
+
+    #[test]
+    fn models_are_uploaded_through_the_legacy_endpoint() {
+        let url = "https://data.roblox.com/Data/Upload.ashx?assetid=0&type=Model&groupId=4\
+                   &name=foo&description=bar&ispublic=false&allowComments=false";
+
+        let mut client = replay_client(
+            "model",
+            serde_json::json!([
+                { "method": "POST", "url": url, "status": 200, "headers": [], "body": "7\n" },
+                { "method": "POST", "url": url, "status": 200, "headers": [], "body": "oops" },
+            ]),
+        );
+
+        let data = upload_data(AssetFormat::Rbxmx);
+        assert_eq!(client.upload_model(&data, Some(4)).unwrap(), 7);
+
+        match client.upload_model(&data, Some(4)) {
+            Err(RobloxApiError::ApiError { message }) => assert!(message.contains("oops")),
+            other => panic!("expected an invalid asset ID error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn videos_wait_for_their_upload_operation() {
+        let format = AssetFormat::from_path(Path::new("intro.webm")).unwrap();
+        assert_eq!(format.kind(), AssetKind::Video);
+        assert_eq!(format.asset_type(), "Video");
+
+        let mut client = replay_client(
+            "video",
+            serde_json::json!([
+                {
+                    "method": "POST",
+                    "url": "https://apis.roblox.com/assets/v1/assets",
+                    "status": 200,
+                    "headers": [],
+                    "body": r#"{"path":"operations/2"}"#,
+                },
+                {
+                    "method": "GET",
+                    "url": "https://apis.roblox.com/assets/v1/operations/2",
+                    "status": 200,
+                    "headers": [],
+                    "body": r#"{"path":"operations/2","done":true,"response":{"assetId":"8"}}"#,
+                },
+            ]),
+        );
+
+        let id = client
+            .upload_asset_open_cloud(&upload_data(format), "key", AssetCreator::Group(3))
+            .unwrap();
+        assert_eq!(id, 8);
+    }
 }
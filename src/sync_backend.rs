@@ -1,64 +1,229 @@
-use std::{borrow::Cow, io, path::Path, thread, time::Duration};
+use std::{io, path::Path, thread, time::Duration};
 
 use fs_err as fs;
 use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::roblox_web_api::{ImageUploadData, RobloxApiClient, RobloxApiError};
+use crate::{
+    asset_uploader::AssetUploader,
+    roblox_web_api::{
+        AssetCreator, AssetFormat, AssetKind, AssetUploadData, ImageUploadData, RobloxApiClient,
+        RobloxApiError, UploadContents,
+    },
+    upload_cache::{cache_key, UploadCache},
+};
 
 pub trait SyncBackend {
     fn upload(&mut self, data: UploadInfo) -> Result<UploadResponse, Error>;
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct UploadResponse {
     pub id: u64,
+
+    /// The group that owns the uploaded asset, if it wasn't uploaded to a
+    /// user's inventory.
+    pub group_id: Option<u64>,
+
+    /// The user that owns the uploaded asset, if it was uploaded to a user's
+    /// inventory and we know which user that is.
+    #[serde(default)]
+    pub user_id: Option<u64>,
+
+    /// If the asset is an image that was uploaded as a decal, the ID of that
+    /// decal. `id` is always the ID of the image itself.
+    pub decal_id: Option<u64>,
+
+    /// Whether the asset was found in an upload cache instead of being
+    /// uploaded, so it doesn't count towards the assets a sync uploaded.
+    #[serde(default, skip_serializing)]
+    pub cached: bool,
 }
 
+/// The description given to uploaded assets that don't define their own.
+pub static DEFAULT_DESCRIPTION: &str = "Uploaded by Tarmac.";
+
 #[derive(Clone, Debug)]
 pub struct UploadInfo {
     pub name: String,
-    pub contents: Vec<u8>,
+    pub contents: UploadContents,
     pub hash: String,
+
+    /// The description to upload the asset with. If not specified, a default
+    /// description is used.
+    pub description: Option<String>,
+
+    /// The format of `contents`, which decides what kind of asset is created.
+    pub format: AssetFormat,
+
+    /// Who should own the uploaded asset. If not specified, the backend's
+    /// default destination is used.
+    pub owner: Option<AssetCreator>,
+}
+
+impl UploadInfo {
+    fn description(&self) -> &str {
+        self.description.as_deref().unwrap_or(DEFAULT_DESCRIPTION)
+    }
 }
 
-pub struct RobloxSyncBackend<'a> {
-    api_client: &'a mut RobloxApiClient,
+pub struct RobloxSyncBackend<'a, U = RobloxApiClient> {
+    api_client: &'a mut U,
     upload_to_group_id: Option<u64>,
+
+    /// The authenticated user, which assets that aren't uploaded to a group
+    /// belong to, if it's known.
+    user_id: Option<u64>,
 }
 
-impl<'a> RobloxSyncBackend<'a> {
-    pub fn new(api_client: &'a mut RobloxApiClient, upload_to_group_id: Option<u64>) -> Self {
+impl<'a, U: AssetUploader> RobloxSyncBackend<'a, U> {
+    pub fn new(api_client: &'a mut U, upload_to_group_id: Option<u64>) -> Self {
         Self {
             api_client,
             upload_to_group_id,
+            user_id: None,
         }
     }
+
+    /// Records the given user as the owner of assets that aren't uploaded to
+    /// a group.
+    pub fn uploading_as(mut self, user_id: Option<u64>) -> Self {
+        self.user_id = user_id;
+        self
+    }
 }
 
-impl<'a> SyncBackend for RobloxSyncBackend<'a> {
+impl<'a, U: AssetUploader> SyncBackend for RobloxSyncBackend<'a, U> {
     fn upload(&mut self, data: UploadInfo) -> Result<UploadResponse, Error> {
         log::info!("Uploading {} to Roblox", &data.name);
 
+        // The legacy endpoint always uploads to the authenticated user when a
+        // group isn't specified, so we can't honor a specific user ID.
+        let group_id = match data.owner {
+            Some(AssetCreator::Group(group_id)) => Some(group_id),
+            Some(AssetCreator::User(_)) => None,
+            None => self.upload_to_group_id,
+        };
+
+        let upload_data = AssetUploadData {
+            contents: data.contents.clone(),
+            name: &data.name,
+            description: data.description(),
+            format: data.format,
+        };
+
+        // Images are uploaded as decals, which are resolved to their backing
+        // image. Other kinds of assets go through their own endpoints.
+        let result = match data.format.kind() {
+            AssetKind::Image => self
+                .api_client
+                .upload_image_with_moderation_retry(ImageUploadData {
+                    image_data: upload_data.contents,
+                    name: upload_data.name,
+                    description: upload_data.description,
+                    group_id,
+                })
+                .map(|response| (response.backing_asset_id, Some(response.asset_id))),
+            AssetKind::Audio => self
+                .api_client
+                .upload_audio(&upload_data, group_id)
+                .map(|id| (id, None)),
+            AssetKind::Model => self
+                .api_client
+                .upload_model(&upload_data, group_id)
+                .map(|id| (id, None)),
+            // Meshes, videos, and fonts can only be uploaded through Open Cloud.
+            AssetKind::Mesh | AssetKind::Video | AssetKind::Font => {
+                return Err(Error::LegacyUploadUnsupported {
+                    name: data.name.clone(),
+                })
+            }
+        };
+
+        match result {
+            Ok((id, decal_id)) => {
+                log::info!("Uploaded {} to ID {}", &data.name, id);
+
+                Ok(UploadResponse {
+                    id,
+                    group_id,
+                    user_id: if group_id.is_none() {
+                        self.user_id
+                    } else {
+                        None
+                    },
+                    decal_id,
+                    cached: false,
+                })
+            }
+
+            Err(RobloxApiError::ResponseError {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                ..
+            }) => Err(Error::RateLimited),
+
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Uploads assets through the Open Cloud assets API, authenticated with an API
+/// key instead of a cookie.
+pub struct OpenCloudSyncBackend<'a, U = RobloxApiClient> {
+    api_client: &'a mut U,
+    api_key: String,
+    creator: Option<AssetCreator>,
+}
+
+impl<'a, U: AssetUploader> OpenCloudSyncBackend<'a, U> {
+    pub fn new(api_client: &'a mut U, api_key: String, creator: Option<AssetCreator>) -> Self {
+        Self {
+            api_client,
+            api_key,
+            creator,
+        }
+    }
+}
+
+impl<'a, U: AssetUploader> SyncBackend for OpenCloudSyncBackend<'a, U> {
+    fn upload(&mut self, data: UploadInfo) -> Result<UploadResponse, Error> {
+        log::info!("Uploading {} to Roblox with Open Cloud", &data.name);
+
+        let creator = data
+            .owner
+            .or(self.creator)
+            .ok_or_else(|| Error::NoOpenCloudCreator {
+                name: data.name.clone(),
+            })?;
+
+        let upload_data = AssetUploadData {
+            contents: data.contents.clone(),
+            name: &data.name,
+            description: data.description(),
+            format: data.format,
+        };
+
         let result = self
             .api_client
-            .upload_image_with_moderation_retry(ImageUploadData {
-                image_data: Cow::Owned(data.contents),
-                name: &data.name,
-                description: "Uploaded by Tarmac.",
-                group_id: self.upload_to_group_id,
-            });
+            .upload_asset_open_cloud(&upload_data, &self.api_key, creator);
 
         match result {
-            Ok(response) => {
-                log::info!(
-                    "Uploaded {} to ID {}",
-                    &data.name,
-                    response.backing_asset_id
-                );
+            Ok(id) => {
+                log::info!("Uploaded {} to ID {}", &data.name, id);
+
+                let (group_id, user_id) = match creator {
+                    AssetCreator::Group(group_id) => (Some(group_id), None),
+                    AssetCreator::User(user_id) => (None, Some(user_id)),
+                };
 
                 Ok(UploadResponse {
-                    id: response.backing_asset_id,
+                    id,
+                    group_id,
+                    user_id,
+                    decal_id: None,
+                    cached: false,
                 })
             }
 
@@ -101,9 +266,18 @@ impl SyncBackend for DebugSyncBackend {
         fs::create_dir_all(path)?;
 
         let file_path = path.join(id.to_string());
-        fs::write(&file_path, &data.contents)?;
-
-        Ok(UploadResponse { id })
+        io::copy(
+            &mut data.contents.reader()?,
+            &mut fs::File::create(&file_path)?,
+        )?;
+
+        Ok(UploadResponse {
+            id,
+            group_id: None,
+            user_id: None,
+            decal_id: None,
+            cached: false,
+        })
     }
 }
 
@@ -152,11 +326,75 @@ impl<InnerSyncBackend: SyncBackend> SyncBackend for RetryBackend<InnerSyncBacken
     }
 }
 
+/// Looks up each upload in a cache before uploading it, and records new
+/// uploads in the cache. The cache is only an
+/// optimization, so problems with it are logged without stopping the upload.
+pub struct CachedBackend<InnerSyncBackend, Cache> {
+    inner: InnerSyncBackend,
+    cache: Cache,
+
+    /// The owner of uploads that don't specify one, which their cache keys
+    /// are made with.
+    default_owner: Option<AssetCreator>,
+}
+
+impl<InnerSyncBackend, Cache> CachedBackend<InnerSyncBackend, Cache> {
+    pub fn new(inner: InnerSyncBackend, cache: Cache, default_owner: Option<AssetCreator>) -> Self {
+        Self {
+            inner,
+            cache,
+            default_owner,
+        }
+    }
+}
+
+impl<InnerSyncBackend: SyncBackend, Cache: UploadCache> SyncBackend
+    for CachedBackend<InnerSyncBackend, Cache>
+{
+    fn upload(&mut self, data: UploadInfo) -> Result<UploadResponse, Error> {
+        let key = match cache_key(data.owner.or(self.default_owner), &data.hash) {
+            Some(key) => key,
+            None => return self.inner.upload(data),
+        };
+
+        match self.cache.get(&key) {
+            Ok(Some(mut response)) => {
+                response.cached = true;
+                log::info!(
+                    "Using ID {} for {} from {}",
+                    response.id,
+                    data.name,
+                    self.cache.name()
+                );
+                return Ok(response);
+            }
+            Ok(None) => {}
+            Err(err) => log::warn!("{:?}", anyhow::Error::from(err)),
+        }
+
+        let response = self.inner.upload(data)?;
+
+        if let Err(err) = self.cache.put(&key, &response) {
+            log::warn!("{:?}", anyhow::Error::from(err));
+        }
+
+        Ok(response)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Cannot upload assets with the 'none' target.")]
     NoneBackend,
 
+    #[error(
+        "Cannot upload {name} with Open Cloud because no owner was specified. Set upload-to-group-id or upload-to-user-id."
+    )]
+    NoOpenCloudCreator { name: String },
+
+    #[error("Cannot upload {name} with the legacy upload API. Set upload-api to \"open-cloud\".")]
+    LegacyUploadUnsupported { name: String },
+
     #[error("Tarmac was rate-limited trying to upload assets. Try again in a little bit.")]
     RateLimited,
 
@@ -211,8 +449,11 @@ mod test {
         fn any_upload_info() -> UploadInfo {
             UploadInfo {
                 name: "foo".to_owned(),
-                contents: Vec::new(),
+                contents: Vec::new().into(),
                 hash: "hash".to_owned(),
+                description: None,
+                format: AssetFormat::Png,
+                owner: None,
             }
         }
 
@@ -249,7 +490,13 @@ mod test {
         #[test]
         fn upload_returns_first_success_result() {
             let mut counter = 0;
-            let success = UploadResponse { id: 10 };
+            let success = UploadResponse {
+                id: 10,
+                group_id: None,
+                user_id: None,
+                decal_id: None,
+                cached: false,
+            };
             let inner = CountUploads::new(&mut counter).with_results(vec![
                 Err(Error::RateLimited),
                 Err(Error::RateLimited),
@@ -283,4 +530,167 @@ mod test {
             });
         }
     }
+    mod test_roblox_backends {
+        use super::*;
+
+        use crate::asset_uploader::MemoryUploader;
+
+        fn upload_info(format: AssetFormat, owner: Option<AssetCreator>) -> UploadInfo {
+            UploadInfo {
+                name: "foo".to_owned(),
+                contents: vec![1, 2, 3].into(),
+                hash: "hash".to_owned(),
+                description: None,
+                format,
+                owner,
+            }
+        }
+
+        #[test]
+        fn legacy_images_keep_decal_and_backing_image() {
+            let mut uploader = MemoryUploader::default();
+            let mut backend = RobloxSyncBackend::new(&mut uploader, Some(7));
+
+            let response = backend.upload(upload_info(AssetFormat::Png, None)).unwrap();
+
+            assert_eq!(
+                response,
+                UploadResponse {
+                    id: 2,
+                    group_id: Some(7),
+                    user_id: None,
+                    decal_id: Some(1),
+                    cached: false,
+                }
+            );
+            assert_eq!(uploader.uploads[1].contents, vec![1, 2, 3]);
+        }
+
+        #[test]
+        fn legacy_owner_overrides_default_group() {
+            let mut uploader = MemoryUploader::default();
+            let mut backend = RobloxSyncBackend::new(&mut uploader, Some(7)).uploading_as(Some(9));
+
+            let owner = Some(AssetCreator::User(3));
+            let response = backend
+                .upload(upload_info(AssetFormat::Ogg, owner))
+                .unwrap();
+
+            // The legacy API always uploads to the authenticated user.
+            assert_eq!(response.group_id, None);
+            assert_eq!(response.user_id, Some(9));
+            assert_eq!(uploader.uploads[0].group_id, None);
+        }
+
+        #[test]
+        fn legacy_rejects_meshes() {
+            let mut uploader = MemoryUploader::default();
+            let mut backend = RobloxSyncBackend::new(&mut uploader, None);
+
+            let result = backend.upload(upload_info(AssetFormat::Fbx, None));
+
+            assert!(matches!(result, Err(Error::LegacyUploadUnsupported { .. })));
+            assert!(uploader.uploads.is_empty());
+        }
+
+        #[test]
+        fn open_cloud_requires_creator() {
+            let mut uploader = MemoryUploader::default();
+            let mut backend = OpenCloudSyncBackend::new(&mut uploader, "key".to_owned(), None);
+
+            let result = backend.upload(upload_info(AssetFormat::Png, None));
+
+            assert!(matches!(result, Err(Error::NoOpenCloudCreator { .. })));
+        }
+
+        #[test]
+        fn open_cloud_uploads_for_owner() {
+            let mut uploader = MemoryUploader::default();
+            let default_creator = Some(AssetCreator::User(1));
+            let mut backend =
+                OpenCloudSyncBackend::new(&mut uploader, "key".to_owned(), default_creator);
+
+            let owner = Some(AssetCreator::Group(5));
+            let response = backend
+                .upload(upload_info(AssetFormat::Png, owner))
+                .unwrap();
+
+            assert_eq!(response.group_id, Some(5));
+            assert_eq!(response.user_id, None);
+            assert_eq!(uploader.uploads[0].creator, owner);
+        }
+    }
+
+    mod test_cached_backend {
+        use std::collections::HashMap;
+
+        use super::*;
+
+        use crate::{asset_uploader::MemoryUploader, upload_cache::UploadCacheError};
+
+        #[derive(Default)]
+        struct MemoryCache {
+            entries: HashMap<String, UploadResponse>,
+        }
+
+        impl UploadCache for MemoryCache {
+            fn name(&self) -> &'static str {
+                "memory"
+            }
+
+            fn get(&mut self, key: &str) -> Result<Option<UploadResponse>, UploadCacheError> {
+                Ok(self.entries.get(key).cloned())
+            }
+
+            fn put(
+                &mut self,
+                key: &str,
+                response: &UploadResponse,
+            ) -> Result<(), UploadCacheError> {
+                self.entries.insert(key.to_owned(), response.clone());
+                Ok(())
+            }
+        }
+
+        fn upload_info(hash: &str) -> UploadInfo {
+            UploadInfo {
+                name: "foo".to_owned(),
+                contents: vec![1, 2, 3].into(),
+                hash: hash.to_owned(),
+                description: None,
+                format: AssetFormat::Ogg,
+                owner: None,
+            }
+        }
+
+        #[test]
+        fn reuse_cached_uploads() {
+            let mut uploader = MemoryUploader::default();
+            let mut cache = MemoryCache::default();
+            cache.entries.insert(
+                "group-7/cached".to_owned(),
+                UploadResponse {
+                    id: 50,
+                    group_id: Some(7),
+                    user_id: None,
+                    decal_id: None,
+                    cached: false,
+                },
+            );
+
+            let inner = RobloxSyncBackend::new(&mut uploader, Some(7));
+            let mut backend = CachedBackend::new(inner, cache, Some(AssetCreator::Group(7)));
+
+            let cached = backend.upload(upload_info("cached")).unwrap();
+            let uploaded = backend.upload(upload_info("new")).unwrap();
+
+            assert_eq!(cached.id, 50);
+            assert!(cached.cached);
+            assert!(!uploaded.cached);
+            assert_eq!(backend.cache.entries.get("group-7/new"), Some(&uploaded));
+
+            drop(backend);
+            assert_eq!(uploader.uploads.len(), 1);
+        }
+    }
 }
@@ -0,0 +1,264 @@
+//! Caches that map the contents of uploaded assets to their IDs, so that the
+//! same bytes aren't uploaded twice. The remote cache is shared between
+//! machines, even from different branches or CI runners, and the user cache is
+//! shared between every project on one machine. Both are keyed by the group or
+//! user that owns each asset, so every account that can upload for an owner
+//! shares its entries.
+
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+};
+
+use fs_err as fs;
+use reqwest::{Client, StatusCode};
+use thiserror::Error;
+
+use crate::{roblox_web_api::AssetCreator, sync_backend::UploadResponse};
+
+/// The folder in the platform's cache directory that the user cache is kept
+/// in, like `~/.cache/tarmac/upload-cache` on Linux.
+static USER_CACHE_FOLDER: &str = "tarmac/upload-cache";
+
+/// The environment variable holding the token that requests to the remote
+/// cache are authorized with, if it needs one.
+pub static REMOTE_CACHE_TOKEN_VAR: &str = "TARMAC_REMOTE_CACHE_TOKEN";
+
+/// Somewhere to look up assets that were already uploaded by their contents.
+pub trait UploadCache {
+    /// Describes the cache in log messages, like "the remote cache".
+    fn name(&self) -> &'static str;
+
+    /// Finds the asset that the given key was uploaded to, if it's known.
+    fn get(&mut self, key: &str) -> Result<Option<UploadResponse>, UploadCacheError>;
+
+    /// Records the asset that the given key was uploaded to.
+    fn put(&mut self, key: &str, response: &UploadResponse) -> Result<(), UploadCacheError>;
+}
+
+/// The key that an upload is cached under: its destination, followed by the
+/// hash of its contents. Assets owned by one group or user can't always be
+/// used by another, so each destination has its own entries.
+///
+/// Returns None if the destination isn't known, since a shared entry could
+/// then point to an asset owned by a different account.
+pub fn cache_key(owner: Option<AssetCreator>, hash: &str) -> Option<String> {
+    match owner? {
+        AssetCreator::Group(group_id) => Some(format!("group-{}/{}", group_id, hash)),
+        AssetCreator::User(user_id) => Some(format!("user-{}/{}", user_id, hash)),
+    }
+}
+
+/// A cache kept on an HTTP server, which stores each entry as a JSON document
+/// at `<url>/<key>`. Entries are read with GET and written with PUT, optionally
+/// authorized with a bearer token. Requests aren't signed, so storage that
+/// needs signed requests has to be put behind a server that signs them.
+pub struct HttpUploadCache {
+    client: Client,
+    url: String,
+    token: Option<String>,
+}
+
+impl HttpUploadCache {
+    /// Creates a cache at the given URL that sends its requests with the given
+    /// client, usually the one shared with the Roblox API client.
+    pub fn new(client: Client, url: &str) -> Self {
+        Self {
+            client,
+            url: url.trim_end_matches('/').to_owned(),
+            token: env::var(REMOTE_CACHE_TOKEN_VAR).ok(),
+        }
+    }
+
+    fn entry_url(&self, key: &str) -> String {
+        format!("{}/{}", self.url, key)
+    }
+}
+
+impl UploadCache for HttpUploadCache {
+    fn name(&self) -> &'static str {
+        "the remote cache"
+    }
+
+    fn get(&mut self, key: &str) -> Result<Option<UploadResponse>, UploadCacheError> {
+        let url = self.entry_url(key);
+        let mut request = self.client.get(&url);
+
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let request_error = |source| UploadCacheError::Request {
+            url: url.clone(),
+            source,
+        };
+
+        let response = request.send().map_err(request_error)?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let entry = response
+            .error_for_status()
+            .and_then(|mut response| response.json())
+            .map_err(request_error)?;
+
+        Ok(Some(entry))
+    }
+
+    fn put(&mut self, key: &str, response: &UploadResponse) -> Result<(), UploadCacheError> {
+        let url = self.entry_url(key);
+        let mut request = self.client.put(&url).json(response);
+
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        request
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|source| UploadCacheError::Request { url, source })?;
+
+        Ok(())
+    }
+}
+
+/// A cache kept in the current user's cache directory, which stores each entry
+/// as a JSON file.
+pub struct FolderUploadCache {
+    folder: PathBuf,
+}
+
+impl FolderUploadCache {
+    /// The cache in the current user's cache directory, if it can be found.
+    pub fn for_user() -> Option<Self> {
+        Some(Self::new(dirs::cache_dir()?.join(USER_CACHE_FOLDER)))
+    }
+
+    pub fn new<P: AsRef<Path>>(folder: P) -> Self {
+        Self {
+            folder: folder.as_ref().to_owned(),
+        }
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.folder.join(format!("{}.json", key))
+    }
+}
+
+impl UploadCache for FolderUploadCache {
+    fn name(&self) -> &'static str {
+        "the user cache"
+    }
+
+    fn get(&mut self, key: &str) -> Result<Option<UploadResponse>, UploadCacheError> {
+        let path = self.entry_path(key);
+
+        let contents = match fs::read(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let entry = serde_json::from_slice(&contents)
+            .map_err(|source| UploadCacheError::Json { path, source })?;
+
+        Ok(Some(entry))
+    }
+
+    fn put(&mut self, key: &str, response: &UploadResponse) -> Result<(), UploadCacheError> {
+        let path = self.entry_path(key);
+
+        fs::create_dir_all(path.parent().unwrap())?;
+
+        let serialized = serde_json::to_vec(response).map_err(|source| UploadCacheError::Json {
+            path: path.clone(),
+            source,
+        })?;
+        fs::write(&path, serialized)?;
+
+        Ok(())
+    }
+}
+
+/// A cache that isn't configured never has any entries.
+impl<Cache: UploadCache> UploadCache for Option<Cache> {
+    fn name(&self) -> &'static str {
+        match self {
+            Some(cache) => cache.name(),
+            None => "nowhere",
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Result<Option<UploadResponse>, UploadCacheError> {
+        match self {
+            Some(cache) => cache.get(key),
+            None => Ok(None),
+        }
+    }
+
+    fn put(&mut self, key: &str, response: &UploadResponse) -> Result<(), UploadCacheError> {
+        match self {
+            Some(cache) => cache.put(key, response),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum UploadCacheError {
+    #[error("Request to the remote cache at {url} failed")]
+    Request { url: String, source: reqwest::Error },
+
+    #[error("Cache entry {} isn't valid JSON", .path.display())]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    #[error(transparent)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn folder_cache_round_trip() {
+        let folder = env::temp_dir().join(format!("tarmac-upload-cache-{}", std::process::id()));
+        let mut cache = FolderUploadCache::new(&folder);
+
+        let key = cache_key(Some(AssetCreator::Group(7)), "hash").unwrap();
+        let response = UploadResponse {
+            id: 50,
+            group_id: Some(7),
+            user_id: None,
+            decal_id: None,
+            cached: false,
+        };
+
+        assert_eq!(cache.get(&key).unwrap(), None);
+        cache.put(&key, &response).unwrap();
+        assert_eq!(cache.get(&key).unwrap(), Some(response));
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn cache_keys_depend_on_owner() {
+        assert_eq!(
+            cache_key(Some(AssetCreator::Group(7)), "hash").as_deref(),
+            Some("group-7/hash")
+        );
+        assert_eq!(
+            cache_key(Some(AssetCreator::User(7)), "hash").as_deref(),
+            Some("user-7/hash")
+        );
+        assert_eq!(cache_key(None, "hash"), None);
+    }
+}
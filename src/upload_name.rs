@@ -0,0 +1,127 @@
+//! Naming of uploaded assets. The names that Roblox sees are built from a
+//! template and cleaned up so that they aren't rejected or filtered, without
+//! affecting the `AssetName` that Tarmac tracks the asset by.
+
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// The template used when a project doesn't define one.
+pub static DEFAULT_TEMPLATE: &str = "{name}";
+
+/// The longest name that Roblox accepts for an asset.
+const MAX_LENGTH: usize = 50;
+
+/// The name given to an asset whose name has nothing left after cleaning it
+/// up.
+static FALLBACK_NAME: &str = "Asset";
+
+/// How many digits of an asset's hash `{hash}` is replaced with, which is
+/// plenty to tell apart different versions of the same file.
+const HASH_LENGTH: usize = 8;
+
+/// The values that placeholders in an upload name template are replaced with.
+pub struct UploadNameParts<'a> {
+    /// The asset's file name without its extension, like `icon (2x)`.
+    pub name: &'a str,
+
+    /// The asset's path relative to the project, like `ui/icon@2x.png`.
+    pub path: &'a str,
+
+    /// The hexadecimal encoded hash of the asset's contents.
+    pub hash: &'a str,
+
+    /// The name of the project.
+    pub project: &'a str,
+}
+
+/// Fills in an upload name template and sanitizes the result.
+pub fn format_upload_name(template: &str, parts: &UploadNameParts) -> String {
+    let folder = match parts.path.rfind('/') {
+        Some(index) => &parts.path[..index],
+        None => "",
+    };
+    let hash = &parts.hash[..parts.hash.len().min(HASH_LENGTH)];
+
+    let expanded = template
+        .replace("{name}", parts.name)
+        .replace("{path}", parts.path)
+        .replace("{folder}", folder)
+        .replace("{hash}", hash)
+        .replace("{project}", parts.project);
+
+    sanitize(&expanded)
+}
+
+/// Turns a name into one that Roblox will accept: accents are stripped,
+/// characters besides letters, digits, spaces, and `-_.()` are replaced with
+/// underscores, and the result is limited to Roblox's maximum length.
+pub fn sanitize(name: &str) -> String {
+    let mut sanitized = String::with_capacity(name.len());
+
+    for c in name.nfkd() {
+        if is_combining_mark(c) {
+            continue;
+        }
+
+        let c = if c.is_ascii_alphanumeric() || "-_.()".contains(c) {
+            c
+        } else if c.is_whitespace() {
+            ' '
+        } else {
+            '_'
+        };
+
+        // Runs of replaced characters only need one replacement.
+        if (c == ' ' || c == '_') && sanitized.ends_with(c) {
+            continue;
+        }
+
+        sanitized.push(c);
+    }
+
+    let trimmed = sanitized.trim_matches([' ', '_']);
+
+    // Everything left is ASCII, so any index is a character boundary.
+    let truncated = &trimmed[..trimmed.len().min(MAX_LENGTH)];
+    let truncated = truncated.trim_end_matches([' ', '_']);
+
+    if truncated.is_empty() {
+        FALLBACK_NAME.to_owned()
+    } else {
+        truncated.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sanitize_names() {
+        assert_eq!(sanitize("icon (2x)"), "icon (2x)");
+        assert_eq!(sanitize("Café  Menü"), "Cafe Menu");
+        assert_eq!(sanitize("ui/buttons/close#1"), "ui_buttons_close_1");
+        assert_eq!(sanitize("  __hello__  "), "hello");
+        assert_eq!(sanitize("日本"), "Asset");
+        assert_eq!(sanitize(&"a".repeat(80)).len(), MAX_LENGTH);
+    }
+
+    #[test]
+    fn format_template() {
+        let parts = UploadNameParts {
+            name: "close",
+            path: "ui/buttons/close.png",
+            hash: "0123456789abcdef",
+            project: "game",
+        };
+
+        assert_eq!(format_upload_name(DEFAULT_TEMPLATE, &parts), "close");
+        assert_eq!(
+            format_upload_name("{project}-{path}", &parts),
+            "game-ui_buttons_close.png"
+        );
+        assert_eq!(
+            format_upload_name("{folder}/{name} {hash}", &parts),
+            "ui_buttons_close 01234567"
+        );
+    }
+}
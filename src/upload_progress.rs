@@ -0,0 +1,131 @@
+//! Progress reporting for request bodies, so that uploading large assets like
+//! spritesheets and audio doesn't look like Tarmac has stopped responding.
+
+use std::io::{self, Read};
+
+use reqwest::{multipart::Part, Body};
+
+use crate::roblox_web_api::{RobloxApiError, UploadContents};
+
+/// Uploads smaller than this are sent quickly enough that reporting their
+/// progress would only be noise.
+const PROGRESS_THRESHOLD: u64 = 1024 * 1024;
+
+/// How often progress is reported, in percent of the upload.
+const PROGRESS_STEP: u64 = 10;
+
+/// Creates a request body that streams the given upload, logging its progress
+/// if it's large.
+pub fn upload_body(name: &str, contents: &UploadContents) -> Result<Body, RobloxApiError> {
+    let len = contents.len();
+    let reader = open_reader(name, contents)?;
+
+    if len < PROGRESS_THRESHOLD {
+        return Ok(Body::sized(reader, len));
+    }
+
+    Ok(Body::sized(ProgressReader::new(name, reader, len), len))
+}
+
+/// Creates a multipart form part that streams the given upload, logging its
+/// progress if it's large.
+pub fn upload_part(name: &str, contents: &UploadContents) -> Result<Part, RobloxApiError> {
+    let len = contents.len();
+    let reader = open_reader(name, contents)?;
+
+    if len < PROGRESS_THRESHOLD {
+        return Ok(Part::reader_with_length(reader, len));
+    }
+
+    Ok(Part::reader_with_length(
+        ProgressReader::new(name, reader, len),
+        len,
+    ))
+}
+
+fn open_reader(
+    name: &str,
+    contents: &UploadContents,
+) -> Result<Box<dyn Read + Send>, RobloxApiError> {
+    contents
+        .reader()
+        .map_err(|source| RobloxApiError::ReadContents {
+            name: name.to_owned(),
+            source,
+        })
+}
+
+/// Wraps the contents of an upload, logging how much of it has been read as
+/// it's sent.
+struct ProgressReader<R> {
+    name: String,
+    inner: R,
+    sent: u64,
+    total: u64,
+
+    /// The last percentage that we reported.
+    reported: u64,
+}
+
+impl<R: Read> ProgressReader<R> {
+    fn new(name: &str, inner: R, total: u64) -> Self {
+        Self {
+            name: name.to_owned(),
+            inner,
+            sent: 0,
+            total,
+            reported: 0,
+        }
+    }
+
+    fn percent_sent(&self) -> u64 {
+        if self.total == 0 {
+            return 100;
+        }
+
+        self.sent * 100 / self.total
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.sent += read as u64;
+
+        let percent = self.percent_sent();
+        if percent >= self.reported + PROGRESS_STEP {
+            self.reported = percent - percent % PROGRESS_STEP;
+
+            log::info!(
+                "Uploading {}: {}% of {} KB",
+                self.name,
+                self.reported,
+                self.total / 1024
+            );
+        }
+
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reports_in_steps() {
+        let mut reader = ProgressReader::new("foo", io::Cursor::new(vec![0; 100]), 100);
+        let mut buf = [0; 25];
+
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(reader.reported, 20);
+
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(reader.reported, 50);
+
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest.len(), 50);
+        assert_eq!(reader.reported, 100);
+    }
+}